@@ -0,0 +1,431 @@
+use std::path::Path;
+
+use nix::sys::ptrace;
+use nix::sys::ptrace::AddressType;
+use nix::unistd::Pid;
+
+use crate::dwarf::error::{DwarfError, Result};
+use crate::dwarf::frame::{CallFrameInfo, FrameSectionKind, PointerBases};
+use crate::dwarf::unwind::{
+    self, RegisterSet, FRAME_POINTER_REGISTER, RETURN_ADDRESS_REGISTER, STACK_POINTER_REGISTER,
+};
+use crate::elf::file::ElfFile;
+use crate::elf::parser::ElfParser;
+use crate::elf::section::Elf64SectionFlags;
+use crate::elf::symbol::SymType;
+
+/// Frames beyond this depth are not unwound, in case the stack is corrupted
+const MAX_FRAMES: usize = 1024;
+/// Functions that start the program, whose callers are not unwound. Newer glibc defines
+/// `__libc_start_main` as an alias of `__libc_start_main_impl`.
+const OUTERMOST_FUNCTIONS: [&str; 4] = [
+    "_start",
+    "__libc_start_main",
+    "__libc_start_main_impl",
+    "__libc_start_call_main",
+];
+/// `mov $15, %rax; syscall`, the `rt_sigreturn` trampoline signal handlers return to
+const SIGRETURN_CODE: [u8; 9] = [0x48, 0xc7, 0xc0, 0x0f, 0x00, 0x00, 0x00, 0x0f, 0x05];
+/// Offset of the general purpose registers in the `ucontext_t` on the stack of a signal frame
+const UCONTEXT_GREGS_OFFSET: u64 = 40;
+/// DWARF numbers of the registers, in the order of the `gregs` of `ucontext_t`
+const UCONTEXT_GREGS: [u16; 17] = [8, 9, 10, 11, 12, 13, 14, 15, 5, 4, 6, 3, 1, 0, 2, 7, 16];
+
+/// A function symbol of an ELF file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionSymbol {
+    pub address: u64,
+    pub size: u64,
+    pub name: String,
+}
+
+/// The function symbols of `elf_file` from `.symtab` and `.dynsym`, sorted by address. The
+/// labels of code without a type, like those of hand-written assembly, count as functions.
+pub fn function_symbols(elf_file: &ElfFile) -> Vec<FunctionSymbol> {
+    let is_code = |index: u16| {
+        elf_file
+            .sections
+            .get(index as usize)
+            .is_some_and(|section| section.flags().contains(Elf64SectionFlags::EXECINSTR))
+    };
+    let mut symbols: Vec<FunctionSymbol> = elf_file
+        .symbols
+        .iter()
+        .chain(elf_file.dynamic_symbols.iter())
+        .filter(|symbol| {
+            let is_function = match symbol.r#type() {
+                Some(SymType::Func) => true,
+                Some(SymType::NoType) => is_code(symbol.shndx()),
+                _ => false,
+            };
+            is_function && symbol.shndx() != 0 && !symbol.name.is_empty()
+        })
+        .map(|symbol| FunctionSymbol {
+            address: symbol.value(),
+            size: symbol.size(),
+            name: symbol.name.to_string(),
+        })
+        .collect();
+    symbols.sort_by_key(|symbol| symbol.address);
+    symbols.dedup();
+    symbols
+}
+
+/// The symbol of the function containing `address`, from symbols sorted by address
+pub fn find_function_symbol(symbols: &[FunctionSymbol], address: u64) -> Option<&FunctionSymbol> {
+    let index = symbols.partition_point(|symbol| symbol.address <= address);
+    symbols[..index]
+        .iter()
+        .rev()
+        .take_while(|symbol| symbol.address == symbols[index - 1].address)
+        .find(|symbol| address < symbol.address + symbol.size.max(1))
+}
+
+/// The symbol of the function containing `address` like [`find_function_symbol`], or else
+/// the closest symbol before it without a size, as labels of hand-written assembly have none
+pub fn find_nearest_symbol(symbols: &[FunctionSymbol], address: u64) -> Option<&FunctionSymbol> {
+    find_function_symbol(symbols, address).or_else(|| {
+        let index = symbols.partition_point(|symbol| symbol.address <= address);
+        symbols[..index].last().filter(|symbol| symbol.size == 0)
+    })
+}
+
+/// What is needed to unwind through the code of a shared object, copied out of its file
+/// as shared objects are only found once they are mapped into the process
+#[derive(Debug, Clone)]
+pub struct SharedObject {
+    eh_frame: Vec<u8>,
+    bases: PointerBases,
+    symbols: Vec<FunctionSymbol>,
+}
+
+impl SharedObject {
+    /// Reads the shared object at `path`, `None` if the file is gone
+    pub fn load(path: &Path) -> Option<Self> {
+        if !path.is_file() {
+            return None;
+        }
+        let mut parser = ElfParser::new(path);
+        let elf_file = parser.parse_elf_file();
+        let address = |name| {
+            elf_file
+                .get_section_by_name(name)
+                .map(|section| section.address())
+        };
+        let eh_frame = elf_file.get_section_by_name(".eh_frame");
+        Some(Self {
+            eh_frame: eh_frame
+                .map(|section| section.bytes().to_vec())
+                .unwrap_or_default(),
+            bases: PointerBases {
+                section: address(".eh_frame").unwrap_or(0),
+                text: address(".text"),
+                data: address(".got"),
+            },
+            symbols: function_symbols(&elf_file),
+        })
+    }
+
+    pub fn call_frame_info(&self) -> Option<CallFrameInfo<'_>> {
+        if self.eh_frame.is_empty() {
+            return None;
+        }
+        CallFrameInfo::parse(FrameSectionKind::EhFrame, &self.eh_frame, self.bases, 8).ok()
+    }
+
+    pub fn symbols(&self) -> &[FunctionSymbol] {
+        &self.symbols
+    }
+}
+
+/// The code of a file mapped into the process, with what is needed to unwind through it
+#[derive(Debug, Clone, Copy)]
+pub struct Module<'m> {
+    pub path: &'m Path,
+    pub start: u64,
+    pub end: u64,
+    /// Difference between the addresses of the process and those of the file
+    pub load_bias: u64,
+    pub cfi: Option<&'m CallFrameInfo<'m>>,
+    pub symbols: &'m [FunctionSymbol],
+    /// The module is the executable being debugged rather than a shared object
+    pub is_executable: bool,
+}
+
+impl<'m> Module<'m> {
+    pub fn contains(&self, address: u64) -> bool {
+        self.start <= address && address < self.end
+    }
+
+    /// The name of the symbol of the function containing `address`, an address of the process
+    pub fn function_name(&self, address: u64) -> Option<&'m str> {
+        find_function_symbol(self.symbols, address.wrapping_sub(self.load_bias))
+            .map(|symbol| symbol.name.as_str())
+    }
+
+    /// The symbol nearest to `address`, an address of the process, and the offset of
+    /// `address` from it, see [`find_nearest_symbol`]
+    pub fn symbol_offset(&self, address: u64) -> Option<(&'m str, u64)> {
+        let address = address.wrapping_sub(self.load_bias);
+        find_nearest_symbol(self.symbols, address)
+            .map(|symbol| (symbol.name.as_str(), address - symbol.address))
+    }
+}
+
+/// The module whose code contains `address`
+pub fn find_module<'s, 'm>(modules: &'s [Module<'m>], address: u64) -> Option<&'s Module<'m>> {
+    modules.iter().find(|module| module.contains(address))
+}
+
+/// A frame of the call stack
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub pc: u64,
+    /// The registers as they were in the frame, as far as they could be recovered
+    pub registers: RegisterSet,
+    /// The CFA of the frame, if it was unwound through CFI
+    pub cfa: Option<u64>,
+    /// The pc is a return address, the instruction after a call
+    pub is_return_address: bool,
+    /// The frame is the trampoline a signal handler returns to
+    pub is_signal_frame: bool,
+    /// Number of inlined calls between the code at the pc and the function of the frame.
+    /// Frames of functions inlined into another share its registers and come before the
+    /// frame of the function they are inlined into, whose depth is one more.
+    pub inline_depth: usize,
+}
+
+impl Frame {
+    /// The address to look up the code of the frame at. A return address may be the
+    /// start of the next line or function, so the call instruction is looked up instead.
+    pub fn lookup_pc(&self) -> u64 {
+        if self.is_return_address {
+            self.pc - 1
+        } else {
+            self.pc
+        }
+    }
+}
+
+/// Unwinds the call stack of the stopped process starting with the innermost frame,
+/// whose registers are `registers`. Unwinding uses the CFI of the modules and falls
+/// back to the frame pointer, it stops at the functions starting the program, like `_start`
+/// and `__libc_start_main`, or where no caller can be found.
+pub fn unwind(pid: Pid, registers: RegisterSet, modules: &[Module]) -> Vec<Frame> {
+    let mut frames: Vec<Frame> = Vec::new();
+    let mut next = Some(registers);
+    let mut is_return_address = false;
+
+    while let Some(registers) = next.take() {
+        let pc = match registers.pc() {
+            Some(pc) if pc != 0 => pc,
+            _ => break,
+        };
+        let mut frame = Frame {
+            pc,
+            registers,
+            cfa: None,
+            is_return_address,
+            is_signal_frame: false,
+            inline_depth: 0,
+        };
+
+        let module = find_module(modules, frame.lookup_pc());
+        let is_outermost = module
+            .and_then(|module| module.function_name(frame.lookup_pc()))
+            .is_some_and(is_outermost_function);
+        if !is_outermost && frames.len() + 1 < MAX_FRAMES {
+            next = unwind_caller(pid, &mut frame, module);
+        }
+        // Callers are further up the stack, except for signal handlers on their own stack
+        if let Some(caller) = &next {
+            let sp = frame.registers.sp().unwrap_or(0);
+            if !frame.is_signal_frame && caller.sp().is_none_or(|caller_sp| caller_sp <= sp) {
+                next = None;
+            }
+        }
+
+        is_return_address = !frame.is_signal_frame;
+        frames.push(frame);
+    }
+    frames
+}
+
+/// Whether the function `name` starts the program, so that its frame is the outermost one
+fn is_outermost_function(name: &str) -> bool {
+    OUTERMOST_FUNCTIONS.contains(&name)
+}
+
+/// Adds a frame before each frame of `frames` for every function inlined at its pc, of
+/// which `inline_depth` tells the number, the innermost inlined function first
+pub fn with_inline_frames<F>(frames: Vec<Frame>, inline_depth: F) -> Vec<Frame>
+where
+    F: Fn(&Frame) -> usize,
+{
+    let mut result = Vec::with_capacity(frames.len());
+    for frame in frames {
+        let depth = if frame.is_signal_frame {
+            0
+        } else {
+            inline_depth(&frame)
+        };
+        for inline_depth in 0..=depth {
+            result.push(Frame {
+                inline_depth,
+                ..frame.clone()
+            });
+        }
+    }
+    result
+}
+
+/// Recovers the registers of the caller of `frame`, marking the frame as a signal frame
+/// if it is one
+fn unwind_caller(pid: Pid, frame: &mut Frame, module: Option<&Module>) -> Option<RegisterSet> {
+    let read_memory = |address| read_word(pid, address);
+    if let Some(module) = module {
+        let pc = frame.lookup_pc().wrapping_sub(module.load_bias);
+        let cfi = module.cfi;
+        if let Some((cfi, fde)) = cfi.and_then(|cfi| Some((cfi, cfi.fde_for_pc(pc)?))) {
+            let cie = cfi.cie(fde);
+            frame.is_signal_frame = cie.is_signal_frame;
+            let row = cfi
+                .unwind_table(fde)
+                .ok()?
+                .into_iter()
+                .find(|row| row.contains(pc))?;
+            let (cfa, caller) = unwind::unwind_frame(
+                &row,
+                cie.return_address_register,
+                &frame.registers,
+                read_memory,
+            )
+            .ok()?;
+            frame.cfa = Some(cfa);
+            return Some(caller);
+        }
+    }
+
+    if is_sigreturn(pid, frame.pc) {
+        frame.is_signal_frame = true;
+        return unwind_signal_frame(pid, frame);
+    }
+    unwind_frame_pointer(pid, frame)
+}
+
+/// Whether the code at `pc` is the `rt_sigreturn` trampoline
+fn is_sigreturn(pid: Pid, pc: u64) -> bool {
+    let mut code = [0; 16];
+    for (index, chunk) in code.chunks_mut(8).enumerate() {
+        match read_word(pid, pc + 8 * index as u64) {
+            Ok(word) => chunk.copy_from_slice(&word.to_le_bytes()),
+            Err(_) => return false,
+        }
+    }
+    code.starts_with(&SIGRETURN_CODE)
+}
+
+/// Recovers the registers a signal interrupted from the `ucontext_t` the stack pointer
+/// of the trampoline points to
+fn unwind_signal_frame(pid: Pid, frame: &mut Frame) -> Option<RegisterSet> {
+    let gregs = frame.registers.sp()? + UCONTEXT_GREGS_OFFSET;
+    let mut caller = RegisterSet::default();
+    for (index, register) in UCONTEXT_GREGS.iter().enumerate() {
+        caller.set(*register, read_word(pid, gregs + 8 * index as u64).ok()?);
+    }
+    Some(caller)
+}
+
+/// Recovers the caller through the frame pointer, for code without CFI that keeps the
+/// saved frame pointer and the return address at the frame pointer
+fn unwind_frame_pointer(pid: Pid, frame: &mut Frame) -> Option<RegisterSet> {
+    let frame_pointer = frame.registers.get(FRAME_POINTER_REGISTER)?;
+    if frame_pointer == 0 || frame.registers.sp().is_some_and(|sp| frame_pointer < sp) {
+        return None;
+    }
+    let cfa = frame_pointer + 16;
+    frame.cfa = Some(cfa);
+
+    let mut caller = RegisterSet::default();
+    caller.set(FRAME_POINTER_REGISTER, read_word(pid, frame_pointer).ok()?);
+    caller.set(
+        RETURN_ADDRESS_REGISTER,
+        read_word(pid, frame_pointer + 8).ok()?,
+    );
+    caller.set(STACK_POINTER_REGISTER, cfa);
+    Some(caller)
+}
+
+fn read_word(pid: Pid, address: u64) -> Result<u64> {
+    ptrace::read(pid, address as AddressType)
+        .map(|word| word as u64)
+        .map_err(|_| DwarfError::InvalidMemoryAccess(address))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_function_symbol() {
+        let symbol = |address, size, name: &str| FunctionSymbol {
+            address,
+            size,
+            name: name.to_string(),
+        };
+        let symbols = vec![
+            symbol(0x1000, 0x10, "_start"),
+            symbol(0x1020, 0x20, "main"),
+            symbol(0x1040, 0, "end"),
+        ];
+        let name = |address| find_function_symbol(&symbols, address).map(|s| s.name.as_str());
+        assert_eq!(name(0xfff), None);
+        assert_eq!(name(0x100f), Some("_start"));
+        assert_eq!(name(0x1010), None);
+        assert_eq!(name(0x103f), Some("main"));
+        assert_eq!(name(0x1040), Some("end"));
+        assert_eq!(name(0x1041), None);
+
+        let nearest = |address| find_nearest_symbol(&symbols, address).map(|s| s.name.as_str());
+        assert_eq!(nearest(0xfff), None);
+        assert_eq!(nearest(0x1008), Some("_start"));
+        assert_eq!(nearest(0x1010), None);
+        assert_eq!(nearest(0x1045), Some("end"));
+    }
+
+    #[test]
+    fn test_is_outermost_function() {
+        assert!(is_outermost_function("_start"));
+        assert!(is_outermost_function("__libc_start_main"));
+        assert!(is_outermost_function("__libc_start_main_impl"));
+        assert!(is_outermost_function("__libc_start_call_main"));
+        assert!(!is_outermost_function("main"));
+        assert!(!is_outermost_function("__libc_start"));
+    }
+
+    #[test]
+    fn test_with_inline_frames() {
+        let frame = |pc, is_return_address| Frame {
+            pc,
+            registers: RegisterSet::default(),
+            cfa: None,
+            is_return_address,
+            is_signal_frame: false,
+            inline_depth: 0,
+        };
+        let frames = vec![frame(0x1010, false), frame(0x1041, true)];
+        // Two functions are inlined at the pc of the innermost frame
+        let frames = with_inline_frames(frames, |frame| match frame.lookup_pc() {
+            0x1010 => 2,
+            _ => 0,
+        });
+        let summary: Vec<(u64, usize)> = frames
+            .iter()
+            .map(|frame| (frame.pc, frame.inline_depth))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![(0x1010, 0), (0x1010, 1), (0x1010, 2), (0x1041, 0)]
+        );
+    }
+}
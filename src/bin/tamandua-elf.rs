@@ -0,0 +1,33 @@
+// std
+use std::env;
+use std::path::Path;
+use std::process;
+
+// own
+use tamandua::elf::diff::diff;
+use tamandua::elf::parser::ElfParser;
+
+const USAGE: &str = "Usage: tamandua-elf diff <old> <new>";
+
+/// Prints the structural differences of two ELF files as JSON
+fn diff_command(old_path: &Path, new_path: &Path) {
+    let mut old_parser = ElfParser::new(old_path);
+    let old_file = old_parser.parse_elf_file();
+    let mut new_parser = ElfParser::new(new_path);
+    let new_file = new_parser.parse_elf_file();
+
+    println!("{}", diff(&old_file, &new_file).to_json());
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let args: Vec<&str> = args.iter().skip(1).map(String::as_str).collect();
+
+    match args[..] {
+        ["diff", old_path, new_path] => diff_command(Path::new(old_path), Path::new(new_path)),
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    }
+}
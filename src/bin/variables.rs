@@ -1,5 +1,4 @@
 #[allow(unused_assignments, unused_variables)]
-
 fn main() {
     let mut a: u64 = 3;
     let b: u64 = 2;
@@ -4,6 +4,54 @@ use nix::unistd::Pid;
 const BOTTOM_BYTE_MASK: u64 = 0xff;
 const INT3: u64 = 0xcc;
 
+/// A breakpoint the user set, numbered in the order breakpoints and watchpoints are set.
+/// It stops at the int3 inserted at each of its addresses, several for a function that
+/// was inlined or a line whose code is in several functions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserBreakpoint {
+    pub number: usize,
+    /// Where the breakpoint was set, as it was given
+    pub location: String,
+    pub addresses: Vec<u64>,
+    pub enabled: bool,
+    /// An expression evaluated when the breakpoint is reached, the process only stops
+    /// where it is true
+    pub condition: Option<String>,
+    /// How often the process reached the breakpoint where its condition held
+    pub hit_count: usize,
+    /// How many more hits the process continues from without stopping
+    pub ignore_count: usize,
+    /// The breakpoint is deleted when the process stops at it
+    pub temporary: bool,
+}
+
+impl UserBreakpoint {
+    pub fn new(number: usize, location: String, addresses: Vec<u64>) -> Self {
+        Self {
+            number,
+            location,
+            addresses,
+            enabled: true,
+            condition: None,
+            hit_count: 0,
+            ignore_count: 0,
+            temporary: false,
+        }
+    }
+
+    /// Counts a hit of the breakpoint, where its condition held. Returns whether the
+    /// process stops, which it does once the hits to ignore are used up.
+    pub fn hit(&mut self) -> bool {
+        self.hit_count += 1;
+        if self.ignore_count > 0 {
+            self.ignore_count -= 1;
+            return false;
+        }
+        true
+    }
+}
+
+/// The int3 instruction inserted at an address, which makes the process trap there
 pub struct Breakpoint {
     pid: Pid,
     addr: u64,
@@ -21,11 +69,9 @@ impl Breakpoint {
         }
     }
 
-    pub fn enable(&mut self) {
-        let data: u64 = ptrace::read(self.pid, self.addr as ptrace::AddressType)
-            .expect("Could not read memory")
-            .try_into()
-            .expect("Data read from memory does not fit into u64");
+    /// Writes the int3 over the instruction, which fails if the address is not mapped
+    pub fn enable(&mut self) -> nix::Result<()> {
+        let data: u64 = ptrace::read(self.pid, self.addr as ptrace::AddressType)? as u64;
         self.saved_data = bottom_byte(data);
         let data_with_int3: u64 = set_int3_at_end_of_data(data);
 
@@ -35,18 +81,15 @@ impl Breakpoint {
                 self.pid,
                 self.addr as ptrace::AddressType,
                 data_with_int3 as ptrace::AddressType,
-            )
-            .expect("Failed to write memory");
+            )?;
         }
 
         self.enabled = true;
+        Ok(())
     }
 
-    pub fn disable(&mut self) {
-        let data: u64 = ptrace::read(self.pid, self.addr as ptrace::AddressType)
-            .expect("Failed to read memory")
-            .try_into()
-            .expect("Data read from memory does not fit into u64");
+    pub fn disable(&mut self) -> nix::Result<()> {
+        let data: u64 = ptrace::read(self.pid, self.addr as ptrace::AddressType)? as u64;
 
         let restored_data = restore_data_from_int3(data, self.saved_data);
 
@@ -56,16 +99,21 @@ impl Breakpoint {
                 self.pid,
                 self.addr as ptrace::AddressType,
                 restored_data as ptrace::AddressType,
-            )
-            .expect("Failed to write memory")
+            )?;
         }
 
         self.enabled = false;
+        Ok(())
     }
 
     pub fn is_enabled(&self) -> bool {
         self.enabled
     }
+
+    /// The byte of the instruction the int3 replaces while the breakpoint is enabled
+    pub fn saved_data(&self) -> u8 {
+        self.saved_data
+    }
 }
 
 /// Retrieves the bottom byte of a u64
@@ -87,7 +135,7 @@ fn set_int3_at_end_of_data(orig_data: u64) -> u64 {
 fn restore_data_from_int3(data_with_int3: u64, saved_data: u8) -> u64 {
     // Uses the !0xff (0xffffffffffffff00) mask to ignore
     // the original bottom byte and then OR it with the saved data
-    (data_with_int3 & !0xff) as u64 | saved_data as u64
+    (data_with_int3 & !0xff) | saved_data as u64
 }
 
 #[cfg(test)]
@@ -118,4 +166,16 @@ mod tests {
         let expected: u64 = 0x4800000e6d358d48;
         assert_eq!(expected, restored_data);
     }
+
+    #[test]
+    fn hit_after_ignore_count() {
+        let mut breakpoint = UserBreakpoint::new(1, String::from("main"), vec![0x401000]);
+        breakpoint.ignore_count = 2;
+        assert!(!breakpoint.hit());
+        assert!(!breakpoint.hit());
+        assert!(breakpoint.hit());
+        assert!(breakpoint.hit());
+        assert_eq!(breakpoint.hit_count, 4);
+        assert_eq!(breakpoint.ignore_count, 0);
+    }
 }
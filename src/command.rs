@@ -1,4 +1,7 @@
+use std::fmt::Display;
+
 use crate::register::{self, RegisterKind};
+use crate::watchpoint::WatchKind;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ReadContainer<T> {
@@ -13,12 +16,38 @@ pub struct WriteContainer<T, U> {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Command {
-    Break(u64),
+    Backtrace,
+    Break(BreakLocation, Option<String>),
+    Checksec,
+    /// Sets the condition of the numbered breakpoint, or removes it without one
+    Condition(usize, Option<String>),
     Continue,
+    /// Deletes the numbered breakpoints and watchpoints, all of them without numbers
+    Delete(Vec<usize>),
+    Disable(Vec<usize>),
+    Down(usize),
+    Enable(Vec<usize>),
     Exit,
+    Finish,
+    Frame(Option<usize>),
+    HardwareBreak(BreakLocation),
+    Ignore(usize, usize),
+    Info(InfoCommand),
+    List(Option<LineSpec>),
     Memory(MemoryCommand),
+    Next(usize),
+    NextInstruction(usize),
+    Print(String),
+    Ptype(String),
     Register(RegisterCommand),
+    Set(SetCommand),
+    Step(usize),
+    StepInstruction(usize),
+    TemporaryBreak(BreakLocation, Option<String>),
     Unknown,
+    Up(usize),
+    Watch(WatchKind, Address, Option<u64>),
+    Whatis(String),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -28,22 +57,158 @@ pub enum RegisterCommand {
     Write(WriteContainer<RegisterKind, u64>),
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SetCommand {
+    /// Replaces the directory prefix of source paths with another one
+    SubstitutePath(String, String),
+}
+
+/// A place in the source, like gdb's linespecs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LineSpec {
+    /// A line of the current source file
+    Line(u64),
+    /// A line of the file whose path ends with the given one
+    FileLine(String, u64),
+    Function(String),
+}
+
+impl Display for LineSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LineSpec::Line(line) => write!(f, "{}", line),
+            LineSpec::FileLine(file, line) => write!(f, "{}:{}", file, line),
+            LineSpec::Function(name) => f.write_str(name),
+        }
+    }
+}
+
+/// Parses `line`, `file:line` or `function`
+pub fn parse_linespec(spec: &str) -> Option<LineSpec> {
+    if let Some((file, line)) = spec.rsplit_once(':') {
+        // C++ names like `ns::f` have colons too
+        if let Ok(line) = line.parse() {
+            return Some(LineSpec::FileLine(file.to_string(), line));
+        }
+    }
+    if spec.starts_with(|c: char| c.is_ascii_digit()) {
+        return spec.parse().ok().map(LineSpec::Line);
+    }
+    Some(LineSpec::Function(spec.to_string()))
+}
+
+/// Where 'break' sets a breakpoint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BreakLocation {
+    /// `*0x401126`, an address of the process
+    Address(u64),
+    /// `*expression`, an address computed in the selected frame like `*$pc + 4`
+    Expression(String),
+    /// `+offset` or `-offset`, a line relative to the line of the selected frame
+    Offset(i64),
+    /// `function`, `file:line` or `line`
+    Source(LineSpec),
+}
+
+impl Display for BreakLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BreakLocation::Address(address) => write!(f, "*0x{:x}", address),
+            BreakLocation::Expression(expression) => write!(f, "*{}", expression),
+            BreakLocation::Offset(offset) => write!(f, "{:+}", offset),
+            BreakLocation::Source(spec) => spec.fmt(f),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InfoCommand {
+    Args,
+    Breakpoints,
+    Got,
+    Locals,
+    Plt,
+}
+
+/// An address given to a command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Address {
+    /// Hexadecimal digits without a prefix. Variables named with hexadecimal digits only,
+    /// like `add`, are read with an expression like `(add)`.
+    Value(u64),
+    /// An expression evaluated in the selected frame, like `&counter` or `$rsp + 8`
+    Expression(String),
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum MemoryCommand {
-    Read(ReadContainer<u64>),
+    Read(ReadContainer<Address>),
     Write(WriteContainer<u64, u64>),
 }
 
 /// Takes in a string, depending on the first argument in the string several types of commands are possible
 /// 1. 'continue': Continues the execution of the program to the next breakpoint or until the end
-/// 2. 'break' 'address: hex': Sets a breakpoint at the given address
+/// 2. 'break' '*0xaddress: hex': Sets a breakpoint at the given address
+///    'break' 'line: u64': Sets a breakpoint at the code of the line of the current source file
+///    'break' 'function: &str': Sets a breakpoint after the prologue of the function, and at the
+///    entry of every instance of it inlined into other functions
+///    'break' 'file: &str:line: u64': Sets a breakpoint at the code of the line
+///    'break' '*expression: &str': Sets a breakpoint at the address the expression evaluates to
+///    'break' '+offset: u64' or '-offset: u64': Sets a breakpoint `offset` lines after or before
+///    the line of the selected frame
+///    'break' 'location' 'if' 'condition: &str': Sets a breakpoint the process only stops at when
+///    the condition, an expression evaluated in the innermost frame, is true
 /// 3. 'register'
 ///      - 'read' 'reg_name: &str':               Reads the value from the specified register by name
 ///      - 'write' 'reg_name: &str' 'value: hex': Write the speicified hexadecimal value to the register by name.
 ///      - 'dump':                                Dumps the values of all the registers specified in breakpoint::REGISTERS
 /// 4. 'memory'
-///      - 'read' 'address: hex':                 Read memory from a specific address location
+///      - 'read' 'address: hex | expression':    Read memory from a specific address location
 ///      - 'write' 'addres: hex' 'value: hex':    Write 'value' to memory location at 'address'
+/// 5. 'info'
+///      - 'plt':                                 Lists the PLT stubs as 'foo@plt' symbols
+///      - 'got':                                 Shows the GOT slots and what they are bound to
+///      - 'locals':                              Prints the local variables of the selected frame
+///      - 'args':                                Prints the arguments of the selected frame
+///      - 'breakpoints' or 'break':              Lists the breakpoints and watchpoints with their state
+/// 6. 'checksec': Reports the exploit mitigations the executable is built with
+/// 7. 'print' 'expression: &str': Prints the value of a C or Rust expression evaluated in the
+///    selected frame, formatted by its type. Expressions may use registers like `$rip` and
+///    assign convenience variables like `$n = 1`.
+/// 8. 'backtrace' or 'bt': Prints the call stack, innermost frame first
+/// 9. 'frame' 'level: usize': Selects the frame at the level of the call stack, or prints the
+///    selected frame without a level. 'print' and 'register' operate in the selected frame.
+/// 10. 'up' 'count: usize' and 'down' 'count: usize': Selects the frame `count` levels further
+///     out or in, one level without a count
+/// 11. 'list' or 'l' 'location: linespec': Lists the source around a line, 'line', 'file:line' or
+///     'function'. Without a location it lists around the selected frame, or continues the listing.
+/// 12. 'set' 'substitute-path' 'from: &str' 'to: &str': Looks up source files whose path starts
+///     with the directory 'from' in 'to' instead
+/// 13. 'ptype' 'expression | type: &str': Prints the declaration of a type, or of the type of an
+///     expression, with the offset and size of each member of structures and unions
+/// 14. 'whatis' 'expression | type: &str': Prints the name of the type of an expression, or what
+///     a typedef names
+/// 15. 'step' or 's' 'count: usize': Runs to the next line of source, entering the functions it
+///     calls, `count` times or once without a count
+/// 16. 'next' or 'n' 'count: usize': Runs to the next line of source in the current function,
+///     over the functions it calls, `count` times or once without a count
+/// 17. 'finish' or 'fin': Runs until the selected frame returns and prints the value it returns
+/// 18. 'stepi' or 'si' 'count: usize': Executes one instruction, `count` times or once without a
+///     count, and prints the instruction the pc is at then
+/// 19. 'nexti' or 'ni' 'count: usize': Like 'stepi', but runs over the functions called
+/// 20. 'hbreak' 'location': Like 'break', but with a debug register instead of an int3, which
+///     works in code that cannot be written to
+/// 21. 'watch' 'address: hex | expression' 'length: usize': Stops when the memory of an lvalue
+///     expression, or `length` bytes at an address, is written with another value. The length
+///     defaults to the size of the expression, or 8 bytes at an address.
+///     'rwatch' stops when the memory is read, 'awatch' when it is read or written.
+/// 22. 'tbreak' 'location': Like 'break', but the breakpoint is deleted when the process stops at it
+/// 23. 'ignore' 'number: usize' 'count: usize': Continues from the next `count` hits of the
+///     breakpoint without stopping
+/// 24. 'delete', 'disable' and 'enable' 'numbers: usize...': Deletes, disables or enables the
+///     breakpoints and watchpoints with the numbers, or all of them without numbers
+/// 25. 'condition' 'number: usize' 'condition: &str': Makes the breakpoint stop only when the
+///     condition is true, like 'break' 'location' 'if', or always without a condition
 pub fn parse_command(line: String) -> Command {
     // TODO: Lots of repeating here, should be possible to
     // move some of it out into private functions
@@ -52,22 +217,78 @@ pub fn parse_command(line: String) -> Command {
 
     match command {
         "break" => {
-            let break_point_addr = u64::from_str_radix(
-                args.next().expect("No breakpoint address argument given"),
-                16,
-            )
-            .expect("Failed to parse breakpoint address to hexadecimal value");
-            Command::Break(break_point_addr)
+            let (location, condition) = split_condition(arguments(&line, 1));
+            match parse_break_location(location) {
+                Some(location) => Command::Break(location, condition),
+                None => Command::Unknown,
+            }
         }
+        "backtrace" | "bt" => Command::Backtrace,
+        "checksec" => Command::Checksec,
         "continue" => Command::Continue,
+        "down" => match parse_count(args.next()) {
+            Some(count) => Command::Down(count),
+            None => Command::Unknown,
+        },
         "exit" => Command::Exit,
+        "finish" | "fin" => Command::Finish,
+        "frame" | "f" => match args.next() {
+            Some(level) => match level.parse() {
+                Ok(level) => Command::Frame(Some(level)),
+                Err(_) => Command::Unknown,
+            },
+            None => Command::Frame(None),
+        },
+        "hbreak" => match parse_break_location(arguments(&line, 1)) {
+            Some(location) => Command::HardwareBreak(location),
+            None => Command::Unknown,
+        },
+        "delete" => match parse_numbers(args) {
+            Some(numbers) => Command::Delete(numbers),
+            None => Command::Unknown,
+        },
+        "condition" => match args.next().map(str::parse) {
+            Some(Ok(number)) => {
+                let condition = arguments(&line, 2);
+                Command::Condition(
+                    number,
+                    (!condition.is_empty()).then(|| condition.to_string()),
+                )
+            }
+            _ => Command::Unknown,
+        },
+        "disable" => match parse_numbers(args) {
+            Some(numbers) => Command::Disable(numbers),
+            None => Command::Unknown,
+        },
+        "enable" => match parse_numbers(args) {
+            Some(numbers) => Command::Enable(numbers),
+            None => Command::Unknown,
+        },
+        "ignore" => match (args.next().map(str::parse), args.next().map(str::parse)) {
+            (Some(Ok(number)), Some(Ok(count))) => Command::Ignore(number, count),
+            _ => Command::Unknown,
+        },
+        "info" => match args.next() {
+            Some("args") => Command::Info(InfoCommand::Args),
+            Some("breakpoints" | "break") => Command::Info(InfoCommand::Breakpoints),
+            Some("got") => Command::Info(InfoCommand::Got),
+            Some("locals") => Command::Info(InfoCommand::Locals),
+            Some("plt") => Command::Info(InfoCommand::Plt),
+            _ => Command::Unknown,
+        },
+        "list" | "l" => match args.next() {
+            Some(spec) => match parse_linespec(spec) {
+                Some(spec) => Command::List(Some(spec)),
+                None => Command::Unknown,
+            },
+            None => Command::List(None),
+        },
         "memory" => {
             let command_arg = args.next().expect("No memory command argument given");
             match command_arg {
                 "read" => {
-                    let source =
-                        u64::from_str_radix(args.next().expect("No read memory address given"), 16)
-                            .expect("Failed to parse read memory address to hexadecimal value");
+                    let source = parse_address(arguments(&line, 2));
                     Command::Memory(MemoryCommand::Read(ReadContainer { source }))
                 }
                 "write" => {
@@ -84,6 +305,28 @@ pub fn parse_command(line: String) -> Command {
                 _ => Command::Unknown,
             }
         }
+        "next" | "n" => match parse_count(args.next()) {
+            Some(count) => Command::Next(count),
+            None => Command::Unknown,
+        },
+        "nexti" | "ni" => match parse_count(args.next()) {
+            Some(count) => Command::NextInstruction(count),
+            None => Command::Unknown,
+        },
+        "set" => match (args.next(), args.next(), args.next()) {
+            (Some("substitute-path"), Some(from), Some(to)) => {
+                Command::Set(SetCommand::SubstitutePath(from.to_string(), to.to_string()))
+            }
+            _ => Command::Unknown,
+        },
+        "print" | "p" => match arguments(&line, 1) {
+            "" => Command::Unknown,
+            expression => Command::Print(expression.to_string()),
+        },
+        "ptype" => match arguments(&line, 1) {
+            "" => Command::Unknown,
+            expression => Command::Ptype(expression.to_string()),
+        },
         "register" => {
             let command_arg = args.next().expect("No register command argument given");
             match command_arg {
@@ -110,10 +353,123 @@ pub fn parse_command(line: String) -> Command {
                 _ => Command::Unknown,
             }
         }
+        "step" | "s" => match parse_count(args.next()) {
+            Some(count) => Command::Step(count),
+            None => Command::Unknown,
+        },
+        "stepi" | "si" => match parse_count(args.next()) {
+            Some(count) => Command::StepInstruction(count),
+            None => Command::Unknown,
+        },
+        "tbreak" => {
+            let (location, condition) = split_condition(arguments(&line, 1));
+            match parse_break_location(location) {
+                Some(location) => Command::TemporaryBreak(location, condition),
+                None => Command::Unknown,
+            }
+        }
+        "up" => match parse_count(args.next()) {
+            Some(count) => Command::Up(count),
+            None => Command::Unknown,
+        },
+        "watch" => parse_watch(WatchKind::Write, arguments(&line, 1)),
+        "rwatch" => parse_watch(WatchKind::Read, arguments(&line, 1)),
+        "awatch" => parse_watch(WatchKind::Access, arguments(&line, 1)),
+        "whatis" => match arguments(&line, 1) {
+            "" => Command::Unknown,
+            expression => Command::Whatis(expression.to_string()),
+        },
         _ => Command::Unknown,
     }
 }
 
+/// The rest of `line` after its first `words`, with the spaces in it
+fn arguments(line: &str, words: usize) -> &str {
+    let mut rest = line.trim_start();
+    for _ in 0..words {
+        rest = rest
+            .split_once(char::is_whitespace)
+            .map_or("", |(_, rest)| rest.trim_start());
+    }
+    rest.trim_end()
+}
+
+/// Parses an address of hexadecimal digits, anything else is an expression
+fn parse_address(text: &str) -> Address {
+    match u64::from_str_radix(text, 16) {
+        Ok(address) => Address::Value(address),
+        Err(_) => Address::Expression(text.to_string()),
+    }
+}
+
+/// Parses the location of 'break'. A number is a line of the current file like for
+/// 'list', an address is given as `*0x401126`.
+fn parse_break_location(location: &str) -> Option<BreakLocation> {
+    if let Some(expression) = location.strip_prefix('*') {
+        let expression = expression.trim();
+        if let Some(address) = expression
+            .strip_prefix("0x")
+            .and_then(|digits| u64::from_str_radix(digits, 16).ok())
+        {
+            return Some(BreakLocation::Address(address));
+        }
+        return match expression {
+            "" => None,
+            expression => Some(BreakLocation::Expression(expression.to_string())),
+        };
+    }
+    if location.starts_with(['+', '-']) {
+        return location.parse().ok().map(BreakLocation::Offset);
+    }
+    match location {
+        "" => None,
+        location => parse_linespec(location).map(BreakLocation::Source),
+    }
+}
+
+/// Splits the arguments of 'break' into the location and the condition after `if`
+fn split_condition(arguments: &str) -> (&str, Option<String>) {
+    match arguments.split_once(" if ") {
+        Some((location, condition)) => (location.trim_end(), Some(condition.trim().to_string())),
+        None => (arguments, None),
+    }
+}
+
+/// Parses what 'watch', 'rwatch' and 'awatch' watch, with the length after it. A number
+/// after an operator is part of the expression, like in `a + 1`.
+fn parse_watch(kind: WatchKind, arguments: &str) -> Command {
+    let (target, length) = match arguments.rsplit_once(char::is_whitespace) {
+        Some((target, length))
+            if target
+                .trim_end()
+                .ends_with(|c: char| c.is_ascii_alphanumeric() || matches!(c, '_' | ')' | ']')) =>
+        {
+            match length.parse() {
+                Ok(length) => (target.trim_end(), Some(length)),
+                Err(_) => (arguments, None),
+            }
+        }
+        _ => (arguments, None),
+    };
+    match target {
+        "" => Command::Unknown,
+        target => Command::Watch(kind, parse_address(target), length),
+    }
+}
+
+/// Parses the numbers of breakpoints of 'delete', 'disable' and 'enable'
+fn parse_numbers<'l>(args: impl Iterator<Item = &'l str>) -> Option<Vec<usize>> {
+    args.map(|number| number.parse().ok()).collect()
+}
+
+/// Parses the optional count of frames of 'up' and 'down', which defaults to one
+fn parse_count(arg: Option<&str>) -> Option<usize> {
+    match arg {
+        Some(count) => count.parse().ok(),
+        None => Some(1),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -126,6 +482,12 @@ mod tests {
         assert_eq!(command, Command::Continue);
     }
 
+    #[test]
+    fn test_checksec_command() {
+        let command = parse_command(String::from("checksec"));
+        assert_eq!(command, Command::Checksec);
+    }
+
     #[test]
     fn test_exit_command() {
         let command = parse_command(String::from("exit"));
@@ -146,7 +508,7 @@ mod tests {
         let command = parse_command(format!("memory read {}", source_address_str));
         match command {
             Command::Memory(MemoryCommand::Read(read_container)) => {
-                assert_eq!(read_container.source, source_address_hex)
+                assert_eq!(read_container.source, Address::Value(source_address_hex))
             }
             _ => unreachable!(),
         }
@@ -172,6 +534,335 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_info_commands() {
+        assert_eq!(
+            parse_command(String::from("info got")),
+            Command::Info(InfoCommand::Got)
+        );
+        assert_eq!(
+            parse_command(String::from("info plt")),
+            Command::Info(InfoCommand::Plt)
+        );
+        assert_eq!(
+            parse_command(String::from("info locals")),
+            Command::Info(InfoCommand::Locals)
+        );
+        assert_eq!(
+            parse_command(String::from("info args")),
+            Command::Info(InfoCommand::Args)
+        );
+        assert_eq!(
+            parse_command(String::from("info nothing")),
+            Command::Unknown
+        );
+        assert_eq!(parse_command(String::from("info")), Command::Unknown);
+    }
+
+    #[test]
+    fn test_print_command() {
+        assert_eq!(
+            parse_command(String::from("print counter")),
+            Command::Print(String::from("counter"))
+        );
+        assert_eq!(
+            parse_command(String::from("p  s->next[1].x + 2 ")),
+            Command::Print(String::from("s->next[1].x + 2"))
+        );
+        assert_eq!(parse_command(String::from("print")), Command::Unknown);
+    }
+
+    #[test]
+    fn test_type_commands() {
+        assert_eq!(
+            parse_command(String::from("ptype struct point *")),
+            Command::Ptype(String::from("struct point *"))
+        );
+        assert_eq!(
+            parse_command(String::from("whatis s->next")),
+            Command::Whatis(String::from("s->next"))
+        );
+        assert_eq!(parse_command(String::from("ptype")), Command::Unknown);
+    }
+
+    #[test]
+    fn test_memory_command_read_expression() {
+        assert_eq!(
+            parse_command(String::from("memory read &counter")),
+            Command::Memory(MemoryCommand::Read(ReadContainer {
+                source: Address::Expression(String::from("&counter"))
+            }))
+        );
+        assert_eq!(
+            parse_command(String::from("memory read 0x10 + $rsp")),
+            Command::Memory(MemoryCommand::Read(ReadContainer {
+                source: Address::Expression(String::from("0x10 + $rsp"))
+            }))
+        );
+    }
+
+    #[test]
+    fn test_backtrace_command() {
+        assert_eq!(parse_command(String::from("backtrace")), Command::Backtrace);
+        assert_eq!(parse_command(String::from("bt")), Command::Backtrace);
+    }
+
+    #[test]
+    fn test_break_command() {
+        assert_eq!(
+            parse_command(String::from("break *0x401a2f")),
+            Command::Break(BreakLocation::Address(0x401a2f), None)
+        );
+        assert_eq!(
+            parse_command(String::from("break 12")),
+            Command::Break(BreakLocation::Source(LineSpec::Line(12)), None)
+        );
+        assert_eq!(
+            parse_command(String::from("break add")),
+            Command::Break(
+                BreakLocation::Source(LineSpec::Function(String::from("add"))),
+                None
+            )
+        );
+        assert_eq!(
+            parse_command(String::from("break entry_point.c:12")),
+            Command::Break(
+                BreakLocation::Source(LineSpec::FileLine(String::from("entry_point.c"), 12)),
+                None
+            )
+        );
+        assert_eq!(
+            parse_command(String::from("break *$pc + 4")),
+            Command::Break(BreakLocation::Expression(String::from("$pc + 4")), None)
+        );
+        assert_eq!(
+            parse_command(String::from("break +2")),
+            Command::Break(BreakLocation::Offset(2), None)
+        );
+        assert_eq!(
+            parse_command(String::from("break -1")),
+            Command::Break(BreakLocation::Offset(-1), None)
+        );
+        assert_eq!(parse_command(String::from("break")), Command::Unknown);
+        assert_eq!(parse_command(String::from("break 12zz")), Command::Unknown);
+        assert_eq!(
+            parse_command(String::from("break 401a2f")),
+            Command::Unknown
+        );
+    }
+
+    #[test]
+    fn test_conditional_and_temporary_break_commands() {
+        assert_eq!(
+            parse_command(String::from("break loop.c:8 if i == 10000")),
+            Command::Break(
+                BreakLocation::Source(LineSpec::FileLine(String::from("loop.c"), 8)),
+                Some(String::from("i == 10000"))
+            )
+        );
+        assert_eq!(
+            parse_command(String::from("tbreak main")),
+            Command::TemporaryBreak(
+                BreakLocation::Source(LineSpec::Function(String::from("main"))),
+                None
+            )
+        );
+        assert_eq!(
+            parse_command(String::from("tbreak *$pc if $rax != 0")),
+            Command::TemporaryBreak(
+                BreakLocation::Expression(String::from("$pc")),
+                Some(String::from("$rax != 0"))
+            )
+        );
+        assert_eq!(
+            parse_command(String::from("ignore 2 9999")),
+            Command::Ignore(2, 9999)
+        );
+        assert_eq!(parse_command(String::from("ignore 2")), Command::Unknown);
+        assert_eq!(
+            parse_command(String::from("condition 2 i == 10000")),
+            Command::Condition(2, Some(String::from("i == 10000")))
+        );
+        assert_eq!(
+            parse_command(String::from("condition 2")),
+            Command::Condition(2, None)
+        );
+        assert_eq!(parse_command(String::from("condition")), Command::Unknown);
+        assert_eq!(
+            parse_command(String::from("ignore main 2")),
+            Command::Unknown
+        );
+    }
+
+    #[test]
+    fn test_breakpoint_number_commands() {
+        assert_eq!(
+            parse_command(String::from("delete 1 3")),
+            Command::Delete(vec![1, 3])
+        );
+        assert_eq!(
+            parse_command(String::from("delete")),
+            Command::Delete(vec![])
+        );
+        assert_eq!(
+            parse_command(String::from("disable 2")),
+            Command::Disable(vec![2])
+        );
+        assert_eq!(
+            parse_command(String::from("enable 2 4")),
+            Command::Enable(vec![2, 4])
+        );
+        assert_eq!(parse_command(String::from("enable main")), Command::Unknown);
+        assert_eq!(
+            parse_command(String::from("info breakpoints")),
+            Command::Info(InfoCommand::Breakpoints)
+        );
+        assert_eq!(
+            parse_command(String::from("info break")),
+            Command::Info(InfoCommand::Breakpoints)
+        );
+    }
+
+    #[test]
+    fn test_break_location_description() {
+        let description = |location: &str| parse_break_location(location).unwrap().to_string();
+        assert_eq!(description("*0x401a2f"), "*0x401a2f");
+        assert_eq!(description("* 0x401a2f"), "*0x401a2f");
+        assert_eq!(description("*$pc + 4"), "*$pc + 4");
+        assert_eq!(description("+2"), "+2");
+        assert_eq!(description("-1"), "-1");
+        assert_eq!(description("main"), "main");
+        assert_eq!(description("loop.c:12"), "loop.c:12");
+        assert_eq!(description("12"), "12");
+    }
+
+    #[test]
+    fn test_hbreak_command() {
+        assert_eq!(
+            parse_command(String::from("hbreak *0x401a2f")),
+            Command::HardwareBreak(BreakLocation::Address(0x401a2f))
+        );
+        assert_eq!(
+            parse_command(String::from("hbreak main.c:7")),
+            Command::HardwareBreak(BreakLocation::Source(LineSpec::FileLine(
+                String::from("main.c"),
+                7
+            )))
+        );
+        assert_eq!(parse_command(String::from("hbreak")), Command::Unknown);
+    }
+
+    #[test]
+    fn test_watch_commands() {
+        assert_eq!(
+            parse_command(String::from("watch counter")),
+            Command::Watch(
+                WatchKind::Write,
+                Address::Expression(String::from("counter")),
+                None
+            )
+        );
+        assert_eq!(
+            parse_command(String::from("rwatch 404028 4")),
+            Command::Watch(WatchKind::Read, Address::Value(0x404028), Some(4))
+        );
+        assert_eq!(
+            parse_command(String::from("awatch values[2] 16")),
+            Command::Watch(
+                WatchKind::Access,
+                Address::Expression(String::from("values[2]")),
+                Some(16)
+            )
+        );
+        assert_eq!(
+            parse_command(String::from("watch *(p + 1)")),
+            Command::Watch(
+                WatchKind::Write,
+                Address::Expression(String::from("*(p + 1)")),
+                None
+            )
+        );
+        assert_eq!(
+            parse_command(String::from("watch a - 1")),
+            Command::Watch(
+                WatchKind::Write,
+                Address::Expression(String::from("a - 1")),
+                None
+            )
+        );
+        assert_eq!(parse_command(String::from("watch")), Command::Unknown);
+    }
+
+    #[test]
+    fn test_list_command() {
+        assert_eq!(parse_command(String::from("list")), Command::List(None));
+        assert_eq!(
+            parse_command(String::from("l 12")),
+            Command::List(Some(LineSpec::Line(12)))
+        );
+        assert_eq!(
+            parse_command(String::from("list src/main.c:40")),
+            Command::List(Some(LineSpec::FileLine(String::from("src/main.c"), 40)))
+        );
+        assert_eq!(
+            parse_command(String::from("list ns::work")),
+            Command::List(Some(LineSpec::Function(String::from("ns::work"))))
+        );
+        assert_eq!(parse_command(String::from("list 1x")), Command::Unknown);
+    }
+
+    #[test]
+    fn test_set_substitute_path_command() {
+        assert_eq!(
+            parse_command(String::from("set substitute-path /build /home/me/src")),
+            Command::Set(SetCommand::SubstitutePath(
+                String::from("/build"),
+                String::from("/home/me/src")
+            ))
+        );
+        assert_eq!(
+            parse_command(String::from("set substitute-path /build")),
+            Command::Unknown
+        );
+    }
+
+    #[test]
+    fn test_frame_commands() {
+        assert_eq!(
+            parse_command(String::from("frame 2")),
+            Command::Frame(Some(2))
+        );
+        assert_eq!(parse_command(String::from("frame")), Command::Frame(None));
+        assert_eq!(parse_command(String::from("frame x")), Command::Unknown);
+        assert_eq!(parse_command(String::from("up")), Command::Up(1));
+        assert_eq!(parse_command(String::from("up 3")), Command::Up(3));
+        assert_eq!(parse_command(String::from("down")), Command::Down(1));
+    }
+
+    #[test]
+    fn test_stepping_commands() {
+        assert_eq!(parse_command(String::from("step")), Command::Step(1));
+        assert_eq!(parse_command(String::from("s 3")), Command::Step(3));
+        assert_eq!(parse_command(String::from("next")), Command::Next(1));
+        assert_eq!(parse_command(String::from("n 2")), Command::Next(2));
+        assert_eq!(parse_command(String::from("next x")), Command::Unknown);
+        assert_eq!(parse_command(String::from("finish")), Command::Finish);
+        assert_eq!(parse_command(String::from("fin")), Command::Finish);
+        assert_eq!(
+            parse_command(String::from("stepi")),
+            Command::StepInstruction(1)
+        );
+        assert_eq!(
+            parse_command(String::from("si 4")),
+            Command::StepInstruction(4)
+        );
+        assert_eq!(
+            parse_command(String::from("ni")),
+            Command::NextInstruction(1)
+        );
+        assert_eq!(parse_command(String::from("nexti -1")), Command::Unknown);
+    }
+
     #[test]
     fn test_register_dump() {
         let command = parse_command(String::from("register dump"));
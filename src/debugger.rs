@@ -1,18 +1,81 @@
-use std::borrow::BorrowMut;
-use std::collections::HashMap;
-use std::fs::File;
-use std::path::Path;
-use std::{io, io::prelude::*, io::BufReader};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
 
 use nix::sys::ptrace;
 use nix::sys::ptrace::AddressType;
-use nix::sys::wait::waitpid;
+use nix::sys::signal::Signal;
+use nix::sys::wait::{waitpid, WaitStatus};
 use nix::unistd::Pid;
 
-use crate::breakpoint::Breakpoint;
-use crate::command::{parse_command, Command, MemoryCommand, RegisterCommand};
+use crate::backtrace::{
+    self, find_function_symbol, find_module, Frame, FunctionSymbol, Module, SharedObject,
+};
+use crate::breakpoint::{Breakpoint, UserBreakpoint};
+use crate::command::{
+    parse_command, Address, BreakLocation, Command, InfoCommand, LineSpec, MemoryCommand,
+    RegisterCommand, SetCommand,
+};
+use crate::disasm;
+use crate::dwarf;
+use crate::dwarf::abi::{return_value, Class, ReturnValue};
+use crate::dwarf::aranges::AddressIndex;
+use crate::dwarf::constants::DW_TAG_TYPEDEF;
+use crate::dwarf::die::Die;
+use crate::dwarf::error::DwarfError;
+use crate::dwarf::frame::CallFrameInfo;
+use crate::dwarf::layout::{describe_type, with_pointers};
+use crate::dwarf::line::{LineTables, SourceLocation};
+use crate::dwarf::process::ProcessContext;
+use crate::dwarf::rnglist::contains_pc;
+use crate::dwarf::scope::{
+    find_function, find_global_variable, find_type, function_entries, Scope,
+};
+use crate::dwarf::sections::DwarfSections;
+use crate::dwarf::split::SplitDwarf;
+use crate::dwarf::types::{byte_size, strip_typedefs, type_of};
+use crate::dwarf::unit::Unit;
+use crate::dwarf::unwind::RETURN_ADDRESS_REGISTER;
+use crate::dwarf::value::{self, format_variable, read_variable, ValueFormatter};
+use crate::elf::checksec::checksec;
+use crate::elf::file::ElfFile;
+use crate::elf::parser::ElfParser;
+use crate::elf::plt::{self, GotSlotState, PLT_SECTIONS};
+use crate::eval::{Environment, ExpressionEvaluator, Type, Value};
+use crate::expr::{self, Expr, ExpressionError, TypeName};
+use crate::maps::{self, MemoryMap};
 use crate::register;
 use crate::register::{RegisterKind, REGISTERS};
+use crate::source::SourceFiles;
+use crate::watchpoint::{self, DebugRegisters, WatchKind, Watchpoint};
+
+/// Number of lines `list` prints
+const LIST_LINES: usize = 10;
+/// The longest an x86-64 instruction can be, which bounds how far past a call its
+/// return address is
+const MAX_INSTRUCTION_LENGTH: u64 = 15;
+/// The flag of rflags that keeps the instruction at the pc from trapping on a hardware
+/// breakpoint
+const RESUME_FLAG: u64 = 1 << 16;
+
+/// Running the process to a location was interrupted by a breakpoint, a signal or the
+/// process exiting, which was reported
+struct Interrupted;
+
+/// What made the process trap, from the debug registers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Trap {
+    /// A breakpoint or a single step, no debug register
+    Other,
+    /// A hardware breakpoint or a watchpoint, which was reported
+    Hardware,
+    /// A watchpoint whose condition is not met, like a read watchpoint that trapped on a
+    /// write, which the process continues from
+    Ignored,
+}
 
 // TODO: Remove allow macro
 #[allow(dead_code)]
@@ -20,16 +83,114 @@ pub struct Debugger<'a> {
     path: &'a Path,
     pid: Pid,
     running: bool,
-    breakpoints: HashMap<u64, Breakpoint>,
+    /// Signal the process stopped with, delivered to it when it continues
+    pending_signal: Option<Signal>,
+    /// Breakpoints the user set, in the order they were set
+    breakpoints: Vec<UserBreakpoint>,
+    /// The int3 instructions inserted into the process, at the locations of the enabled
+    /// breakpoints and where stepping runs to
+    breakpoint_sites: HashMap<u64, Breakpoint>,
+    /// Hardware breakpoints and watchpoints, in the order they were set
+    watchpoints: Vec<Watchpoint<'a>>,
+    debug_registers: DebugRegisters,
+    /// The number of the breakpoint or watchpoint that was set last
+    last_breakpoint_number: usize,
+    /// The process stopped where no breakpoint is left, at a hardware breakpoint, a
+    /// watchpoint or a temporary breakpoint that was deleted, which is reported like a
+    /// breakpoint
+    report_stop: bool,
+    elf_file: ElfFile<'a>,
+    dwarf: DwarfSections<'a>,
+    address_index: AddressIndex<'a>,
+    line_tables: LineTables<'a>,
+    cfi: Option<CallFrameInfo<'a>>,
+    /// Function symbols of the executable, sorted by address
+    symbols: Vec<FunctionSymbol>,
+    /// Shared objects the process has mapped, `None` for files that could not be read
+    shared_objects: HashMap<PathBuf, Option<SharedObject>>,
+    /// The call stack, innermost frame first. Empty until it is unwound after a stop.
+    frames: Vec<Frame>,
+    /// Index into `frames` of the frame commands operate in, 0 is the innermost frame
+    selected_frame: usize,
+    sources: SourceFiles,
+    /// The file `list` continues to list, with the next line. `None` after the process
+    /// stopped or another frame was selected, when `list` lists around the frame.
+    listing: Option<(PathBuf, usize)>,
+    /// Variables like `$n` that expressions assign, they keep their value while the process runs
+    convenience_variables: RefCell<HashMap<String, Value<'a>>>,
 }
 
 impl<'a> Debugger<'a> {
-    pub fn new(path: &'a Path, pid: Pid) -> Self {
+    /// `elf_file` is the parsed executable at `path`, with any separate debug file merged into it.
+    /// `split_dwarf` has the split units of an executable built with split DWARF.
+    pub fn new(
+        path: &'a Path,
+        pid: Pid,
+        elf_file: ElfFile<'a>,
+        split_dwarf: &'a SplitDwarf<'a>,
+    ) -> Self {
+        let dwarf = DwarfSections::new(&elf_file).with_split_dwarf(split_dwarf);
+        let address_index = AddressIndex::new(&dwarf).unwrap_or_else(|err| {
+            eprintln!(
+                "Failed to index the addresses of the debug information: {}",
+                err
+            );
+            AddressIndex::default()
+        });
+        let line_tables = LineTables::new(&dwarf).unwrap_or_else(|err| {
+            eprintln!("Failed to read line tables: {}", err);
+            LineTables::default()
+        });
+        let cfi = CallFrameInfo::from_eh_frame(&elf_file)
+            .or_else(|_| CallFrameInfo::from_debug_frame(&elf_file))
+            .ok();
+        let mut symbols = backtrace::function_symbols(&elf_file);
+        // Calls into shared objects go through the stubs named like `printf@plt`
+        symbols.extend(
+            plt::plt_stubs(&elf_file)
+                .into_iter()
+                .map(|stub| FunctionSymbol {
+                    address: stub.address,
+                    size: stub.size,
+                    name: stub.name,
+                }),
+        );
+        symbols.sort_by_key(|symbol| symbol.address);
+        let comp_dirs = line_tables
+            .programs()
+            .iter()
+            .filter_map(|program| program.header.comp_dir.map(PathBuf::from))
+            .collect();
+        // Sources relative to the compilation directory are looked up from where we are
+        let mut search_dirs: Vec<PathBuf> = env::current_dir().into_iter().collect();
+        if let Some(dir) = path.parent() {
+            search_dirs.push(dir.to_path_buf());
+        }
+        let sources = SourceFiles::new(comp_dirs, search_dirs);
+
         Self {
             path,
             pid,
             running: true,
-            breakpoints: HashMap::new(),
+            pending_signal: None,
+            breakpoints: Vec::new(),
+            breakpoint_sites: HashMap::new(),
+            watchpoints: Vec::new(),
+            debug_registers: DebugRegisters::default(),
+            last_breakpoint_number: 0,
+            report_stop: false,
+            elf_file,
+            dwarf,
+            address_index,
+            line_tables,
+            cfi,
+            symbols,
+            shared_objects: HashMap::new(),
+            frames: Vec::new(),
+            selected_frame: 0,
+            sources,
+            listing: None,
+            convenience_variables: RefCell::new(HashMap::new()),
         }
     }
 
@@ -48,17 +209,1379 @@ impl<'a> Debugger<'a> {
         }
     }
 
+    /// Continues the process until it stops, over the breakpoints whose condition does not
+    /// hold or whose hits are ignored
     pub fn continue_execution(&mut self) {
-        self.step_over_breakpoint();
-        ptrace::cont(self.pid, None).expect("failed to continue execution");
-        wait_for_signal(self.pid);
+        self.reset_frames();
+        loop {
+            let status = self.resume();
+            if !self.trapped(status) {
+                return;
+            }
+            if self.report_stop || self.breakpoint_stops(get_pc(self.pid)) {
+                break;
+            }
+        }
+        self.print_stop_location();
+    }
+
+    /// Continues the process, over a breakpoint at the pc, and waits until it stops. The pc
+    /// is moved back onto a breakpoint it stopped at. Watchpoints that are hit are reported,
+    /// those whose condition is not met are continued from.
+    fn resume(&mut self) -> WaitStatus {
+        self.report_stop = false;
+        loop {
+            if let Some(status) = self.step_over_breakpoint() {
+                return status;
+            }
+            ptrace::cont(self.pid, self.pending_signal.take())
+                .expect("failed to continue execution");
+            let status = wait_for_signal(self.pid);
+            if let WaitStatus::Stopped(_, Signal::SIGTRAP) = status {
+                match self.check_debug_registers() {
+                    Trap::Other => self.rewind_to_breakpoint(),
+                    Trap::Hardware => {}
+                    Trap::Ignored => continue,
+                }
+            }
+            return status;
+        }
+    }
+
+    /// Whether the process stopped with a trap, for a breakpoint or a single step. Any
+    /// other stop is reported, and a signal it stopped with is passed on when it continues.
+    fn trapped(&mut self, status: WaitStatus) -> bool {
+        match status {
+            WaitStatus::Exited(_, code) => println!("Process exited with code {}", code),
+            WaitStatus::Signaled(_, signal, _) => println!("Process killed by {}", signal),
+            WaitStatus::Stopped(_, Signal::SIGTRAP) => return true,
+            WaitStatus::Stopped(_, signal) => {
+                println!("Process received {}", signal);
+                self.pending_signal = Some(signal);
+            }
+            _ => {}
+        }
+        false
+    }
+
+    /// Runs to the next line of source `count` times. `step` enters the functions that are
+    /// called, `next` runs over them with `over_calls` set, and over the functions inlined
+    /// into the line as well.
+    pub fn step_lines(&mut self, count: usize, over_calls: bool) {
+        self.reset_frames();
+        let function = self.function_at_pc();
+        for _ in 0..count {
+            if self.step_line(over_calls).is_err() {
+                return;
+            }
+        }
+        self.print_step_location(function);
+    }
+
+    /// Runs to where a statement of another line of source begins, see [`Self::step_lines`].
+    /// Code without line information is run until it returns to code with it.
+    fn step_line(&mut self, over_calls: bool) -> Result<(), Interrupted> {
+        let pc = get_pc(self.pid);
+        let start = match self.line_position(pc) {
+            Some((location, _)) => location,
+            None => {
+                println!(
+                    "Single stepping until exit from function {}, which has no line number information.",
+                    self.function_name(pc)
+                );
+                return self.return_to_line_information();
+            }
+        };
+        let function = self.function_start(pc);
+        let inline_depth = self.inline_depth(pc);
+        loop {
+            let sp = get_sp(self.pid);
+            if let Some(return_address) = self.step_instruction()? {
+                let entry = get_pc(self.pid);
+                if !over_calls && self.line_position(entry).is_some() {
+                    return self.step_past_prologue(entry);
+                }
+                self.run_to(return_address, Some(sp))?;
+            }
+
+            let mut pc = get_pc(self.pid);
+            if self.line_position(pc).is_none() {
+                self.return_to_line_information()?;
+                pc = get_pc(self.pid);
+            }
+            let (location, is_statement) = match self.line_position(pc) {
+                Some(position) => position,
+                None => continue,
+            };
+            if !is_statement {
+                continue;
+            }
+            let same_function = self.function_start(pc) == function;
+            if same_function && location.path == start.path && location.line == start.line {
+                continue;
+            }
+            if over_calls && same_function && self.inline_depth(pc) > inline_depth {
+                continue;
+            }
+            return Ok(());
+        }
+    }
+
+    /// Executes `count` instructions and prints each instruction the pc reaches. `nexti`
+    /// runs over calls with `over_calls` set, to the instruction after the call.
+    pub fn step_instructions(&mut self, count: usize, over_calls: bool) {
+        self.reset_frames();
+        for _ in 0..count {
+            let pc = get_pc(self.pid);
+            let instruction = disasm::decode(&self.read_code(pc), pc);
+            let result = if over_calls && instruction.is_call {
+                let sp = get_sp(self.pid);
+                self.run_to(pc + instruction.length as u64, Some(sp))
+            } else {
+                self.step_instruction().map(|_| ())
+            };
+            if result.is_err() {
+                return;
+            }
+            self.print_instruction(get_pc(self.pid));
+        }
+    }
+
+    /// Runs until the selected frame returns, and prints the value it returns. The frame
+    /// of an inlined function is stepped out of instead, its value is not known.
+    pub fn finish(&mut self) {
+        self.unwind_stack();
+        let index = self.selected_frame;
+        let (frame, caller) = match (self.frames.get(index), self.frames.get(index + 1)) {
+            (Some(frame), Some(caller)) => (frame.clone(), caller.clone()),
+            _ => {
+                println!("\"finish\" not meaningful in the outermost frame.");
+                return;
+            }
+        };
+        let (description, scope) = match self.with_modules(|debugger, modules| {
+            let scope = find_module(modules, frame.lookup_pc())
+                .and_then(|module| debugger.frame_scope(&frame, module))
+                .map(|scope| scope.outer(frame.inline_depth));
+            (debugger.describe_frame(index, &frame, modules), scope)
+        }) {
+            Some(frame) => frame,
+            None => return,
+        };
+        println!("Run till exit from {}", description);
+
+        let result = match scope.as_ref().and_then(|scope| scope.inlined_subroutine()) {
+            Some(subroutine) => {
+                let subroutine = subroutine.clone();
+                let unit = &scope.as_ref().unwrap().unit;
+                self.step_out_of_inlined(&frame, unit, &subroutine)
+            }
+            None => self.run_to(caller.pc, caller.registers.sp()),
+        };
+        if result.is_err() {
+            return;
+        }
+        self.reset_frames();
+        self.print_selected_frame();
+        if let Some(scope) = scope.filter(|scope| scope.inlined_subroutine().is_none()) {
+            self.print_return_value(&scope.unit, &scope.function);
+        }
+    }
+
+    /// Runs from the code of the frame of an inlined function until the pc leaves the
+    /// inlined subroutine, over the calls it makes. A frame further out is returned to first.
+    fn step_out_of_inlined(
+        &mut self,
+        frame: &Frame,
+        unit: &Unit<'a>,
+        subroutine: &Die<'a>,
+    ) -> Result<(), Interrupted> {
+        if frame.pc != get_pc(self.pid) {
+            self.run_to(frame.pc, frame.registers.sp())?;
+        }
+        let load_address = self.load_address();
+        loop {
+            let sp = get_sp(self.pid);
+            if let Some(return_address) = self.step_instruction()? {
+                self.run_to(return_address, Some(sp))?;
+            }
+            let pc = get_pc(self.pid).wrapping_sub(load_address);
+            if !contains_pc(unit, subroutine, pc).unwrap_or(false) {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Prints the value `function` returned, from where the System V ABI puts it
+    fn print_return_value(&self, unit: &Unit<'a>, function: &Die<'a>) {
+        let context = ProcessContext::new(self.pid, self.load_address());
+        let value = type_of(unit, function).and_then(|return_type| {
+            let size = match strip_typedefs(unit, return_type.clone())? {
+                Some(stripped) => byte_size(unit, &stripped)?.unwrap_or(0) as usize,
+                None => return Ok(None),
+            };
+            let bytes = match return_value(unit, &context, return_type.as_ref())? {
+                ReturnValue::Void => return Ok(None),
+                ReturnValue::Registers(parts) => {
+                    let mut integers = [RegisterKind::Rax, RegisterKind::Rdx]
+                        .into_iter()
+                        .map(|reg| register::get_register_value(self.pid, reg).to_le_bytes());
+                    let mut sse = (0..2).map(|index| register::get_xmm_register(self.pid, index));
+                    let mut bytes = vec![0; size];
+                    for part in parts {
+                        let register = match part.class {
+                            Class::Integer => integers.next().map(|value| value.to_vec()),
+                            Class::Sse => sse.next().map(|value| value.to_vec()),
+                        };
+                        let (start, end) =
+                            (part.offset as usize, (part.offset + part.size) as usize);
+                        if let (Some(register), Some(target)) =
+                            (register, bytes.get_mut(start..end))
+                        {
+                            target.copy_from_slice(&register[..end - start]);
+                        }
+                    }
+                    bytes
+                }
+                ReturnValue::X87 => register::get_st0_register(self.pid)[..size].to_vec(),
+                ReturnValue::Memory => {
+                    let address = register::get_register_value(self.pid, RegisterKind::Rax);
+                    value::read_memory(&context, address, size)?
+                }
+            };
+            ValueFormatter::new(unit, &context)
+                .format(return_type.as_ref(), &bytes)
+                .map(Some)
+        });
+        match value {
+            Ok(Some(value)) => println!("Value returned is {}", value),
+            Ok(None) => {}
+            Err(err) => println!("Cannot read the returned value: {}", err),
+        }
+    }
+
+    /// Continues the process until it reaches `address` with a stack pointer of at least
+    /// `sp`, which skips where deeper, recursive calls reach it. A temporary breakpoint is
+    /// set at `address` unless there already is one.
+    fn run_to(&mut self, address: u64, sp: Option<u64>) -> Result<(), Interrupted> {
+        let temporary = !self.breakpoint_sites.contains_key(&address);
+        if temporary {
+            let mut breakpoint = Breakpoint::new(self.pid, address);
+            if let Err(err) = breakpoint.enable() {
+                println!(
+                    "Cannot insert breakpoint at 0x{:x}: {}",
+                    address,
+                    err.desc()
+                );
+                return Err(Interrupted);
+            }
+            self.breakpoint_sites.insert(address, breakpoint);
+        }
+        let (result, alive) = loop {
+            let status = self.resume();
+            if !self.trapped(status) {
+                break (Err(Interrupted), matches!(status, WaitStatus::Stopped(..)));
+            }
+            let pc = get_pc(self.pid);
+            if pc != address {
+                if self.report_stop || self.breakpoint_stops(pc) {
+                    break (Err(Interrupted), true);
+                }
+                continue;
+            }
+            if sp.is_none_or(|sp| get_sp(self.pid) >= sp) {
+                break (Ok(()), true);
+            }
+        };
+        if temporary {
+            let breakpoint = self.breakpoint_sites.remove(&address);
+            // The breakpoint is gone with the process if it exited
+            if let Some(mut breakpoint) = breakpoint.filter(|_| alive) {
+                breakpoint.disable().expect("Failed to remove breakpoint");
+            }
+        }
+        if result.is_err() && alive {
+            self.print_stop_location();
+        }
+        result
+    }
+
+    /// Steps from the entry of the function at `entry` to where its body begins, over the
+    /// calls the prologue makes. The body may not be reached when the function returns
+    /// early, stepping stops in the caller then.
+    fn step_past_prologue(&mut self, entry: u64) -> Result<(), Interrupted> {
+        let load_address = self.load_address();
+        let symbol = match find_function_symbol(&self.symbols, entry - load_address) {
+            Some(symbol) => symbol,
+            None => return Ok(()),
+        };
+        let code = load_address + symbol.address..load_address + symbol.address + symbol.size;
+        let body = match self.line_tables.find_prologue_end(
+            &self.address_index,
+            symbol.address,
+            symbol.address + symbol.size,
+        ) {
+            Some(body) => load_address + body,
+            None => return Ok(()),
+        };
+        let entry_sp = get_sp(self.pid);
+        loop {
+            let (pc, sp) = (get_pc(self.pid), get_sp(self.pid));
+            if pc == body || !code.contains(&pc) || sp > entry_sp {
+                return Ok(());
+            }
+            if let Some(return_address) = self.step_instruction()? {
+                self.run_to(return_address, Some(sp))?;
+            }
+        }
+    }
+
+    /// Runs until the code without line information the pc is in returns to a function
+    /// with it. Without such a caller, like after `main` returned, the process continues.
+    fn return_to_line_information(&mut self) -> Result<(), Interrupted> {
+        self.reset_frames();
+        self.unwind_stack();
+        let caller = self
+            .frames
+            .iter()
+            .skip(1)
+            .filter(|frame| !frame.is_signal_frame)
+            .find(|frame| self.line_position(frame.lookup_pc()).is_some())
+            .map(|frame| (frame.pc, frame.registers.sp()));
+        self.reset_frames();
+        match caller {
+            Some((address, sp)) => self.run_to(address, sp),
+            None => {
+                let status = self.resume();
+                if self.trapped(status) {
+                    self.print_stop_location();
+                }
+                Err(Interrupted)
+            }
+        }
+    }
+
+    /// The line of source of the code at `pc`, and whether a statement of it begins at
+    /// `pc`. `None` for code without line information, like that of shared objects.
+    fn line_position(&self, pc: u64) -> Option<(SourceLocation, bool)> {
+        let pc = pc.checked_sub(self.load_address())?;
+        let (program, row) = self.line_tables.find_indexed_row(&self.address_index, pc)?;
+        let location = SourceLocation {
+            path: program.file_path(row.file)?,
+            line: row.line,
+            column: row.column,
+        };
+        Some((location, program.is_statement_start(pc)))
+    }
+
+    /// The address of the symbol of the function of the executable `pc` is in
+    fn function_start(&self, pc: u64) -> Option<u64> {
+        let pc = pc.checked_sub(self.load_address())?;
+        find_function_symbol(&self.symbols, pc).map(|symbol| symbol.address)
+    }
+
+    /// The name of the function `pc` is in, of the executable or a shared object
+    fn function_name(&mut self, pc: u64) -> String {
+        self.with_modules(|_, modules| {
+            find_module(modules, pc)
+                .and_then(|module| module.function_name(pc))
+                .map(String::from)
+        })
+        .flatten()
+        .unwrap_or_else(|| "??".to_string())
+    }
+
+    /// Number of functions inlined into each other at `pc`
+    fn inline_depth(&self, pc: u64) -> usize {
+        Scope::find(&self.address_index, pc.wrapping_sub(self.load_address()))
+            .ok()
+            .flatten()
+            .map_or(0, |scope| scope.inline_depth())
+    }
+
+    /// The function the pc is in with the number of functions inlined there, which tells
+    /// whether a step went into another function
+    fn function_at_pc(&self) -> (Option<u64>, usize) {
+        let pc = get_pc(self.pid);
+        (self.function_start(pc), self.inline_depth(pc))
+    }
+
+    /// Prints the line a step stopped at, with the frame if it is not in `function`, the
+    /// function the step started in, see [`Self::function_at_pc`]
+    fn print_step_location(&mut self, function: (Option<u64>, usize)) {
+        if self.function_at_pc() != function {
+            self.print_selected_frame();
+            return;
+        }
+        if let Some(location) = self.current_location() {
+            let line = location.line as usize;
+            self.print_source_lines(&location.path, line, line, Some(line));
+        }
+    }
+
+    /// Prints the instruction at `address` with the symbol it is in, and the symbol of the
+    /// address it refers to
+    fn print_instruction(&mut self, address: u64) {
+        let instruction = disasm::decode(&self.read_code(address), address);
+        let target = instruction
+            .target()
+            .map_or_else(String::new, |target| self.symbol_offset(target));
+        println!(
+            "0x{:016x}{}:\t{}{}",
+            address,
+            self.symbol_offset(address),
+            instruction,
+            target
+        );
+    }
+
+    /// ` <symbol+offset>` for the symbol nearest to `address`, empty without one
+    fn symbol_offset(&mut self, address: u64) -> String {
+        self.with_modules(|_, modules| {
+            let (name, offset) = find_module(modules, address)?.symbol_offset(address)?;
+            Some(match offset {
+                0 => format!(" <{}>", name),
+                offset => format!(" <{}+{}>", name, offset),
+            })
+        })
+        .flatten()
+        .unwrap_or_default()
+    }
+
+    /// The code at `address`, as much as the longest instruction takes, with the bytes
+    /// breakpoints replaced restored. Less is read at the end of the mapped memory.
+    fn read_code(&self, address: u64) -> Vec<u8> {
+        let mut code = Vec::new();
+        for word_address in (address..address + MAX_INSTRUCTION_LENGTH).step_by(8) {
+            match ptrace::read(self.pid, word_address as AddressType) {
+                Ok(word) => code.extend_from_slice(&(word as u64).to_le_bytes()),
+                Err(_) => break,
+            }
+        }
+        code.truncate(MAX_INSTRUCTION_LENGTH as usize);
+        for (breakpoint_address, breakpoint) in &self.breakpoint_sites {
+            let byte = breakpoint_address
+                .checked_sub(address)
+                .and_then(|offset| code.get_mut(offset as usize));
+            if let Some(byte) = byte.filter(|_| breakpoint.is_enabled()) {
+                *byte = breakpoint.saved_data();
+            }
+        }
+        code
+    }
+
+    /// What made the process trap, from the debug status register. The watchpoints that
+    /// are hit are reported with their value, and the value they had before.
+    fn check_debug_registers(&mut self) -> Trap {
+        let slots = match self.debug_registers.take_triggered(self.pid) {
+            Ok(slots) if !slots.is_empty() => slots,
+            _ => return Trap::Other,
+        };
+        let context = ProcessContext::new(self.pid, self.load_address());
+        let mut reported = false;
+        for watchpoint in self
+            .watchpoints
+            .iter_mut()
+            .filter(|watchpoint| watchpoint.slots.iter().any(|slot| slots.contains(slot)))
+        {
+            if watchpoint.kind == WatchKind::Execute {
+                watchpoint.hit_count += 1;
+                println!("{} {}", watchpoint.kind.description(), watchpoint.number);
+                reported = true;
+                continue;
+            }
+            let value =
+                value::read_memory(&context, watchpoint.address, watchpoint.length as usize)
+                    .unwrap_or_default();
+            let changed = value != watchpoint.value;
+            let old_value = std::mem::replace(&mut watchpoint.value, value);
+            match (watchpoint.kind, changed) {
+                // Writes of the same value trap too
+                (WatchKind::Write, false) => continue,
+                // Read watchpoints trap on writes as well
+                (WatchKind::Read, true) => continue,
+                _ => {}
+            }
+            watchpoint.hit_count += 1;
+            println!();
+            println!(
+                "{} {}: {}",
+                watchpoint.kind.description(),
+                watchpoint.number,
+                watchpoint.expression
+            );
+            println!();
+            if changed {
+                println!(
+                    "Old value = {}",
+                    format_watched(watchpoint, &old_value, &context)
+                );
+                println!(
+                    "New value = {}",
+                    format_watched(watchpoint, &watchpoint.value, &context)
+                );
+            } else {
+                println!(
+                    "Value = {}",
+                    format_watched(watchpoint, &watchpoint.value, &context)
+                );
+            }
+            reported = true;
+        }
+        self.report_stop = reported;
+        match reported {
+            true => Trap::Hardware,
+            false => Trap::Ignored,
+        }
+    }
+
+    /// Sets the resume flag when the pc is at a hardware breakpoint, so that it does not trap
+    /// again before the instruction there executes. It is set already when the process
+    /// stopped at the breakpoint, but not when it stepped onto it.
+    fn suppress_hardware_breakpoint(&self) {
+        if self.is_hardware_breakpoint(get_pc(self.pid)) {
+            let flags = register::get_register_value(self.pid, RegisterKind::Rflags);
+            register::set_register_value(self.pid, RegisterKind::Rflags, flags | RESUME_FLAG);
+        }
+    }
+
+    /// Moves the pc back onto a breakpoint that was hit, as it is past the int3 instruction
+    fn rewind_to_breakpoint(&self) {
+        let address = get_pc(self.pid) - 1;
+        if self.is_breakpoint(address) {
+            set_pc(self.pid, address);
+        }
+    }
+
+    /// Prints the source location of a breakpoint or watchpoint that was hit, with the
+    /// surrounding lines, or the instruction there for code without line information
+    fn print_stop_location(&mut self) {
+        let address = get_pc(self.pid);
+        let at_breakpoint =
+            self.breakpoint_sites.contains_key(&address) || self.is_hardware_breakpoint(address);
+        if !self.report_stop && !at_breakpoint {
+            return;
+        }
+
+        let location = match self
+            .line_tables
+            .find_indexed_location(&self.address_index, address - self.load_address())
+        {
+            Some(location) => location,
+            None => {
+                self.print_instruction(address);
+                return;
+            }
+        };
+        println!("Stopped at {}:{}", location.path.display(), location.line);
+        let line = location.line as usize;
+        self.print_source_lines(&location.path, line.saturating_sub(3), line + 3, Some(line));
+    }
+
+    /// Prints the lines `first..=last` of the source file recorded as `path`, marking
+    /// `current_line` and the lines with breakpoints. Returns the number of lines of the
+    /// file, `None` if it is not found.
+    fn print_source_lines(
+        &mut self,
+        path: &Path,
+        first: usize,
+        last: usize,
+        current_line: Option<usize>,
+    ) -> Option<usize> {
+        let breakpoint_lines = self.breakpoint_lines(path);
+        let source = self.sources.lines(path)?;
+        print_source(&source, first, last, current_line, &breakpoint_lines);
+        Some(source.len())
+    }
+
+    /// Lines of the source file recorded as `path` with an enabled breakpoint
+    fn breakpoint_lines(&self, path: &Path) -> HashSet<usize> {
+        let load_address = self.load_address();
+        self.breakpoint_sites
+            .iter()
+            .filter(|(_, breakpoint)| breakpoint.is_enabled())
+            .filter_map(|(address, _)| {
+                self.line_tables
+                    .find_indexed_location(&self.address_index, address - load_address)
+            })
+            .filter(|location| location.path == path)
+            .map(|location| location.line as usize)
+            .collect()
+    }
+
+    /// Lists the source around `spec`, or continues the last listing without one
+    pub fn list_source(&mut self, spec: Option<LineSpec>) {
+        let current = self.current_location();
+        let (path, first) = match spec {
+            None => match self.listing.take() {
+                Some(listing) => listing,
+                None => match current.clone().or_else(|| self.function_location("main")) {
+                    Some(location) => (location.path, centered(location.line as usize)),
+                    None => {
+                        println!("No current source file.");
+                        return;
+                    }
+                },
+            },
+            Some(LineSpec::Line(line)) => {
+                let path = self
+                    .listing
+                    .take()
+                    .map(|(path, _)| path)
+                    .or_else(|| current.clone().map(|location| location.path));
+                match path {
+                    Some(path) => (path, centered(line as usize)),
+                    None => {
+                        println!("No current source file.");
+                        return;
+                    }
+                }
+            }
+            Some(LineSpec::FileLine(file, line)) => {
+                match self.line_tables.find_file(Path::new(&file)) {
+                    Some(path) => (path, centered(line as usize)),
+                    None => {
+                        println!("No source file named {}.", file);
+                        return;
+                    }
+                }
+            }
+            Some(LineSpec::Function(name)) => match self.function_location(&name) {
+                Some(location) => (location.path, centered(location.line as usize)),
+                None => {
+                    println!("Function \"{}\" not defined.", name);
+                    return;
+                }
+            },
+        };
+
+        let current_line = current
+            .filter(|location| location.path == path)
+            .map(|location| location.line as usize);
+        let last = first + LIST_LINES - 1;
+        match self.sources.lines(&path).map(|source| source.len()) {
+            None => println!("Cannot find source file {}.", path.display()),
+            Some(len) if first > len => println!(
+                "Line number {} out of range; \"{}\" has {} lines.",
+                first,
+                path.display(),
+                len
+            ),
+            Some(_) => {
+                self.print_source_lines(&path, first, last, current_line);
+                self.listing = Some((path, last + 1));
+            }
+        }
+    }
+
+    /// The source location of the selected frame
+    fn current_location(&mut self) -> Option<SourceLocation> {
+        self.unwind_stack();
+        let index = self.selected_frame;
+        self.with_modules(|debugger, modules| {
+            let frame = debugger.frames.get(index)?;
+            let module = find_module(modules, frame.lookup_pc())?;
+            debugger.frame_location(frame, module)
+        })
+        .flatten()
+    }
+
+    /// The source location of where the function `name` is entered
+    fn function_location(&self, name: &str) -> Option<SourceLocation> {
+        let entry = *function_entries(&self.dwarf, name).ok()?.first()?;
+        self.line_tables
+            .find_indexed_location(&self.address_index, entry.address)
+    }
+
+    /// Makes source files whose path starts with `from` be looked up in `to` instead
+    pub fn substitute_path(&mut self, from: &str, to: &str) {
+        self.sources
+            .add_substitution(PathBuf::from(from), PathBuf::from(to));
+    }
+
+    /// Sets a breakpoint at `location`, see [`BreakLocation`], which only stops where
+    /// `condition` is true. It has a location at each address `location` is found at. A
+    /// temporary breakpoint is deleted when the process stops at it.
+    pub fn set_breakpoint(
+        &mut self,
+        location: BreakLocation,
+        condition: Option<String>,
+        temporary: bool,
+    ) {
+        let description = location.to_string();
+        let mut addresses = self.location_addresses(location);
+        addresses.sort_unstable();
+        addresses.dedup();
+        // Only the locations whose int3 could be written are kept
+        addresses.retain(|&address| match self.insert_breakpoint_site(address) {
+            Ok(()) => true,
+            Err(err) => {
+                println!(
+                    "Cannot insert breakpoint at 0x{:x}: {}",
+                    address,
+                    err.desc()
+                );
+                false
+            }
+        });
+        if addresses.is_empty() {
+            return;
+        }
+        let number = self.next_breakpoint_number();
+        let kind = match temporary {
+            true => "temporary breakpoint",
+            false => "breakpoint",
+        };
+        match addresses.as_slice() {
+            [address] => println!(
+                "Set {} {} at address 0x{:016x}{}",
+                kind,
+                number,
+                address,
+                self.describe_address(*address)
+            ),
+            _ => {
+                println!("Set {} {} at {} locations:", kind, number, addresses.len());
+                for (index, address) in addresses.iter().enumerate() {
+                    println!(
+                        "  {}.{} at address 0x{:016x}{}",
+                        number,
+                        index + 1,
+                        address,
+                        self.describe_address(*address)
+                    );
+                }
+            }
+        }
+        for &address in &addresses {
+            let others: Vec<String> = self
+                .breakpoints
+                .iter()
+                .filter(|breakpoint| breakpoint.addresses.contains(&address))
+                .map(|breakpoint| breakpoint.number.to_string())
+                .collect();
+            if !others.is_empty() {
+                println!(
+                    "Note: breakpoint {} also set at address 0x{:016x}.",
+                    others.join(", "),
+                    address
+                );
+            }
+        }
+
+        let mut breakpoint = UserBreakpoint::new(number, description, addresses);
+        breakpoint.condition = condition;
+        breakpoint.temporary = temporary;
+        self.breakpoints.push(breakpoint);
+    }
+
+    /// The number of the next breakpoint or watchpoint that is set
+    fn next_breakpoint_number(&mut self) -> usize {
+        self.last_breakpoint_number += 1;
+        self.last_breakpoint_number
+    }
+
+    /// Inserts the int3 at `address` when an enabled breakpoint has a location there, and
+    /// removes it when none has
+    fn update_breakpoint_site(&mut self, address: u64) -> nix::Result<()> {
+        let needed = self
+            .breakpoints
+            .iter()
+            .any(|breakpoint| breakpoint.enabled && breakpoint.addresses.contains(&address));
+        match (needed, self.breakpoint_sites.contains_key(&address)) {
+            (true, false) => self.insert_breakpoint_site(address)?,
+            (false, true) => {
+                if let Some(mut site) = self.breakpoint_sites.remove(&address) {
+                    site.disable()?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Writes the int3 at `address` unless there is one already. The site is only kept once
+    /// it is written, which fails at addresses that are not mapped.
+    fn insert_breakpoint_site(&mut self, address: u64) -> nix::Result<()> {
+        if !self.breakpoint_sites.contains_key(&address) {
+            let mut site = Breakpoint::new(self.pid, address);
+            site.enable()?;
+            self.breakpoint_sites.insert(address, site);
+        }
+        Ok(())
+    }
+
+    /// Makes breakpoint `number` stop only when `condition` is true, or always without one
+    pub fn set_breakpoint_condition(&mut self, number: usize, condition: Option<String>) {
+        let breakpoint = match self
+            .breakpoints
+            .iter_mut()
+            .find(|breakpoint| breakpoint.number == number)
+        {
+            Some(breakpoint) => breakpoint,
+            None => {
+                println!("No breakpoint number {}.", number);
+                return;
+            }
+        };
+        if condition.is_none() && breakpoint.condition.is_some() {
+            println!("Breakpoint {} now unconditional.", number);
+        }
+        breakpoint.condition = condition;
+    }
+
+    /// Makes breakpoint `number` continue from its next `count` hits
+    pub fn ignore_breakpoint(&mut self, number: usize, count: usize) {
+        let breakpoint = match self
+            .breakpoints
+            .iter_mut()
+            .find(|breakpoint| breakpoint.number == number)
+        {
+            Some(breakpoint) => breakpoint,
+            None => {
+                println!("No breakpoint number {}.", number);
+                return;
+            }
+        };
+        breakpoint.ignore_count = count;
+        match count {
+            0 => println!("Will stop next time breakpoint {} is reached.", number),
+            1 => println!("Will ignore next crossing of breakpoint {}.", number),
+            count => println!(
+                "Will ignore next {} crossings of breakpoint {}.",
+                count, number
+            ),
+        }
+    }
+
+    /// Deletes the breakpoints and watchpoints `numbers`, all of them without numbers
+    pub fn delete_breakpoints(&mut self, numbers: &[usize]) {
+        for number in self.selected_breakpoints(numbers) {
+            if let Some(index) = self
+                .breakpoints
+                .iter()
+                .position(|breakpoint| breakpoint.number == number)
+            {
+                let breakpoint = self.breakpoints.remove(index);
+                for address in breakpoint.addresses {
+                    if let Err(err) = self.update_breakpoint_site(address) {
+                        println!(
+                            "Cannot remove breakpoint at 0x{:x}: {}",
+                            address,
+                            err.desc()
+                        );
+                    }
+                }
+            } else if let Some(index) = self
+                .watchpoints
+                .iter()
+                .position(|watchpoint| watchpoint.number == number)
+            {
+                let watchpoint = self.watchpoints.remove(index);
+                if let Err(err) = self.debug_registers.remove(self.pid, &watchpoint.slots) {
+                    println!("Cannot delete watchpoint {}: {}", number, err);
+                }
+            } else {
+                println!("No breakpoint number {}.", number);
+            }
+        }
+    }
+
+    /// Enables or disables the breakpoints and watchpoints `numbers`, all of them without
+    /// numbers. Disabled watchpoints free their debug registers.
+    pub fn enable_breakpoints(&mut self, numbers: &[usize], enabled: bool) {
+        for number in self.selected_breakpoints(numbers) {
+            if let Some(index) = self
+                .breakpoints
+                .iter()
+                .position(|breakpoint| breakpoint.number == number)
+            {
+                let addresses = self.breakpoints[index].addresses.clone();
+                if enabled {
+                    let failed = addresses.iter().find_map(|&address| {
+                        self.insert_breakpoint_site(address)
+                            .err()
+                            .map(|err| (address, err))
+                    });
+                    if let Some((address, err)) = failed {
+                        println!(
+                            "Cannot insert breakpoint at 0x{:x}: {}",
+                            address,
+                            err.desc()
+                        );
+                        // It stays disabled, the sites only inserted for it are removed
+                        for &address in &addresses {
+                            let _ = self.update_breakpoint_site(address);
+                        }
+                        continue;
+                    }
+                }
+                self.breakpoints[index].enabled = enabled;
+                for address in addresses {
+                    if let Err(err) = self.update_breakpoint_site(address) {
+                        println!(
+                            "Cannot remove breakpoint at 0x{:x}: {}",
+                            address,
+                            err.desc()
+                        );
+                    }
+                }
+            } else if let Some(index) = self
+                .watchpoints
+                .iter()
+                .position(|watchpoint| watchpoint.number == number)
+            {
+                if let Err(err) = self.enable_watchpoint(index, enabled) {
+                    println!("Cannot enable watchpoint {}: {}", number, err);
+                }
+            } else {
+                println!("No breakpoint number {}.", number);
+            }
+        }
+    }
+
+    /// Programs the debug registers of the watchpoint at `index` when it is enabled, and
+    /// frees them when it is disabled. The value is read again, it changed meanwhile.
+    fn enable_watchpoint(&mut self, index: usize, enabled: bool) -> watchpoint::Result<()> {
+        let context = ProcessContext::new(self.pid, self.load_address());
+        let watchpoint = &mut self.watchpoints[index];
+        if watchpoint.enabled == enabled {
+            return Ok(());
+        }
+        if enabled {
+            watchpoint.slots = self.debug_registers.insert(
+                self.pid,
+                watchpoint.kind,
+                watchpoint.address,
+                watchpoint.length,
+            )?;
+            watchpoint.value =
+                value::read_memory(&context, watchpoint.address, watchpoint.length as usize)
+                    .unwrap_or_default();
+        } else {
+            self.debug_registers.remove(self.pid, &watchpoint.slots)?;
+            watchpoint.slots.clear();
+        }
+        watchpoint.enabled = enabled;
+        Ok(())
+    }
+
+    /// The numbers of the breakpoints and watchpoints a command operates on: `numbers`, or
+    /// all of them without numbers
+    fn selected_breakpoints(&self, numbers: &[usize]) -> Vec<usize> {
+        if !numbers.is_empty() {
+            return numbers.to_vec();
+        }
+        let mut all: Vec<usize> = self
+            .breakpoints
+            .iter()
+            .map(|breakpoint| breakpoint.number)
+            .chain(self.watchpoints.iter().map(|watchpoint| watchpoint.number))
+            .collect();
+        all.sort();
+        all
+    }
+
+    /// Lists the breakpoints and watchpoints with their state, where they are, their
+    /// conditions and how often they were hit
+    pub fn print_breakpoints(&mut self) {
+        let mut rows: Vec<(usize, Vec<String>)> = Vec::new();
+        for breakpoint in self.breakpoints.clone() {
+            let number = breakpoint.number.to_string();
+            let disposition = if breakpoint.temporary { "del" } else { "keep" };
+            let mut lines = Vec::new();
+            match breakpoint.addresses.as_slice() {
+                [address] => lines.push(breakpoint_row([
+                    &number,
+                    "breakpoint",
+                    disposition,
+                    yes_no(breakpoint.enabled),
+                    &format!("0x{:016x}", address),
+                    &self.describe_location(*address),
+                ])),
+                addresses => {
+                    lines.push(breakpoint_row([
+                        &number,
+                        "breakpoint",
+                        disposition,
+                        yes_no(breakpoint.enabled),
+                        "<MULTIPLE>",
+                        &breakpoint.location,
+                    ]));
+                    for (index, address) in addresses.iter().enumerate() {
+                        lines.push(breakpoint_row([
+                            &format!("{}.{}", number, index + 1),
+                            "",
+                            "",
+                            yes_no(breakpoint.enabled),
+                            &format!("0x{:016x}", address),
+                            &self.describe_location(*address),
+                        ]));
+                    }
+                }
+            }
+            if let Some(condition) = &breakpoint.condition {
+                lines.push(format!("\tstop only if {}", condition));
+            }
+            lines.extend(hit_count_line("breakpoint", breakpoint.hit_count));
+            match breakpoint.ignore_count {
+                0 => {}
+                1 => lines.push(String::from("\tWill ignore next crossing of breakpoint.")),
+                count => lines.push(format!(
+                    "\tWill ignore next {} crossings of breakpoint.",
+                    count
+                )),
+            }
+            rows.push((breakpoint.number, lines));
+        }
+        for watchpoint in self.watchpoints.clone() {
+            let (address, what) = match watchpoint.kind {
+                WatchKind::Execute => (
+                    format!("0x{:016x}", watchpoint.address),
+                    self.describe_location(watchpoint.address),
+                ),
+                _ => (String::new(), watchpoint.expression.clone()),
+            };
+            let mut lines = vec![breakpoint_row([
+                &watchpoint.number.to_string(),
+                watchpoint.kind.name(),
+                "keep",
+                yes_no(watchpoint.enabled),
+                &address,
+                &what,
+            ])];
+            let noun = match watchpoint.kind {
+                WatchKind::Execute => "breakpoint",
+                _ => "watchpoint",
+            };
+            lines.extend(hit_count_line(noun, watchpoint.hit_count));
+            rows.push((watchpoint.number, lines));
+        }
+        if rows.is_empty() {
+            println!("No breakpoints or watchpoints.");
+            return;
+        }
+        rows.sort_by_key(|(number, _)| *number);
+        println!(
+            "{}",
+            breakpoint_row(["Num", "Type", "Disp", "Enb", "Address", "What"])
+        );
+        for line in rows.into_iter().flat_map(|(_, lines)| lines) {
+            println!("{}", line);
+        }
+    }
+
+    /// Where `address` is in the list of breakpoints: the function and source line, or the
+    /// symbol for code without line information
+    fn describe_location(&mut self, address: u64) -> String {
+        let location = self.line_tables.find_indexed_location(
+            &self.address_index,
+            address.wrapping_sub(self.load_address()),
+        );
+        match location {
+            Some(location) => format!(
+                "in {} at {}:{}",
+                self.function_name(address),
+                location.path.display(),
+                location.line
+            ),
+            None => self.symbol_offset(address).trim_start().to_string(),
+        }
+    }
+
+    /// Whether the process stops at the breakpoints it reached at `address`: the condition
+    /// of one of them holds, or cannot be evaluated, and no more of its hits are ignored.
+    /// The breakpoints that stop are reported, temporary ones are deleted. The process
+    /// stops where no breakpoint of the user is, like at an int3 of its own.
+    fn breakpoint_stops(&mut self, address: u64) -> bool {
+        let reached: Vec<(usize, Option<String>)> = self
+            .breakpoints
+            .iter()
+            .filter(|breakpoint| breakpoint.enabled && breakpoint.addresses.contains(&address))
+            .map(|breakpoint| (breakpoint.number, breakpoint.condition.clone()))
+            .collect();
+        if reached.is_empty() {
+            return true;
+        }
+        let mut stops = false;
+        for (number, condition) in reached {
+            if let Some(condition) = condition {
+                // The condition is evaluated in the innermost frame
+                self.reset_frames();
+                match self.evaluate(&condition, |_, value| value.is_true()) {
+                    Ok(true) => {}
+                    Ok(false) => continue,
+                    Err(err) => println!(
+                        "Error in testing condition for breakpoint {}: {}",
+                        number, err
+                    ),
+                }
+            }
+            let breakpoint = match self
+                .breakpoints
+                .iter_mut()
+                .find(|breakpoint| breakpoint.number == number)
+            {
+                Some(breakpoint) => breakpoint,
+                None => continue,
+            };
+            if !breakpoint.hit() {
+                continue;
+            }
+            stops = true;
+            if breakpoint.temporary {
+                println!("Temporary breakpoint {}", number);
+                self.delete_breakpoints(&[number]);
+                self.report_stop = true;
+            } else {
+                println!("Breakpoint {}", number);
+            }
+        }
+        stops
+    }
+
+    /// The addresses a breakpoint at `location` is set at, none if it is not found, which
+    /// is reported
+    fn location_addresses(&mut self, location: BreakLocation) -> Vec<u64> {
+        match location {
+            BreakLocation::Address(address) => vec![address],
+            BreakLocation::Expression(text) => {
+                match self.evaluate(&text, |_, value| value.as_address()) {
+                    Ok(address) => vec![address],
+                    Err(err) => {
+                        println!("Cannot set a breakpoint at {}: {}", text, err);
+                        Vec::new()
+                    }
+                }
+            }
+            BreakLocation::Offset(offset) => match self.default_location() {
+                Some(location) => {
+                    let line = location.line.saturating_add_signed(offset).max(1);
+                    self.line_addresses(&location.path, line)
+                }
+                None => {
+                    println!("No current source file.");
+                    Vec::new()
+                }
+            },
+            BreakLocation::Source(LineSpec::Function(name)) => self.function_addresses(&name),
+            BreakLocation::Source(LineSpec::FileLine(file, line)) => {
+                self.line_addresses(Path::new(&file), line)
+            }
+            BreakLocation::Source(LineSpec::Line(line)) => match self.default_location() {
+                Some(location) => self.line_addresses(&location.path, line),
+                None => {
+                    println!("No current source file.");
+                    Vec::new()
+                }
+            },
+        }
+    }
+
+    /// The location lines without a file refer to: that of the selected frame, or `main`
+    /// before the program reached code with line information
+    fn default_location(&mut self) -> Option<SourceLocation> {
+        self.current_location()
+            .or_else(|| self.function_location("main"))
+    }
+
+    /// The source location of `address` as it is reported when a breakpoint is set there,
+    /// empty for code without line information
+    fn describe_address(&self, address: u64) -> String {
+        let location = self.line_tables.find_indexed_location(
+            &self.address_index,
+            address.wrapping_sub(self.load_address()),
+        );
+        match location {
+            Some(location) => format!(
+                ": file {}, line {}.",
+                location.path.display(),
+                location.line
+            ),
+            None => String::new(),
+        }
+    }
+
+    /// Where each instance of the function `name` is entered: after the prologue of the
+    /// out-of-line copies of the function, and where it was inlined into other functions.
+    /// Functions without debug information are found by their symbol.
+    fn function_addresses(&self, name: &str) -> Vec<u64> {
+        let entries = match function_entries(&self.dwarf, name) {
+            Ok(entries) => entries,
+            Err(err) => {
+                println!("Cannot look up function \"{}\": {}", name, err);
+                return Vec::new();
+            }
+        };
+        let load_address = self.load_address();
+        if entries.is_empty() {
+            let symbol = self.symbols.iter().find(|symbol| symbol.name == name);
+            return match symbol.map(|symbol| load_address + symbol.address) {
+                Some(address) => vec![address + self.frame_setup_length(address)],
+                None => {
+                    println!("Function \"{}\" not defined.", name);
+                    Vec::new()
+                }
+            };
+        }
+        entries
+            .into_iter()
+            .map(|entry| match entry.inlined {
+                true => entry.address,
+                false => self
+                    .line_tables
+                    .find_prologue_end(&self.address_index, entry.address, entry.end)
+                    .unwrap_or(entry.address),
+            })
+            .map(|address| load_address + address)
+            .collect()
+    }
+
+    /// The code of `line` in the files whose path ends with `path`, or of the next line with
+    /// code. Lines whose code is split, like the condition of a loop, have one address in
+    /// each function, the lowest.
+    fn line_addresses(&self, path: &Path, line: u64) -> Vec<u64> {
+        if self.line_tables.find_file(path).is_none() {
+            println!("No source file named {}.", path.display());
+            return Vec::new();
+        }
+        let mut addresses = match self.line_tables.find_addresses(path, line) {
+            Some((_, addresses)) if !addresses.is_empty() => addresses,
+            _ => {
+                println!("No line {} in file \"{}\".", line, path.display());
+                return Vec::new();
+            }
+        };
+        let mut functions = HashSet::new();
+        addresses.retain(|address| {
+            functions.insert(find_function_symbol(&self.symbols, *address).map(|f| f.address))
+        });
+        let load_address = self.load_address();
+        addresses
+            .into_iter()
+            .map(|address| load_address + address)
+            .collect()
+    }
+
+    /// Sets a hardware breakpoint at `location`, with a debug register for each address
+    pub fn set_hardware_breakpoint(&mut self, location: BreakLocation) {
+        for address in self.location_addresses(location) {
+            let slots = match self
+                .debug_registers
+                .insert(self.pid, WatchKind::Execute, address, 1)
+            {
+                Ok(slots) => slots,
+                Err(err) => {
+                    println!(
+                        "Cannot set a hardware breakpoint at 0x{:016x}: {}",
+                        address, err
+                    );
+                    continue;
+                }
+            };
+            let number = self.next_breakpoint_number();
+            println!(
+                "{} {} at address 0x{:016x}{}",
+                WatchKind::Execute.description(),
+                number,
+                address,
+                self.describe_address(address)
+            );
+            self.watchpoints.push(Watchpoint {
+                number,
+                kind: WatchKind::Execute,
+                address,
+                length: 1,
+                expression: format!("*0x{:x}", address),
+                value_type: None,
+                value: Vec::new(),
+                slots,
+                enabled: true,
+                hit_count: 0,
+            });
+        }
     }
 
-    pub fn set_breakpoint_at_address(&mut self, addr: u64) {
-        println!("Set breakpoint at address 0x{:016x}", addr);
-        let mut breakpoint = Breakpoint::new(self.pid, addr);
-        breakpoint.enable();
-        self.breakpoints.insert(addr, breakpoint);
+    /// Sets a watchpoint of `kind` on `target`, an lvalue expression or an address. The
+    /// length defaults to the size of the expression, or a word at an address.
+    pub fn set_watchpoint(&mut self, kind: WatchKind, target: Address, length: Option<u64>) {
+        let (address, value_type, expression) = match target {
+            Address::Value(address) => {
+                let size = length.unwrap_or(8);
+                let value_type = matches!(size, 1 | 2 | 4 | 8).then_some(Type::Int {
+                    size: size as usize,
+                    signed: true,
+                });
+                (address, value_type, format!("*0x{:x}", address))
+            }
+            Address::Expression(text) => {
+                match self.evaluate(&text, |_, value| {
+                    Ok((value.address, value.value_type.size()?, value.value_type))
+                }) {
+                    Ok((Some(address), size, value_type)) => {
+                        // Another length than the size of the type watches plain memory
+                        let value_type = match length {
+                            Some(length) if Some(length) != size => None,
+                            _ => Some(value_type),
+                        };
+                        (address, value_type, text)
+                    }
+                    Ok((None, ..)) => {
+                        println!("Cannot watch constant value `{}'.", text);
+                        return;
+                    }
+                    Err(err) => {
+                        println!("Cannot watch {}: {}", text, err);
+                        return;
+                    }
+                }
+            }
+        };
+        let length = match length.or_else(|| value_type.as_ref()?.size().ok()?) {
+            Some(length) if length > 0 => length,
+            _ => {
+                println!("Cannot watch {}, its length is not known.", expression);
+                return;
+            }
+        };
+        let context = ProcessContext::new(self.pid, self.load_address());
+        let value = match value::read_memory(&context, address, length as usize) {
+            Ok(value) => value,
+            Err(err) => {
+                println!("Cannot access memory at address 0x{:x}: {}", address, err);
+                return;
+            }
+        };
+        let slots = match self.debug_registers.insert(self.pid, kind, address, length) {
+            Ok(slots) => slots,
+            Err(err) => {
+                println!("Cannot watch {}: {}", expression, err);
+                return;
+            }
+        };
+        let number = self.next_breakpoint_number();
+        println!("{} {}: {}", kind.description(), number, expression);
+        self.watchpoints.push(Watchpoint {
+            number,
+            kind,
+            address,
+            length,
+            expression,
+            value_type,
+            value,
+            slots,
+            enabled: true,
+            hit_count: 0,
+        });
+    }
+
+    /// The length of the instructions setting up the frame pointer at the function at
+    /// `address`, which the breakpoints of functions without line information skip
+    fn frame_setup_length(&self, address: u64) -> u64 {
+        match ptrace::read(self.pid, address as AddressType) {
+            Ok(word) => frame_setup_length(&(word as u64).to_le_bytes()) as u64,
+            Err(_) => 0,
+        }
     }
 
     // TODO: Need proper formatting and printing here, in line with what is in the
@@ -66,18 +1589,199 @@ impl<'a> Debugger<'a> {
     pub fn dump_registers(&self) {
         for reg in REGISTERS {
             println!(
-                "{}: 0x{:016x}",
+                "{}: {}",
                 reg.name,
-                register::get_register_value(self.pid, reg.reg_kind)
+                format_register(self.frame_register_value(reg.reg_kind))
+            );
+        }
+    }
+
+    /// The value of a register in the selected frame, `None` if an outer frame did not
+    /// save it. Registers beyond the general purpose ones are the same in all frames.
+    fn frame_register_value(&self, reg: RegisterKind) -> Option<u64> {
+        let frame = match self.selected() {
+            Some(frame) => frame,
+            None => return Some(register::get_register_value(self.pid, reg)),
+        };
+        match register::get_dwarf_register_number(&reg) {
+            _ if reg == RegisterKind::Rip => Some(frame.pc),
+            Some(number) if number <= RETURN_ADDRESS_REGISTER => frame.registers.get(number),
+            _ => Some(register::get_register_value(self.pid, reg)),
+        }
+    }
+
+    fn write_register(&mut self, reg: RegisterKind, value: u64) {
+        if self.selected().is_some() {
+            println!(
+                "Registers can only be written in the innermost frame, select it with 'frame 0'"
+            );
+            return;
+        }
+        register::set_register_value(self.pid, reg, value);
+        self.reset_frames();
+    }
+
+    /// The address the executable is loaded at, which is 0 for non position
+    /// independent executables as their addresses are absolute.
+    pub fn load_address(&self) -> u64 {
+        if !self.elf_file.is_position_independent() {
+            return 0;
+        }
+        maps::read_memory_maps(self.pid)
+            .ok()
+            .and_then(|maps| maps::load_address(&maps, self.path))
+            .unwrap_or(0)
+    }
+
+    /// Prints the hardening report of the executable. The file is parsed again, since
+    /// the loaded one may have a separate debug file merged into it.
+    pub fn print_checksec(&self) {
+        let mut parser = ElfParser::new(self.path);
+        let elf_file = parser.parse_elf_file();
+        println!("{}", checksec(&elf_file));
+    }
+
+    pub fn print_plt_stubs(&self) {
+        let load_address = self.load_address();
+        for stub in plt::plt_stubs(&self.elf_file) {
+            println!(
+                "0x{:016x} {} ({})",
+                load_address + stub.address,
+                stub.name,
+                stub.section
             );
         }
     }
 
+    /// Prints each GOT slot with the value it currently holds in the process and
+    /// what that value points to, flagging slots that look overwritten.
+    pub fn print_got(&self) {
+        let maps = match maps::read_memory_maps(self.pid) {
+            Ok(maps) => maps,
+            Err(_) => {
+                println!("The program is not being run.");
+                return;
+            }
+        };
+        let load_address = self.load_address();
+        let mut library_symbols = HashMap::new();
+
+        for entry in plt::got_entries(&self.elf_file) {
+            let slot_address = load_address + entry.address;
+            let value = match ptrace::read(self.pid, slot_address as AddressType) {
+                Ok(value) => value as u64,
+                Err(_) => {
+                    println!("The program is not being run.");
+                    return;
+                }
+            };
+            let state = self.got_slot_state(value, load_address, &maps, &mut library_symbols);
+
+            let description = match &state {
+                GotSlotState::Lazy => String::from("lazy, not yet bound"),
+                GotSlotState::Unrelocated => String::from("not yet relocated"),
+                GotSlotState::Resolved {
+                    library,
+                    offset,
+                    symbols,
+                } => match symbols.iter().find(|symbol| **symbol == entry.symbol) {
+                    Some(symbol) => format!("resolved to {}!{}", library, symbol),
+                    None => match symbols.first() {
+                        Some(symbol) => format!("resolved to {}!{}", library, symbol),
+                        None => format!("resolved to {}+0x{:x}", library, offset),
+                    },
+                },
+                GotSlotState::Null => String::from("null, undefined weak symbol"),
+                GotSlotState::Unmapped => String::from("points to unmapped memory"),
+            };
+            let warning = if state.is_suspicious(&entry.symbol) {
+                " <-- possibly overwritten"
+            } else {
+                ""
+            };
+
+            println!(
+                "0x{:016x} {:<24} 0x{:016x} {}{}",
+                slot_address, entry.symbol, value, description, warning
+            );
+        }
+    }
+
+    fn got_slot_state(
+        &self,
+        value: u64,
+        load_address: u64,
+        maps: &[MemoryMap],
+        library_symbols: &mut HashMap<PathBuf, HashMap<u64, Vec<String>>>,
+    ) -> GotSlotState {
+        if value == 0 {
+            return GotSlotState::Null;
+        }
+
+        let in_plt = |load_address: u64| {
+            PLT_SECTIONS
+                .iter()
+                .filter_map(|name| self.elf_file.get_section_by_name(name))
+                .any(|section| {
+                    let start = load_address + section.address();
+                    start <= value && value < start + section.size()
+                })
+        };
+        if in_plt(load_address) {
+            return GotSlotState::Lazy;
+        }
+        // Before the dynamic loader ran, the slots of a position independent executable
+        // hold the addresses of the PLT stubs relative to the load address
+        if load_address != 0 && in_plt(0) {
+            return GotSlotState::Unrelocated;
+        }
+
+        let path = match maps::find_mapping(maps, value).and_then(|map| map.path.as_ref()) {
+            Some(path) => path,
+            None => return GotSlotState::Unmapped,
+        };
+        let library = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let is_executable = std::fs::canonicalize(self.path)
+            .map(|executable| executable == *path)
+            .unwrap_or(false);
+        if is_executable {
+            let offset = value - load_address;
+            let symbols = self
+                .elf_file
+                .dynamic_symbols
+                .iter()
+                .chain(self.elf_file.symbols.iter())
+                .filter(|symbol| symbol.value() == offset && !symbol.name.is_empty())
+                .map(|symbol| symbol.name.to_string())
+                .collect();
+            return GotSlotState::Resolved {
+                library,
+                offset,
+                symbols,
+            };
+        }
+
+        let base = maps::load_address(maps, path).unwrap_or(0);
+        let offset = value - base;
+        let symbols = library_symbols
+            .entry(path.clone())
+            .or_insert_with(|| plt::defined_dynamic_symbols(path))
+            .get(&offset)
+            .cloned()
+            .unwrap_or_default();
+        GotSlotState::Resolved {
+            library,
+            offset,
+            symbols,
+        }
+    }
+
     fn read_memory(&self, address: u64) -> u64 {
-        ptrace::read(self.pid, address as AddressType)
-            .expect("Failed to read memory")
-            .try_into()
-            .expect("The i64 address does not fit into u64")
+        ptrace::read(self.pid, address as AddressType).expect("Failed to read memory") as u64
     }
 
     // Safety: We're relying on ptrace to ensure safety here.
@@ -88,61 +1792,574 @@ impl<'a> Debugger<'a> {
         }
     }
 
-    fn step_over_breakpoint(&mut self) {
-        // -1 because execution will go past the breakpoint
-        let possible_breakpoint_location = get_pc(self.pid) - 1;
+    /// Executes the instruction at the pc, that of a breakpoint if there is one, and
+    /// returns where a call it made returns to. Stopping at a breakpoint or for another
+    /// reason than the step is reported and interrupts stepping.
+    fn step_instruction(&mut self) -> Result<Option<u64>, Interrupted> {
+        let pc = get_pc(self.pid);
+        let sp = get_sp(self.pid);
+        let breakpoint = self
+            .breakpoint_sites
+            .get_mut(&pc)
+            .filter(|breakpoint| breakpoint.is_enabled());
+        let stepped_over = breakpoint
+            .map(|breakpoint| breakpoint.disable().expect("Failed to remove breakpoint"))
+            .is_some();
+        self.suppress_hardware_breakpoint();
+        self.report_stop = false;
+        ptrace::step(self.pid, self.pending_signal.take()).expect("Failed to single step");
+        let status = wait_for_signal(self.pid);
+        if stepped_over && matches!(status, WaitStatus::Stopped(..)) {
+            if let Some(breakpoint) = self.breakpoint_sites.get_mut(&pc) {
+                breakpoint.enable().expect("Failed to insert breakpoint");
+            }
+        }
+        if !self.trapped(status) {
+            return Err(Interrupted);
+        }
+        let new_pc = get_pc(self.pid);
+        if self.check_debug_registers() == Trap::Hardware
+            || (self.is_breakpoint(new_pc) && self.breakpoint_stops(new_pc))
+            || self.is_hardware_breakpoint(new_pc)
+        {
+            self.print_stop_location();
+            return Err(Interrupted);
+        }
 
-        if self.breakpoints.contains_key(&possible_breakpoint_location) {
-            let bp = self
-                .breakpoints
-                .borrow_mut()
-                .get_mut(&possible_breakpoint_location)
-                .unwrap();
+        // A call pushes the address of the instruction after it
+        let new_sp = get_sp(self.pid);
+        if new_sp != sp.wrapping_sub(8) {
+            return Ok(None);
+        }
+        let return_address = self.read_memory(new_sp);
+        let is_call = return_address > pc
+            && return_address - pc <= MAX_INSTRUCTION_LENGTH
+            && return_address != new_pc;
+        Ok(is_call.then_some(return_address))
+    }
+
+    /// Whether there is an enabled breakpoint at `address`
+    fn is_breakpoint(&self, address: u64) -> bool {
+        self.breakpoint_sites
+            .get(&address)
+            .is_some_and(|breakpoint| breakpoint.is_enabled())
+    }
+
+    /// Whether there is a hardware breakpoint at `address`
+    fn is_hardware_breakpoint(&self, address: u64) -> bool {
+        self.watchpoints.iter().any(|watchpoint| {
+            watchpoint.kind == WatchKind::Execute && watchpoint.address == address
+        })
+    }
+
+    /// Executes the instruction of a breakpoint at the pc with the breakpoint removed,
+    /// passing on the pending signal. Returns the status of the process unless it only
+    /// stepped, e.g. when the instruction hit a watchpoint or faulted, or the process exited.
+    fn step_over_breakpoint(&mut self) -> Option<WaitStatus> {
+        let pc = get_pc(self.pid);
+        self.suppress_hardware_breakpoint();
+
+        let bp = self.breakpoint_sites.get_mut(&pc)?;
+        if !bp.is_enabled() {
+            return None;
+        }
+        bp.disable().expect("Failed to remove breakpoint");
+        ptrace::step(self.pid, self.pending_signal.take()).expect("Failed to single step");
+        let status = wait_for_signal(self.pid);
+        // The breakpoint is gone with the process if it exited
+        if matches!(status, WaitStatus::Exited(..) | WaitStatus::Signaled(..)) {
+            return Some(status);
+        }
+        bp.enable().expect("Failed to insert breakpoint");
+        match status {
+            WaitStatus::Stopped(_, Signal::SIGTRAP)
+                if self.check_debug_registers() != Trap::Hardware =>
+            {
+                None
+            }
+            status => Some(status),
+        }
+    }
+
+    /// Prints the value of an expression evaluated in the selected frame
+    pub fn print_expression(&self, text: &str) {
+        match self.expression_line(text) {
+            Ok(line) => println!("{}", line),
+            Err(ExpressionError::NoSymbol(name)) => {
+                println!("No symbol \"{}\" in current context.", name)
+            }
+            Err(ExpressionError::OptimizedOut) => println!("{} = <optimized out>", text),
+            Err(err) => println!("Cannot print {}: {}", text, err),
+        }
+    }
 
-            if bp.is_enabled() {
-                let previous_instruction_address = possible_breakpoint_location;
-                set_pc(self.pid, previous_instruction_address);
+    /// The line `print` shows for `text`, `text = value`, or only the value of an
+    /// assignment, which names what is assigned already
+    fn expression_line(&self, text: &str) -> Result<String, ExpressionError> {
+        let mut environment = FrameEnvironment::new(self)?;
+        let mut evaluator = ExpressionEvaluator::new(&mut environment);
+        let expr = evaluator.parse_text(text)?;
+        let value = evaluator.evaluate(&expr)?;
+        let value = value.format(environment.context())?;
+        Ok(match expr {
+            Expr::Assign(..) => value,
+            _ => format!("{} = {}", text, value),
+        })
+    }
+
+    /// Prints the type named `text`, or the type of the expression `text`. `ptype` describes
+    /// the members of structures and the values of enumerations, `whatis` only names it.
+    pub fn print_type(&self, text: &str, describe: bool) {
+        match self.type_description(text, describe) {
+            Ok(description) => println!("{}", description),
+            Err(ExpressionError::NoSymbol(name)) => {
+                println!("No symbol \"{}\" in current context.", name)
+            }
+            Err(err) => println!("Cannot print the type of {}: {}", text, err),
+        }
+    }
+
+    fn type_description(&self, text: &str, describe: bool) -> Result<String, ExpressionError> {
+        let mut environment = FrameEnvironment::new(self)?;
+        let name = text.trim_end_matches(|c: char| c == '*' || c.is_whitespace());
+        let type_name = TypeName {
+            name: name.to_string(),
+            pointers: text[name.len()..].matches('*').count(),
+        };
+
+        let mut evaluator = ExpressionEvaluator::new(&mut environment);
+        let mut value_type = match evaluator.resolve_type(&type_name) {
+            // `whatis` of a typedef names the type it stands for
+            Ok(Type::Dwarf(unit, Some(die))) if !describe && die.tag == DW_TAG_TYPEDEF => {
+                let target = type_of(&unit, &die)?;
+                Type::Dwarf(unit, target)
+            }
+            Ok(value_type) => value_type,
+            Err(ExpressionError::UnknownType(_)) => match evaluator.evaluate_text(text) {
+                Ok(value) => value.value_type,
+                // Functions are not values of expressions, but have a type
+                Err(ExpressionError::NoSymbol(name)) if name == text => {
+                    let unit = environment.scope.as_ref().map(|scope| scope.unit.id());
+                    match find_function(&self.dwarf, text, unit)? {
+                        Some((unit, die)) => Type::Dwarf(unit, Some(die)),
+                        None => return Err(ExpressionError::NoSymbol(name)),
+                    }
+                }
+                Err(err) => return Err(err),
+            },
+            Err(err) => return Err(err),
+        };
+        if !describe {
+            return Ok(format!("type = {}", value_type.name()?));
+        }
+
+        let mut pointers = 0;
+        while let Type::Pointer(target) = value_type {
+            value_type = *target;
+            pointers += 1;
+        }
+        match &value_type {
+            Type::Dwarf(unit, die) => Ok(describe_type(
+                unit,
+                environment.context(),
+                die.as_ref(),
+                pointers,
+            )?),
+            _ => Ok(format!(
+                "type = {}",
+                with_pointers(value_type.name()?, &"*".repeat(pointers))
+            )),
+        }
+    }
 
-                bp.disable();
-                ptrace::step(self.pid, None).expect("Failed to single step");
-                wait_for_signal(self.pid);
-                bp.enable();
+    /// Prints the local variables of the selected frame, those of the innermost block first
+    pub fn print_locals(&self) {
+        self.print_frame_variables(Scope::locals, "No locals.");
+    }
+
+    /// Prints the arguments of the selected frame
+    pub fn print_args(&self) {
+        self.print_frame_variables(Scope::parameters, "No arguments.");
+    }
+
+    /// Prints the values of the variables `variables` finds in the scope of the selected frame
+    fn print_frame_variables(
+        &self,
+        variables: fn(&Scope<'a>) -> dwarf::error::Result<Vec<Die<'a>>>,
+        none: &str,
+    ) {
+        let environment = match FrameEnvironment::new(self) {
+            Ok(environment) => environment,
+            Err(err) => {
+                println!("Cannot read the frame: {}", err);
+                return;
             }
+        };
+        let scope = match &environment.scope {
+            Some(scope) => scope,
+            None => {
+                println!("No symbol table info available.");
+                return;
+            }
+        };
+        let variables = match variables(scope) {
+            Ok(variables) => variables,
+            Err(err) => {
+                println!("Cannot read the variables of the frame: {}", err);
+                return;
+            }
+        };
+
+        let pc = environment.context.file_pc();
+        let mut printed = false;
+        for variable in variables {
+            let name = match scope.unit.name_of(&variable) {
+                Ok(Some(name)) => name,
+                _ => continue,
+            };
+            let value = format_variable(&scope.unit, &variable, &environment.context, pc)
+                .unwrap_or_else(|err| format!("<error: {}>", err));
+            println!("{} = {}", name, value);
+            printed = true;
+        }
+        if !printed {
+            println!("{}", none);
         }
     }
 
+    /// Prints the word of memory at an address, given as hexadecimal digits or an expression
+    pub fn print_memory(&self, address: Address) {
+        let address = match address {
+            Address::Value(address) => address,
+            Address::Expression(text) => {
+                match self.evaluate(&text, |_, value| value.as_address()) {
+                    Ok(address) => address,
+                    Err(err) => {
+                        println!("Cannot read memory at {}: {}", text, err);
+                        return;
+                    }
+                }
+            }
+        };
+        match ptrace::read(self.pid, address as AddressType) {
+            Ok(word) => println!("0x{:016x}", word as u64),
+            Err(err) => println!("Cannot access memory at address 0x{:x}: {}", address, err),
+        }
+    }
+
+    /// Evaluates `text` in the selected frame and passes its value to `f`, which may use
+    /// the frame to format it
+    fn evaluate<F, R>(&self, text: &str, f: F) -> Result<R, ExpressionError>
+    where
+        F: FnOnce(&FrameEnvironment<'_, 'a>, Value<'a>) -> Result<R, ExpressionError>,
+    {
+        let mut environment = FrameEnvironment::new(self)?;
+        let value = ExpressionEvaluator::new(&mut environment).evaluate_text(text)?;
+        f(&environment, value)
+    }
+
+    /// Prints the call stack of the process, innermost frame first
+    pub fn print_backtrace(&mut self) {
+        self.unwind_stack();
+        self.with_modules(|debugger, modules| {
+            for (index, frame) in debugger.frames.iter().enumerate() {
+                println!("{}", debugger.describe_frame(index, frame, modules));
+            }
+        });
+    }
+
+    /// The selected frame if it is an outer frame, whose registers were recovered by
+    /// unwinding. `None` for the innermost frame, whose registers are those of the process.
+    fn selected(&self) -> Option<&Frame> {
+        match self.selected_frame {
+            0 => None,
+            index => self.frames.get(index),
+        }
+    }
+
+    /// Forgets the call stack, as the process has run or its registers or memory changed
+    fn reset_frames(&mut self) {
+        self.frames.clear();
+        self.selected_frame = 0;
+        self.listing = None;
+    }
+
+    /// Selects the frame at `level` of the call stack and prints it
+    pub fn select_frame(&mut self, level: usize) {
+        self.unwind_stack();
+        if level >= self.frames.len() {
+            println!("No frame at level {}.", level);
+            return;
+        }
+        self.selected_frame = level;
+        self.print_selected_frame();
+    }
+
+    /// Selects the frame `count` levels further out, towards `_start`
+    pub fn frame_up(&mut self, count: usize) {
+        self.unwind_stack();
+        if self.selected_frame + 1 >= self.frames.len() {
+            println!("Initial frame selected; you cannot go up.");
+            return;
+        }
+        self.select_frame((self.selected_frame + count).min(self.frames.len() - 1));
+    }
+
+    /// Selects the frame `count` levels further in, towards the innermost frame
+    pub fn frame_down(&mut self, count: usize) {
+        if self.selected_frame == 0 {
+            println!("Bottom (innermost) frame selected; you cannot go down.");
+            return;
+        }
+        self.select_frame(self.selected_frame.saturating_sub(count));
+    }
+
+    /// Prints the selected frame with its line of source
+    pub fn print_selected_frame(&mut self) {
+        self.unwind_stack();
+        self.listing = None;
+        let index = self.selected_frame;
+        let location = self
+            .with_modules(|debugger, modules| {
+                let frame = debugger.frames.get(index)?;
+                println!("{}", debugger.describe_frame(index, frame, modules));
+                let module = find_module(modules, frame.lookup_pc())?;
+                debugger.frame_location(frame, module)
+            })
+            .flatten();
+        if let Some(location) = location {
+            let line = location.line as usize;
+            self.print_source_lines(&location.path, line, line, Some(line));
+        }
+    }
+
+    /// Unwinds the call stack into `frames`, unless it already is
+    fn unwind_stack(&mut self) {
+        if !self.frames.is_empty() {
+            return;
+        }
+        let pid = self.pid;
+        self.frames = self
+            .with_modules(|debugger, modules| {
+                let frames = backtrace::unwind(pid, register::get_register_set(pid), modules);
+                backtrace::with_inline_frames(frames, |frame| {
+                    find_module(modules, frame.lookup_pc())
+                        .and_then(|module| debugger.frame_scope(frame, module))
+                        .map_or(0, |scope| scope.inline_depth())
+                })
+            })
+            .unwrap_or_default();
+    }
+
+    /// Calls `f` with the modules of the files mapped into the process, reading the
+    /// shared objects among them that were not read before. Returns `None` if the
+    /// mapped files can not be found.
+    fn with_modules<F, R>(&mut self, f: F) -> Option<R>
+    where
+        F: FnOnce(&Self, &[Module]) -> R,
+    {
+        let maps = match maps::read_memory_maps(self.pid) {
+            Ok(maps) => maps,
+            Err(err) => {
+                println!("Cannot read the memory maps of the process: {}", err);
+                return None;
+            }
+        };
+        let executable = fs::canonicalize(self.path).unwrap_or_else(|_| self.path.to_path_buf());
+        let mapped_files: Vec<&PathBuf> = maps
+            .iter()
+            .filter(|map| map.is_executable())
+            .filter_map(|map| map.path.as_ref())
+            .filter(|path| **path != executable)
+            .collect();
+        for path in &mapped_files {
+            if !self.shared_objects.contains_key(*path) {
+                let object = SharedObject::load(path);
+                self.shared_objects.insert((*path).clone(), object);
+            }
+        }
+
+        // The modules borrow the call frame information of the shared objects
+        let shared_cfi: HashMap<&Path, CallFrameInfo> = mapped_files
+            .iter()
+            .filter_map(|path| {
+                let object = self.shared_objects.get(*path)?.as_ref()?;
+                Some((path.as_path(), object.call_frame_info()?))
+            })
+            .collect();
+        let load_address = self.load_address();
+        let mut modules = Vec::new();
+        for map in maps.iter().filter(|map| map.is_executable()) {
+            let path = match &map.path {
+                Some(path) => path,
+                None => continue,
+            };
+            let module = if *path == executable {
+                Module {
+                    path,
+                    start: map.start,
+                    end: map.end,
+                    load_bias: load_address,
+                    cfi: self.cfi.as_ref(),
+                    symbols: &self.symbols,
+                    is_executable: true,
+                }
+            } else {
+                let object = match self.shared_objects.get(path) {
+                    Some(Some(object)) => object,
+                    _ => continue,
+                };
+                Module {
+                    path,
+                    start: map.start,
+                    end: map.end,
+                    load_bias: maps::load_address(&maps, path).unwrap_or(0),
+                    cfi: shared_cfi.get(path.as_path()),
+                    symbols: object.symbols(),
+                    is_executable: false,
+                }
+            };
+            modules.push(module);
+        }
+        Some(f(self, &modules))
+    }
+
+    /// Describes a frame like `#1  0x0000555555555189 in main at /src/main.c:12`, or
+    /// with the shared object its code is in instead of the source location. Frames of
+    /// inlined functions have no address of their own and leave it out.
+    fn describe_frame(&self, index: usize, frame: &Frame, modules: &[Module]) -> String {
+        if frame.is_signal_frame {
+            return format!("#{:<2} <signal handler called>", index);
+        }
+        let pc = frame.lookup_pc();
+        let address = format!("#{:<2} 0x{:016x} in ", index, frame.pc);
+        let module = match find_module(modules, pc) {
+            Some(module) => module,
+            None => return format!("{}??", address),
+        };
+        if !module.is_executable {
+            return format!(
+                "{}{} from {}",
+                address,
+                module.function_name(pc).unwrap_or("??"),
+                module.path.display()
+            );
+        }
+
+        let scope = self.frame_scope(frame, module);
+        let mut description = match &scope {
+            Some(scope) if frame.inline_depth < scope.inline_depth() => format!("#{:<2} ", index),
+            _ => address,
+        };
+        let function = scope
+            .and_then(|scope| {
+                let scope = scope.outer(frame.inline_depth);
+                scope.unit.name_of(scope.frame_function()).ok()?
+            })
+            .or_else(|| module.function_name(pc))
+            .unwrap_or("??");
+        description.push_str(function);
+        if let Some(location) = self.frame_location(frame, module) {
+            description.push_str(&format!(
+                " at {}:{}",
+                location.path.display(),
+                location.line
+            ));
+        }
+        description
+    }
+
+    /// The scope of the code at the pc of a frame in the executable, with all functions
+    /// inlined there
+    fn frame_scope(&self, frame: &Frame, module: &Module) -> Option<Scope<'a>> {
+        if !module.is_executable {
+            return None;
+        }
+        Scope::find(&self.address_index, frame.lookup_pc() - module.load_bias).ok()?
+    }
+
+    /// The source location of the code of a frame in the executable. For the frame of a
+    /// function another one is inlined into, it is the call site of the inlined function.
+    fn frame_location(&self, frame: &Frame, module: &Module) -> Option<SourceLocation> {
+        if !module.is_executable {
+            return None;
+        }
+        if frame.inline_depth == 0 {
+            return self
+                .line_tables
+                .find_indexed_location(&self.address_index, frame.lookup_pc() - module.load_bias);
+        }
+
+        let scope = self
+            .frame_scope(frame, module)?
+            .outer(frame.inline_depth - 1);
+        let (file, line, column) = scope.call_site()?;
+        let path = self
+            .line_tables
+            .program_of_unit(scope.unit.id())?
+            .file_path(file)?;
+        Some(SourceLocation { path, line, column })
+    }
+
     fn handle_command(&mut self, line: String) {
         let command = parse_command(line);
 
         match command {
+            Command::Backtrace => self.print_backtrace(),
+            Command::Checksec => self.print_checksec(),
             Command::Continue => self.continue_execution(),
-            Command::Break(addr) => self.set_breakpoint_at_address(addr),
+            Command::Break(location, condition) => self.set_breakpoint(location, condition, false),
+            Command::TemporaryBreak(location, condition) => {
+                self.set_breakpoint(location, condition, true)
+            }
+            Command::Condition(number, condition) => {
+                self.set_breakpoint_condition(number, condition)
+            }
+            Command::Ignore(number, count) => self.ignore_breakpoint(number, count),
+            Command::Delete(numbers) => self.delete_breakpoints(&numbers),
+            Command::Disable(numbers) => self.enable_breakpoints(&numbers, false),
+            Command::Enable(numbers) => self.enable_breakpoints(&numbers, true),
+            Command::HardwareBreak(location) => self.set_hardware_breakpoint(location),
+            Command::Watch(kind, target, length) => self.set_watchpoint(kind, target, length),
+            Command::Print(expression) => self.print_expression(&expression),
+            Command::Ptype(expression) => self.print_type(&expression, true),
+            Command::Whatis(expression) => self.print_type(&expression, false),
+            Command::Frame(Some(level)) => self.select_frame(level),
+            Command::Frame(None) => self.print_selected_frame(),
+            Command::Up(count) => self.frame_up(count),
+            Command::Down(count) => self.frame_down(count),
             Command::Exit => self.running = false,
+            Command::Finish => self.finish(),
+            Command::Next(count) => self.step_lines(count, true),
+            Command::NextInstruction(count) => self.step_instructions(count, true),
+            Command::Step(count) => self.step_lines(count, false),
+            Command::StepInstruction(count) => self.step_instructions(count, false),
+            Command::List(spec) => self.list_source(spec),
+            Command::Set(SetCommand::SubstitutePath(from, to)) => self.substitute_path(&from, &to),
+            Command::Info(info_command) => match info_command {
+                InfoCommand::Args => self.print_args(),
+                InfoCommand::Breakpoints => self.print_breakpoints(),
+                InfoCommand::Got => self.print_got(),
+                InfoCommand::Locals => self.print_locals(),
+                InfoCommand::Plt => self.print_plt_stubs(),
+            },
             Command::Memory(memory_kind) => match memory_kind {
-                MemoryCommand::Read(read_container) => {
-                    println!("0x{:016x}", self.read_memory(read_container.source))
-                }
+                MemoryCommand::Read(read_container) => self.print_memory(read_container.source),
                 MemoryCommand::Write(write_container) => {
                     self.write_memory(write_container.dest, write_container.value);
+                    self.reset_frames();
                 }
             },
             Command::Register(register_command_kind) => match register_command_kind {
                 RegisterCommand::Dump => self.dump_registers(),
                 RegisterCommand::Read(read_container) => {
-                    println!(
-                        "0x{:016x}",
-                        register::get_register_value(
-                            self.pid,
-                            register::get_register_from_name(read_container.source)
-                                .expect("The reg enum was None")
-                        )
-                    );
+                    let reg = register::get_register_from_name(read_container.source)
+                        .expect("The reg enum was None");
+                    println!("{}", format_register(self.frame_register_value(reg)));
+                }
+                RegisterCommand::Write(write_container) => {
+                    self.write_register(write_container.dest, write_container.value)
                 }
-                RegisterCommand::Write(write_container) => register::set_register_value(
-                    self.pid,
-                    write_container.dest,
-                    write_container.value,
-                ),
             },
             Command::Unknown => eprintln!("Unknown command"),
         }
@@ -153,56 +2370,306 @@ impl<'a> Debugger<'a> {
     fn get_function_from_pc(_pc: u64) {}
 }
 
+/// The variables, registers and types visible in the selected frame, which expressions
+/// are evaluated against
+struct FrameEnvironment<'d, 'a> {
+    debugger: &'d Debugger<'a>,
+    context: ProcessContext,
+    /// The scope of the pc, without the functions inlined into the frame's function there
+    scope: Option<Scope<'a>>,
+}
+
+impl<'d, 'a> FrameEnvironment<'d, 'a> {
+    fn new(debugger: &'d Debugger<'a>) -> dwarf::error::Result<Self> {
+        let mut context = ProcessContext::new(debugger.pid, debugger.load_address());
+        let mut inline_depth = 0;
+        if let Some(frame) = debugger.selected() {
+            context = context.with_frame(frame);
+            inline_depth = frame.inline_depth;
+        }
+        context.set_tls_segment(&debugger.elf_file);
+
+        // The variables of a frame of an inlined function are those of its instance
+        let scope = Scope::find(&debugger.address_index, context.file_pc())?
+            .map(|scope| scope.outer(inline_depth));
+        if let Some(scope) = &scope {
+            // Without unwind information only expressions not using the CFA can be evaluated
+            if let Some(cfi) = &debugger.cfi {
+                context.set_cfa(cfi).ok();
+            }
+            context.set_function(&scope.unit, &scope.function)?;
+        }
+        Ok(Self {
+            debugger,
+            context,
+            scope,
+        })
+    }
+}
+
+impl<'d, 'a> Environment<'a> for FrameEnvironment<'d, 'a> {
+    type Context = ProcessContext;
+
+    fn context(&self) -> &ProcessContext {
+        &self.context
+    }
+
+    /// Variables of the scope hide the global variables, of which those of the unit of the
+    /// scope hide those of other units
+    fn variable(&self, name: &str) -> expr::Result<Option<Value<'a>>> {
+        let mut variable = None;
+        if let Some(scope) = &self.scope {
+            variable = scope
+                .find_variable(name)?
+                .map(|variable| (scope.unit.clone(), variable));
+        }
+        if variable.is_none() {
+            let unit = self.scope.as_ref().map(|scope| scope.unit.id());
+            variable = find_global_variable(&self.debugger.dwarf, name, unit)?;
+        }
+        let (unit, variable) = match variable {
+            Some(variable) => variable,
+            None => return Ok(None),
+        };
+
+        match read_variable(&unit, &variable, &self.context, self.context.file_pc())? {
+            Some(value) => Ok(Some(Value::new(
+                Type::Dwarf(unit, value.variable_type),
+                value.bytes,
+                value.address,
+            ))),
+            None => Err(ExpressionError::OptimizedOut),
+        }
+    }
+
+    /// The registers of the selected frame, `pc`, `sp` and `fp` name those of the pc, the
+    /// stack pointer and the frame pointer, which are pointers
+    fn register(&self, name: &str) -> expr::Result<Option<Value<'a>>> {
+        let (name, is_pointer) = match name {
+            "pc" | "rip" => ("rip", true),
+            "sp" | "rsp" => ("rsp", true),
+            "fp" | "rbp" => ("rbp", true),
+            name => (name, false),
+        };
+        let reg = match register::get_register_from_name(name.to_string()) {
+            Some(reg) => reg,
+            None => return Ok(None),
+        };
+        let value = self
+            .debugger
+            .frame_register_value(reg)
+            .ok_or(DwarfError::Unavailable("register of the outer frame"))?;
+        if is_pointer {
+            Ok(Some(Value::pointer(Type::Void, value)))
+        } else {
+            Ok(Some(Value::integer(value as i128, 8, false)))
+        }
+    }
+
+    fn find_type(&self, name: &str) -> expr::Result<Option<Type<'a>>> {
+        let unit = self.scope.as_ref().map(|scope| scope.unit.id());
+        Ok(find_type(&self.debugger.dwarf, name, unit)?
+            .map(|(unit, die)| Type::Dwarf(unit, Some(die))))
+    }
+
+    fn convenience_variable(&self, name: &str) -> Option<Value<'a>> {
+        self.debugger
+            .convenience_variables
+            .borrow()
+            .get(name)
+            .cloned()
+    }
+
+    fn set_convenience_variable(&mut self, name: &str, value: Value<'a>) {
+        self.debugger
+            .convenience_variables
+            .borrow_mut()
+            .insert(name.to_string(), value);
+    }
+}
+
+/// The length of the `endbr64`, `push %rbp` and `mov %rsp,%rbp` that functions compiled
+/// with frame pointers start with, like gdb skips for functions without line information
+fn frame_setup_length(code: &[u8]) -> usize {
+    let mut length = 0;
+    if code.starts_with(&[0xf3, 0x0f, 0x1e, 0xfa]) {
+        length += 4;
+    }
+    let code = &code[length..];
+    // `mov %rsp,%rbp` has two encodings
+    if code.starts_with(&[0x55, 0x48, 0x89, 0xe5]) || code.starts_with(&[0x55, 0x48, 0x8b, 0xec]) {
+        length += 4;
+    }
+    length
+}
+
+/// Formats the bytes of `watchpoint` by the type of what it watches
+fn format_watched(watchpoint: &Watchpoint, bytes: &[u8], context: &ProcessContext) -> String {
+    let value = watchpoint.value_type.clone().map(|value_type| {
+        Value::new(value_type, bytes.to_vec(), Some(watchpoint.address)).format(context)
+    });
+    match value {
+        Some(Ok(value)) => value,
+        _ => bytes
+            .iter()
+            .map(|byte| format!("0x{:02x}", byte))
+            .collect::<Vec<_>>()
+            .join(" "),
+    }
+}
+
+/// A row of the list of breakpoints, with the columns aligned
+fn breakpoint_row(columns: [&str; 6]) -> String {
+    let [number, kind, disposition, enabled, address, what] = columns;
+    format!(
+        "{:<7} {:<15} {:<4} {:<3} {:<18} {}",
+        number, kind, disposition, enabled, address, what
+    )
+    .trim_end()
+    .to_string()
+}
+
+/// How often a breakpoint or watchpoint was hit, nothing if it was not
+fn hit_count_line(noun: &str, hit_count: usize) -> Option<String> {
+    match hit_count {
+        0 => None,
+        1 => Some(format!("\t{} already hit 1 time", noun)),
+        count => Some(format!("\t{} already hit {} times", noun, count)),
+    }
+}
+
+fn yes_no(value: bool) -> &'static str {
+    match value {
+        true => "y",
+        false => "n",
+    }
+}
+
+/// Formats a register value of a frame, which outer frames may not have saved
+fn format_register(value: Option<u64>) -> String {
+    match value {
+        Some(value) => format!("0x{:016x}", value),
+        None => String::from("<not saved>"),
+    }
+}
+
 fn get_pc(pid: Pid) -> u64 {
     register::get_register_value(pid, RegisterKind::Rip)
 }
 
+fn get_sp(pid: Pid) -> u64 {
+    register::get_register_value(pid, RegisterKind::Rsp)
+}
+
 fn set_pc(pid: Pid, pc: u64) {
     register::set_register_value(pid, RegisterKind::Rip, pc);
 }
 
-fn wait_for_signal(pid: Pid) {
-    waitpid(pid, None).expect("Failed to waitpid()");
+fn wait_for_signal(pid: Pid) -> WaitStatus {
+    waitpid(pid, None).expect("Failed to waitpid()")
 }
 
-// TODO: Remove allowing of dead code when this is used.
-#[allow(dead_code)]
-fn print_source(file_path: &Path, line: usize, n_lines_context: usize) {
-    let start_line = if line <= n_lines_context {
-        1
-    } else {
-        line - n_lines_context
-    };
-
-    let line_diff = if line < n_lines_context {
-        n_lines_context - line
-    } else {
-        0
-    };
-
-    let end_line = line + n_lines_context + line_diff + 1;
-
-    let file = File::open(file_path).expect("Failed to open file");
-    let reader = BufReader::new(file);
-
-    for (current_line, src_line) in reader.lines().enumerate() {
-        if current_line >= start_line && current_line <= end_line {
-            if current_line == line {
-                println!("> {} {}", current_line, src_line.unwrap());
-            } else {
-                println!("  {} {}", current_line, src_line.unwrap());
-            }
-        }
+/// The first line to list so that `line` is in the middle
+fn centered(line: usize) -> usize {
+    line.saturating_sub(LIST_LINES / 2).max(1)
+}
+
+/// Prints the lines `first..=last` of `source`, see [`source_lines`]
+fn print_source(
+    source: &[String],
+    first: usize,
+    last: usize,
+    current_line: Option<usize>,
+    breakpoint_lines: &HashSet<usize>,
+) {
+    for line in source_lines(source, first, last, current_line, breakpoint_lines) {
+        println!("{}", line);
     }
 }
 
+/// Renders the lines `first..=last` of `source`, whose lines are numbered from 1. The
+/// current line is marked with `>`, lines with a breakpoint with `*`.
+fn source_lines(
+    source: &[String],
+    first: usize,
+    last: usize,
+    current_line: Option<usize>,
+    breakpoint_lines: &HashSet<usize>,
+) -> Vec<String> {
+    let first = first.max(1);
+    let last = last.min(source.len());
+    (first..=last)
+        .map(|line| {
+            format!(
+                "{}{} {} {}",
+                if current_line == Some(line) { '>' } else { ' ' },
+                if breakpoint_lines.contains(&line) {
+                    '*'
+                } else {
+                    ' '
+                },
+                line,
+                source[line - 1]
+            )
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn print_source_test() {
-        let path = Path::new("./src/main.rs");
-        print_source(path, 10, 6);
+        let source: Vec<String> = (1..=20).map(|line| format!("line {}", line)).collect();
+        let lines = source_lines(&source, 4, 8, Some(6), &HashSet::from([5, 6]));
+        assert_eq!(
+            lines,
+            vec![
+                "   4 line 4",
+                " * 5 line 5",
+                ">* 6 line 6",
+                "   7 line 7",
+                "   8 line 8",
+            ]
+        );
+
+        // Lines before the start and past the end of the file are left out
+        let lines = source_lines(&source, 0, 2, Some(1), &HashSet::new());
+        assert_eq!(lines, vec![">  1 line 1", "   2 line 2"]);
+        let lines = source_lines(&source, 19, 25, None, &HashSet::new());
+        assert_eq!(lines, vec!["   19 line 19", "   20 line 20"]);
+    }
+
+    #[test]
+    fn test_breakpoint_row() {
+        assert_eq!(
+            breakpoint_row(["Num", "Type", "Disp", "Enb", "Address", "What"]),
+            "Num     Type            Disp Enb Address            What"
+        );
+        assert_eq!(
+            breakpoint_row(["2", "hw watchpoint", "keep", "y", "", "counter"]),
+            "2       hw watchpoint   keep y                      counter"
+        );
+        assert_eq!(
+            breakpoint_row(["1.2", "", "", "n", "0x0000555555555189", ""]),
+            "1.2                          n   0x0000555555555189"
+        );
+        assert_eq!(hit_count_line("breakpoint", 0), None);
+        assert_eq!(
+            hit_count_line("watchpoint", 1).unwrap(),
+            "\twatchpoint already hit 1 time"
+        );
+    }
+
+    #[test]
+    fn test_frame_setup_length() {
+        let endbr64 = [0xf3, 0x0f, 0x1e, 0xfa];
+        let setup = [0x55, 0x48, 0x89, 0xe5];
+        assert_eq!(frame_setup_length(&[endbr64, setup].concat()), 8);
+        assert_eq!(frame_setup_length(&setup), 4);
+        assert_eq!(frame_setup_length(&endbr64), 4);
+        // `push %rbx` is not the frame pointer
+        assert_eq!(frame_setup_length(&[0x53, 0x48, 0x89, 0xe5]), 0);
     }
 }
@@ -0,0 +1,1258 @@
+use std::fmt::{self, Display};
+
+/// Mnemonics of the arithmetic instructions of opcodes `0x00..0x40` and of group 1,
+/// by the 3 bits selecting the operation
+const ARITHMETIC: [&str; 8] = ["add", "or", "adc", "sbb", "and", "sub", "xor", "cmp"];
+/// Mnemonics of the shifts and rotations of group 2
+const SHIFTS: [&str; 8] = ["rol", "ror", "rcl", "rcr", "shl", "shr", "shl", "sar"];
+/// Suffixes of `j`, `set` and `cmov` for the conditions of the low 4 bits of their opcodes
+const CONDITIONS: [&str; 16] = [
+    "o", "no", "b", "ae", "e", "ne", "be", "a", "s", "ns", "p", "np", "l", "ge", "le", "g",
+];
+
+const REGISTERS_64: [&str; 16] = [
+    "rax", "rcx", "rdx", "rbx", "rsp", "rbp", "rsi", "rdi", "r8", "r9", "r10", "r11", "r12", "r13",
+    "r14", "r15",
+];
+const REGISTERS_32: [&str; 16] = [
+    "eax", "ecx", "edx", "ebx", "esp", "ebp", "esi", "edi", "r8d", "r9d", "r10d", "r11d", "r12d",
+    "r13d", "r14d", "r15d",
+];
+const REGISTERS_16: [&str; 16] = [
+    "ax", "cx", "dx", "bx", "sp", "bp", "si", "di", "r8w", "r9w", "r10w", "r11w", "r12w", "r13w",
+    "r14w", "r15w",
+];
+/// The byte registers with a REX prefix, which replaces `ah` to `bh` with the low bytes of
+/// `rsp` to `rdi`
+const REGISTERS_8: [&str; 16] = [
+    "al", "cl", "dl", "bl", "spl", "bpl", "sil", "dil", "r8b", "r9b", "r10b", "r11b", "r12b",
+    "r13b", "r14b", "r15b",
+];
+const REGISTERS_8_LEGACY: [&str; 8] = ["al", "cl", "dl", "bl", "ah", "ch", "dh", "bh"];
+
+/// An x86-64 instruction decoded from machine code, which prints in the AT&T syntax
+/// like gdb and objdump do
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    /// Number of bytes of the instruction, 1 for code that could not be decoded
+    pub length: usize,
+    /// The instruction is a `call`, which returns to the instruction after it
+    pub is_call: bool,
+    /// The address a relative jump or call goes to
+    pub branch_target: Option<u64>,
+    /// The address a `%rip` relative memory operand refers to
+    pub memory_target: Option<u64>,
+    mnemonic: String,
+    operands: String,
+}
+
+impl Instruction {
+    /// What is printed for code that could not be decoded
+    fn bad() -> Self {
+        Self {
+            length: 1,
+            is_call: false,
+            branch_target: None,
+            memory_target: None,
+            mnemonic: "(bad)".to_string(),
+            operands: String::new(),
+        }
+    }
+
+    /// The address the instruction jumps to or accesses, which is worth naming
+    pub fn target(&self) -> Option<u64> {
+        self.branch_target.or(self.memory_target)
+    }
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.operands.is_empty() {
+            return write!(f, "{}", self.mnemonic);
+        }
+        write!(f, "{:<6} {}", self.mnemonic, self.operands)?;
+        if let Some(address) = self.memory_target {
+            write!(f, "        # {:#x}", address)?;
+        }
+        Ok(())
+    }
+}
+
+/// Decodes the instruction at the start of `code`, which is at `address`. The general
+/// purpose, SSE and system instructions compilers emit are known, others are `(bad)`.
+pub fn decode(code: &[u8], address: u64) -> Instruction {
+    let decoder = Decoder {
+        code,
+        address,
+        position: 0,
+        rex: 0,
+        operand_size: false,
+        redundant_operand_size: 0,
+        address_size: false,
+        lock: false,
+        repeat: None,
+        segment: None,
+        ignored_segment: None,
+        rip_displacement: None,
+    };
+    decoder.decode().unwrap_or_else(Instruction::bad)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Size {
+    Byte,
+    Word,
+    Dword,
+    Qword,
+}
+
+impl Size {
+    /// The suffix of mnemonics whose operands don't tell their size
+    fn suffix(self) -> char {
+        match self {
+            Size::Byte => 'b',
+            Size::Word => 'w',
+            Size::Dword => 'l',
+            Size::Qword => 'q',
+        }
+    }
+
+    fn mask(self) -> u64 {
+        match self {
+            Size::Byte => 0xff,
+            Size::Word => 0xffff,
+            Size::Dword => 0xffff_ffff,
+            Size::Qword => u64::MAX,
+        }
+    }
+
+    /// Number of bytes of the immediates of instructions of this size, which are sign
+    /// extended for 64-bit operands
+    fn immediate_length(self) -> usize {
+        match self {
+            Size::Byte => 1,
+            Size::Word => 2,
+            Size::Dword | Size::Qword => 4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Operand {
+    Register(String),
+    Memory(String),
+    Immediate(u64),
+    /// The target of a relative jump or call
+    Address(u64),
+    /// The register or memory holding where a jump or call goes to
+    Indirect(Box<Operand>),
+}
+
+impl Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operand::Register(name) => write!(f, "%{}", name),
+            Operand::Memory(memory) => write!(f, "{}", memory),
+            Operand::Immediate(value) => write!(f, "${:#x}", value),
+            Operand::Address(address) => write!(f, "{:#x}", address),
+            Operand::Indirect(operand) => write!(f, "*{}", operand),
+        }
+    }
+}
+
+/// The fields of a ModRM byte
+struct ModRm {
+    mode: u8,
+    reg: u8,
+    rm: u8,
+}
+
+struct Decoder<'c> {
+    code: &'c [u8],
+    address: u64,
+    position: usize,
+    rex: u8,
+    /// The `0x66` prefix, for 16-bit operands or selecting an SSE instruction
+    operand_size: bool,
+    /// Number of `0x66` prefixes repeated, which pad instructions like long `nop`s
+    redundant_operand_size: usize,
+    /// The `0x67` prefix, for 32-bit addresses
+    address_size: bool,
+    lock: bool,
+    /// The `0xf2` or `0xf3` prefix, unless it selected an SSE instruction
+    repeat: Option<u8>,
+    /// The `fs` or `gs` segment override of a memory operand
+    segment: Option<&'static str>,
+    /// The `cs`, `ds`, `es` or `ss` segment override, which 64-bit code ignores
+    ignored_segment: Option<&'static str>,
+    rip_displacement: Option<i64>,
+}
+
+impl<'c> Decoder<'c> {
+    fn decode(mut self) -> Option<Instruction> {
+        loop {
+            match self.peek()? {
+                0x66 => {
+                    if self.operand_size {
+                        self.redundant_operand_size += 1;
+                    }
+                    self.operand_size = true;
+                }
+                0x67 => self.address_size = true,
+                0xf0 => self.lock = true,
+                prefix @ (0xf2 | 0xf3) => self.repeat = Some(prefix),
+                0x26 => self.ignored_segment = Some("es"),
+                0x2e => self.ignored_segment = Some("cs"),
+                0x36 => self.ignored_segment = Some("ss"),
+                0x3e => self.ignored_segment = Some("ds"),
+                0x64 => self.segment = Some("fs"),
+                0x65 => self.segment = Some("gs"),
+                _ => break,
+            }
+            self.position += 1;
+        }
+        if self.peek()? & 0xf0 == 0x40 {
+            self.rex = self.byte()?;
+        }
+
+        let opcode = self.byte()?;
+        let size = self.operand_size();
+        match opcode {
+            0x0f => self.decode_two_byte(),
+            0x00..=0x3f if opcode & 7 < 6 => {
+                let mnemonic = ARITHMETIC[(opcode >> 3) as usize];
+                match opcode & 7 {
+                    0 => self.rm_reg(mnemonic, Size::Byte),
+                    1 => self.rm_reg(mnemonic, size),
+                    2 => self.reg_rm(mnemonic, Size::Byte),
+                    3 => self.reg_rm(mnemonic, size),
+                    4 => self.accumulator_immediate(mnemonic, Size::Byte),
+                    _ => self.accumulator_immediate(mnemonic, size),
+                }
+            }
+            0x50..=0x57 => {
+                let register = self.register(opcode & 7 | self.rex_b(), self.stack_size());
+                self.finish("push", vec![register], None)
+            }
+            0x58..=0x5f => {
+                let register = self.register(opcode & 7 | self.rex_b(), self.stack_size());
+                self.finish("pop", vec![register], None)
+            }
+            0x63 => {
+                let modrm = self.modrm()?;
+                let source = self.rm(&modrm, Size::Dword)?;
+                let mnemonic = if size == Size::Qword {
+                    "movslq"
+                } else {
+                    "movsxd"
+                };
+                let destination = self.register(self.reg(&modrm), size);
+                self.finish(mnemonic, vec![destination, source], None)
+            }
+            0x68 | 0x6a => {
+                let length = if opcode == 0x6a {
+                    1
+                } else {
+                    self.stack_size().immediate_length()
+                };
+                let immediate = self.immediate(length, self.stack_size())?;
+                self.finish("push", vec![immediate], None)
+            }
+            0x69 | 0x6b => {
+                let modrm = self.modrm()?;
+                let source = self.rm(&modrm, size)?;
+                let length = if opcode == 0x6b {
+                    1
+                } else {
+                    size.immediate_length()
+                };
+                let immediate = self.immediate(length, size)?;
+                let destination = self.register(self.reg(&modrm), size);
+                self.finish("imul", vec![destination, source, immediate], None)
+            }
+            0x70..=0x7f => {
+                let target = self.relative(1)?;
+                let mnemonic = format!("j{}", CONDITIONS[(opcode & 0xf) as usize]);
+                self.finish(&mnemonic, vec![target], None)
+            }
+            0x80 | 0x81 | 0x83 => {
+                let size = if opcode == 0x80 { Size::Byte } else { size };
+                let modrm = self.modrm()?;
+                let destination = self.rm(&modrm, size)?;
+                let length = if opcode == 0x81 {
+                    size.immediate_length()
+                } else {
+                    1
+                };
+                let immediate = self.immediate(length, size)?;
+                let mnemonic = ARITHMETIC[modrm.reg as usize];
+                self.finish(mnemonic, vec![destination, immediate], Some(size))
+            }
+            0x84 => self.rm_reg("test", Size::Byte),
+            0x85 => self.rm_reg("test", size),
+            0x86 => self.rm_reg("xchg", Size::Byte),
+            0x87 => self.rm_reg("xchg", size),
+            0x88 => self.rm_reg("mov", Size::Byte),
+            0x89 => self.rm_reg("mov", size),
+            0x8a => self.reg_rm("mov", Size::Byte),
+            0x8b => self.reg_rm("mov", size),
+            0x8d => {
+                let modrm = self.modrm()?;
+                if modrm.mode == 3 {
+                    return None;
+                }
+                self.reg_rm_with("lea", &modrm, size)
+            }
+            0x8f => {
+                let modrm = self.modrm()?;
+                let size = self.stack_size();
+                let destination = self.rm(&modrm, size)?;
+                self.finish("pop", vec![destination], None)
+            }
+            0x90 if self.rex_b() != 0 => {
+                let register = self.register(8, size);
+                let accumulator = self.register(0, size);
+                self.finish("xchg", vec![register, accumulator], None)
+            }
+            0x90 if self.repeat == Some(0xf3) => {
+                self.repeat = None;
+                self.finish("pause", vec![], None)
+            }
+            0x90 if self.operand_size => {
+                let register = self.register(0, Size::Word);
+                self.finish("xchg", vec![register.clone(), register], None)
+            }
+            0x90 => self.finish("nop", vec![], None),
+            0x91..=0x97 => {
+                let register = self.register(opcode & 7 | self.rex_b(), size);
+                let accumulator = self.register(0, size);
+                self.finish("xchg", vec![register, accumulator], None)
+            }
+            0x98 => {
+                let mnemonic = match size {
+                    Size::Word => "cbtw",
+                    Size::Qword => "cltq",
+                    _ => "cwtl",
+                };
+                self.finish(mnemonic, vec![], None)
+            }
+            0x99 => {
+                let mnemonic = match size {
+                    Size::Word => "cwtd",
+                    Size::Qword => "cqto",
+                    _ => "cltd",
+                };
+                self.finish(mnemonic, vec![], None)
+            }
+            0x9c => self.finish("pushf", vec![], None),
+            0x9d => self.finish("popf", vec![], None),
+            0x9e => self.finish("sahf", vec![], None),
+            0x9f => self.finish("lahf", vec![], None),
+            0xa4..=0xa7 | 0xaa..=0xaf => self.string_operation(opcode, size),
+            0xa8 => self.accumulator_immediate("test", Size::Byte),
+            0xa9 => self.accumulator_immediate("test", size),
+            0xb0..=0xb7 => {
+                let immediate = self.immediate(1, Size::Byte)?;
+                let register = self.register(opcode & 7 | self.rex_b(), Size::Byte);
+                self.finish("mov", vec![register, immediate], None)
+            }
+            0xb8..=0xbf => {
+                let register = self.register(opcode & 7 | self.rex_b(), size);
+                if size == Size::Qword {
+                    let immediate = self.immediate(8, size)?;
+                    return self.finish("movabs", vec![register, immediate], None);
+                }
+                let immediate = self.immediate(size.immediate_length(), size)?;
+                self.finish("mov", vec![register, immediate], None)
+            }
+            0xc0 | 0xc1 | 0xd0..=0xd3 => {
+                let size = if opcode & 1 == 0 { Size::Byte } else { size };
+                let modrm = self.modrm()?;
+                let destination = self.rm(&modrm, size)?;
+                // `%cl` is the count, it doesn't tell the size of a memory operand
+                let mnemonic = match destination {
+                    Operand::Memory(_) => {
+                        format!("{}{}", SHIFTS[modrm.reg as usize], size.suffix())
+                    }
+                    _ => SHIFTS[modrm.reg as usize].to_string(),
+                };
+                let mut operands = vec![destination];
+                match opcode {
+                    0xc0 | 0xc1 => operands.push(self.immediate(1, Size::Byte)?),
+                    0xd2 | 0xd3 => operands.push(Operand::Register("cl".to_string())),
+                    _ => {}
+                }
+                self.finish(&mnemonic, operands, None)
+            }
+            0xc2 => {
+                let immediate = self.immediate(2, Size::Word)?;
+                self.finish("ret", vec![immediate], None)
+            }
+            0xc3 => self.finish("ret", vec![], None),
+            0xc6 | 0xc7 => {
+                let size = if opcode == 0xc6 { Size::Byte } else { size };
+                let modrm = self.modrm()?;
+                if modrm.reg != 0 {
+                    return None;
+                }
+                let destination = self.rm(&modrm, size)?;
+                let immediate = self.immediate(size.immediate_length(), size)?;
+                self.finish("mov", vec![destination, immediate], Some(size))
+            }
+            0xc9 => self.finish("leave", vec![], None),
+            0xcc => self.finish("int3", vec![], None),
+            0xcd => {
+                let immediate = self.immediate(1, Size::Byte)?;
+                self.finish("int", vec![immediate], None)
+            }
+            0xe0..=0xe3 => {
+                let target = self.relative(1)?;
+                let mnemonic = ["loopne", "loope", "loop", "jrcxz"][(opcode & 3) as usize];
+                self.finish(mnemonic, vec![target], None)
+            }
+            0xe8 => {
+                let target = self.relative(4)?;
+                self.finish("call", vec![target], None)
+            }
+            0xe9 => {
+                let target = self.relative(4)?;
+                self.finish("jmp", vec![target], None)
+            }
+            0xeb => {
+                let target = self.relative(1)?;
+                self.finish("jmp", vec![target], None)
+            }
+            0xf4 => self.finish("hlt", vec![], None),
+            0xf5 => self.finish("cmc", vec![], None),
+            0xf6 | 0xf7 => {
+                let size = if opcode == 0xf6 { Size::Byte } else { size };
+                let modrm = self.modrm()?;
+                let operand = self.rm(&modrm, size)?;
+                let mut operands = vec![operand];
+                if modrm.reg < 2 {
+                    operands.push(self.immediate(size.immediate_length(), size)?);
+                }
+                let mnemonic = ["test", "test", "not", "neg", "mul", "imul", "div", "idiv"];
+                self.finish(mnemonic[modrm.reg as usize], operands, Some(size))
+            }
+            0xf8 => self.finish("clc", vec![], None),
+            0xf9 => self.finish("stc", vec![], None),
+            0xfa => self.finish("cli", vec![], None),
+            0xfb => self.finish("sti", vec![], None),
+            0xfc => self.finish("cld", vec![], None),
+            0xfd => self.finish("std", vec![], None),
+            0xfe => {
+                let modrm = self.modrm()?;
+                let mnemonic = ["inc", "dec"].get(modrm.reg as usize)?;
+                let operand = self.rm(&modrm, Size::Byte)?;
+                self.finish(mnemonic, vec![operand], Some(Size::Byte))
+            }
+            0xff => {
+                let modrm = self.modrm()?;
+                match modrm.reg {
+                    0 | 1 => {
+                        let operand = self.rm(&modrm, size)?;
+                        let mnemonic = ["inc", "dec"][modrm.reg as usize];
+                        self.finish(mnemonic, vec![operand], Some(size))
+                    }
+                    2 | 4 => {
+                        let operand = self.rm(&modrm, Size::Qword)?;
+                        let mnemonic = if modrm.reg == 2 { "call" } else { "jmp" };
+                        self.finish(mnemonic, vec![Operand::Indirect(Box::new(operand))], None)
+                    }
+                    6 => {
+                        let size = self.stack_size();
+                        let operand = self.rm(&modrm, size)?;
+                        self.finish("push", vec![operand], None)
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Decodes the instructions whose opcodes start with `0x0f`
+    fn decode_two_byte(mut self) -> Option<Instruction> {
+        let opcode = self.byte()?;
+        let size = self.operand_size();
+        match opcode {
+            0x05 => self.finish("syscall", vec![], None),
+            0x0b => self.finish("ud2", vec![], None),
+            0x1e if self.repeat == Some(0xf3) && matches!(self.peek(), Some(0xfa | 0xfb)) => {
+                self.repeat = None;
+                let mnemonic = if self.byte()? == 0xfa {
+                    "endbr64"
+                } else {
+                    "endbr32"
+                };
+                self.finish(mnemonic, vec![], None)
+            }
+            0x18 => {
+                let modrm = self.modrm()?;
+                let mnemonic = ["prefetchnta", "prefetcht0", "prefetcht1", "prefetcht2"]
+                    .get(modrm.reg as usize)
+                    .filter(|_| modrm.mode != 3)?;
+                let operand = self.memory(&modrm)?;
+                self.finish(mnemonic, vec![operand], None)
+            }
+            0x1f => {
+                let modrm = self.modrm()?;
+                let operand = self.rm(&modrm, size)?;
+                self.finish("nop", vec![operand], Some(size))
+            }
+            0x10 | 0x11 | 0x14 | 0x15 | 0x28 | 0x29 | 0x2e | 0x2f | 0x51 | 0x54..=0x5f => {
+                let mnemonic = self.sse_mnemonic(opcode)?;
+                let modrm = self.modrm()?;
+                let source = self.xmm_rm(&modrm)?;
+                let register = xmm(self.reg(&modrm));
+                // The odd opcodes of the moves store the register
+                if matches!(opcode, 0x11 | 0x29) {
+                    self.finish(mnemonic, vec![source, register], None)
+                } else {
+                    self.finish(mnemonic, vec![register, source], None)
+                }
+            }
+            0x2a if self.repeat.is_some() => {
+                let mnemonic = self.sse_mnemonic(opcode)?;
+                let modrm = self.modrm()?;
+                let size = if self.rex & 8 != 0 {
+                    Size::Qword
+                } else {
+                    Size::Dword
+                };
+                let source = self.rm(&modrm, size)?;
+                let destination = xmm(self.reg(&modrm));
+                let mnemonic = match source {
+                    Operand::Memory(_) => format!("{}{}", mnemonic, size.suffix()),
+                    _ => mnemonic.to_string(),
+                };
+                self.finish(&mnemonic, vec![destination, source], None)
+            }
+            0x2c | 0x2d if self.repeat.is_some() => {
+                let mnemonic = self.sse_mnemonic(opcode)?;
+                let modrm = self.modrm()?;
+                let source = self.xmm_rm(&modrm)?;
+                let size = if self.rex & 8 != 0 {
+                    Size::Qword
+                } else {
+                    Size::Dword
+                };
+                let destination = self.register(self.reg(&modrm), size);
+                self.finish(mnemonic, vec![destination, source], None)
+            }
+            0x2b | 0xe7 if (opcode == 0xe7) == self.operand_size => {
+                let mnemonic = if opcode == 0x2b { "movntps" } else { "movntdq" };
+                let modrm = self.modrm()?;
+                if modrm.mode == 3 {
+                    return None;
+                }
+                let destination = self.memory(&modrm)?;
+                let source = xmm(self.reg(&modrm));
+                self.finish(mnemonic, vec![destination, source], None)
+            }
+            0x31 => self.finish("rdtsc", vec![], None),
+            0x50 => {
+                let modrm = self.modrm()?;
+                if modrm.mode != 3 {
+                    return None;
+                }
+                let mnemonic = if self.operand_size {
+                    "movmskpd"
+                } else {
+                    "movmskps"
+                };
+                let source = xmm(modrm.rm | self.rex_b());
+                let destination = self.register(self.reg(&modrm), Size::Dword);
+                self.finish(mnemonic, vec![destination, source], None)
+            }
+            0x71..=0x73 if self.operand_size => {
+                let modrm = self.modrm()?;
+                if modrm.mode != 3 {
+                    return None;
+                }
+                let element = ["w", "d", "q"][(opcode - 0x71) as usize];
+                let mnemonic = match (modrm.reg, opcode) {
+                    (2, _) => format!("psrl{}", element),
+                    (3, 0x73) => "psrldq".to_string(),
+                    (4, 0x71 | 0x72) => format!("psra{}", element),
+                    (6, _) => format!("psll{}", element),
+                    (7, 0x73) => "pslldq".to_string(),
+                    _ => return None,
+                };
+                let register = xmm(modrm.rm | self.rex_b());
+                let immediate = self.immediate(1, Size::Byte)?;
+                self.finish(&mnemonic, vec![register, immediate], None)
+            }
+            0xae => {
+                let modrm = self.modrm()?;
+                match (modrm.mode, modrm.reg) {
+                    (3, 5) => self.finish("lfence", vec![], None),
+                    (3, 6) => self.finish("mfence", vec![], None),
+                    (3, 7) => self.finish("sfence", vec![], None),
+                    (3, _) => None,
+                    (_, 2 | 3) => {
+                        let operand = self.memory(&modrm)?;
+                        let mnemonic = if modrm.reg == 2 { "ldmxcsr" } else { "stmxcsr" };
+                        self.finish(mnemonic, vec![operand], None)
+                    }
+                    _ => None,
+                }
+            }
+            0x40..=0x4f => {
+                let mnemonic = format!("cmov{}", CONDITIONS[(opcode & 0xf) as usize]);
+                self.reg_rm(&mnemonic, size)
+            }
+            0x6e | 0x7e if self.operand_size => {
+                let modrm = self.modrm()?;
+                let size = if self.rex & 8 != 0 {
+                    Size::Qword
+                } else {
+                    Size::Dword
+                };
+                let other = self.rm(&modrm, size)?;
+                let register = xmm(self.reg(&modrm));
+                let mnemonic = if size == Size::Qword { "movq" } else { "movd" };
+                if opcode == 0x6e {
+                    self.finish(mnemonic, vec![register, other], None)
+                } else {
+                    self.finish(mnemonic, vec![other, register], None)
+                }
+            }
+            0x7e if self.repeat == Some(0xf3) => {
+                self.repeat = None;
+                self.xmm_reg_rm("movq")
+            }
+            0xd6 if self.operand_size => {
+                let modrm = self.modrm()?;
+                let destination = self.xmm_rm(&modrm)?;
+                let source = xmm(self.reg(&modrm));
+                self.finish("movq", vec![destination, source], None)
+            }
+            0x6f | 0x7f if self.operand_size || self.repeat == Some(0xf3) => {
+                let mnemonic = if self.repeat.take().is_some() {
+                    "movdqu"
+                } else {
+                    "movdqa"
+                };
+                let modrm = self.modrm()?;
+                let memory = self.xmm_rm(&modrm)?;
+                let register = xmm(self.reg(&modrm));
+                if opcode == 0x6f {
+                    self.finish(mnemonic, vec![register, memory], None)
+                } else {
+                    self.finish(mnemonic, vec![memory, register], None)
+                }
+            }
+            0x70 if self.operand_size => {
+                let modrm = self.modrm()?;
+                let source = self.xmm_rm(&modrm)?;
+                let immediate = self.immediate(1, Size::Byte)?;
+                let destination = xmm(self.reg(&modrm));
+                self.finish("pshufd", vec![destination, source, immediate], None)
+            }
+            0xd7 if self.operand_size => {
+                let modrm = self.modrm()?;
+                let source = self.xmm_rm(&modrm)?;
+                let destination = self.register(self.reg(&modrm), Size::Dword);
+                self.finish("pmovmskb", vec![destination, source], None)
+            }
+            0x80..=0x8f => {
+                let target = self.relative(4)?;
+                let mnemonic = format!("j{}", CONDITIONS[(opcode & 0xf) as usize]);
+                self.finish(&mnemonic, vec![target], None)
+            }
+            0x90..=0x9f => {
+                let modrm = self.modrm()?;
+                let operand = self.rm(&modrm, Size::Byte)?;
+                let mnemonic = format!("set{}", CONDITIONS[(opcode & 0xf) as usize]);
+                self.finish(&mnemonic, vec![operand], None)
+            }
+            0xa2 => self.finish("cpuid", vec![], None),
+            0xa3 => self.rm_reg("bt", size),
+            0xa4 | 0xa5 | 0xac | 0xad => {
+                let modrm = self.modrm()?;
+                let destination = self.rm(&modrm, size)?;
+                let source = self.register(self.reg(&modrm), size);
+                let count = if opcode & 1 == 0 {
+                    self.immediate(1, Size::Byte)?
+                } else {
+                    Operand::Register("cl".to_string())
+                };
+                let mnemonic = if opcode < 0xa8 { "shld" } else { "shrd" };
+                self.finish(mnemonic, vec![destination, source, count], None)
+            }
+            0xab => self.rm_reg("bts", size),
+            0xaf => self.reg_rm("imul", size),
+            0xb0 => self.rm_reg("cmpxchg", Size::Byte),
+            0xb1 => self.rm_reg("cmpxchg", size),
+            0xb3 => self.rm_reg("btr", size),
+            0xb6 | 0xb7 | 0xbe | 0xbf => {
+                let source_size = if opcode & 1 == 0 {
+                    Size::Byte
+                } else {
+                    Size::Word
+                };
+                let extension = if opcode < 0xb8 { "movz" } else { "movs" };
+                let mnemonic = format!("{}{}{}", extension, source_size.suffix(), size.suffix());
+                let modrm = self.modrm()?;
+                let source = self.rm(&modrm, source_size)?;
+                let destination = self.register(self.reg(&modrm), size);
+                self.finish(&mnemonic, vec![destination, source], None)
+            }
+            0xba => {
+                let modrm = self.modrm()?;
+                let mnemonic =
+                    ["bt", "bts", "btr", "btc"].get((modrm.reg as usize).checked_sub(4)?)?;
+                let operand = self.rm(&modrm, size)?;
+                let immediate = self.immediate(1, Size::Byte)?;
+                self.finish(mnemonic, vec![operand, immediate], Some(size))
+            }
+            0xbb => self.rm_reg("btc", size),
+            0xbc | 0xbd => {
+                let mnemonic = match (self.repeat.take(), opcode) {
+                    (Some(0xf3), 0xbc) => "tzcnt",
+                    (Some(0xf3), _) => "lzcnt",
+                    (_, 0xbc) => "bsf",
+                    _ => "bsr",
+                };
+                self.reg_rm(mnemonic, size)
+            }
+            0xc0 => self.rm_reg("xadd", Size::Byte),
+            0xc1 => self.rm_reg("xadd", size),
+            0xc8..=0xcf => {
+                let register = self.register(opcode & 7 | self.rex_b(), size);
+                self.finish("bswap", vec![register], None)
+            }
+            _ if self.operand_size => {
+                let mnemonic = packed_integer_mnemonic(opcode)?;
+                self.xmm_reg_rm(mnemonic)
+            }
+            _ => None,
+        }
+    }
+
+    /// The scalar and packed single and double precision SSE instructions, selected by
+    /// their prefix, which is not a repeat prefix then
+    fn sse_mnemonic(&mut self, opcode: u8) -> Option<&'static str> {
+        let mnemonics = match opcode {
+            0x10 | 0x11 => ["movups", "movupd", "movss", "movsd"],
+            0x14 => ["unpcklps", "unpcklpd", "", ""],
+            0x15 => ["unpckhps", "unpckhpd", "", ""],
+            0x28 | 0x29 => ["movaps", "movapd", "", ""],
+            0x2a => ["", "", "cvtsi2ss", "cvtsi2sd"],
+            0x2c => ["", "", "cvttss2si", "cvttsd2si"],
+            0x2d => ["", "", "cvtss2si", "cvtsd2si"],
+            0x2e => ["ucomiss", "ucomisd", "", ""],
+            0x2f => ["comiss", "comisd", "", ""],
+            0x51 => ["sqrtps", "sqrtpd", "sqrtss", "sqrtsd"],
+            0x54 => ["andps", "andpd", "", ""],
+            0x55 => ["andnps", "andnpd", "", ""],
+            0x56 => ["orps", "orpd", "", ""],
+            0x57 => ["xorps", "xorpd", "", ""],
+            0x58 => ["addps", "addpd", "addss", "addsd"],
+            0x59 => ["mulps", "mulpd", "mulss", "mulsd"],
+            0x5a => ["cvtps2pd", "cvtpd2ps", "cvtss2sd", "cvtsd2ss"],
+            0x5b => ["cvtdq2ps", "cvtps2dq", "cvttps2dq", ""],
+            0x5c => ["subps", "subpd", "subss", "subsd"],
+            0x5d => ["minps", "minpd", "minss", "minsd"],
+            0x5e => ["divps", "divpd", "divss", "divsd"],
+            0x5f => ["maxps", "maxpd", "maxss", "maxsd"],
+            _ => return None,
+        };
+        let index = match self.repeat.take() {
+            Some(0xf3) => 2,
+            Some(_) => 3,
+            None if self.operand_size => 1,
+            None => 0,
+        };
+        Some(mnemonics[index]).filter(|mnemonic| !mnemonic.is_empty())
+    }
+
+    /// Builds the instruction from its operands in the order of the Intel manuals, the
+    /// destination first, which AT&T reverses. The mnemonic gets the suffix of `size`
+    /// when there is a memory operand and no register telling its size.
+    fn finish(
+        self,
+        mnemonic: &str,
+        operands: Vec<Operand>,
+        size: Option<Size>,
+    ) -> Option<Instruction> {
+        let length = self.position;
+        let is_branch = matches!(mnemonic, "call" | "jmp" | "ret") || mnemonic.starts_with('j');
+        let mut text = String::new();
+        for _ in 0..self.redundant_operand_size {
+            text.push_str("data16 ");
+        }
+        match self.ignored_segment {
+            // `ds` marks indirect branches that CET allows to go without `endbr64`
+            Some("ds") if is_branch => text.push_str("notrack "),
+            Some(segment) => {
+                text.push_str(segment);
+                text.push(' ');
+            }
+            None => {}
+        }
+        if self.lock {
+            text.push_str("lock ");
+        }
+        match self.repeat {
+            Some(0xf2) if is_branch => text.push_str("bnd "),
+            Some(0xf2) => text.push_str("repnz "),
+            Some(_) => text.push_str("repz "),
+            None => {}
+        }
+        text.push_str(mnemonic);
+        let has_memory = operands
+            .iter()
+            .any(|operand| matches!(operand, Operand::Memory(_)));
+        let has_register = operands
+            .iter()
+            .any(|operand| matches!(operand, Operand::Register(_)));
+        if let Some(size) = size.filter(|_| has_memory && !has_register) {
+            text.push(size.suffix());
+        }
+
+        let branch_target = operands.iter().find_map(|operand| match operand {
+            Operand::Address(address) => Some(*address),
+            _ => None,
+        });
+        let memory_target = self
+            .rip_displacement
+            .map(|displacement| self.next_address().wrapping_add(displacement as u64));
+        let operands = operands
+            .iter()
+            .rev()
+            .map(|operand| operand.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        Some(Instruction {
+            length,
+            is_call: mnemonic == "call",
+            branch_target,
+            memory_target,
+            mnemonic: text,
+            operands,
+        })
+    }
+
+    /// `movs`, `cmps`, `stos`, `lods` and `scas`, whose operands are implied
+    fn string_operation(mut self, opcode: u8, size: Size) -> Option<Instruction> {
+        let size = if opcode & 1 == 0 { Size::Byte } else { size };
+        let accumulator = self.register(0, size);
+        let (mnemonic, operands, compares) = match opcode {
+            0xa4 | 0xa5 => ("movs", "%ds:(%rsi),%es:(%rdi)".to_string(), false),
+            0xa6 | 0xa7 => ("cmps", "%es:(%rdi),%ds:(%rsi)".to_string(), true),
+            0xaa | 0xab => ("stos", format!("{},%es:(%rdi)", accumulator), false),
+            0xac | 0xad => ("lods", format!("%ds:(%rsi),{}", accumulator), false),
+            _ => ("scas", format!("%es:(%rdi),{}", accumulator), true),
+        };
+        let prefix = match self.repeat.take() {
+            Some(0xf3) if compares => "repz ",
+            Some(0xf3) => "rep ",
+            Some(_) => "repnz ",
+            None => "",
+        };
+        // The operands of `movs` and `cmps` don't tell the size
+        let suffix = if matches!(opcode, 0xa4..=0xa7) {
+            size.suffix().to_string()
+        } else {
+            String::new()
+        };
+        let mut instruction = self.finish("", vec![], None)?;
+        instruction.mnemonic = format!("{}{}{}{}", instruction.mnemonic, prefix, mnemonic, suffix);
+        instruction.operands = operands;
+        Some(instruction)
+    }
+
+    /// An instruction with the register of the ModRM byte as source, `op E, G`
+    fn rm_reg(mut self, mnemonic: &str, size: Size) -> Option<Instruction> {
+        let modrm = self.modrm()?;
+        let destination = self.rm(&modrm, size)?;
+        let source = self.register(self.reg(&modrm), size);
+        self.finish(mnemonic, vec![destination, source], None)
+    }
+
+    /// An instruction with the register of the ModRM byte as destination, `op G, E`
+    fn reg_rm(mut self, mnemonic: &str, size: Size) -> Option<Instruction> {
+        let modrm = self.modrm()?;
+        self.reg_rm_with(mnemonic, &modrm, size)
+    }
+
+    fn reg_rm_with(mut self, mnemonic: &str, modrm: &ModRm, size: Size) -> Option<Instruction> {
+        let source = self.rm(modrm, size)?;
+        let destination = self.register(self.reg(modrm), size);
+        self.finish(mnemonic, vec![destination, source], None)
+    }
+
+    /// An SSE instruction from an XMM register or memory to an XMM register
+    fn xmm_reg_rm(mut self, mnemonic: &str) -> Option<Instruction> {
+        let modrm = self.modrm()?;
+        let source = self.xmm_rm(&modrm)?;
+        let destination = xmm(self.reg(&modrm));
+        self.finish(mnemonic, vec![destination, source], None)
+    }
+
+    /// An instruction with an immediate operand for `%al`, `%ax`, `%eax` or `%rax`
+    fn accumulator_immediate(mut self, mnemonic: &str, size: Size) -> Option<Instruction> {
+        let immediate = self.immediate(size.immediate_length(), size)?;
+        let accumulator = self.register(0, size);
+        self.finish(mnemonic, vec![accumulator, immediate], None)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.code.get(self.position).copied()
+    }
+
+    fn byte(&mut self) -> Option<u8> {
+        let byte = self.peek()?;
+        self.position += 1;
+        Some(byte)
+    }
+
+    /// Reads a little endian signed integer of `length` bytes
+    fn signed(&mut self, length: usize) -> Option<i64> {
+        let bytes = self.code.get(self.position..self.position + length)?;
+        self.position += length;
+        let mut value = [0; 8];
+        value[..length].copy_from_slice(bytes);
+        let shift = 64 - 8 * length as u32;
+        Some(i64::from_le_bytes(value) << shift >> shift)
+    }
+
+    /// Reads an immediate of `length` bytes, sign extended to `size`
+    fn immediate(&mut self, length: usize, size: Size) -> Option<Operand> {
+        let value = self.signed(length)?;
+        Some(Operand::Immediate(value as u64 & size.mask()))
+    }
+
+    /// Reads the displacement of a relative jump or call, which is relative to the next
+    /// instruction
+    fn relative(&mut self, length: usize) -> Option<Operand> {
+        let displacement = self.signed(length)?;
+        Some(Operand::Address(
+            self.next_address().wrapping_add(displacement as u64),
+        ))
+    }
+
+    /// The address of the instruction after the part decoded so far
+    fn next_address(&self) -> u64 {
+        self.address.wrapping_add(self.position as u64)
+    }
+
+    fn modrm(&mut self) -> Option<ModRm> {
+        let byte = self.byte()?;
+        Some(ModRm {
+            mode: byte >> 6,
+            reg: (byte >> 3) & 7,
+            rm: byte & 7,
+        })
+    }
+
+    /// The size of the operands of most instructions, set by REX.W and the `0x66` prefix
+    fn operand_size(&self) -> Size {
+        if self.rex & 8 != 0 {
+            Size::Qword
+        } else if self.operand_size {
+            Size::Word
+        } else {
+            Size::Dword
+        }
+    }
+
+    /// The size of what `push` and `pop` move, which can't be 32 bits
+    fn stack_size(&self) -> Size {
+        if self.operand_size {
+            Size::Word
+        } else {
+            Size::Qword
+        }
+    }
+
+    /// REX.B, which extends the register of the opcode, the rm field or the SIB base
+    fn rex_b(&self) -> u8 {
+        (self.rex & 1) << 3
+    }
+
+    /// The register of the reg field, extended by REX.R
+    fn reg(&self, modrm: &ModRm) -> u8 {
+        modrm.reg | (self.rex & 4) << 1
+    }
+
+    fn register(&self, number: u8, size: Size) -> Operand {
+        let number = number as usize;
+        let name = match size {
+            Size::Byte if self.rex == 0 && number < 8 => REGISTERS_8_LEGACY[number],
+            Size::Byte => REGISTERS_8[number],
+            Size::Word => REGISTERS_16[number],
+            Size::Dword => REGISTERS_32[number],
+            Size::Qword => REGISTERS_64[number],
+        };
+        Operand::Register(name.to_string())
+    }
+
+    /// The register or memory of the rm field
+    fn rm(&mut self, modrm: &ModRm, size: Size) -> Option<Operand> {
+        if modrm.mode == 3 {
+            return Some(self.register(modrm.rm | self.rex_b(), size));
+        }
+        self.memory(modrm)
+    }
+
+    /// The XMM register or memory of the rm field
+    fn xmm_rm(&mut self, modrm: &ModRm) -> Option<Operand> {
+        if modrm.mode == 3 {
+            return Some(xmm(modrm.rm | self.rex_b()));
+        }
+        self.memory(modrm)
+    }
+
+    /// The memory operand of the rm field and the SIB byte, `disp(base,index,scale)`
+    fn memory(&mut self, modrm: &ModRm) -> Option<Operand> {
+        let registers = if self.address_size {
+            REGISTERS_32
+        } else {
+            REGISTERS_64
+        };
+        let mut base = None;
+        let mut index = None;
+        let mut has_displacement = modrm.mode != 0;
+        if modrm.rm == 4 {
+            let sib = self.byte()?;
+            let index_number = (sib >> 3) & 7 | (self.rex & 2) << 2;
+            if index_number != 4 {
+                index = Some((registers[index_number as usize], 1 << (sib >> 6)));
+            }
+            if sib & 7 == 5 && modrm.mode == 0 {
+                has_displacement = true;
+            } else {
+                base = Some(registers[(sib & 7 | self.rex_b()) as usize]);
+            }
+        } else if modrm.rm == 5 && modrm.mode == 0 {
+            let displacement = self.signed(4)?;
+            self.rip_displacement = Some(displacement);
+            let rip = if self.address_size { "eip" } else { "rip" };
+            return Some(self.segmented(format!("{}(%{})", signed_hex(displacement), rip)));
+        } else {
+            base = Some(registers[(modrm.rm | self.rex_b()) as usize]);
+        }
+
+        let displacement = match modrm.mode {
+            1 => self.signed(1)?,
+            _ if has_displacement => self.signed(4)?,
+            _ => 0,
+        };
+        let mut text = if has_displacement {
+            signed_hex(displacement)
+        } else {
+            String::new()
+        };
+        if base.is_some() || index.is_some() {
+            text.push('(');
+            if let Some(base) = base {
+                text.push_str(&format!("%{}", base));
+            }
+            if let Some((index, scale)) = index {
+                text.push_str(&format!(",%{},{}", index, scale));
+            }
+            text.push(')');
+        }
+        Some(self.segmented(text))
+    }
+
+    /// A memory operand with the segment of a segment override prefix
+    fn segmented(&self, memory: String) -> Operand {
+        match self.segment {
+            Some(segment) => Operand::Memory(format!("%{}:{}", segment, memory)),
+            None => Operand::Memory(memory),
+        }
+    }
+}
+
+/// The SSE2 integer instructions between XMM registers or memory, with a `0x66` prefix
+fn packed_integer_mnemonic(opcode: u8) -> Option<&'static str> {
+    let mnemonic = match opcode {
+        0x60 => "punpcklbw",
+        0x61 => "punpcklwd",
+        0x62 => "punpckldq",
+        0x64 => "pcmpgtb",
+        0x65 => "pcmpgtw",
+        0x66 => "pcmpgtd",
+        0x6a => "punpckhdq",
+        0x6c => "punpcklqdq",
+        0x6d => "punpckhqdq",
+        0x74 => "pcmpeqb",
+        0x75 => "pcmpeqw",
+        0x76 => "pcmpeqd",
+        0xd4 => "paddq",
+        0xda => "pminub",
+        0xdb => "pand",
+        0xde => "pmaxub",
+        0xdf => "pandn",
+        0xeb => "por",
+        0xef => "pxor",
+        0xf2 => "pslld",
+        0xf4 => "pmuludq",
+        0xf8 => "psubb",
+        0xf9 => "psubw",
+        0xfa => "psubd",
+        0xfb => "psubq",
+        0xfc => "paddb",
+        0xfd => "paddw",
+        0xfe => "paddd",
+        _ => return None,
+    };
+    Some(mnemonic)
+}
+
+fn xmm(number: u8) -> Operand {
+    Operand::Register(format!("xmm{}", number))
+}
+
+fn signed_hex(value: i64) -> String {
+    if value < 0 {
+        format!("-{:#x}", value.unsigned_abs())
+    } else {
+        format!("{:#x}", value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn disassemble(code: &[u8]) -> String {
+        let instruction = decode(code, 0x401000);
+        assert_eq!(instruction.length, code.len(), "length of {:02x?}", code);
+        instruction.to_string()
+    }
+
+    #[test]
+    fn test_decode_hello() {
+        // Encodings of the instructions of `samples/src/assembly/hello.asm`
+        assert_eq!(disassemble(&[0xbf, 0x01, 0, 0, 0]), "mov    $0x1,%edi");
+        assert_eq!(
+            disassemble(&[0x48, 0xbe, 0x00, 0x20, 0x40, 0, 0, 0, 0, 0]),
+            "movabs $0x402000,%rsi"
+        );
+        assert_eq!(disassemble(&[0x0f, 0x05]), "syscall");
+        assert_eq!(disassemble(&[0x48, 0x31, 0xff]), "xor    %rdi,%rdi");
+        assert_eq!(
+            disassemble(&[0x48, 0xc7, 0xc0, 0x3c, 0, 0, 0]),
+            "mov    $0x3c,%rax"
+        );
+    }
+
+    #[test]
+    fn test_decode_branches() {
+        let call = decode(&[0xe8, 0x13, 0x00, 0x00, 0x00], 0x401011);
+        assert_eq!(call.to_string(), "call   0x401029");
+        assert!(call.is_call);
+        assert_eq!(call.length, 5);
+        assert_eq!(call.target(), Some(0x401029));
+
+        let jump = decode(&[0x7e, 0xf0], 0x401000);
+        assert_eq!(jump.to_string(), "jle    0x400ff2");
+        assert!(!jump.is_call);
+
+        let indirect = decode(&[0xff, 0xd0], 0x401000);
+        assert_eq!(indirect.to_string(), "call   *%rax");
+        assert!(indirect.is_call);
+        assert_eq!(indirect.target(), None);
+
+        assert_eq!(
+            disassemble(&[0xff, 0x25, 0xe2, 0x2f, 0x00, 0x00]),
+            "jmp    *0x2fe2(%rip)        # 0x403fe8"
+        );
+        assert_eq!(disassemble(&[0xf2, 0xff, 0xe0]), "bnd jmp *%rax");
+        assert_eq!(disassemble(&[0xc3]), "ret");
+    }
+
+    #[test]
+    fn test_decode_memory_operands() {
+        let lea = decode(&[0x48, 0x8d, 0x05, 0xc5, 0x0e, 0x00, 0x00], 0x401000);
+        assert_eq!(lea.to_string(), "lea    0xec5(%rip),%rax        # 0x401ecc");
+        assert_eq!(lea.memory_target, Some(0x401ecc));
+        assert_eq!(disassemble(&[0x8b, 0x45, 0xfc]), "mov    -0x4(%rbp),%eax");
+        assert_eq!(
+            disassemble(&[0x42, 0x8b, 0x04, 0x8d, 0x00, 0x00, 0x00, 0x00]),
+            "mov    0x0(,%r9,4),%eax"
+        );
+        assert_eq!(
+            disassemble(&[0x49, 0x89, 0x44, 0x24, 0x08]),
+            "mov    %rax,0x8(%r12)"
+        );
+        assert_eq!(
+            disassemble(&[0x64, 0x48, 0x8b, 0x04, 0x25, 0x28, 0, 0, 0]),
+            "mov    %fs:0x28,%rax"
+        );
+    }
+
+    #[test]
+    fn test_decode_suffixes() {
+        assert_eq!(
+            disassemble(&[0xc7, 0x45, 0xfc, 0x00, 0x00, 0x00, 0x00]),
+            "movl   $0x0,-0x4(%rbp)"
+        );
+        assert_eq!(
+            disassemble(&[0x48, 0x83, 0x45, 0xf8, 0x01]),
+            "addq   $0x1,-0x8(%rbp)"
+        );
+        assert_eq!(
+            disassemble(&[0x48, 0x83, 0xe4, 0xf0]),
+            "and    $0xfffffffffffffff0,%rsp"
+        );
+        assert_eq!(
+            disassemble(&[0x48, 0xd3, 0x6d, 0xc0]),
+            "shrq   %cl,-0x40(%rbp)"
+        );
+        assert_eq!(disassemble(&[0x0f, 0xb6, 0x00]), "movzbl (%rax),%eax");
+        assert_eq!(disassemble(&[0x48, 0x63, 0xd0]), "movslq %eax,%rdx");
+        assert_eq!(disassemble(&[0x48, 0x98]), "cltq");
+    }
+
+    #[test]
+    fn test_decode_prefixes() {
+        assert_eq!(disassemble(&[0xf3, 0x0f, 0x1e, 0xfa]), "endbr64");
+        assert_eq!(
+            disassemble(&[0x66, 0x2e, 0x0f, 0x1f, 0x84, 0, 0, 0, 0, 0]),
+            "cs nopw 0x0(%rax,%rax,1)"
+        );
+        assert_eq!(disassemble(&[0xf3, 0x48, 0xab]), "rep stos %rax,%es:(%rdi)");
+        assert_eq!(
+            disassemble(&[0xf0, 0x0f, 0xb1, 0x17]),
+            "lock cmpxchg %edx,(%rdi)"
+        );
+        assert_eq!(disassemble(&[0x41, 0x88, 0xc0]), "mov    %al,%r8b");
+        assert_eq!(disassemble(&[0x40, 0x88, 0xc6]), "mov    %al,%sil");
+        assert_eq!(disassemble(&[0x88, 0xc6]), "mov    %al,%dh");
+    }
+
+    #[test]
+    fn test_decode_sse() {
+        assert_eq!(
+            disassemble(&[0xf2, 0x0f, 0x10, 0x45, 0xf8]),
+            "movsd  -0x8(%rbp),%xmm0"
+        );
+        assert_eq!(disassemble(&[0xf2, 0x0f, 0x5e, 0xc1]), "divsd  %xmm1,%xmm0");
+        assert_eq!(
+            disassemble(&[0x66, 0x48, 0x0f, 0x6e, 0xc0]),
+            "movq   %rax,%xmm0"
+        );
+        assert_eq!(
+            disassemble(&[0xf2, 0x0f, 0x2a, 0x45, 0xfc]),
+            "cvtsi2sdl -0x4(%rbp),%xmm0"
+        );
+        assert_eq!(disassemble(&[0x66, 0x0f, 0xef, 0xc0]), "pxor   %xmm0,%xmm0");
+    }
+
+    #[test]
+    fn test_decode_bad() {
+        let bad = decode(&[0x06], 0x401000);
+        assert_eq!(bad.to_string(), "(bad)");
+        assert_eq!(bad.length, 1);
+        // Cut off in the middle of the immediate
+        assert_eq!(decode(&[0xb8, 0x01, 0x00], 0x401000).to_string(), "(bad)");
+        assert_eq!(decode(&[], 0x401000).to_string(), "(bad)");
+    }
+}
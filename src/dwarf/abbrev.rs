@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+
+use super::constants::DW_FORM_IMPLICIT_CONST;
+use super::error::{DwarfError, Result};
+use super::reader::Reader;
+
+/// An attribute of an abbreviation, its name and how its value is encoded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttributeSpec {
+    pub name: u16,
+    pub form: u16,
+    /// The value of `DW_FORM_implicit_const` attributes, stored in the abbreviation itself
+    pub implicit_const: Option<i64>,
+}
+
+/// Describes the tag and the attributes of the DIEs using its code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Abbreviation {
+    pub code: u64,
+    pub tag: u16,
+    pub has_children: bool,
+    pub attributes: Vec<AttributeSpec>,
+}
+
+/// An abbreviation table of `.debug_abbrev`, shared by one or more units
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Abbreviations {
+    abbreviations: HashMap<u64, Abbreviation>,
+}
+
+impl Abbreviations {
+    /// Parses the table starting at `offset` up to its terminating null entry
+    pub fn parse(debug_abbrev: &[u8], offset: u64) -> Result<Self> {
+        let mut reader = Reader::at(debug_abbrev, offset as usize)?;
+        let mut abbreviations = HashMap::new();
+
+        loop {
+            let code = reader.read_uleb128()?;
+            if code == 0 {
+                break;
+            }
+            let tag = reader.read_uleb128()? as u16;
+            let has_children = reader.read_u8()? != 0;
+
+            let mut attributes = Vec::new();
+            loop {
+                let name = reader.read_uleb128()? as u16;
+                let form = reader.read_uleb128()? as u16;
+                if name == 0 && form == 0 {
+                    break;
+                }
+                let implicit_const = match form {
+                    DW_FORM_IMPLICIT_CONST => Some(reader.read_sleb128()?),
+                    _ => None,
+                };
+                attributes.push(AttributeSpec {
+                    name,
+                    form,
+                    implicit_const,
+                });
+            }
+
+            abbreviations.insert(
+                code,
+                Abbreviation {
+                    code,
+                    tag,
+                    has_children,
+                    attributes,
+                },
+            );
+        }
+
+        Ok(Self { abbreviations })
+    }
+
+    pub fn get(&self, code: u64) -> Result<&Abbreviation> {
+        self.abbreviations
+            .get(&code)
+            .ok_or(DwarfError::UnknownAbbreviation(code))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dwarf::constants::*;
+
+    #[test]
+    fn test_parse_abbreviations() {
+        // The table starts at offset 1, after a byte of padding
+        let mut bytes = vec![0xff];
+        // 1: compile unit with children, name strp, language data1
+        bytes.extend_from_slice(&[0x01, 0x11, 0x01, 0x03, 0x0e, 0x13, 0x0b, 0x00, 0x00]);
+        // 2: variable without children, decl_file implicit_const 1
+        bytes.extend_from_slice(&[0x02, 0x34, 0x00, 0x3a, 0x21, 0x01, 0x00, 0x00]);
+        // End of the table
+        bytes.push(0x00);
+
+        let abbreviations = Abbreviations::parse(&bytes, 1).unwrap();
+        let compile_unit = abbreviations.get(1).unwrap();
+        assert_eq!(compile_unit.tag, DW_TAG_COMPILE_UNIT);
+        assert!(compile_unit.has_children);
+        assert_eq!(
+            compile_unit.attributes,
+            vec![
+                AttributeSpec {
+                    name: DW_AT_NAME,
+                    form: DW_FORM_STRP,
+                    implicit_const: None
+                },
+                AttributeSpec {
+                    name: DW_AT_LANGUAGE,
+                    form: DW_FORM_DATA1,
+                    implicit_const: None
+                },
+            ]
+        );
+
+        let variable = abbreviations.get(2).unwrap();
+        assert!(!variable.has_children);
+        assert_eq!(variable.attributes[0].implicit_const, Some(1));
+        assert_eq!(
+            abbreviations.get(3),
+            Err(DwarfError::UnknownAbbreviation(3))
+        );
+    }
+}
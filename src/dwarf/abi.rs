@@ -0,0 +1,344 @@
+use super::constants::*;
+use super::die::Die;
+use super::error::{DwarfError, Result};
+use super::expression::EvaluationContext;
+use super::types::{array_dimensions, byte_size, is_rust_language, strip_typedefs, type_of};
+use super::unit::Unit;
+use super::value::ValueFormatter;
+
+/// Size of the part of a value each register passes, an eightbyte
+const EIGHTBYTE: u64 = 8;
+/// Values larger than this are returned in memory
+const MAX_REGISTERS_SIZE: u64 = 2 * EIGHTBYTE;
+
+/// The kind of register a part of a value is passed in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Class {
+    /// The next free of rax and rdx
+    Integer,
+    /// The low half of the next free of xmm0 and xmm1
+    Sse,
+}
+
+/// Bytes of a value that are passed in one register, in its low bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Part {
+    pub offset: u64,
+    pub size: u64,
+    pub class: Class,
+}
+
+/// Where a function returns its value, following the System V ABI for x86-64
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReturnValue {
+    /// The function returns `void`
+    Void,
+    /// In registers, with the parts of the value in the order the registers are used
+    Registers(Vec<Part>),
+    /// In st0, for `long double`
+    X87,
+    /// In memory the caller provides, whose address the function returns in rax
+    Memory,
+}
+
+/// Where a function returns a value of the type `die`, `None` for `void`. The context
+/// is only used for the locations of members that are computed by an expression.
+///
+/// Functions of Rust units use the Rust ABI, which returns a value of two scalars, like
+/// `(i32, i32)` or `Option<u32>`, with each scalar in a register of its own.
+pub fn return_value<'a, C: EvaluationContext>(
+    unit: &Unit<'a>,
+    context: &C,
+    die: Option<&Die<'a>>,
+) -> Result<ReturnValue> {
+    let die = match strip_typedefs(unit, die.cloned())? {
+        Some(die) => die,
+        None => return Ok(ReturnValue::Void),
+    };
+    let size = byte_size(unit, &die)?.ok_or(DwarfError::Unavailable("size of return type"))?;
+    if size > MAX_REGISTERS_SIZE {
+        return Ok(ReturnValue::Memory);
+    }
+    if is_long_double(unit, &die)? {
+        return Ok(ReturnValue::X87);
+    }
+
+    let formatter = ValueFormatter::new(unit, context);
+    let mut scalars = Vec::new();
+    if !collect_scalars(unit, &formatter, &die, 0, &mut scalars)? {
+        return Ok(ReturnValue::Memory);
+    }
+    scalars.sort_by_key(|scalar| scalar.offset);
+    scalars.dedup();
+    if is_rust_language(unit)? && scalars.len() == 2 && scalars[0].end() <= scalars[1].offset {
+        return Ok(ReturnValue::Registers(scalars));
+    }
+
+    let mut classes = vec![None; size.div_ceil(EIGHTBYTE) as usize];
+    for scalar in &scalars {
+        let first = scalar.offset / EIGHTBYTE;
+        let last = (scalar.end().max(scalar.offset + 1) - 1) / EIGHTBYTE;
+        for index in first..=last {
+            let class = match classes.get_mut(index as usize) {
+                Some(class) => class,
+                None => return Ok(ReturnValue::Memory),
+            };
+            // An eightbyte with any integer in it is passed in a general purpose register
+            *class = match (*class, scalar.class) {
+                (Some(Class::Integer), _) | (_, Class::Integer) => Some(Class::Integer),
+                _ => Some(Class::Sse),
+            };
+        }
+    }
+    // An eightbyte that is all padding takes no register, the ABI leaves it out
+    let parts = classes
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, class)| {
+            let offset = index as u64 * EIGHTBYTE;
+            Some(Part {
+                offset,
+                size: EIGHTBYTE.min(size - offset),
+                class: class?,
+            })
+        })
+        .collect();
+    Ok(ReturnValue::Registers(parts))
+}
+
+impl Part {
+    fn end(&self) -> u64 {
+        self.offset + self.size
+    }
+}
+
+/// Adds the scalars of the value of type `die` at `offset`, with the class of register
+/// each would be passed in on its own. Returns `false` if the value can not be passed
+/// in registers.
+fn collect_scalars<'a, C: EvaluationContext>(
+    unit: &Unit<'a>,
+    formatter: &ValueFormatter<'_, 'a, C>,
+    die: &Die<'a>,
+    offset: u64,
+    scalars: &mut Vec<Part>,
+) -> Result<bool> {
+    let die = match strip_typedefs(unit, Some(die.clone()))? {
+        Some(die) => die,
+        None => return Ok(true),
+    };
+    match die.tag {
+        DW_TAG_STRUCTURE_TYPE
+        | DW_TAG_CLASS_TYPE
+        | DW_TAG_UNION_TYPE
+        | DW_TAG_VARIANT_PART
+        | DW_TAG_VARIANT => {
+            for child in unit.children(&die)? {
+                let fits = match child.tag {
+                    DW_TAG_MEMBER | DW_TAG_INHERITANCE if !child.has_attr(DW_AT_DECLARATION) => {
+                        let member_type = match type_of(unit, &child)? {
+                            Some(member_type) => member_type,
+                            None => continue,
+                        };
+                        let member_offset = offset + formatter.member_offset(&child)?;
+                        collect_scalars(unit, formatter, &member_type, member_offset, scalars)?
+                    }
+                    DW_TAG_VARIANT_PART | DW_TAG_VARIANT => {
+                        collect_scalars(unit, formatter, &child, offset, scalars)?
+                    }
+                    _ => true,
+                };
+                if !fits {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        }
+        DW_TAG_ARRAY_TYPE => {
+            let element = match type_of(unit, &die)? {
+                Some(element) => element,
+                None => return Ok(true),
+            };
+            let element_size = match byte_size(unit, &element)? {
+                Some(size) if size > 0 => size,
+                _ => return Ok(true),
+            };
+            let count: u64 = array_dimensions(unit, &die)?
+                .into_iter()
+                .map(|count| count.unwrap_or(0))
+                .product();
+            for index in 0..count.min(MAX_REGISTERS_SIZE / element_size) {
+                let element_offset = offset + index * element_size;
+                if !collect_scalars(unit, formatter, &element, element_offset, scalars)? {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        }
+        _ => {
+            if is_long_double(unit, &die)? {
+                return Ok(false);
+            }
+            scalars.push(Part {
+                offset,
+                size: byte_size(unit, &die)?.unwrap_or(0),
+                class: if is_float(&die) {
+                    Class::Sse
+                } else {
+                    Class::Integer
+                },
+            });
+            Ok(true)
+        }
+    }
+}
+
+/// The type is a binary floating point or complex number
+fn is_float(die: &Die) -> bool {
+    die.tag == DW_TAG_BASE_TYPE
+        && matches!(
+            die.attr(DW_AT_ENCODING).and_then(|value| value.udata()),
+            Some(encoding) if encoding == DW_ATE_FLOAT as u64
+                || encoding == DW_ATE_COMPLEX_FLOAT as u64
+        )
+}
+
+/// The type is the 80-bit extended precision `long double` of the x87, stored in 16 bytes
+fn is_long_double(unit: &Unit, die: &Die) -> Result<bool> {
+    Ok(die.tag == DW_TAG_BASE_TYPE
+        && die.attr(DW_AT_ENCODING).and_then(|value| value.udata()) == Some(DW_ATE_FLOAT as u64)
+        && byte_size(unit, die)? == Some(16))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dwarf::testing::{abbrev_table, reference, sections, NoProcess, UnitBuilder};
+
+    fn abbrev_bytes() -> Vec<u8> {
+        abbrev_table(&[
+            // 2: base type, name string, byte_size data1, encoding data1
+            0x02, 0x24, 0x00, 0x03, 0x08, 0x0b, 0x0b, 0x3e, 0x0b, 0x00, 0x00,
+            // 3: structure type with children, byte_size data1
+            0x03, 0x13, 0x01, 0x0b, 0x0b, 0x00, 0x00,
+            // 4: member, type ref4, data_member_location data1
+            0x04, 0x0d, 0x00, 0x49, 0x13, 0x38, 0x0b, 0x00, 0x00,
+            // 5: pointer type, byte_size data1, type ref4
+            0x05, 0x0f, 0x00, 0x0b, 0x0b, 0x49, 0x13, 0x00, 0x00,
+        ])
+    }
+
+    /// Appends a structure of `size` bytes with members of the types at the offsets,
+    /// returning its offset
+    fn structure(unit: &mut UnitBuilder, size: u8, members: &[(u64, u8)]) -> u64 {
+        let offset = unit.die(&[0x03, size]);
+        for &(member_type, member_offset) in members {
+            unit.die(&[&[0x04][..], &reference(member_type), &[member_offset]].concat());
+        }
+        unit.end_children();
+        offset
+    }
+
+    /// A DWARF 4 unit in `language` with the types [`test_return_value`] classifies
+    struct TestUnit {
+        info: Vec<u8>,
+        int: u64,
+        double: u64,
+        long_double: u64,
+        pointer: u64,
+        floats: u64,
+        mixed: u64,
+        shared: u64,
+        large: u64,
+        extended: u64,
+    }
+
+    fn test_unit(language: u8) -> TestUnit {
+        let mut unit = UnitBuilder::new(language);
+        let int = unit.die(&[0x02, b'i', 0, 4, DW_ATE_SIGNED]);
+        let float = unit.die(&[0x02, b'f', 0, 4, DW_ATE_FLOAT]);
+        let double = unit.die(&[0x02, b'd', 0, 8, DW_ATE_FLOAT]);
+        let long_double = unit.die(&[0x02, b'l', 0, 16, DW_ATE_FLOAT]);
+        let pointer = unit.die(&[&[0x05, 8][..], &reference(int)].concat());
+        let floats = structure(&mut unit, 8, &[(float, 0), (float, 4)]);
+        let mixed = structure(&mut unit, 16, &[(double, 0), (int, 8), (float, 12)]);
+        let shared = structure(&mut unit, 8, &[(float, 0), (int, 4)]);
+        let large = structure(&mut unit, 24, &[(double, 0), (double, 8), (double, 16)]);
+        let extended = structure(&mut unit, 16, &[(long_double, 0)]);
+
+        TestUnit {
+            info: unit.finish(),
+            int,
+            double,
+            long_double,
+            pointer,
+            floats,
+            mixed,
+            shared,
+            large,
+            extended,
+        }
+    }
+
+    fn part(offset: u64, size: u64, class: Class) -> Part {
+        Part {
+            offset,
+            size,
+            class,
+        }
+    }
+
+    #[test]
+    fn test_return_value() {
+        use Class::*;
+
+        let abbrev = abbrev_bytes();
+        // C99
+        let c = test_unit(0x0c);
+        let c_sections = sections(&c.info, &abbrev);
+        let unit = c_sections.unit_at(0).unwrap();
+        let classify = |offset: u64| {
+            let die = unit.entry(offset).unwrap();
+            return_value(&unit, &NoProcess, Some(&die)).unwrap()
+        };
+        let registers = |parts: &[Part]| ReturnValue::Registers(parts.to_vec());
+
+        assert_eq!(
+            return_value(&unit, &NoProcess, None).unwrap(),
+            ReturnValue::Void
+        );
+        assert_eq!(classify(c.int), registers(&[part(0, 4, Integer)]));
+        assert_eq!(classify(c.pointer), registers(&[part(0, 8, Integer)]));
+        assert_eq!(classify(c.double), registers(&[part(0, 8, Sse)]));
+        assert_eq!(classify(c.long_double), ReturnValue::X87);
+        assert_eq!(classify(c.floats), registers(&[part(0, 8, Sse)]));
+        assert_eq!(
+            classify(c.mixed),
+            registers(&[part(0, 8, Sse), part(8, 8, Integer)])
+        );
+        assert_eq!(classify(c.shared), registers(&[part(0, 8, Integer)]));
+        assert_eq!(classify(c.large), ReturnValue::Memory);
+        assert_eq!(classify(c.extended), ReturnValue::Memory);
+
+        // Rust passes the scalars of a pair in registers of their own
+        let rust = test_unit(DW_LANG_RUST as u8);
+        let rust_sections = sections(&rust.info, &abbrev);
+        let unit = rust_sections.unit_at(0).unwrap();
+        let classify = |offset: u64| {
+            let die = unit.entry(offset).unwrap();
+            return_value(&unit, &NoProcess, Some(&die)).unwrap()
+        };
+        assert_eq!(classify(rust.int), registers(&[part(0, 4, Integer)]));
+        assert_eq!(
+            classify(rust.floats),
+            registers(&[part(0, 4, Sse), part(4, 4, Sse)])
+        );
+        assert_eq!(
+            classify(rust.shared),
+            registers(&[part(0, 4, Sse), part(4, 4, Integer)])
+        );
+        assert_eq!(
+            classify(rust.mixed),
+            registers(&[part(0, 8, Sse), part(8, 8, Integer)])
+        );
+    }
+}
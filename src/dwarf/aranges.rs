@@ -0,0 +1,238 @@
+use std::collections::HashSet;
+
+use super::constants::*;
+use super::error::Result;
+use super::reader::Reader;
+use super::rnglist::pc_ranges;
+use super::sections::DwarfSections;
+use super::unit::Unit;
+
+/// The address ranges of the code of one unit, from a set of `.debug_aranges`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddressRangeSet {
+    /// Offset of the unit header in `.debug_info`
+    pub unit_offset: u64,
+    pub ranges: Vec<(u64, u64)>,
+}
+
+impl AddressRangeSet {
+    /// Reads all sets of a `.debug_aranges` section
+    pub fn parse_all(section: &[u8]) -> Result<Vec<Self>> {
+        let mut sets = Vec::new();
+        let mut reader = Reader::new(section);
+        while !reader.is_empty() {
+            let start = reader.offset();
+            let (length, format) = reader.read_initial_length()?;
+            let length_size = reader.offset() - start;
+            let mut set = reader.split(length as usize)?;
+            // Only version 2 exists, so the version is not checked
+            set.read_u16()?;
+            let unit_offset = set.read_offset(format)?;
+            let address_size = set.read_u8()?;
+            let segment_size = set.read_u8()?;
+
+            // The tuples are aligned to their size from the start of the set
+            let tuple_size = 2 * address_size as usize + segment_size as usize;
+            let header_size = length_size + set.offset();
+            if tuple_size > 0 && !header_size.is_multiple_of(tuple_size) {
+                set.skip(tuple_size - header_size % tuple_size)?;
+            }
+
+            let mut ranges = Vec::new();
+            while set.remaining() >= tuple_size {
+                set.skip(segment_size as usize)?;
+                let address = set.read_address(address_size)?;
+                let length = set.read_address(address_size)?;
+                if address == 0 && length == 0 {
+                    break;
+                }
+                ranges.push((address, address.wrapping_add(length)));
+            }
+            sets.push(Self {
+                unit_offset,
+                ranges,
+            });
+        }
+        Ok(sets)
+    }
+}
+
+/// An address range of the code of the unit at `unit_offset` of `.debug_info`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AddressRange {
+    pub begin: u64,
+    pub end: u64,
+    pub unit_offset: u64,
+}
+
+/// Finds the unit whose code contains an address without reading the DIEs of all units.
+/// The ranges come from `.debug_aranges`, units it leaves out are indexed by the ranges
+/// of their unit DIE.
+#[derive(Debug, Clone, Default)]
+pub struct AddressIndex<'a> {
+    sections: DwarfSections<'a>,
+    /// Sorted ranges that do not overlap
+    ranges: Vec<AddressRange>,
+}
+
+impl<'a> AddressIndex<'a> {
+    pub fn new(sections: &DwarfSections<'a>) -> Result<Self> {
+        let mut ranges = Vec::new();
+        let mut indexed = HashSet::new();
+        // A broken `.debug_aranges` only costs reading the unit DIEs instead
+        for set in AddressRangeSet::parse_all(sections.debug_aranges).unwrap_or_default() {
+            indexed.insert(set.unit_offset);
+            ranges.extend(set.ranges.into_iter().map(|(begin, end)| AddressRange {
+                begin,
+                end,
+                unit_offset: set.unit_offset,
+            }));
+        }
+
+        for offset in unit_offsets(sections.debug_info)? {
+            if indexed.contains(&offset) {
+                continue;
+            }
+            let unit = sections.unit_at(offset)?;
+            // Type units have no code
+            if matches!(unit.unit_type, DW_UT_TYPE | DW_UT_SPLIT_TYPE) {
+                continue;
+            }
+            ranges.extend(
+                unit_ranges(&unit)?
+                    .into_iter()
+                    .map(|(begin, end)| AddressRange {
+                        begin,
+                        end,
+                        unit_offset: offset,
+                    }),
+            );
+        }
+
+        Ok(Self {
+            sections: *sections,
+            ranges: without_overlaps(ranges),
+        })
+    }
+
+    pub fn ranges(&self) -> &[AddressRange] {
+        &self.ranges
+    }
+
+    /// Offset in `.debug_info` of the unit containing `pc`, an address of the file
+    pub fn find_unit_offset(&self, pc: u64) -> Option<u64> {
+        let index = self.ranges.partition_point(|range| range.begin <= pc);
+        let range = self.ranges.get(index.checked_sub(1)?)?;
+        (pc < range.end).then_some(range.unit_offset)
+    }
+
+    /// The unit containing `pc`, an address of the file
+    pub fn find_unit(&self, pc: u64) -> Result<Option<Unit<'a>>> {
+        self.find_unit_offset(pc)
+            .map(|offset| self.sections.unit_at(offset))
+            .transpose()
+    }
+}
+
+/// Offsets of the unit headers of `.debug_info`, found from their lengths alone
+fn unit_offsets(debug_info: &[u8]) -> Result<Vec<u64>> {
+    let mut offsets = Vec::new();
+    let mut reader = Reader::new(debug_info);
+    while !reader.is_empty() {
+        offsets.push(reader.offset() as u64);
+        let (length, _) = reader.read_initial_length()?;
+        reader.skip(length as usize)?;
+    }
+    Ok(offsets)
+}
+
+/// The ranges of a unit DIE, or of its skeleton for a split unit. Units without them,
+/// which old compilers produce, are indexed by the ranges of their functions instead.
+fn unit_ranges(unit: &Unit) -> Result<Vec<(u64, u64)>> {
+    let root_unit = unit.skeleton().unwrap_or(unit);
+    let ranges = pc_ranges(root_unit, &root_unit.root()?)?;
+    if !ranges.is_empty() {
+        return Ok(ranges);
+    }
+    let mut ranges = Vec::new();
+    for entry in unit.entries() {
+        let (_, die) = entry?;
+        if die.tag == DW_TAG_SUBPROGRAM {
+            ranges.extend(pc_ranges(unit, &die)?);
+        }
+    }
+    Ok(ranges)
+}
+
+/// Sorts `ranges` and cuts them where they overlap, the earlier range keeps the addresses
+fn without_overlaps(mut ranges: Vec<AddressRange>) -> Vec<AddressRange> {
+    ranges.sort_by_key(|range| (range.begin, range.end));
+    let mut result: Vec<AddressRange> = Vec::with_capacity(ranges.len());
+    for mut range in ranges {
+        if let Some(last) = result.last() {
+            range.begin = range.begin.max(last.end);
+        }
+        if range.begin < range.end {
+            result.push(range);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_aranges() {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&44u32.to_le_bytes());
+        bytes.extend_from_slice(&2u16.to_le_bytes());
+        bytes.extend_from_slice(&0x30u32.to_le_bytes());
+        // Address size 8, no segments, padded to 16 bytes
+        bytes.extend_from_slice(&[8, 0, 0, 0, 0, 0]);
+        bytes.extend_from_slice(&0x1000u64.to_le_bytes());
+        bytes.extend_from_slice(&0x20u64.to_le_bytes());
+        bytes.extend_from_slice(&[0; 16]);
+
+        let sets = AddressRangeSet::parse_all(&bytes).unwrap();
+        assert_eq!(
+            sets,
+            vec![AddressRangeSet {
+                unit_offset: 0x30,
+                ranges: vec![(0x1000, 0x1020)],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_find_unit_offset() {
+        let range = |begin, end, unit_offset| AddressRange {
+            begin,
+            end,
+            unit_offset,
+        };
+        let index = AddressIndex {
+            sections: DwarfSections::default(),
+            ranges: without_overlaps(vec![
+                range(0x2000, 0x2100, 2),
+                range(0x1000, 0x1100, 1),
+                range(0x1080, 0x1200, 3),
+            ]),
+        };
+        assert_eq!(
+            index.ranges(),
+            &[
+                range(0x1000, 0x1100, 1),
+                range(0x1100, 0x1200, 3),
+                range(0x2000, 0x2100, 2),
+            ]
+        );
+        assert_eq!(index.find_unit_offset(0xfff), None);
+        assert_eq!(index.find_unit_offset(0x1000), Some(1));
+        assert_eq!(index.find_unit_offset(0x1180), Some(3));
+        assert_eq!(index.find_unit_offset(0x1200), None);
+        assert_eq!(index.find_unit_offset(0x20ff), Some(2));
+        assert_eq!(index.find_unit_offset(0x2100), None);
+    }
+}
@@ -0,0 +1,329 @@
+// The constants follow the naming of the DWARF specification
+#![allow(dead_code)]
+
+// Unit types, DWARF 5
+pub const DW_UT_COMPILE: u8 = 0x01;
+pub const DW_UT_TYPE: u8 = 0x02;
+pub const DW_UT_PARTIAL: u8 = 0x03;
+pub const DW_UT_SKELETON: u8 = 0x04;
+pub const DW_UT_SPLIT_COMPILE: u8 = 0x05;
+pub const DW_UT_SPLIT_TYPE: u8 = 0x06;
+
+// Tags
+pub const DW_TAG_ARRAY_TYPE: u16 = 0x01;
+pub const DW_TAG_CLASS_TYPE: u16 = 0x02;
+pub const DW_TAG_ENUMERATION_TYPE: u16 = 0x04;
+pub const DW_TAG_FORMAL_PARAMETER: u16 = 0x05;
+pub const DW_TAG_LABEL: u16 = 0x0a;
+pub const DW_TAG_LEXICAL_BLOCK: u16 = 0x0b;
+pub const DW_TAG_MEMBER: u16 = 0x0d;
+pub const DW_TAG_POINTER_TYPE: u16 = 0x0f;
+pub const DW_TAG_REFERENCE_TYPE: u16 = 0x10;
+pub const DW_TAG_COMPILE_UNIT: u16 = 0x11;
+pub const DW_TAG_STRUCTURE_TYPE: u16 = 0x13;
+pub const DW_TAG_SUBROUTINE_TYPE: u16 = 0x15;
+pub const DW_TAG_TYPEDEF: u16 = 0x16;
+pub const DW_TAG_UNION_TYPE: u16 = 0x17;
+pub const DW_TAG_UNSPECIFIED_PARAMETERS: u16 = 0x18;
+pub const DW_TAG_VARIANT: u16 = 0x19;
+pub const DW_TAG_INHERITANCE: u16 = 0x1c;
+pub const DW_TAG_INLINED_SUBROUTINE: u16 = 0x1d;
+pub const DW_TAG_PTR_TO_MEMBER_TYPE: u16 = 0x1f;
+pub const DW_TAG_SUBRANGE_TYPE: u16 = 0x21;
+pub const DW_TAG_BASE_TYPE: u16 = 0x24;
+pub const DW_TAG_CONST_TYPE: u16 = 0x26;
+pub const DW_TAG_ENUMERATOR: u16 = 0x28;
+pub const DW_TAG_SUBPROGRAM: u16 = 0x2e;
+pub const DW_TAG_TEMPLATE_TYPE_PARAMETER: u16 = 0x2f;
+pub const DW_TAG_TEMPLATE_VALUE_PARAMETER: u16 = 0x30;
+pub const DW_TAG_VARIANT_PART: u16 = 0x33;
+pub const DW_TAG_VARIABLE: u16 = 0x34;
+pub const DW_TAG_VOLATILE_TYPE: u16 = 0x35;
+pub const DW_TAG_RESTRICT_TYPE: u16 = 0x37;
+pub const DW_TAG_NAMESPACE: u16 = 0x39;
+pub const DW_TAG_UNSPECIFIED_TYPE: u16 = 0x3b;
+pub const DW_TAG_PARTIAL_UNIT: u16 = 0x3c;
+pub const DW_TAG_TYPE_UNIT: u16 = 0x41;
+pub const DW_TAG_RVALUE_REFERENCE_TYPE: u16 = 0x42;
+pub const DW_TAG_ATOMIC_TYPE: u16 = 0x47;
+pub const DW_TAG_CALL_SITE: u16 = 0x48;
+pub const DW_TAG_SKELETON_UNIT: u16 = 0x4a;
+pub const DW_TAG_GNU_CALL_SITE: u16 = 0x4109;
+
+// Attributes
+pub const DW_AT_SIBLING: u16 = 0x01;
+pub const DW_AT_LOCATION: u16 = 0x02;
+pub const DW_AT_NAME: u16 = 0x03;
+pub const DW_AT_BYTE_SIZE: u16 = 0x0b;
+pub const DW_AT_BIT_OFFSET: u16 = 0x0c;
+pub const DW_AT_BIT_SIZE: u16 = 0x0d;
+pub const DW_AT_STMT_LIST: u16 = 0x10;
+pub const DW_AT_LOW_PC: u16 = 0x11;
+pub const DW_AT_HIGH_PC: u16 = 0x12;
+pub const DW_AT_LANGUAGE: u16 = 0x13;
+pub const DW_AT_DISCR: u16 = 0x15;
+pub const DW_AT_DISCR_VALUE: u16 = 0x16;
+pub const DW_AT_COMP_DIR: u16 = 0x1b;
+pub const DW_AT_CONST_VALUE: u16 = 0x1c;
+pub const DW_AT_CONTAINING_TYPE: u16 = 0x1d;
+pub const DW_AT_INLINE: u16 = 0x20;
+pub const DW_AT_LOWER_BOUND: u16 = 0x22;
+pub const DW_AT_PRODUCER: u16 = 0x25;
+pub const DW_AT_PROTOTYPED: u16 = 0x27;
+pub const DW_AT_UPPER_BOUND: u16 = 0x2f;
+pub const DW_AT_ABSTRACT_ORIGIN: u16 = 0x31;
+pub const DW_AT_ACCESSIBILITY: u16 = 0x32;
+pub const DW_AT_ARTIFICIAL: u16 = 0x34;
+pub const DW_AT_COUNT: u16 = 0x37;
+pub const DW_AT_DATA_MEMBER_LOCATION: u16 = 0x38;
+pub const DW_AT_DECL_COLUMN: u16 = 0x39;
+pub const DW_AT_DECL_FILE: u16 = 0x3a;
+pub const DW_AT_DECL_LINE: u16 = 0x3b;
+pub const DW_AT_DECLARATION: u16 = 0x3c;
+pub const DW_AT_DISCR_LIST: u16 = 0x3d;
+pub const DW_AT_ENCODING: u16 = 0x3e;
+pub const DW_AT_EXTERNAL: u16 = 0x3f;
+pub const DW_AT_FRAME_BASE: u16 = 0x40;
+pub const DW_AT_SPECIFICATION: u16 = 0x47;
+pub const DW_AT_TYPE: u16 = 0x49;
+pub const DW_AT_VIRTUALITY: u16 = 0x4c;
+pub const DW_AT_DATA_LOCATION: u16 = 0x50;
+pub const DW_AT_BYTE_STRIDE: u16 = 0x51;
+pub const DW_AT_ENTRY_PC: u16 = 0x52;
+pub const DW_AT_RANGES: u16 = 0x55;
+pub const DW_AT_CALL_COLUMN: u16 = 0x57;
+pub const DW_AT_CALL_FILE: u16 = 0x58;
+pub const DW_AT_CALL_LINE: u16 = 0x59;
+pub const DW_AT_OBJECT_POINTER: u16 = 0x64;
+pub const DW_AT_MAIN_SUBPROGRAM: u16 = 0x6a;
+pub const DW_AT_DATA_BIT_OFFSET: u16 = 0x6b;
+pub const DW_AT_ENUM_CLASS: u16 = 0x6d;
+pub const DW_AT_LINKAGE_NAME: u16 = 0x6e;
+pub const DW_AT_STR_OFFSETS_BASE: u16 = 0x72;
+pub const DW_AT_ADDR_BASE: u16 = 0x73;
+pub const DW_AT_RNGLISTS_BASE: u16 = 0x74;
+pub const DW_AT_DWO_NAME: u16 = 0x76;
+pub const DW_AT_CALL_RETURN_PC: u16 = 0x7d;
+pub const DW_AT_CALL_VALUE: u16 = 0x7e;
+pub const DW_AT_CALL_ORIGIN: u16 = 0x7f;
+pub const DW_AT_LOCLISTS_BASE: u16 = 0x8c;
+pub const DW_AT_ALIGNMENT: u16 = 0x88;
+pub const DW_AT_MIPS_LINKAGE_NAME: u16 = 0x2007;
+pub const DW_AT_GNU_CALL_SITE_VALUE: u16 = 0x2111;
+pub const DW_AT_GNU_DWO_NAME: u16 = 0x2130;
+pub const DW_AT_GNU_DWO_ID: u16 = 0x2131;
+pub const DW_AT_GNU_RANGES_BASE: u16 = 0x2132;
+pub const DW_AT_GNU_ADDR_BASE: u16 = 0x2133;
+
+// Base type encodings
+pub const DW_ATE_ADDRESS: u8 = 0x01;
+pub const DW_ATE_BOOLEAN: u8 = 0x02;
+pub const DW_ATE_COMPLEX_FLOAT: u8 = 0x03;
+pub const DW_ATE_FLOAT: u8 = 0x04;
+pub const DW_ATE_SIGNED: u8 = 0x05;
+pub const DW_ATE_SIGNED_CHAR: u8 = 0x06;
+pub const DW_ATE_UNSIGNED: u8 = 0x07;
+pub const DW_ATE_UNSIGNED_CHAR: u8 = 0x08;
+pub const DW_ATE_UTF: u8 = 0x10;
+pub const DW_ATE_ASCII: u8 = 0x12;
+
+// Accessibility of members and base classes
+pub const DW_ACCESS_PUBLIC: u64 = 0x01;
+pub const DW_ACCESS_PROTECTED: u64 = 0x02;
+pub const DW_ACCESS_PRIVATE: u64 = 0x03;
+
+// Discriminant descriptors of `DW_AT_discr_list`
+pub const DW_DSC_LABEL: u8 = 0x00;
+pub const DW_DSC_RANGE: u8 = 0x01;
+
+// Source languages
+pub const DW_LANG_C89: u16 = 0x01;
+pub const DW_LANG_C: u16 = 0x02;
+pub const DW_LANG_C_PLUS_PLUS: u16 = 0x04;
+pub const DW_LANG_C99: u16 = 0x0c;
+pub const DW_LANG_RUST: u16 = 0x1c;
+pub const DW_LANG_C11: u16 = 0x1d;
+pub const DW_LANG_C_PLUS_PLUS_11: u16 = 0x1a;
+pub const DW_LANG_C_PLUS_PLUS_14: u16 = 0x21;
+pub const DW_LANG_C17: u16 = 0x2c;
+
+// Attribute forms
+pub const DW_FORM_ADDR: u16 = 0x01;
+pub const DW_FORM_BLOCK2: u16 = 0x03;
+pub const DW_FORM_BLOCK4: u16 = 0x04;
+pub const DW_FORM_DATA2: u16 = 0x05;
+pub const DW_FORM_DATA4: u16 = 0x06;
+pub const DW_FORM_DATA8: u16 = 0x07;
+pub const DW_FORM_STRING: u16 = 0x08;
+pub const DW_FORM_BLOCK: u16 = 0x09;
+pub const DW_FORM_BLOCK1: u16 = 0x0a;
+pub const DW_FORM_DATA1: u16 = 0x0b;
+pub const DW_FORM_FLAG: u16 = 0x0c;
+pub const DW_FORM_SDATA: u16 = 0x0d;
+pub const DW_FORM_STRP: u16 = 0x0e;
+pub const DW_FORM_UDATA: u16 = 0x0f;
+pub const DW_FORM_REF_ADDR: u16 = 0x10;
+pub const DW_FORM_REF1: u16 = 0x11;
+pub const DW_FORM_REF2: u16 = 0x12;
+pub const DW_FORM_REF4: u16 = 0x13;
+pub const DW_FORM_REF8: u16 = 0x14;
+pub const DW_FORM_REF_UDATA: u16 = 0x15;
+pub const DW_FORM_INDIRECT: u16 = 0x16;
+pub const DW_FORM_SEC_OFFSET: u16 = 0x17;
+pub const DW_FORM_EXPRLOC: u16 = 0x18;
+pub const DW_FORM_FLAG_PRESENT: u16 = 0x19;
+pub const DW_FORM_STRX: u16 = 0x1a;
+pub const DW_FORM_ADDRX: u16 = 0x1b;
+pub const DW_FORM_REF_SUP4: u16 = 0x1c;
+pub const DW_FORM_STRP_SUP: u16 = 0x1d;
+pub const DW_FORM_DATA16: u16 = 0x1e;
+pub const DW_FORM_LINE_STRP: u16 = 0x1f;
+pub const DW_FORM_REF_SIG8: u16 = 0x20;
+pub const DW_FORM_IMPLICIT_CONST: u16 = 0x21;
+pub const DW_FORM_LOCLISTX: u16 = 0x22;
+pub const DW_FORM_RNGLISTX: u16 = 0x23;
+pub const DW_FORM_REF_SUP8: u16 = 0x24;
+pub const DW_FORM_STRX1: u16 = 0x25;
+pub const DW_FORM_STRX2: u16 = 0x26;
+pub const DW_FORM_STRX3: u16 = 0x27;
+pub const DW_FORM_STRX4: u16 = 0x28;
+pub const DW_FORM_ADDRX1: u16 = 0x29;
+pub const DW_FORM_ADDRX2: u16 = 0x2a;
+pub const DW_FORM_ADDRX3: u16 = 0x2b;
+pub const DW_FORM_ADDRX4: u16 = 0x2c;
+pub const DW_FORM_GNU_ADDR_INDEX: u16 = 0x1f01;
+pub const DW_FORM_GNU_STR_INDEX: u16 = 0x1f02;
+pub const DW_FORM_GNU_REF_ALT: u16 = 0x1f20;
+pub const DW_FORM_GNU_STRP_ALT: u16 = 0x1f21;
+
+// Expression operations
+pub const DW_OP_ADDR: u8 = 0x03;
+pub const DW_OP_DEREF: u8 = 0x06;
+pub const DW_OP_CONST1U: u8 = 0x08;
+pub const DW_OP_CONST1S: u8 = 0x09;
+pub const DW_OP_CONST2U: u8 = 0x0a;
+pub const DW_OP_CONST2S: u8 = 0x0b;
+pub const DW_OP_CONST4U: u8 = 0x0c;
+pub const DW_OP_CONST4S: u8 = 0x0d;
+pub const DW_OP_CONST8U: u8 = 0x0e;
+pub const DW_OP_CONST8S: u8 = 0x0f;
+pub const DW_OP_CONSTU: u8 = 0x10;
+pub const DW_OP_CONSTS: u8 = 0x11;
+pub const DW_OP_DUP: u8 = 0x12;
+pub const DW_OP_DROP: u8 = 0x13;
+pub const DW_OP_OVER: u8 = 0x14;
+pub const DW_OP_PICK: u8 = 0x15;
+pub const DW_OP_SWAP: u8 = 0x16;
+pub const DW_OP_ROT: u8 = 0x17;
+pub const DW_OP_XDEREF: u8 = 0x18;
+pub const DW_OP_ABS: u8 = 0x19;
+pub const DW_OP_AND: u8 = 0x1a;
+pub const DW_OP_DIV: u8 = 0x1b;
+pub const DW_OP_MINUS: u8 = 0x1c;
+pub const DW_OP_MOD: u8 = 0x1d;
+pub const DW_OP_MUL: u8 = 0x1e;
+pub const DW_OP_NEG: u8 = 0x1f;
+pub const DW_OP_NOT: u8 = 0x20;
+pub const DW_OP_OR: u8 = 0x21;
+pub const DW_OP_PLUS: u8 = 0x22;
+pub const DW_OP_PLUS_UCONST: u8 = 0x23;
+pub const DW_OP_SHL: u8 = 0x24;
+pub const DW_OP_SHR: u8 = 0x25;
+pub const DW_OP_SHRA: u8 = 0x26;
+pub const DW_OP_XOR: u8 = 0x27;
+pub const DW_OP_BRA: u8 = 0x28;
+pub const DW_OP_EQ: u8 = 0x29;
+pub const DW_OP_GE: u8 = 0x2a;
+pub const DW_OP_GT: u8 = 0x2b;
+pub const DW_OP_LE: u8 = 0x2c;
+pub const DW_OP_LT: u8 = 0x2d;
+pub const DW_OP_NE: u8 = 0x2e;
+pub const DW_OP_SKIP: u8 = 0x2f;
+pub const DW_OP_LIT0: u8 = 0x30;
+pub const DW_OP_LIT31: u8 = 0x4f;
+pub const DW_OP_REG0: u8 = 0x50;
+pub const DW_OP_REG31: u8 = 0x6f;
+pub const DW_OP_BREG0: u8 = 0x70;
+pub const DW_OP_BREG31: u8 = 0x8f;
+pub const DW_OP_REGX: u8 = 0x90;
+pub const DW_OP_FBREG: u8 = 0x91;
+pub const DW_OP_BREGX: u8 = 0x92;
+pub const DW_OP_PIECE: u8 = 0x93;
+pub const DW_OP_DEREF_SIZE: u8 = 0x94;
+pub const DW_OP_XDEREF_SIZE: u8 = 0x95;
+pub const DW_OP_NOP: u8 = 0x96;
+pub const DW_OP_PUSH_OBJECT_ADDRESS: u8 = 0x97;
+pub const DW_OP_CALL2: u8 = 0x98;
+pub const DW_OP_CALL4: u8 = 0x99;
+pub const DW_OP_CALL_REF: u8 = 0x9a;
+pub const DW_OP_FORM_TLS_ADDRESS: u8 = 0x9b;
+pub const DW_OP_CALL_FRAME_CFA: u8 = 0x9c;
+pub const DW_OP_BIT_PIECE: u8 = 0x9d;
+pub const DW_OP_IMPLICIT_VALUE: u8 = 0x9e;
+pub const DW_OP_STACK_VALUE: u8 = 0x9f;
+pub const DW_OP_IMPLICIT_POINTER: u8 = 0xa0;
+pub const DW_OP_ADDRX: u8 = 0xa1;
+pub const DW_OP_CONSTX: u8 = 0xa2;
+pub const DW_OP_ENTRY_VALUE: u8 = 0xa3;
+pub const DW_OP_CONST_TYPE: u8 = 0xa4;
+pub const DW_OP_REGVAL_TYPE: u8 = 0xa5;
+pub const DW_OP_DEREF_TYPE: u8 = 0xa6;
+pub const DW_OP_XDEREF_TYPE: u8 = 0xa7;
+pub const DW_OP_CONVERT: u8 = 0xa8;
+pub const DW_OP_REINTERPRET: u8 = 0xa9;
+pub const DW_OP_GNU_PUSH_TLS_ADDRESS: u8 = 0xe0;
+pub const DW_OP_GNU_UNINIT: u8 = 0xf0;
+pub const DW_OP_GNU_ENCODED_ADDR: u8 = 0xf1;
+pub const DW_OP_GNU_IMPLICIT_POINTER: u8 = 0xf2;
+pub const DW_OP_GNU_ENTRY_VALUE: u8 = 0xf3;
+pub const DW_OP_GNU_CONST_TYPE: u8 = 0xf4;
+pub const DW_OP_GNU_REGVAL_TYPE: u8 = 0xf5;
+pub const DW_OP_GNU_DEREF_TYPE: u8 = 0xf6;
+pub const DW_OP_GNU_CONVERT: u8 = 0xf7;
+pub const DW_OP_GNU_REINTERPRET: u8 = 0xf9;
+pub const DW_OP_GNU_PARAMETER_REF: u8 = 0xfa;
+pub const DW_OP_GNU_ADDR_INDEX: u8 = 0xfb;
+pub const DW_OP_GNU_CONST_INDEX: u8 = 0xfc;
+pub const DW_OP_GNU_VARIABLE_VALUE: u8 = 0xfd;
+
+// Location list entries, DWARF 5
+pub const DW_LLE_END_OF_LIST: u8 = 0x00;
+pub const DW_LLE_BASE_ADDRESSX: u8 = 0x01;
+pub const DW_LLE_STARTX_ENDX: u8 = 0x02;
+pub const DW_LLE_STARTX_LENGTH: u8 = 0x03;
+pub const DW_LLE_OFFSET_PAIR: u8 = 0x04;
+pub const DW_LLE_DEFAULT_LOCATION: u8 = 0x05;
+pub const DW_LLE_BASE_ADDRESS: u8 = 0x06;
+pub const DW_LLE_START_END: u8 = 0x07;
+pub const DW_LLE_START_LENGTH: u8 = 0x08;
+pub const DW_LLE_GNU_VIEW_PAIR: u8 = 0x09;
+
+// Location list entries of `.debug_loc.dwo`, GNU split DWARF before DWARF 5
+pub const DW_LLE_GNU_END_OF_LIST_ENTRY: u8 = 0x00;
+pub const DW_LLE_GNU_BASE_ADDRESS_SELECTION_ENTRY: u8 = 0x01;
+pub const DW_LLE_GNU_START_END_ENTRY: u8 = 0x02;
+pub const DW_LLE_GNU_START_LENGTH_ENTRY: u8 = 0x03;
+
+// Range list entries, DWARF 5
+pub const DW_RLE_END_OF_LIST: u8 = 0x00;
+pub const DW_RLE_BASE_ADDRESSX: u8 = 0x01;
+pub const DW_RLE_STARTX_ENDX: u8 = 0x02;
+pub const DW_RLE_STARTX_LENGTH: u8 = 0x03;
+pub const DW_RLE_OFFSET_PAIR: u8 = 0x04;
+pub const DW_RLE_BASE_ADDRESS: u8 = 0x05;
+pub const DW_RLE_START_END: u8 = 0x06;
+pub const DW_RLE_START_LENGTH: u8 = 0x07;
+
+// Section identifiers of package index tables, DWARF 5
+pub const DW_SECT_INFO: u32 = 1;
+pub const DW_SECT_ABBREV: u32 = 3;
+pub const DW_SECT_LINE: u32 = 4;
+pub const DW_SECT_LOCLISTS: u32 = 5;
+pub const DW_SECT_STR_OFFSETS: u32 = 6;
+pub const DW_SECT_MACRO: u32 = 7;
+pub const DW_SECT_RNGLISTS: u32 = 8;
+// Identifiers of version 2 packages, the GNU extension before DWARF 5, that differ
+pub const DW_SECT_GNU_TYPES: u32 = 2;
+pub const DW_SECT_GNU_LOC: u32 = 5;
+pub const DW_SECT_GNU_MACINFO: u32 = 7;
+pub const DW_SECT_GNU_MACRO: u32 = 8;
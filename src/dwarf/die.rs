@@ -0,0 +1,125 @@
+/// The value of an attribute, decoded from its form. String and address indexes
+/// are resolved when the DIE is read, and are only left as indexes if the sections
+/// they refer to are missing, like for split DWARF units.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AttributeValue<'a> {
+    Address(u64),
+    Block(&'a [u8]),
+    /// Constants of the `data` and `udata` forms, their signedness depends on the attribute
+    Udata(u64),
+    Sdata(i64),
+    Flag(bool),
+    String(&'a str),
+    /// Offset of a DIE relative to the start of its unit
+    UnitRef(u64),
+    /// Offset of a DIE in `.debug_info`
+    DebugInfoRef(u64),
+    /// Offset into another section, e.g. `.debug_line`, `.debug_loclists` or `.debug_rnglists`
+    SecOffset(u64),
+    Exprloc(&'a [u8]),
+    /// Signature of a type unit
+    TypeSignature(u64),
+    LoclistsIndex(u64),
+    RnglistsIndex(u64),
+    StrIndex(u64),
+    AddrIndex(u64),
+    /// Offset of a string or DIE in a supplementary object file, which is not loaded
+    Supplementary(u64),
+}
+
+impl<'a> AttributeValue<'a> {
+    /// The value as an unsigned constant
+    pub fn udata(&self) -> Option<u64> {
+        match *self {
+            AttributeValue::Udata(value) => Some(value),
+            AttributeValue::Sdata(value) => Some(value as u64),
+            _ => None,
+        }
+    }
+
+    /// The value as a signed constant
+    pub fn sdata(&self) -> Option<i64> {
+        match *self {
+            AttributeValue::Udata(value) => Some(value as i64),
+            AttributeValue::Sdata(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn string(&self) -> Option<&'a str> {
+        match *self {
+            AttributeValue::String(string) => Some(string),
+            _ => None,
+        }
+    }
+
+    pub fn address(&self) -> Option<u64> {
+        match *self {
+            AttributeValue::Address(address) => Some(address),
+            _ => None,
+        }
+    }
+
+    pub fn flag(&self) -> bool {
+        matches!(*self, AttributeValue::Flag(true))
+    }
+
+    /// Offset of a section or of a DIE in `.debug_info`, given the offset of the unit
+    pub fn offset(&self) -> Option<u64> {
+        match *self {
+            AttributeValue::SecOffset(offset) | AttributeValue::DebugInfoRef(offset) => {
+                Some(offset)
+            }
+            // DWARF 2 and 3 encode section offsets as data4 or data8
+            AttributeValue::Udata(offset) => Some(offset),
+            _ => None,
+        }
+    }
+
+    /// A DWARF expression, stored as a block before DWARF 4
+    pub fn expression(&self) -> Option<&'a [u8]> {
+        match *self {
+            AttributeValue::Exprloc(expression) | AttributeValue::Block(expression) => {
+                Some(expression)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attribute<'a> {
+    pub name: u16,
+    pub form: u16,
+    pub value: AttributeValue<'a>,
+}
+
+/// A debugging information entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Die<'a> {
+    /// Offset of the entry in `.debug_info`
+    pub offset: u64,
+    pub tag: u16,
+    pub has_children: bool,
+    pub attributes: Vec<Attribute<'a>>,
+    /// Offset after the attributes, where the first child or the next sibling starts
+    pub(crate) next_offset: u64,
+}
+
+impl<'a> Die<'a> {
+    pub fn attr(&self, name: u16) -> Option<&AttributeValue<'a>> {
+        self.attributes
+            .iter()
+            .find(|attribute| attribute.name == name)
+            .map(|attribute| &attribute.value)
+    }
+
+    pub fn has_attr(&self, name: u16) -> bool {
+        self.attr(name).is_some()
+    }
+
+    pub fn name(&self) -> Option<&'a str> {
+        self.attr(super::constants::DW_AT_NAME)
+            .and_then(AttributeValue::string)
+    }
+}
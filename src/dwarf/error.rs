@@ -0,0 +1,100 @@
+use std::error::Error;
+use std::fmt::Display;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DwarfError {
+    UnexpectedEof,
+    Leb128Overflow,
+    MissingSection(&'static str),
+    UnsupportedVersion(u16),
+    UnsupportedAddressSize(u8),
+    UnknownPointerEncoding(u8),
+    UnknownAugmentation(String),
+    InvalidCiePointer(u64),
+    UnknownCfiInstruction(u8),
+    InvalidRegisterState,
+    UnknownAbbreviation(u64),
+    UnknownForm(u16),
+    InvalidDieOffset(u64),
+    UnknownOperation(u8),
+    StackUnderflow,
+    DivisionByZero,
+    InvalidBranch(i64),
+    /// An expression ran more operations than the limit, e.g. a branch looping forever
+    TooManyOperations(usize),
+    /// `DW_OP_call*` nested deeper than the limit, e.g. a location calling itself
+    CallTooDeep(usize),
+    UnknownRegister(u16),
+    InvalidMemoryAccess(u64),
+    /// Something an expression depends on is not known, e.g. the frame base
+    Unavailable(&'static str),
+    UnknownLocationListEntry(u8),
+    UnknownRangeListEntry(u8),
+}
+
+impl Display for DwarfError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DwarfError::UnexpectedEof => f.write_str("unexpected end of dwarf data"),
+            DwarfError::Leb128Overflow => f.write_str("leb128 value does not fit into 64 bits"),
+            DwarfError::MissingSection(name) => write!(f, "missing section {}", name),
+            DwarfError::UnsupportedVersion(version) => {
+                write!(f, "unsupported dwarf version {}", version)
+            }
+            DwarfError::UnsupportedAddressSize(size) => {
+                write!(f, "unsupported address size {}", size)
+            }
+            DwarfError::UnknownPointerEncoding(encoding) => {
+                write!(f, "unknown pointer encoding 0x{:x}", encoding)
+            }
+            DwarfError::UnknownAugmentation(augmentation) => {
+                write!(f, "unknown augmentation string {:?}", augmentation)
+            }
+            DwarfError::InvalidCiePointer(offset) => {
+                write!(f, "no cie at offset 0x{:x}", offset)
+            }
+            DwarfError::UnknownCfiInstruction(opcode) => {
+                write!(f, "unknown call frame instruction 0x{:x}", opcode)
+            }
+            DwarfError::InvalidRegisterState => {
+                f.write_str("restore state without a remembered state")
+            }
+            DwarfError::UnknownAbbreviation(code) => write!(f, "unknown abbreviation {}", code),
+            DwarfError::UnknownForm(form) => write!(f, "unknown attribute form 0x{:x}", form),
+            DwarfError::InvalidDieOffset(offset) => {
+                write!(f, "no debugging information entry at offset 0x{:x}", offset)
+            }
+            DwarfError::UnknownOperation(opcode) => {
+                write!(f, "unknown expression operation 0x{:x}", opcode)
+            }
+            DwarfError::StackUnderflow => f.write_str("expression stack underflow"),
+            DwarfError::DivisionByZero => f.write_str("division by zero in expression"),
+            DwarfError::InvalidBranch(offset) => {
+                write!(f, "expression branch to invalid offset {}", offset)
+            }
+            DwarfError::TooManyOperations(limit) => {
+                write!(f, "expression executes more than {} operations", limit)
+            }
+            DwarfError::CallTooDeep(limit) => {
+                write!(f, "expression calls nest deeper than {}", limit)
+            }
+            DwarfError::UnknownRegister(register) => {
+                write!(f, "unknown dwarf register {}", register)
+            }
+            DwarfError::InvalidMemoryAccess(address) => {
+                write!(f, "cannot access memory at address 0x{:x}", address)
+            }
+            DwarfError::Unavailable(what) => write!(f, "{} is not available", what),
+            DwarfError::UnknownLocationListEntry(kind) => {
+                write!(f, "unknown location list entry 0x{:x}", kind)
+            }
+            DwarfError::UnknownRangeListEntry(kind) => {
+                write!(f, "unknown range list entry 0x{:x}", kind)
+            }
+        }
+    }
+}
+
+impl Error for DwarfError {}
+
+pub type Result<T> = std::result::Result<T, DwarfError>;
@@ -0,0 +1,656 @@
+use super::constants::*;
+use super::error::{DwarfError, Result};
+use super::frame::{read_encoded_pointer, PointerBases};
+use super::reader::{Format, Reader};
+use super::unit::Unit;
+
+/// Bounds the operations an expression executes, since branches of malformed DWARF
+/// can loop forever
+const MAX_OPERATIONS: usize = 100_000;
+/// Bounds the nesting of `DW_OP_call*`, since a location can call itself
+const MAX_CALL_DEPTH: usize = 16;
+
+/// Where the value described by an expression is stored
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Location<'a> {
+    /// The value is not available, e.g. it was optimized out
+    Empty,
+    Memory(u64),
+    Register(u16),
+    /// The value itself, computed by the expression
+    Value(u64),
+    /// The bytes of the value, stored in the expression
+    Bytes(&'a [u8]),
+    /// An optimized out pointer to the value of the DIE at `die_offset` plus `offset`
+    ImplicitPointer {
+        die_offset: u64,
+        offset: i64,
+    },
+    /// The value is split over several locations, in order of increasing significance
+    Pieces(Vec<Piece<'a>>),
+}
+
+/// Part of a value stored in a location of its own
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Piece<'a> {
+    pub size_in_bits: u64,
+    /// Offset of the piece into its location, only used by `DW_OP_bit_piece`
+    pub bit_offset: u64,
+    pub location: Location<'a>,
+}
+
+/// What an expression reads from the program it describes. Only registers and
+/// memory are required, the rest fails with `DwarfError::Unavailable` by default.
+pub trait EvaluationContext {
+    fn register(&self, register: u16) -> Result<u64>;
+
+    /// Reads `size` bytes, at most 8, at `address` as a little endian value
+    fn read_memory(&self, address: u64, size: u8) -> Result<u64>;
+
+    /// Offset to add to the link-time addresses of `DW_OP_addr` and `DW_OP_addrx`
+    fn load_bias(&self) -> u64 {
+        0
+    }
+
+    /// The value of `DW_AT_frame_base` of the current function, used by `DW_OP_fbreg`
+    fn frame_base(&self) -> Result<u64> {
+        Err(DwarfError::Unavailable("frame base"))
+    }
+
+    fn call_frame_cfa(&self) -> Result<u64> {
+        Err(DwarfError::Unavailable("call frame address"))
+    }
+
+    /// The address of the thread local variable at `offset` of the TLS block of the module
+    fn tls_address(&self, _offset: u64) -> Result<u64> {
+        Err(DwarfError::Unavailable("thread local storage"))
+    }
+
+    /// The value `expression` had when the current function was entered
+    fn entry_value(&self, _expression: &[u8]) -> Result<u64> {
+        Err(DwarfError::Unavailable("entry value"))
+    }
+
+    /// The address of the object whose member is being located, used by `DW_OP_push_object_address`
+    fn object_address(&self) -> Result<u64> {
+        Err(DwarfError::Unavailable("object address"))
+    }
+}
+
+/// Stack machine evaluating DWARF expressions. Values are untyped 64-bit integers,
+/// typed operations of DWARF 5 are accepted but their types are ignored.
+pub struct Evaluator<'u, 'a, C> {
+    context: &'u C,
+    /// The unit of the expression, needed for indexed addresses and `DW_OP_call*`
+    unit: Option<&'u Unit<'a>>,
+    address_size: u8,
+    format: Format,
+    stack: Vec<u64>,
+    /// A location set by a register or implicit location operation, ended by a piece
+    /// or by the end of the expression
+    pending: Option<Location<'a>>,
+    pieces: Vec<Piece<'a>>,
+    /// Operations executed so far, including those of called expressions
+    operations: usize,
+    call_depth: usize,
+}
+
+impl<'u, 'a, C: EvaluationContext> Evaluator<'u, 'a, C> {
+    /// An evaluator for expressions outside of a unit, like those of call frame information
+    pub fn new(context: &'u C, address_size: u8) -> Self {
+        Self {
+            context,
+            unit: None,
+            address_size,
+            format: Format::Dwarf32,
+            stack: Vec::new(),
+            pending: None,
+            pieces: Vec::new(),
+            operations: 0,
+            call_depth: 0,
+        }
+    }
+
+    pub fn for_unit(context: &'u C, unit: &'u Unit<'a>) -> Self {
+        Self {
+            unit: Some(unit),
+            format: unit.format,
+            ..Self::new(context, unit.address_size)
+        }
+    }
+
+    /// Pushes a value before evaluating, e.g. the CFA for `DW_CFA_expression`
+    pub fn push(&mut self, value: u64) {
+        self.stack.push(value);
+    }
+
+    pub fn evaluate(mut self, expression: &'a [u8]) -> Result<Location<'a>> {
+        self.execute(expression)?;
+
+        if !self.pieces.is_empty() {
+            return Ok(Location::Pieces(self.pieces));
+        }
+        if let Some(location) = self.pending {
+            return Ok(location);
+        }
+        // An empty expression describes a value that is not available
+        Ok(self.stack.pop().map_or(Location::Empty, Location::Memory))
+    }
+
+    fn pop(&mut self) -> Result<u64> {
+        self.stack.pop().ok_or(DwarfError::StackUnderflow)
+    }
+
+    fn peek(&self, index: usize) -> Result<u64> {
+        self.stack
+            .len()
+            .checked_sub(index + 1)
+            .map(|position| self.stack[position])
+            .ok_or(DwarfError::StackUnderflow)
+    }
+
+    /// Pops the two top values and pushes `operation(second, top)`
+    fn binary<F>(&mut self, operation: F) -> Result<()>
+    where
+        F: FnOnce(u64, u64) -> Result<u64>,
+    {
+        let top = self.pop()?;
+        let second = self.pop()?;
+        self.stack.push(operation(second, top)?);
+        Ok(())
+    }
+
+    fn compare<F>(&mut self, comparison: F) -> Result<()>
+    where
+        F: FnOnce(i64, i64) -> bool,
+    {
+        self.binary(|a, b| Ok(comparison(a as i64, b as i64) as u64))
+    }
+
+    fn address_by_index(&self, index: u64) -> Result<u64> {
+        self.unit
+            .and_then(|unit| unit.address_by_index(index))
+            .ok_or(DwarfError::Unavailable("indexed address"))
+    }
+
+    fn read_type_size(reader: &mut Reader) -> Result<u8> {
+        let size = reader.read_u8()?;
+        // The type of the value, which is not tracked
+        reader.read_uleb128()?;
+        Ok(size)
+    }
+
+    /// Executes the operations of `expression`, and of the expressions it calls
+    fn execute(&mut self, expression: &'a [u8]) -> Result<()> {
+        let mut reader = Reader::new(expression);
+
+        while !reader.is_empty() {
+            self.operations += 1;
+            if self.operations > MAX_OPERATIONS {
+                return Err(DwarfError::TooManyOperations(MAX_OPERATIONS));
+            }
+            let opcode = reader.read_u8()?;
+            match opcode {
+                DW_OP_ADDR => {
+                    let address = reader.read_address(self.address_size)?;
+                    self.push(address.wrapping_add(self.context.load_bias()));
+                }
+                DW_OP_ADDRX | DW_OP_GNU_ADDR_INDEX => {
+                    let address = self.address_by_index(reader.read_uleb128()?)?;
+                    self.push(address.wrapping_add(self.context.load_bias()));
+                }
+                // Constants that are not relocated, like offsets into the TLS block
+                DW_OP_CONSTX | DW_OP_GNU_CONST_INDEX => {
+                    let value = self.address_by_index(reader.read_uleb128()?)?;
+                    self.push(value);
+                }
+                DW_OP_GNU_ENCODED_ADDR => {
+                    let encoding = reader.read_u8()?;
+                    let address = read_encoded_pointer(
+                        &mut reader,
+                        encoding,
+                        &PointerBases::default(),
+                        self.address_size,
+                    )?
+                    .unwrap_or_default();
+                    self.push(address.wrapping_add(self.context.load_bias()));
+                }
+                DW_OP_CONST1U => self.push(reader.read_u8()? as u64),
+                DW_OP_CONST1S => self.push(reader.read_i8()? as i64 as u64),
+                DW_OP_CONST2U => self.push(reader.read_u16()? as u64),
+                DW_OP_CONST2S => self.push(reader.read_i16()? as i64 as u64),
+                DW_OP_CONST4U => self.push(reader.read_u32()? as u64),
+                DW_OP_CONST4S => self.push(reader.read_i32()? as i64 as u64),
+                DW_OP_CONST8U => self.push(reader.read_u64()?),
+                DW_OP_CONST8S => self.push(reader.read_i64()? as u64),
+                DW_OP_CONSTU => self.push(reader.read_uleb128()?),
+                DW_OP_CONSTS => self.push(reader.read_sleb128()? as u64),
+                DW_OP_CONST_TYPE | DW_OP_GNU_CONST_TYPE => {
+                    reader.read_uleb128()?;
+                    let size = reader.read_u8()? as usize;
+                    let bytes = reader.read_bytes(size)?;
+                    let mut value = [0; 8];
+                    let len = size.min(8);
+                    value[..len].copy_from_slice(&bytes[..len]);
+                    self.push(u64::from_le_bytes(value));
+                }
+                DW_OP_LIT0..=DW_OP_LIT31 => self.push((opcode - DW_OP_LIT0) as u64),
+
+                DW_OP_DUP => self.push(self.peek(0)?),
+                DW_OP_DROP => {
+                    self.pop()?;
+                }
+                DW_OP_OVER => self.push(self.peek(1)?),
+                DW_OP_PICK => {
+                    let index = reader.read_u8()? as usize;
+                    self.push(self.peek(index)?);
+                }
+                DW_OP_SWAP => {
+                    let top = self.pop()?;
+                    let second = self.pop()?;
+                    self.stack.extend([top, second]);
+                }
+                DW_OP_ROT => {
+                    let top = self.pop()?;
+                    let second = self.pop()?;
+                    let third = self.pop()?;
+                    self.stack.extend([top, third, second]);
+                }
+
+                DW_OP_DEREF => {
+                    let address = self.pop()?;
+                    self.push(self.context.read_memory(address, self.address_size)?);
+                }
+                DW_OP_DEREF_SIZE => {
+                    let size = reader.read_u8()?;
+                    let address = self.pop()?;
+                    self.push(self.context.read_memory(address, size)?);
+                }
+                DW_OP_DEREF_TYPE | DW_OP_GNU_DEREF_TYPE => {
+                    let size = Self::read_type_size(&mut reader)?;
+                    let address = self.pop()?;
+                    self.push(self.context.read_memory(address, size)?);
+                }
+                // Address spaces are not supported, the process has a single one
+                DW_OP_XDEREF | DW_OP_XDEREF_SIZE | DW_OP_XDEREF_TYPE => {
+                    let size = match opcode {
+                        DW_OP_XDEREF => self.address_size,
+                        DW_OP_XDEREF_SIZE => reader.read_u8()?,
+                        _ => Self::read_type_size(&mut reader)?,
+                    };
+                    let address = self.pop()?;
+                    self.pop()?;
+                    self.push(self.context.read_memory(address, size)?);
+                }
+
+                DW_OP_ABS => {
+                    let value = self.pop()? as i64;
+                    self.push(value.wrapping_abs() as u64);
+                }
+                DW_OP_NEG => {
+                    let value = self.pop()? as i64;
+                    self.push(value.wrapping_neg() as u64);
+                }
+                DW_OP_NOT => {
+                    let value = self.pop()?;
+                    self.push(!value);
+                }
+                DW_OP_AND => self.binary(|a, b| Ok(a & b))?,
+                DW_OP_OR => self.binary(|a, b| Ok(a | b))?,
+                DW_OP_XOR => self.binary(|a, b| Ok(a ^ b))?,
+                DW_OP_PLUS => self.binary(|a, b| Ok(a.wrapping_add(b)))?,
+                DW_OP_MINUS => self.binary(|a, b| Ok(a.wrapping_sub(b)))?,
+                DW_OP_MUL => self.binary(|a, b| Ok(a.wrapping_mul(b)))?,
+                DW_OP_DIV => self.binary(|a, b| match b {
+                    0 => Err(DwarfError::DivisionByZero),
+                    _ => Ok((a as i64).wrapping_div(b as i64) as u64),
+                })?,
+                DW_OP_MOD => {
+                    self.binary(|a, b| a.checked_rem(b).ok_or(DwarfError::DivisionByZero))?
+                }
+                DW_OP_SHL => self.binary(|a, b| Ok(a.checked_shl(b as u32).unwrap_or(0)))?,
+                DW_OP_SHR => self.binary(|a, b| Ok(a.checked_shr(b as u32).unwrap_or(0)))?,
+                DW_OP_SHRA => self.binary(|a, b| Ok(((a as i64) >> b.min(63)) as u64))?,
+                DW_OP_PLUS_UCONST => {
+                    let value = self.pop()?;
+                    self.push(value.wrapping_add(reader.read_uleb128()?));
+                }
+
+                DW_OP_EQ => self.compare(|a, b| a == b)?,
+                DW_OP_NE => self.compare(|a, b| a != b)?,
+                DW_OP_GE => self.compare(|a, b| a >= b)?,
+                DW_OP_GT => self.compare(|a, b| a > b)?,
+                DW_OP_LE => self.compare(|a, b| a <= b)?,
+                DW_OP_LT => self.compare(|a, b| a < b)?,
+
+                DW_OP_SKIP | DW_OP_BRA => {
+                    let offset = reader.read_i16()? as i64;
+                    if opcode == DW_OP_BRA && self.pop()? == 0 {
+                        continue;
+                    }
+                    let target = reader.offset() as i64 + offset;
+                    if target < 0 || target > expression.len() as i64 {
+                        return Err(DwarfError::InvalidBranch(target));
+                    }
+                    reader.set_offset(target as usize);
+                }
+
+                DW_OP_REG0..=DW_OP_REG31 => {
+                    self.pending = Some(Location::Register((opcode - DW_OP_REG0) as u16))
+                }
+                DW_OP_REGX => {
+                    self.pending = Some(Location::Register(reader.read_uleb128()? as u16))
+                }
+                DW_OP_BREG0..=DW_OP_BREG31 => {
+                    let value = self.context.register((opcode - DW_OP_BREG0) as u16)?;
+                    self.push(value.wrapping_add(reader.read_sleb128()? as u64));
+                }
+                DW_OP_BREGX => {
+                    let value = self.context.register(reader.read_uleb128()? as u16)?;
+                    self.push(value.wrapping_add(reader.read_sleb128()? as u64));
+                }
+                DW_OP_REGVAL_TYPE | DW_OP_GNU_REGVAL_TYPE => {
+                    let value = self.context.register(reader.read_uleb128()? as u16)?;
+                    reader.read_uleb128()?;
+                    self.push(value);
+                }
+                DW_OP_FBREG => {
+                    let frame_base = self.context.frame_base()?;
+                    self.push(frame_base.wrapping_add(reader.read_sleb128()? as u64));
+                }
+                DW_OP_CALL_FRAME_CFA => self.push(self.context.call_frame_cfa()?),
+                DW_OP_FORM_TLS_ADDRESS | DW_OP_GNU_PUSH_TLS_ADDRESS => {
+                    let offset = self.pop()?;
+                    self.push(self.context.tls_address(offset)?);
+                }
+                DW_OP_PUSH_OBJECT_ADDRESS => self.push(self.context.object_address()?),
+                DW_OP_ENTRY_VALUE | DW_OP_GNU_ENTRY_VALUE => {
+                    let len = reader.read_uleb128()? as usize;
+                    let value = self.context.entry_value(reader.read_bytes(len)?)?;
+                    self.push(value);
+                }
+
+                DW_OP_IMPLICIT_VALUE => {
+                    let len = reader.read_uleb128()? as usize;
+                    self.pending = Some(Location::Bytes(reader.read_bytes(len)?));
+                }
+                DW_OP_STACK_VALUE => self.pending = Some(Location::Value(self.pop()?)),
+                DW_OP_IMPLICIT_POINTER | DW_OP_GNU_IMPLICIT_POINTER => {
+                    let die_offset = self.read_reference(&mut reader)?;
+                    let offset = reader.read_sleb128()?;
+                    self.pending = Some(Location::ImplicitPointer { die_offset, offset });
+                }
+                DW_OP_PIECE => {
+                    let size_in_bits = reader.read_uleb128()? * 8;
+                    self.end_piece(size_in_bits, 0);
+                }
+                DW_OP_BIT_PIECE => {
+                    let size_in_bits = reader.read_uleb128()?;
+                    let bit_offset = reader.read_uleb128()?;
+                    self.end_piece(size_in_bits, bit_offset);
+                }
+
+                DW_OP_CALL2 | DW_OP_CALL4 | DW_OP_CALL_REF => {
+                    let offset = match opcode {
+                        DW_OP_CALL2 => reader.read_u16()? as u64,
+                        DW_OP_CALL4 => reader.read_u32()? as u64,
+                        _ => self.read_reference(&mut reader)?,
+                    };
+                    self.call(opcode, offset)?;
+                }
+                DW_OP_CONVERT | DW_OP_GNU_CONVERT | DW_OP_REINTERPRET | DW_OP_GNU_REINTERPRET => {
+                    reader.read_uleb128()?;
+                }
+                DW_OP_NOP | DW_OP_GNU_UNINIT => {}
+                DW_OP_GNU_PARAMETER_REF => {
+                    return Err(DwarfError::Unavailable("parameter of the caller"))
+                }
+                DW_OP_GNU_VARIABLE_VALUE => {
+                    return Err(DwarfError::Unavailable("value of another variable"))
+                }
+                _ => return Err(DwarfError::UnknownOperation(opcode)),
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads the `.debug_info` offset of `DW_OP_call_ref` and `DW_OP_implicit_pointer`
+    fn read_reference(&self, reader: &mut Reader) -> Result<u64> {
+        match self.unit {
+            // DWARF 2 stored references with the size of an address
+            Some(unit) if unit.version == 2 => reader.read_address(self.address_size),
+            _ => reader.read_offset(self.format),
+        }
+    }
+
+    fn end_piece(&mut self, size_in_bits: u64, bit_offset: u64) {
+        let location = match self.pending.take() {
+            Some(location) => location,
+            None => self.stack.pop().map_or(Location::Empty, Location::Memory),
+        };
+        self.pieces.push(Piece {
+            size_in_bits,
+            bit_offset,
+            location,
+        });
+    }
+
+    /// Executes the location expression of the DIE referenced by a `DW_OP_call*`
+    /// on the current stack. DIEs without a location do nothing.
+    fn call(&mut self, opcode: u8, offset: u64) -> Result<()> {
+        let unit = self
+            .unit
+            .ok_or(DwarfError::Unavailable("unit of the called DIE"))?;
+        let offset = match opcode {
+            DW_OP_CALL_REF => offset,
+            _ => unit.offset + offset,
+        };
+
+        let called_unit;
+        let die = if unit.contains(offset) {
+            unit.entry(offset)?
+        } else {
+            called_unit = unit.sections().unit_containing(offset)?;
+            called_unit.entry(offset)?
+        };
+        match die
+            .attr(DW_AT_LOCATION)
+            .and_then(|value| value.expression())
+        {
+            Some(expression) => {
+                if self.call_depth == MAX_CALL_DEPTH {
+                    return Err(DwarfError::CallTooDeep(MAX_CALL_DEPTH));
+                }
+                self.call_depth += 1;
+                let result = self.execute(expression);
+                self.call_depth -= 1;
+                result
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::dwarf::testing::{abbrev_table, sections, UnitBuilder};
+
+    #[derive(Default)]
+    struct TestContext {
+        registers: HashMap<u16, u64>,
+        memory: HashMap<u64, u64>,
+        frame_base: Option<u64>,
+    }
+
+    impl EvaluationContext for TestContext {
+        fn register(&self, register: u16) -> Result<u64> {
+            self.registers
+                .get(&register)
+                .copied()
+                .ok_or(DwarfError::UnknownRegister(register))
+        }
+
+        fn read_memory(&self, address: u64, size: u8) -> Result<u64> {
+            let value = self
+                .memory
+                .get(&address)
+                .copied()
+                .ok_or(DwarfError::InvalidMemoryAccess(address))?;
+            Ok(match size {
+                8 => value,
+                _ => value & ((1 << (size * 8)) - 1),
+            })
+        }
+
+        fn load_bias(&self) -> u64 {
+            0x1000
+        }
+
+        fn frame_base(&self) -> Result<u64> {
+            self.frame_base.ok_or(DwarfError::Unavailable("frame base"))
+        }
+    }
+
+    fn evaluate<'a>(context: &TestContext, expression: &'a [u8]) -> Result<Location<'a>> {
+        Evaluator::new(context, 8).evaluate(expression)
+    }
+
+    #[test]
+    fn test_evaluate_locations() {
+        let context = TestContext {
+            registers: HashMap::from([(6, 0x7ff0), (7, 0x7fe0)]),
+            memory: HashMap::from([(0x7fe8, 0x1122_3344_5566_7788)]),
+            frame_base: Some(0x8000),
+        };
+
+        // DW_OP_fbreg -20
+        assert_eq!(
+            evaluate(&context, &[0x91, 0x6c]),
+            Ok(Location::Memory(0x7fec))
+        );
+        // DW_OP_breg7 (rsp) +8; DW_OP_deref_size 2
+        assert_eq!(
+            evaluate(&context, &[0x77, 0x08, 0x94, 0x02]),
+            Ok(Location::Memory(0x7788))
+        );
+        // DW_OP_addr 0x4010
+        assert_eq!(
+            evaluate(&context, &[0x03, 0x10, 0x40, 0, 0, 0, 0, 0, 0]),
+            Ok(Location::Memory(0x5010))
+        );
+        // DW_OP_reg3 (rbx)
+        assert_eq!(evaluate(&context, &[0x53]), Ok(Location::Register(3)));
+        // DW_OP_lit5; DW_OP_lit3; DW_OP_minus; DW_OP_stack_value
+        assert_eq!(
+            evaluate(&context, &[0x35, 0x33, 0x1c, 0x9f]),
+            Ok(Location::Value(2))
+        );
+        // DW_OP_implicit_value 2 [0xab, 0xcd]
+        assert_eq!(
+            evaluate(&context, &[0x9e, 0x02, 0xab, 0xcd]),
+            Ok(Location::Bytes(&[0xab, 0xcd]))
+        );
+        assert_eq!(evaluate(&context, &[]), Ok(Location::Empty));
+        // DW_OP_call_frame_cfa is not provided by the context
+        assert_eq!(
+            evaluate(&context, &[0x9c]),
+            Err(DwarfError::Unavailable("call frame address"))
+        );
+        assert_eq!(evaluate(&context, &[0x22]), Err(DwarfError::StackUnderflow));
+    }
+
+    #[test]
+    fn test_evaluate_pieces() {
+        let context = TestContext::default();
+        // DW_OP_reg0; DW_OP_piece 4; DW_OP_piece 2; DW_OP_lit1; DW_OP_stack_value; DW_OP_bit_piece 3 5
+        let location = evaluate(
+            &context,
+            &[0x50, 0x93, 0x04, 0x93, 0x02, 0x31, 0x9f, 0x9d, 0x03, 0x05],
+        )
+        .unwrap();
+        assert_eq!(
+            location,
+            Location::Pieces(vec![
+                Piece {
+                    size_in_bits: 32,
+                    bit_offset: 0,
+                    location: Location::Register(0),
+                },
+                Piece {
+                    size_in_bits: 16,
+                    bit_offset: 0,
+                    location: Location::Empty,
+                },
+                Piece {
+                    size_in_bits: 3,
+                    bit_offset: 5,
+                    location: Location::Value(1),
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_evaluate_stack_operations() {
+        let context = TestContext::default();
+        let value = |expression: &[u8]| match evaluate(&context, expression) {
+            Ok(Location::Memory(value)) => value,
+            other => panic!("unexpected result {:?}", other),
+        };
+
+        // DW_OP_lit1; DW_OP_lit2; DW_OP_lit3; DW_OP_rot => 3 1 2
+        assert_eq!(value(&[0x31, 0x32, 0x33, 0x17]), 2);
+        assert_eq!(value(&[0x31, 0x32, 0x33, 0x17, 0x13]), 1);
+        // DW_OP_const1s -8; DW_OP_lit3; DW_OP_div
+        assert_eq!(value(&[0x09, 0xf8, 0x33, 0x1b]) as i64, -2);
+        // DW_OP_const1s -8; DW_OP_lit1; DW_OP_shra
+        assert_eq!(value(&[0x09, 0xf8, 0x31, 0x26]) as i64, -4);
+        // DW_OP_lit0; DW_OP_bra +2; DW_OP_lit7; DW_OP_skip +1; DW_OP_lit8
+        assert_eq!(
+            value(&[0x30, 0x28, 0x02, 0x00, 0x37, 0x2f, 0x01, 0x00, 0x38]),
+            7
+        );
+        // DW_OP_lit1; DW_OP_bra +1; DW_OP_lit7; DW_OP_lit8
+        assert_eq!(value(&[0x31, 0x28, 0x01, 0x00, 0x37, 0x38]), 8);
+        // DW_OP_lit4; DW_OP_lit9; DW_OP_over; DW_OP_pick 1; DW_OP_lt
+        assert_eq!(value(&[0x34, 0x39, 0x14, 0x15, 0x01, 0x2d]), 1);
+        assert_eq!(
+            evaluate(&context, &[0x31, 0x30, 0x1b]),
+            Err(DwarfError::DivisionByZero)
+        );
+        assert_eq!(
+            evaluate(&context, &[0x2f, 0x10, 0x00]),
+            Err(DwarfError::InvalidBranch(0x13))
+        );
+        // DW_OP_skip -3, which jumps back to itself
+        assert_eq!(
+            evaluate(&context, &[0x2f, 0xfd, 0xff]),
+            Err(DwarfError::TooManyOperations(MAX_OPERATIONS))
+        );
+    }
+
+    #[test]
+    fn test_recursive_call() {
+        // Abbreviation 2: variable with an exprloc location
+        let abbrev = abbrev_table(&[0x02, 0x34, 0x00, 0x02, 0x18, 0x00, 0x00]);
+        let mut unit = UnitBuilder::new(0x0c);
+        // The variable is the first DIE after the header and the compile unit
+        let offset = 13u32.to_le_bytes();
+        // DW_OP_call4 of the variable itself
+        let variable = unit.die(&[0x02, 0x05, 0x99, offset[0], offset[1], offset[2], offset[3]]);
+        assert_eq!(variable, 13);
+        let info = unit.finish();
+        let sections = sections(&info, &abbrev);
+        let unit = sections.units().next().unwrap().unwrap();
+
+        let context = TestContext::default();
+        assert_eq!(
+            Evaluator::for_unit(&context, &unit)
+                .evaluate(&[0x99, offset[0], offset[1], offset[2], offset[3]]),
+            Err(DwarfError::CallTooDeep(MAX_CALL_DEPTH))
+        );
+    }
+}
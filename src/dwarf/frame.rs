@@ -0,0 +1,1036 @@
+use std::collections::{BTreeMap, HashMap};
+
+use super::error::{DwarfError, Result};
+use super::reader::{Format, Reader};
+use crate::elf::file::ElfFile;
+use crate::elf::header::ElfHeader;
+
+pub const DW_EH_PE_ABSPTR: u8 = 0x00;
+pub const DW_EH_PE_ULEB128: u8 = 0x01;
+pub const DW_EH_PE_UDATA2: u8 = 0x02;
+pub const DW_EH_PE_UDATA4: u8 = 0x03;
+pub const DW_EH_PE_UDATA8: u8 = 0x04;
+pub const DW_EH_PE_SLEB128: u8 = 0x09;
+pub const DW_EH_PE_SDATA2: u8 = 0x0a;
+pub const DW_EH_PE_SDATA4: u8 = 0x0b;
+pub const DW_EH_PE_SDATA8: u8 = 0x0c;
+pub const DW_EH_PE_PCREL: u8 = 0x10;
+pub const DW_EH_PE_TEXTREL: u8 = 0x20;
+pub const DW_EH_PE_DATAREL: u8 = 0x30;
+pub const DW_EH_PE_FUNCREL: u8 = 0x40;
+pub const DW_EH_PE_ALIGNED: u8 = 0x50;
+pub const DW_EH_PE_INDIRECT: u8 = 0x80;
+pub const DW_EH_PE_OMIT: u8 = 0xff;
+
+const DW_CFA_ADVANCE_LOC: u8 = 0x40;
+const DW_CFA_OFFSET: u8 = 0x80;
+const DW_CFA_RESTORE: u8 = 0xc0;
+const DW_CFA_NOP: u8 = 0x00;
+const DW_CFA_SET_LOC: u8 = 0x01;
+const DW_CFA_ADVANCE_LOC1: u8 = 0x02;
+const DW_CFA_ADVANCE_LOC2: u8 = 0x03;
+const DW_CFA_ADVANCE_LOC4: u8 = 0x04;
+const DW_CFA_OFFSET_EXTENDED: u8 = 0x05;
+const DW_CFA_RESTORE_EXTENDED: u8 = 0x06;
+const DW_CFA_UNDEFINED: u8 = 0x07;
+const DW_CFA_SAME_VALUE: u8 = 0x08;
+const DW_CFA_REGISTER: u8 = 0x09;
+const DW_CFA_REMEMBER_STATE: u8 = 0x0a;
+const DW_CFA_RESTORE_STATE: u8 = 0x0b;
+const DW_CFA_DEF_CFA: u8 = 0x0c;
+const DW_CFA_DEF_CFA_REGISTER: u8 = 0x0d;
+const DW_CFA_DEF_CFA_OFFSET: u8 = 0x0e;
+const DW_CFA_DEF_CFA_EXPRESSION: u8 = 0x0f;
+const DW_CFA_EXPRESSION: u8 = 0x10;
+const DW_CFA_OFFSET_EXTENDED_SF: u8 = 0x11;
+const DW_CFA_DEF_CFA_SF: u8 = 0x12;
+const DW_CFA_DEF_CFA_OFFSET_SF: u8 = 0x13;
+const DW_CFA_VAL_OFFSET: u8 = 0x14;
+const DW_CFA_VAL_OFFSET_SF: u8 = 0x15;
+const DW_CFA_VAL_EXPRESSION: u8 = 0x16;
+const DW_CFA_GNU_WINDOW_SAVE: u8 = 0x2d;
+const DW_CFA_GNU_ARGS_SIZE: u8 = 0x2e;
+const DW_CFA_GNU_NEGATIVE_OFFSET_EXTENDED: u8 = 0x2f;
+
+pub const EH_FRAME_SECTION: &str = ".eh_frame";
+pub const EH_FRAME_HDR_SECTION: &str = ".eh_frame_hdr";
+pub const DEBUG_FRAME_SECTION: &str = ".debug_frame";
+
+/// `.eh_frame` and `.debug_frame` share their format, but differ in how CIEs are
+/// identified and referenced, and only `.eh_frame` uses augmentations and pointer encodings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameSectionKind {
+    EhFrame,
+    DebugFrame,
+}
+
+/// Addresses that encoded pointers can be relative to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PointerBases {
+    /// Address of the section being decoded, used for `DW_EH_PE_pcrel`
+    pub section: u64,
+    pub text: Option<u64>,
+    pub data: Option<u64>,
+}
+
+/// Reads a pointer encoded with one of the `DW_EH_PE_*` encodings. Indirect pointers are
+/// not dereferenced, as that requires the memory of the process, so the address of the
+/// pointer is returned instead. Returns `None` for `DW_EH_PE_omit`.
+pub fn read_encoded_pointer(
+    reader: &mut Reader,
+    encoding: u8,
+    bases: &PointerBases,
+    address_size: u8,
+) -> Result<Option<u64>> {
+    if encoding == DW_EH_PE_OMIT {
+        return Ok(None);
+    }
+
+    let application = encoding & 0x70;
+    if application == DW_EH_PE_ALIGNED {
+        let misalignment = reader.offset() % address_size as usize;
+        if misalignment != 0 {
+            reader.skip(address_size as usize - misalignment)?;
+        }
+        return reader.read_address(address_size).map(Some);
+    }
+
+    let position = bases.section + reader.offset() as u64;
+    let value = match encoding & 0x0f {
+        DW_EH_PE_ABSPTR => reader.read_address(address_size)?,
+        DW_EH_PE_ULEB128 => reader.read_uleb128()?,
+        DW_EH_PE_UDATA2 => reader.read_u16()? as u64,
+        DW_EH_PE_UDATA4 => reader.read_u32()? as u64,
+        DW_EH_PE_UDATA8 => reader.read_u64()?,
+        DW_EH_PE_SLEB128 => reader.read_sleb128()? as u64,
+        DW_EH_PE_SDATA2 => reader.read_i16()? as i64 as u64,
+        DW_EH_PE_SDATA4 => reader.read_i32()? as i64 as u64,
+        DW_EH_PE_SDATA8 => reader.read_i64()? as u64,
+        _ => return Err(DwarfError::UnknownPointerEncoding(encoding)),
+    };
+
+    let base = match application {
+        0 => 0,
+        DW_EH_PE_PCREL => position,
+        DW_EH_PE_TEXTREL => bases
+            .text
+            .ok_or(DwarfError::UnknownPointerEncoding(encoding))?,
+        DW_EH_PE_DATAREL => bases
+            .data
+            .ok_or(DwarfError::UnknownPointerEncoding(encoding))?,
+        _ => return Err(DwarfError::UnknownPointerEncoding(encoding)),
+    };
+    Ok(Some(base.wrapping_add(value)))
+}
+
+/// Common Information Entry, the information shared by many FDEs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cie<'a> {
+    pub offset: u64,
+    pub format: Format,
+    pub version: u8,
+    pub augmentation: String,
+    pub address_size: u8,
+    pub code_alignment_factor: u64,
+    pub data_alignment_factor: i64,
+    pub return_address_register: u16,
+    /// Encoding of the addresses in the FDEs using this CIE
+    pub fde_pointer_encoding: u8,
+    pub lsda_encoding: Option<u8>,
+    /// Address of the personality routine, or of the pointer to it if it is encoded indirectly
+    pub personality: Option<u64>,
+    /// The frames are signal handler trampolines, and the return address is not after a call
+    pub is_signal_frame: bool,
+    pub initial_instructions: &'a [u8],
+    /// Offset of the initial instructions in the section, needed for `DW_CFA_set_loc`
+    instructions_offset: usize,
+}
+
+impl<'a> Cie<'a> {
+    fn has_augmentation_data(&self) -> bool {
+        self.augmentation.starts_with('z')
+    }
+}
+
+/// Frame Description Entry, describing how to unwind the frames of a range of code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fde<'a> {
+    pub offset: u64,
+    pub cie_offset: u64,
+    pub pc_begin: u64,
+    pub pc_range: u64,
+    /// Address of the language specific data area, used by exception handling
+    pub lsda: Option<u64>,
+    pub instructions: &'a [u8],
+    instructions_offset: usize,
+}
+
+impl<'a> Fde<'a> {
+    pub fn pc_end(&self) -> u64 {
+        self.pc_begin + self.pc_range
+    }
+
+    pub fn contains(&self, pc: u64) -> bool {
+        self.pc_begin <= pc && pc < self.pc_end()
+    }
+}
+
+/// How to compute the Canonical Frame Address, the value of the stack pointer
+/// in the calling frame before the call instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CfaRule<'a> {
+    RegisterOffset { register: u16, offset: i64 },
+    Expression(&'a [u8]),
+}
+
+/// How to recover the value a register had in the calling frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegisterRule<'a> {
+    /// The register has no recoverable value in the caller
+    Undefined,
+    /// The register has not been modified
+    SameValue,
+    /// Saved at `CFA + offset`
+    Offset(i64),
+    /// The value is `CFA + offset`
+    ValOffset(i64),
+    /// Saved in another register
+    Register(u16),
+    /// Saved at the address computed by the expression
+    Expression(&'a [u8]),
+    /// The value is computed by the expression
+    ValExpression(&'a [u8]),
+}
+
+/// A row of the unwind table, the rules valid for the addresses `start_address..end_address`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnwindRow<'a> {
+    pub start_address: u64,
+    pub end_address: u64,
+    pub cfa: CfaRule<'a>,
+    /// Rules of the registers, registers not present here have an unspecified rule
+    pub registers: BTreeMap<u16, RegisterRule<'a>>,
+}
+
+impl<'a> UnwindRow<'a> {
+    pub fn contains(&self, pc: u64) -> bool {
+        self.start_address <= pc && pc < self.end_address
+    }
+
+    pub fn register(&self, register: u16) -> Option<&RegisterRule<'a>> {
+        self.registers.get(&register)
+    }
+}
+
+/// The binary search table of `.eh_frame_hdr`, mapping the initial location of each
+/// FDE to the address of the FDE, sorted by initial location.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EhFrameHdr {
+    pub eh_frame_ptr: u64,
+    pub table: Vec<(u64, u64)>,
+}
+
+impl EhFrameHdr {
+    pub fn parse(bytes: &[u8], address: u64, address_size: u8) -> Result<Self> {
+        let mut reader = Reader::new(bytes);
+        let version = reader.read_u8()?;
+        if version != 1 {
+            return Err(DwarfError::UnsupportedVersion(version as u16));
+        }
+        let eh_frame_ptr_encoding = reader.read_u8()?;
+        let fde_count_encoding = reader.read_u8()?;
+        let table_encoding = reader.read_u8()?;
+
+        // Values in the header are relative to the start of the header
+        let bases = PointerBases {
+            section: address,
+            text: None,
+            data: Some(address),
+        };
+        let eh_frame_ptr =
+            read_encoded_pointer(&mut reader, eh_frame_ptr_encoding, &bases, address_size)?
+                .unwrap_or(0);
+        let fde_count =
+            read_encoded_pointer(&mut reader, fde_count_encoding, &bases, address_size)?;
+
+        let mut table = Vec::new();
+        if let Some(fde_count) = fde_count {
+            if table_encoding != DW_EH_PE_OMIT {
+                for _ in 0..fde_count {
+                    let initial_location =
+                        read_encoded_pointer(&mut reader, table_encoding, &bases, address_size)?;
+                    let fde_address =
+                        read_encoded_pointer(&mut reader, table_encoding, &bases, address_size)?;
+                    if let (Some(initial_location), Some(fde_address)) =
+                        (initial_location, fde_address)
+                    {
+                        table.push((initial_location, fde_address));
+                    }
+                }
+            }
+        }
+
+        Ok(Self {
+            eh_frame_ptr,
+            table,
+        })
+    }
+
+    /// Address of the FDE with the greatest initial location not above `pc`.
+    /// The FDE still has to be checked to actually cover `pc`.
+    pub fn lookup(&self, pc: u64) -> Option<u64> {
+        let idx = self
+            .table
+            .partition_point(|(initial_location, _)| *initial_location <= pc);
+        idx.checked_sub(1).map(|idx| self.table[idx].1)
+    }
+}
+
+/// The parsed contents of a `.eh_frame` or `.debug_frame` section
+#[derive(Debug, Clone)]
+pub struct CallFrameInfo<'a> {
+    kind: FrameSectionKind,
+    bytes: &'a [u8],
+    bases: PointerBases,
+    address_size: u8,
+    cies: HashMap<u64, Cie<'a>>,
+    /// Sorted by `pc_begin`
+    fdes: Vec<Fde<'a>>,
+    hdr: Option<EhFrameHdr>,
+}
+
+impl<'a> CallFrameInfo<'a> {
+    pub fn parse(
+        kind: FrameSectionKind,
+        bytes: &'a [u8],
+        bases: PointerBases,
+        address_size: u8,
+    ) -> Result<Self> {
+        let mut cfi = Self {
+            kind,
+            bytes,
+            bases,
+            address_size,
+            cies: HashMap::new(),
+            fdes: Vec::new(),
+            hdr: None,
+        };
+
+        let mut reader = Reader::new(bytes);
+        while !reader.is_empty() {
+            let entry_offset = reader.offset();
+            let (length, format) = reader.read_initial_length()?;
+            if length == 0 {
+                // A zero terminator ends .eh_frame
+                if kind == FrameSectionKind::EhFrame {
+                    break;
+                }
+                continue;
+            }
+            let entry_end = reader.offset() + length as usize;
+            let id_offset = reader.offset() as u64;
+            let id = reader.read_offset(format)?;
+
+            if cfi.is_cie_id(id, format) {
+                let cie = cfi.parse_cie(reader, entry_offset as u64, format, entry_end)?;
+                cfi.cies.insert(cie.offset, cie);
+            } else {
+                let cie_offset = match kind {
+                    FrameSectionKind::EhFrame => id_offset.wrapping_sub(id),
+                    FrameSectionKind::DebugFrame => id,
+                };
+                if !cfi.cies.contains_key(&cie_offset) {
+                    let cie = cfi.parse_cie_at(cie_offset)?;
+                    cfi.cies.insert(cie_offset, cie);
+                }
+                let cie = &cfi.cies[&cie_offset];
+                let fde = cfi.parse_fde(reader, entry_offset as u64, cie, entry_end)?;
+                cfi.fdes.push(fde);
+            }
+            reader.set_offset(entry_end);
+        }
+
+        cfi.fdes.sort_by_key(|fde| fde.pc_begin);
+        Ok(cfi)
+    }
+
+    /// Parses `.eh_frame` of the file, using `.eh_frame_hdr` for lookups if it is present
+    pub fn from_eh_frame(elf_file: &ElfFile<'a>) -> Result<Self> {
+        let section = elf_file
+            .get_section_by_name(EH_FRAME_SECTION)
+            .ok_or(DwarfError::MissingSection(EH_FRAME_SECTION))?;
+        let text = elf_file
+            .get_section_by_name(".text")
+            .map(|text| text.address());
+        let got = elf_file
+            .get_section_by_name(".got")
+            .map(|got| got.address());
+        let bases = PointerBases {
+            section: section.address(),
+            text,
+            data: got,
+        };
+        let address_size = address_size(elf_file);
+        let mut cfi = Self::parse(
+            FrameSectionKind::EhFrame,
+            section.bytes(),
+            bases,
+            address_size,
+        )?;
+
+        if let Some(hdr) = elf_file.get_section_by_name(EH_FRAME_HDR_SECTION) {
+            cfi.hdr = Some(EhFrameHdr::parse(hdr.bytes(), hdr.address(), address_size)?);
+        }
+        Ok(cfi)
+    }
+
+    pub fn from_debug_frame(elf_file: &ElfFile<'a>) -> Result<Self> {
+        let section = elf_file
+            .get_section_by_name(DEBUG_FRAME_SECTION)
+            .ok_or(DwarfError::MissingSection(DEBUG_FRAME_SECTION))?;
+        Self::parse(
+            FrameSectionKind::DebugFrame,
+            section.bytes(),
+            PointerBases::default(),
+            address_size(elf_file),
+        )
+    }
+
+    pub fn kind(&self) -> FrameSectionKind {
+        self.kind
+    }
+
+    pub fn fdes(&self) -> &[Fde<'a>] {
+        &self.fdes
+    }
+
+    pub fn hdr(&self) -> Option<&EhFrameHdr> {
+        self.hdr.as_ref()
+    }
+
+    pub fn cie(&self, fde: &Fde) -> &Cie<'a> {
+        &self.cies[&fde.cie_offset]
+    }
+
+    /// Finds the FDE covering `pc`, through the `.eh_frame_hdr` search table if there is one
+    pub fn fde_for_pc(&self, pc: u64) -> Option<&Fde<'a>> {
+        if let Some(hdr) = &self.hdr {
+            if let Some(fde_address) = hdr.lookup(pc) {
+                let offset = fde_address.wrapping_sub(self.bases.section);
+                if let Some(fde) = self.fdes.iter().find(|fde| fde.offset == offset) {
+                    if fde.contains(pc) {
+                        return Some(fde);
+                    }
+                }
+            }
+        }
+
+        let idx = self.fdes.partition_point(|fde| fde.pc_begin <= pc);
+        idx.checked_sub(1)
+            .map(|idx| &self.fdes[idx])
+            .filter(|fde| fde.contains(pc))
+    }
+
+    /// The row of the unwind table for `pc`, or `None` if no FDE covers `pc`
+    pub fn unwind_row(&self, pc: u64) -> Result<Option<UnwindRow<'a>>> {
+        let fde = match self.fde_for_pc(pc) {
+            Some(fde) => fde,
+            None => return Ok(None),
+        };
+        Ok(self
+            .unwind_table(fde)?
+            .into_iter()
+            .find(|row| row.contains(pc)))
+    }
+
+    /// Evaluates the instructions of the CIE and the FDE into the unwind table for the FDE
+    pub fn unwind_table(&self, fde: &Fde<'a>) -> Result<Vec<UnwindRow<'a>>> {
+        let cie = self.cie(fde);
+        let mut state = UnwindState {
+            cfa: CfaRule::RegisterOffset {
+                register: 0,
+                offset: 0,
+            },
+            registers: BTreeMap::new(),
+        };
+
+        let mut rows = Vec::new();
+        let mut location = fde.pc_begin;
+        self.execute(
+            cie,
+            cie.initial_instructions,
+            cie.instructions_offset,
+            &mut state,
+            None,
+            &mut location,
+            &mut rows,
+        )?;
+        let initial_state = state.clone();
+        self.execute(
+            cie,
+            fde.instructions,
+            fde.instructions_offset,
+            &mut state,
+            Some(&initial_state),
+            &mut location,
+            &mut rows,
+        )?;
+
+        rows.push(UnwindRow {
+            start_address: location,
+            end_address: fde.pc_end(),
+            cfa: state.cfa,
+            registers: state.registers,
+        });
+        rows.retain(|row| row.start_address < row.end_address);
+        Ok(rows)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn execute(
+        &self,
+        cie: &Cie<'a>,
+        instructions: &'a [u8],
+        instructions_offset: usize,
+        state: &mut UnwindState<'a>,
+        initial_state: Option<&UnwindState<'a>>,
+        location: &mut u64,
+        rows: &mut Vec<UnwindRow<'a>>,
+    ) -> Result<()> {
+        let mut reader = Reader::at(
+            &self.bytes[..instructions_offset + instructions.len()],
+            instructions_offset,
+        )?;
+        let mut remembered_states = Vec::new();
+        let caf = cie.code_alignment_factor;
+        let daf = cie.data_alignment_factor;
+
+        while !reader.is_empty() {
+            let opcode = reader.read_u8()?;
+            let operand = opcode & 0x3f;
+            match opcode & 0xc0 {
+                DW_CFA_ADVANCE_LOC => {
+                    advance_to(*location + operand as u64 * caf, state, location, rows);
+                    continue;
+                }
+                DW_CFA_OFFSET => {
+                    let offset = reader.read_uleb128()? as i64 * daf;
+                    state
+                        .registers
+                        .insert(operand as u16, RegisterRule::Offset(offset));
+                    continue;
+                }
+                DW_CFA_RESTORE => {
+                    state.restore(operand as u16, initial_state);
+                    continue;
+                }
+                _ => {}
+            }
+
+            match opcode {
+                DW_CFA_NOP | DW_CFA_GNU_WINDOW_SAVE => {}
+                DW_CFA_SET_LOC => {
+                    let new_location = read_encoded_pointer(
+                        &mut reader,
+                        cie.fde_pointer_encoding,
+                        &self.bases,
+                        cie.address_size,
+                    )?
+                    .unwrap_or(*location);
+                    advance_to(new_location, state, location, rows);
+                }
+                DW_CFA_ADVANCE_LOC1 => {
+                    let delta = reader.read_u8()? as u64;
+                    advance_to(*location + delta * caf, state, location, rows);
+                }
+                DW_CFA_ADVANCE_LOC2 => {
+                    let delta = reader.read_u16()? as u64;
+                    advance_to(*location + delta * caf, state, location, rows);
+                }
+                DW_CFA_ADVANCE_LOC4 => {
+                    let delta = reader.read_u32()? as u64;
+                    advance_to(*location + delta * caf, state, location, rows);
+                }
+                DW_CFA_OFFSET_EXTENDED => {
+                    let register = reader.read_uleb128()? as u16;
+                    let offset = reader.read_uleb128()? as i64 * daf;
+                    state
+                        .registers
+                        .insert(register, RegisterRule::Offset(offset));
+                }
+                DW_CFA_RESTORE_EXTENDED => {
+                    let register = reader.read_uleb128()? as u16;
+                    state.restore(register, initial_state);
+                }
+                DW_CFA_UNDEFINED => {
+                    let register = reader.read_uleb128()? as u16;
+                    state.registers.insert(register, RegisterRule::Undefined);
+                }
+                DW_CFA_SAME_VALUE => {
+                    let register = reader.read_uleb128()? as u16;
+                    state.registers.insert(register, RegisterRule::SameValue);
+                }
+                DW_CFA_REGISTER => {
+                    let register = reader.read_uleb128()? as u16;
+                    let other = reader.read_uleb128()? as u16;
+                    state
+                        .registers
+                        .insert(register, RegisterRule::Register(other));
+                }
+                DW_CFA_REMEMBER_STATE => remembered_states.push(state.clone()),
+                DW_CFA_RESTORE_STATE => {
+                    *state = remembered_states
+                        .pop()
+                        .ok_or(DwarfError::InvalidRegisterState)?;
+                }
+                DW_CFA_DEF_CFA => {
+                    let register = reader.read_uleb128()? as u16;
+                    let offset = reader.read_uleb128()? as i64;
+                    state.cfa = CfaRule::RegisterOffset { register, offset };
+                }
+                DW_CFA_DEF_CFA_SF => {
+                    let register = reader.read_uleb128()? as u16;
+                    let offset = reader.read_sleb128()? * daf;
+                    state.cfa = CfaRule::RegisterOffset { register, offset };
+                }
+                DW_CFA_DEF_CFA_REGISTER => {
+                    let register = reader.read_uleb128()? as u16;
+                    let offset = match state.cfa {
+                        CfaRule::RegisterOffset { offset, .. } => offset,
+                        CfaRule::Expression(_) => 0,
+                    };
+                    state.cfa = CfaRule::RegisterOffset { register, offset };
+                }
+                DW_CFA_DEF_CFA_OFFSET | DW_CFA_DEF_CFA_OFFSET_SF => {
+                    let new_offset = match opcode {
+                        DW_CFA_DEF_CFA_OFFSET => reader.read_uleb128()? as i64,
+                        _ => reader.read_sleb128()? * daf,
+                    };
+                    if let CfaRule::RegisterOffset { offset, .. } = &mut state.cfa {
+                        *offset = new_offset;
+                    }
+                }
+                DW_CFA_DEF_CFA_EXPRESSION => {
+                    let len = reader.read_uleb128()? as usize;
+                    state.cfa = CfaRule::Expression(reader.read_bytes(len)?);
+                }
+                DW_CFA_EXPRESSION | DW_CFA_VAL_EXPRESSION => {
+                    let register = reader.read_uleb128()? as u16;
+                    let len = reader.read_uleb128()? as usize;
+                    let expression = reader.read_bytes(len)?;
+                    let rule = match opcode {
+                        DW_CFA_EXPRESSION => RegisterRule::Expression(expression),
+                        _ => RegisterRule::ValExpression(expression),
+                    };
+                    state.registers.insert(register, rule);
+                }
+                DW_CFA_OFFSET_EXTENDED_SF => {
+                    let register = reader.read_uleb128()? as u16;
+                    let offset = reader.read_sleb128()? * daf;
+                    state
+                        .registers
+                        .insert(register, RegisterRule::Offset(offset));
+                }
+                DW_CFA_VAL_OFFSET | DW_CFA_VAL_OFFSET_SF => {
+                    let register = reader.read_uleb128()? as u16;
+                    let offset = match opcode {
+                        DW_CFA_VAL_OFFSET => reader.read_uleb128()? as i64 * daf,
+                        _ => reader.read_sleb128()? * daf,
+                    };
+                    state
+                        .registers
+                        .insert(register, RegisterRule::ValOffset(offset));
+                }
+                DW_CFA_GNU_ARGS_SIZE => {
+                    reader.read_uleb128()?;
+                }
+                DW_CFA_GNU_NEGATIVE_OFFSET_EXTENDED => {
+                    let register = reader.read_uleb128()? as u16;
+                    let offset = -(reader.read_uleb128()? as i64) * daf;
+                    state
+                        .registers
+                        .insert(register, RegisterRule::Offset(offset));
+                }
+                _ => return Err(DwarfError::UnknownCfiInstruction(opcode)),
+            }
+        }
+        Ok(())
+    }
+
+    fn is_cie_id(&self, id: u64, format: Format) -> bool {
+        match (self.kind, format) {
+            (FrameSectionKind::EhFrame, _) => id == 0,
+            (FrameSectionKind::DebugFrame, Format::Dwarf32) => id == 0xffffffff,
+            (FrameSectionKind::DebugFrame, Format::Dwarf64) => id == 0xffffffffffffffff,
+        }
+    }
+
+    fn parse_cie_at(&self, offset: u64) -> Result<Cie<'a>> {
+        let mut reader = Reader::at(self.bytes, offset as usize)
+            .map_err(|_| DwarfError::InvalidCiePointer(offset))?;
+        let (length, format) = reader.read_initial_length()?;
+        let entry_end = reader.offset() + length as usize;
+        let id = reader.read_offset(format)?;
+        if !self.is_cie_id(id, format) {
+            return Err(DwarfError::InvalidCiePointer(offset));
+        }
+        self.parse_cie(reader, offset, format, entry_end)
+    }
+
+    fn parse_cie(
+        &self,
+        mut reader: Reader<'a>,
+        offset: u64,
+        format: Format,
+        entry_end: usize,
+    ) -> Result<Cie<'a>> {
+        let version = reader.read_u8()?;
+        if !matches!(version, 1 | 3 | 4) {
+            return Err(DwarfError::UnsupportedVersion(version as u16));
+        }
+        let augmentation = String::from_utf8_lossy(reader.read_cstr()?).into_owned();
+
+        // Augmentation of old gcc versions, holding the address of exception tables
+        if augmentation.contains("eh") {
+            reader.read_address(self.address_size)?;
+        }
+
+        let mut address_size = self.address_size;
+        if version >= 4 {
+            address_size = reader.read_u8()?;
+            let _segment_selector_size = reader.read_u8()?;
+        }
+
+        let code_alignment_factor = reader.read_uleb128()?;
+        let data_alignment_factor = reader.read_sleb128()?;
+        let return_address_register = match version {
+            1 => reader.read_u8()? as u16,
+            _ => reader.read_uleb128()? as u16,
+        };
+
+        let mut cie = Cie {
+            offset,
+            format,
+            version,
+            augmentation,
+            address_size,
+            code_alignment_factor,
+            data_alignment_factor,
+            return_address_register,
+            fde_pointer_encoding: DW_EH_PE_ABSPTR,
+            lsda_encoding: None,
+            personality: None,
+            is_signal_frame: false,
+            initial_instructions: &[],
+            instructions_offset: 0,
+        };
+
+        if cie.has_augmentation_data() {
+            let augmentation_length = reader.read_uleb128()? as usize;
+            let augmentation_end = reader.offset() + augmentation_length;
+            for character in cie.augmentation.clone().chars().skip(1) {
+                match character {
+                    'L' => cie.lsda_encoding = Some(reader.read_u8()?),
+                    'P' => {
+                        let encoding = reader.read_u8()?;
+                        cie.personality =
+                            read_encoded_pointer(&mut reader, encoding, &self.bases, address_size)?;
+                    }
+                    'R' => cie.fde_pointer_encoding = reader.read_u8()?,
+                    'S' => cie.is_signal_frame = true,
+                    // The rest of the augmentation data can be skipped using its length
+                    _ => break,
+                }
+            }
+            reader.set_offset(augmentation_end);
+        } else if !cie.augmentation.is_empty() && cie.augmentation != "eh" {
+            return Err(DwarfError::UnknownAugmentation(cie.augmentation));
+        }
+
+        cie.instructions_offset = reader.offset();
+        cie.initial_instructions = self
+            .bytes
+            .get(reader.offset()..entry_end)
+            .ok_or(DwarfError::UnexpectedEof)?;
+        Ok(cie)
+    }
+
+    fn parse_fde(
+        &self,
+        mut reader: Reader<'a>,
+        offset: u64,
+        cie: &Cie<'a>,
+        entry_end: usize,
+    ) -> Result<Fde<'a>> {
+        let encoding = cie.fde_pointer_encoding;
+        let pc_begin = read_encoded_pointer(&mut reader, encoding, &self.bases, cie.address_size)?
+            .unwrap_or(0);
+        // The range is an absolute value in the format of the encoding
+        let pc_range =
+            read_encoded_pointer(&mut reader, encoding & 0x0f, &self.bases, cie.address_size)?
+                .unwrap_or(0);
+
+        let mut lsda = None;
+        if cie.has_augmentation_data() {
+            let augmentation_length = reader.read_uleb128()? as usize;
+            let augmentation_end = reader.offset() + augmentation_length;
+            if let Some(lsda_encoding) = cie.lsda_encoding {
+                if augmentation_length > 0 {
+                    lsda = read_encoded_pointer(
+                        &mut reader,
+                        lsda_encoding,
+                        &self.bases,
+                        cie.address_size,
+                    )?;
+                }
+            }
+            reader.set_offset(augmentation_end);
+        }
+
+        Ok(Fde {
+            offset,
+            cie_offset: cie.offset,
+            pc_begin,
+            pc_range,
+            lsda,
+            instructions: self
+                .bytes
+                .get(reader.offset()..entry_end)
+                .ok_or(DwarfError::UnexpectedEof)?,
+            instructions_offset: reader.offset(),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+struct UnwindState<'a> {
+    cfa: CfaRule<'a>,
+    registers: BTreeMap<u16, RegisterRule<'a>>,
+}
+
+impl<'a> UnwindState<'a> {
+    /// Restores the rule of a register to the one set up by the CIE
+    fn restore(&mut self, register: u16, initial_state: Option<&UnwindState<'a>>) {
+        match initial_state.and_then(|state| state.registers.get(&register)) {
+            Some(rule) => {
+                self.registers.insert(register, rule.clone());
+            }
+            None => {
+                self.registers.remove(&register);
+            }
+        }
+    }
+}
+
+/// Ends the current row at `new_location` and starts a new one there
+fn advance_to<'a>(
+    new_location: u64,
+    state: &UnwindState<'a>,
+    location: &mut u64,
+    rows: &mut Vec<UnwindRow<'a>>,
+) {
+    rows.push(UnwindRow {
+        start_address: *location,
+        end_address: new_location,
+        cfa: state.cfa.clone(),
+        registers: state.registers.clone(),
+    });
+    *location = new_location;
+}
+
+fn address_size(elf_file: &ElfFile) -> u8 {
+    match elf_file.header {
+        ElfHeader::Elf32(_) => 4,
+        ElfHeader::Elf64(_) => 8,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RSP: u16 = 7;
+    const RBP: u16 = 6;
+    const RA: u16 = 16;
+
+    /// A CIE like the ones emitted by gcc for x86-64 followed by an FDE for a function at
+    /// 0x1139 of size 0x20 with a frame pointer, as in `push %rbp; mov %rsp,%rbp; ...; leave; ret`
+    fn eh_frame_bytes() -> Vec<u8> {
+        let mut bytes = Vec::new();
+        // CIE: length, id, version, "zR", caf 1, daf -8, ra 16, aug length 1, pcrel|sdata4
+        bytes.extend_from_slice(&0x14u32.to_le_bytes());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.extend_from_slice(&[1, b'z', b'R', 0, 0x01, 0x78, 0x10, 0x01, 0x1b]);
+        // def_cfa rsp+8, offset ra at cfa-8, nop padding
+        bytes.extend_from_slice(&[0x0c, 0x07, 0x08, 0x90, 0x01, 0x00, 0x00]);
+        assert_eq!(bytes.len(), 0x18);
+
+        // FDE at offset 0x18
+        bytes.extend_from_slice(&0x20u32.to_le_bytes());
+        // CIE pointer, relative to this field at 0x1c
+        bytes.extend_from_slice(&0x1cu32.to_le_bytes());
+        // pc_begin is pcrel, the field is at section address 0x2000 + 0x20
+        let pc_begin = 0x1139i64 - (0x2000 + 0x20);
+        bytes.extend_from_slice(&(pc_begin as i32).to_le_bytes());
+        bytes.extend_from_slice(&0x20u32.to_le_bytes());
+        // augmentation length 0
+        bytes.push(0);
+        bytes.extend_from_slice(&[
+            0x41, // advance_loc 1
+            0x0e, 0x10, // def_cfa_offset 16
+            0x86, 0x02, // offset rbp at cfa-16
+            0x43, // advance_loc 3
+            0x0d, 0x06, // def_cfa_register rbp
+            0x4a, // advance_loc 10
+            0x0a, // remember_state
+            0x0c, 0x07, 0x08, // def_cfa rsp+8
+            0x41, // advance_loc 1
+            0x0b, // restore_state
+            0x00, 0x00, 0x00, 0x00,
+        ]);
+        assert_eq!(bytes.len(), 0x18 + 4 + 0x20);
+        // Terminator
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes
+    }
+
+    fn bases() -> PointerBases {
+        PointerBases {
+            section: 0x2000,
+            text: None,
+            data: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_cie_and_fde() {
+        let bytes = eh_frame_bytes();
+        let cfi = CallFrameInfo::parse(FrameSectionKind::EhFrame, &bytes, bases(), 8).unwrap();
+
+        assert_eq!(cfi.fdes().len(), 1);
+        let fde = &cfi.fdes()[0];
+        assert_eq!(fde.pc_begin, 0x1139);
+        assert_eq!(fde.pc_range, 0x20);
+        assert_eq!(fde.lsda, None);
+
+        let cie = cfi.cie(fde);
+        assert_eq!(cie.augmentation, "zR");
+        assert_eq!(cie.code_alignment_factor, 1);
+        assert_eq!(cie.data_alignment_factor, -8);
+        assert_eq!(cie.return_address_register, RA);
+        assert_eq!(cie.fde_pointer_encoding, DW_EH_PE_PCREL | DW_EH_PE_SDATA4);
+
+        assert!(cfi.fde_for_pc(0x1139).is_some());
+        assert!(cfi.fde_for_pc(0x1158).is_some());
+        assert!(cfi.fde_for_pc(0x1159).is_none());
+        assert!(cfi.fde_for_pc(0x1000).is_none());
+    }
+
+    #[test]
+    fn test_unwind_rows() {
+        let bytes = eh_frame_bytes();
+        let cfi = CallFrameInfo::parse(FrameSectionKind::EhFrame, &bytes, bases(), 8).unwrap();
+
+        let row = cfi.unwind_row(0x1139).unwrap().unwrap();
+        assert_eq!(
+            row.cfa,
+            CfaRule::RegisterOffset {
+                register: RSP,
+                offset: 8
+            }
+        );
+        assert_eq!(row.register(RA), Some(&RegisterRule::Offset(-8)));
+        assert_eq!(row.register(RBP), None);
+
+        let row = cfi.unwind_row(0x113a).unwrap().unwrap();
+        assert_eq!(
+            row.cfa,
+            CfaRule::RegisterOffset {
+                register: RSP,
+                offset: 16
+            }
+        );
+        assert_eq!(row.register(RBP), Some(&RegisterRule::Offset(-16)));
+
+        let row = cfi.unwind_row(0x1140).unwrap().unwrap();
+        assert_eq!(
+            row.cfa,
+            CfaRule::RegisterOffset {
+                register: RBP,
+                offset: 16
+            }
+        );
+
+        // After `leave` the CFA is based on rsp again, until the state is restored
+        let row = cfi.unwind_row(0x1147).unwrap().unwrap();
+        assert_eq!(
+            row.cfa,
+            CfaRule::RegisterOffset {
+                register: RSP,
+                offset: 8
+            }
+        );
+        let row = cfi.unwind_row(0x1148).unwrap().unwrap();
+        assert_eq!(
+            row.cfa,
+            CfaRule::RegisterOffset {
+                register: RBP,
+                offset: 16
+            }
+        );
+        assert_eq!(row.end_address, 0x1159);
+    }
+
+    #[test]
+    fn test_eh_frame_hdr_lookup() {
+        // version 1, eh_frame_ptr pcrel|sdata4, fde_count udata4, table datarel|sdata4
+        let mut bytes = vec![1, 0x1b, 0x03, 0x3b];
+        bytes.extend_from_slice(&0x100i32.to_le_bytes());
+        bytes.extend_from_slice(&2u32.to_le_bytes());
+        for (initial_location, fde_address) in [(0x1000i32, 0x2018i32), (0x1139, 0x2030)] {
+            // Relative to the header at 0x1f00
+            bytes.extend_from_slice(&(initial_location - 0x1f00).to_le_bytes());
+            bytes.extend_from_slice(&(fde_address - 0x1f00).to_le_bytes());
+        }
+
+        let hdr = EhFrameHdr::parse(&bytes, 0x1f00, 8).unwrap();
+        assert_eq!(hdr.eh_frame_ptr, 0x1f04 + 0x100);
+        assert_eq!(hdr.table, vec![(0x1000, 0x2018), (0x1139, 0x2030)]);
+        assert_eq!(hdr.lookup(0xfff), None);
+        assert_eq!(hdr.lookup(0x1000), Some(0x2018));
+        assert_eq!(hdr.lookup(0x1138), Some(0x2018));
+        assert_eq!(hdr.lookup(0x2000), Some(0x2030));
+    }
+
+    #[test]
+    fn test_read_encoded_pointer() {
+        let bytes = [0x10, 0x00, 0x00, 0x00, 0xf0, 0xff, 0xff, 0xff, 0x7f];
+        let bases = PointerBases {
+            section: 0x1000,
+            text: Some(0x4000),
+            data: None,
+        };
+        let mut reader = Reader::new(&bytes);
+        assert_eq!(
+            read_encoded_pointer(&mut reader, DW_EH_PE_UDATA4 | DW_EH_PE_TEXTREL, &bases, 8),
+            Ok(Some(0x4010))
+        );
+        assert_eq!(
+            read_encoded_pointer(&mut reader, DW_EH_PE_SDATA4 | DW_EH_PE_PCREL, &bases, 8),
+            Ok(Some(0x1004 - 0x10))
+        );
+        assert_eq!(
+            read_encoded_pointer(&mut reader, DW_EH_PE_SLEB128, &bases, 8),
+            Ok(Some(-1i64 as u64))
+        );
+        assert_eq!(
+            read_encoded_pointer(&mut reader, DW_EH_PE_OMIT, &bases, 8),
+            Ok(None)
+        );
+        let mut reader = Reader::new(&bytes);
+        assert_eq!(
+            read_encoded_pointer(&mut reader, DW_EH_PE_UDATA4 | DW_EH_PE_DATAREL, &bases, 8),
+            Err(DwarfError::UnknownPointerEncoding(0x33))
+        );
+    }
+}
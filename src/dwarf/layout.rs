@@ -0,0 +1,596 @@
+use super::constants::*;
+use super::die::{AttributeValue, Die};
+use super::error::Result;
+use super::expression::EvaluationContext;
+use super::types::{byte_size, declaration, is_rust_language, strip_typedefs, type_name, type_of};
+use super::unit::Unit;
+use super::value::{is_signed_type, ValueFormatter};
+
+/// Width of the comment with the offset and size of each member
+const COMMENT_WIDTH: usize = 27;
+/// Indentation of the members of a structure, after their comment
+const INDENT: usize = 4;
+
+/// Describes the type `die` the way gdb's `ptype /o` does. Structures, classes and
+/// unions list their members with the offset and size of each and the holes between
+/// them, followed by their member functions. Enumerations list their values.
+///
+/// The description is of a pointer with `pointers` levels to the type. Pointers to
+/// structures describe the structure they point to, e.g. `struct point {...} *`.
+pub fn describe_type<'a, C: EvaluationContext>(
+    unit: &Unit<'a>,
+    context: &C,
+    die: Option<&Die<'a>>,
+    pointers: usize,
+) -> Result<String> {
+    let mut target = strip_typedefs(unit, die.cloned())?;
+    let mut levels = pointers;
+    while let Some(pointer) = target.as_ref().filter(|t| t.tag == DW_TAG_POINTER_TYPE) {
+        target = strip_typedefs(unit, type_of(unit, pointer)?)?;
+        levels += 1;
+    }
+    let stars = "*".repeat(levels);
+
+    match target {
+        Some(target)
+            if matches!(
+                target.tag,
+                DW_TAG_STRUCTURE_TYPE | DW_TAG_CLASS_TYPE | DW_TAG_UNION_TYPE
+            ) && !target
+                .attr(DW_AT_DECLARATION)
+                .is_some_and(|value| value.flag()) =>
+        {
+            let layout = Layout {
+                unit,
+                formatter: ValueFormatter::new(unit, context),
+                is_rust: is_rust_language(unit)?,
+                lines: Vec::new(),
+            };
+            layout.describe(&target, &stars)
+        }
+        Some(target) if target.tag == DW_TAG_ENUMERATION_TYPE => Ok(format!(
+            "type = {}",
+            with_pointers(describe_enum(unit, &target)?, &stars)
+        )),
+        _ => Ok(format!(
+            "type = {}",
+            with_pointers(type_name(unit, die)?, &"*".repeat(pointers))
+        )),
+    }
+}
+
+/// Appends the `*` of pointers to the name of a type, e.g. `char *` or `char **`
+pub fn with_pointers(name: String, stars: &str) -> String {
+    if stars.is_empty() {
+        name
+    } else if name.ends_with('*') {
+        name + stars
+    } else {
+        format!("{} {}", name, stars)
+    }
+}
+
+/// An enumeration with its values, e.g. `enum color {RED, GREEN = 5}`. Values
+/// following the previous one are implied.
+fn describe_enum(unit: &Unit, die: &Die) -> Result<String> {
+    let signed = is_signed_type(unit, Some(die))?;
+    let size = byte_size(unit, die)?.unwrap_or(8).min(8);
+
+    let mut enumerators = Vec::new();
+    let mut next = 0;
+    for enumerator in unit.children(die)? {
+        let value = match enumerator.attr(DW_AT_CONST_VALUE) {
+            Some(AttributeValue::Sdata(value)) => *value as i128,
+            // Constants of signed enumerations may be stored in fewer bytes, unextended
+            Some(AttributeValue::Udata(value)) if signed && size < 8 => {
+                let shift = 64 - size * 8;
+                ((*value << shift) as i64 >> shift) as i128
+            }
+            Some(AttributeValue::Udata(value)) => *value as i128,
+            _ => continue,
+        };
+        let name = enumerator.name().unwrap_or_default();
+        if value == next {
+            enumerators.push(name.to_string());
+        } else {
+            enumerators.push(format!("{} = {}", name, value));
+        }
+        next = value + 1;
+    }
+
+    let header = match unit.name_of(die)? {
+        Some(name) if is_rust_language(unit)? => format!("enum {}", name),
+        _ => type_name(unit, Some(die))?.replace(" {...}", ""),
+    };
+    Ok(format!("{} {{{}}}", header, enumerators.join(", ")))
+}
+
+/// The lines describing a structure, see `describe_type`
+struct Layout<'u, 'a, C> {
+    unit: &'u Unit<'a>,
+    formatter: ValueFormatter<'u, 'a, C>,
+    /// Members of Rust structures are declared like `name: Type`
+    is_rust: bool,
+    lines: Vec<String>,
+}
+
+impl<'u, 'a, C: EvaluationContext> Layout<'u, 'a, C> {
+    fn describe(mut self, die: &Die<'a>, stars: &str) -> Result<String> {
+        let size = byte_size(self.unit, die)?.unwrap_or(0);
+        let header = self.header(die)?;
+        self.lines
+            .push(format!("/* offset      |    size */  type = {} {{", header));
+
+        let parameters = self.template_parameters(die)?;
+        if !parameters.is_empty() {
+            self.note(
+                INDENT,
+                &format!("/* template parameters: {} */", parameters.join(", ")),
+            );
+        }
+        let end = self.members(die, 0, INDENT)?;
+        if die.tag != DW_TAG_UNION_TYPE && size * 8 > end {
+            self.hole(end, size * 8, "padding");
+        }
+        self.member_functions(die)?;
+
+        self.lines.push(String::new());
+        self.note(INDENT, &format!("/* total size (bytes): {:>4} */", size));
+        let suffix = if stars.is_empty() {
+            String::new()
+        } else {
+            format!(" {}", stars)
+        };
+        self.lines
+            .push(format!("{}}}{}", " ".repeat(COMMENT_WIDTH + 2), suffix));
+        Ok(self.lines.join("\n"))
+    }
+
+    /// The keyword and name of the structure with its base classes, e.g.
+    /// `class circle : public shape`
+    fn header(&self, die: &Die<'a>) -> Result<String> {
+        let children = self.unit.children(die)?;
+        let keyword = match die.tag {
+            // Rust enums are structures with a variant part
+            _ if self.is_rust && children.iter().any(|c| c.tag == DW_TAG_VARIANT_PART) => "enum",
+            DW_TAG_STRUCTURE_TYPE => "struct",
+            DW_TAG_CLASS_TYPE => "class",
+            _ => "union",
+        };
+        let mut header = match self.unit.name_of(die)? {
+            Some(name) => format!("{} {}", keyword, name),
+            None => keyword.to_string(),
+        };
+
+        let mut bases = Vec::new();
+        for child in children {
+            if child.tag != DW_TAG_INHERITANCE {
+                continue;
+            }
+            let access = match child.attr(DW_AT_ACCESSIBILITY).and_then(|v| v.udata()) {
+                Some(DW_ACCESS_PROTECTED) => "protected",
+                Some(DW_ACCESS_PRIVATE) => "private",
+                Some(_) => "public",
+                None if die.tag == DW_TAG_CLASS_TYPE => "private",
+                None => "public",
+            };
+            let base = type_of(self.unit, &child)?;
+            let virtuality = match child.attr(DW_AT_VIRTUALITY).and_then(|v| v.udata()) {
+                Some(virtuality) if virtuality != 0 => " virtual",
+                _ => "",
+            };
+            bases.push(format!(
+                "{}{} {}",
+                access,
+                virtuality,
+                type_name(self.unit, base.as_ref())?
+            ));
+        }
+        if !bases.is_empty() {
+            header = format!("{} : {}", header, bases.join(", "));
+        }
+        Ok(header)
+    }
+
+    /// Lists the data members of `die`, which starts `base` bytes into the structure
+    /// being described. Returns the end of the last member in bits.
+    fn members(&mut self, die: &Die<'a>, base: u64, indent: usize) -> Result<u64> {
+        let is_union = die.tag == DW_TAG_UNION_TYPE;
+        let mut end = base * 8;
+        for child in self.unit.children(die)? {
+            match child.tag {
+                // Static members are declared in the structure but stored outside of it
+                DW_TAG_MEMBER | DW_TAG_VARIABLE
+                    if child.tag == DW_TAG_VARIABLE
+                        || child.attr(DW_AT_EXTERNAL).is_some_and(|v| v.flag())
+                        || child.attr(DW_AT_DECLARATION).is_some_and(|v| v.flag()) =>
+                {
+                    let declaration = self.declaration(&child)?;
+                    self.note(indent, &format!("static {}", declaration));
+                }
+                DW_TAG_MEMBER => {
+                    let member_type = type_of(self.unit, &child)?;
+                    let size = match &member_type {
+                        Some(member_type) => byte_size(self.unit, member_type)?.unwrap_or(0),
+                        None => 0,
+                    };
+                    let offset = base + self.formatter.member_offset(&child)?;
+                    let bit_size = child.attr(DW_AT_BIT_SIZE).and_then(|v| v.udata());
+                    let start = match bit_size {
+                        Some(bit_size) => {
+                            base * 8
+                                + self.formatter.bit_position(
+                                    &child,
+                                    member_type.as_ref(),
+                                    bit_size,
+                                )?
+                        }
+                        None => offset * 8,
+                    };
+                    if !is_union && start > end {
+                        self.hole(end, start, "hole");
+                    }
+                    let comment = match bit_size {
+                        Some(_) => {
+                            format!("/* {:>6}:{:>2}   | {:>7} */", start / 8, start % 8, size)
+                        }
+                        None => format!("/* {:>6}      | {:>7} */", offset, size),
+                    };
+
+                    // Members of anonymous structures and unions are listed in place
+                    let anonymous = match strip_typedefs(self.unit, member_type.clone())? {
+                        Some(nested)
+                            if child.name().is_none()
+                                && matches!(
+                                    nested.tag,
+                                    DW_TAG_STRUCTURE_TYPE | DW_TAG_CLASS_TYPE | DW_TAG_UNION_TYPE
+                                ) =>
+                        {
+                            Some(nested)
+                        }
+                        _ => None,
+                    };
+                    match anonymous {
+                        Some(nested) => {
+                            let keyword = match nested.tag {
+                                DW_TAG_UNION_TYPE => "union",
+                                DW_TAG_CLASS_TYPE => "class",
+                                _ => "struct",
+                            };
+                            self.lines.push(format!(
+                                "{}{}{} {{",
+                                comment,
+                                " ".repeat(indent),
+                                keyword
+                            ));
+                            let nested_end = self.members(&nested, offset, indent + INDENT)?;
+                            if nested.tag != DW_TAG_UNION_TYPE && (offset + size) * 8 > nested_end {
+                                self.hole(nested_end, (offset + size) * 8, "padding");
+                            }
+                            self.note(indent, "};");
+                        }
+                        None => {
+                            let mut declaration = self.declaration(&child)?;
+                            if let Some(bit_size) = bit_size {
+                                declaration = format!("{} : {}", declaration, bit_size);
+                            }
+                            let terminator = if self.is_rust { "," } else { ";" };
+                            self.lines.push(format!(
+                                "{}{}{}{}",
+                                comment,
+                                " ".repeat(indent),
+                                declaration,
+                                terminator
+                            ));
+                        }
+                    }
+                    end = end.max(start + bit_size.unwrap_or(size * 8));
+                }
+                // Base classes are listed in the header
+                DW_TAG_INHERITANCE => {
+                    let base_type = type_of(self.unit, &child)?;
+                    let size = match &base_type {
+                        Some(base_type) => byte_size(self.unit, base_type)?.unwrap_or(0),
+                        None => 0,
+                    };
+                    let offset = base + self.formatter.member_offset(&child)?;
+                    end = end.max((offset + size) * 8);
+                }
+                DW_TAG_VARIANT_PART => {
+                    self.variants(&child, base, indent)?;
+                    // Variants overlap like the members of a union
+                    let size = byte_size(self.unit, die)?.unwrap_or(0);
+                    end = end.max((base + size) * 8);
+                }
+                _ => {}
+            }
+        }
+        Ok(end)
+    }
+
+    /// Lists the variants of a Rust enum, e.g. `A(i8)` and `B { x: u64 }`
+    fn variants(&mut self, part: &Die<'a>, base: u64, indent: usize) -> Result<()> {
+        for variant in self.unit.children(part)? {
+            if variant.tag != DW_TAG_VARIANT {
+                continue;
+            }
+            let member = match self
+                .unit
+                .children(&variant)?
+                .into_iter()
+                .find(|child| child.tag == DW_TAG_MEMBER)
+            {
+                Some(member) => member,
+                None => continue,
+            };
+            let offset = base + self.formatter.member_offset(&member)?;
+            let name = member.name().unwrap_or("<variant>");
+            let variant_type = strip_typedefs(self.unit, type_of(self.unit, &member)?)?;
+            let (size, description) = match &variant_type {
+                Some(variant_type) => (
+                    byte_size(self.unit, variant_type)?.unwrap_or(0),
+                    self.variant_declaration(name, variant_type)?,
+                ),
+                None => (0, name.to_string()),
+            };
+            self.lines.push(format!(
+                "/* {:>6}      | {:>7} */{}{},",
+                offset,
+                size,
+                " ".repeat(indent),
+                description
+            ));
+        }
+        Ok(())
+    }
+
+    /// A variant of a Rust enum the way it is declared, named `name` with the fields
+    /// of the structure `die`
+    fn variant_declaration(&self, name: &str, die: &Die<'a>) -> Result<String> {
+        let mut fields = Vec::new();
+        let mut is_tuple = true;
+        for child in self.unit.children(die)? {
+            if child.tag != DW_TAG_MEMBER {
+                continue;
+            }
+            let field = child.name().unwrap_or_default();
+            is_tuple &= field.starts_with("__");
+            let field_type = type_of(self.unit, &child)?;
+            fields.push((field, type_name(self.unit, field_type.as_ref())?));
+        }
+
+        Ok(if fields.is_empty() {
+            name.to_string()
+        } else if is_tuple {
+            let types: Vec<_> = fields
+                .into_iter()
+                .map(|(_, field_type)| field_type)
+                .collect();
+            format!("{}({})", name, types.join(", "))
+        } else {
+            let fields: Vec<_> = fields
+                .into_iter()
+                .map(|(field, field_type)| format!("{}: {}", field, field_type))
+                .collect();
+            format!("{} {{ {} }}", name, fields.join(", "))
+        })
+    }
+
+    /// Lists the member functions of a class after a blank line, without those the
+    /// compiler declared implicitly
+    fn member_functions(&mut self, die: &Die<'a>) -> Result<()> {
+        // Constructors are named after the class, without its template arguments
+        let class = self.unit.name_of(die)?.unwrap_or_default();
+        let class = class.split('<').next().unwrap_or_default();
+
+        let mut functions = Vec::new();
+        for child in self.unit.children(die)? {
+            if child.tag != DW_TAG_SUBPROGRAM
+                || child.attr(DW_AT_ARTIFICIAL).is_some_and(|v| v.flag())
+            {
+                continue;
+            }
+            let name = match self.unit.name_of(&child)? {
+                Some(name) => name,
+                None => continue,
+            };
+
+            let mut parameters = Vec::new();
+            let mut this = None;
+            for parameter in self.unit.children(&child)? {
+                match parameter.tag {
+                    DW_TAG_FORMAL_PARAMETER
+                        if parameter.attr(DW_AT_ARTIFICIAL).is_some_and(|v| v.flag()) =>
+                    {
+                        this = type_of(self.unit, &parameter)?;
+                    }
+                    DW_TAG_FORMAL_PARAMETER => {
+                        let parameter_type = type_of(self.unit, &parameter)?;
+                        parameters.push(type_name(self.unit, parameter_type.as_ref())?);
+                    }
+                    DW_TAG_UNSPECIFIED_PARAMETERS => parameters.push("...".to_string()),
+                    _ => {}
+                }
+            }
+            if parameters.is_empty() && !self.is_rust {
+                parameters.push("void".to_string());
+            }
+            // `this` points to a const object in const member functions
+            let is_const = match &this {
+                Some(this) => type_of(self.unit, this)?.is_some_and(|t| t.tag == DW_TAG_CONST_TYPE),
+                None => false,
+            };
+
+            let call = format!(
+                "{}({}){}",
+                name,
+                parameters.join(", "),
+                if is_const { " const" } else { "" }
+            );
+            let mut function = if name == class || name.starts_with('~') {
+                call
+            } else {
+                let return_type = type_of(self.unit, &child)?;
+                declaration(self.unit, return_type.as_ref(), &call)?
+            };
+            if child
+                .attr(DW_AT_VIRTUALITY)
+                .and_then(|v| v.udata())
+                .is_some_and(|v| v != 0)
+            {
+                function = format!("virtual {}", function);
+            } else if this.is_none() && !self.is_rust {
+                function = format!("static {}", function);
+            }
+            functions.push(function);
+        }
+
+        if !functions.is_empty() {
+            self.lines.push(String::new());
+            for function in functions {
+                self.note(INDENT, &format!("{};", function));
+            }
+        }
+        Ok(())
+    }
+
+    /// The template parameters of a structure, e.g. `T = int` and `N = 3`
+    fn template_parameters(&self, die: &Die<'a>) -> Result<Vec<String>> {
+        let mut parameters = Vec::new();
+        for child in self.unit.children(die)? {
+            let name = child.name().unwrap_or("<unnamed>");
+            match child.tag {
+                DW_TAG_TEMPLATE_TYPE_PARAMETER => {
+                    let parameter_type = type_of(self.unit, &child)?;
+                    let parameter_type = type_name(self.unit, parameter_type.as_ref())?;
+                    parameters.push(format!("{} = {}", name, parameter_type));
+                }
+                DW_TAG_TEMPLATE_VALUE_PARAMETER => {
+                    let value = match child.attr(DW_AT_CONST_VALUE) {
+                        Some(AttributeValue::Sdata(value)) => value.to_string(),
+                        Some(value) => match value.udata() {
+                            Some(value) => value.to_string(),
+                            None => "?".to_string(),
+                        },
+                        None => "?".to_string(),
+                    };
+                    parameters.push(format!("{} = {}", name, value));
+                }
+                _ => {}
+            }
+        }
+        Ok(parameters)
+    }
+
+    /// The declaration of the member `die`, e.g. `char *name` or `name: &str` in Rust
+    fn declaration(&self, die: &Die<'a>) -> Result<String> {
+        let member_type = type_of(self.unit, die)?;
+        let name = die.name().unwrap_or_default();
+        if self.is_rust {
+            Ok(format!(
+                "{}: {}",
+                name,
+                type_name(self.unit, member_type.as_ref())?
+            ))
+        } else {
+            declaration(self.unit, member_type.as_ref(), name)
+        }
+    }
+
+    /// A line without an offset, like those of member functions
+    fn note(&mut self, indent: usize, text: &str) {
+        self.lines
+            .push(format!("{}{}", " ".repeat(COMMENT_WIDTH + indent), text));
+    }
+
+    /// Notes the unused bits between `from` and `to`, like the padding at the end of
+    /// a structure
+    fn hole(&mut self, from: u64, to: u64, kind: &str) {
+        let bits = to - from;
+        if !bits.is_multiple_of(8) {
+            self.lines
+                .push(format!("/* XXX {:>2}-bit {:<11}*/", bits % 8, kind));
+        }
+        if bits >= 8 {
+            self.lines
+                .push(format!("/* XXX {:>2}-byte {:<10}*/", bits / 8, kind));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dwarf::testing::{abbrev_table, reference, sections, NoProcess, UnitBuilder};
+
+    fn abbrev_bytes() -> Vec<u8> {
+        abbrev_table(&[
+            // 2: enumeration type with children, name string, byte_size data1
+            0x02, 0x04, 0x01, 0x03, 0x08, 0x0b, 0x0b, 0x00, 0x00,
+            // 3: enumerator, name string, const_value sdata
+            0x03, 0x28, 0x00, 0x03, 0x08, 0x1c, 0x0d, 0x00, 0x00,
+            // 4: base type, name string, byte_size data1, encoding data1
+            0x04, 0x24, 0x00, 0x03, 0x08, 0x0b, 0x0b, 0x3e, 0x0b, 0x00, 0x00,
+            // 5: structure type with children, name string, byte_size data1
+            0x05, 0x13, 0x01, 0x03, 0x08, 0x0b, 0x0b, 0x00, 0x00,
+            // 6: member, name string, type ref4, data_member_location data1
+            0x06, 0x0d, 0x00, 0x03, 0x08, 0x49, 0x13, 0x38, 0x0b, 0x00, 0x00,
+        ])
+    }
+
+    /// A DWARF 4 unit of a C program with `enum color` and `struct s`, returning the
+    /// offsets of both
+    fn info_bytes() -> (Vec<u8>, u64, u64) {
+        // Compile unit in C99
+        let mut unit = UnitBuilder::new(0x0c);
+        let color = unit.die(b"\x02color\x00\x04");
+        unit.die(b"\x03RED\x00\x00");
+        unit.die(b"\x03GREEN\x00\x05");
+        // -2 as a signed LEB128
+        unit.die(b"\x03BLUE\x00\x7e");
+        unit.end_children();
+
+        let char = unit.die(&[0x04, b'c', b'h', b'a', b'r', 0, 1, DW_ATE_SIGNED_CHAR]);
+        let int = unit.die(&[0x04, b'i', b'n', b't', 0, 4, DW_ATE_SIGNED]);
+        let structure = unit.die(&[0x05, b's', 0, 12]);
+        for (name, member_type, offset) in [(b'c', char, 0), (b'x', int, 4)] {
+            unit.die(&[&[0x06, name, 0][..], &reference(member_type), &[offset]].concat());
+        }
+        unit.end_children();
+        (unit.finish(), color, structure)
+    }
+
+    #[test]
+    fn test_describe_type() {
+        let (info, color, structure) = info_bytes();
+        let abbrev = abbrev_bytes();
+        let sections = sections(&info, &abbrev);
+        let unit = sections.unit_at(0).unwrap();
+        let describe = |offset, pointers| {
+            let die = unit.entry(offset).unwrap();
+            describe_type(&unit, &NoProcess, Some(&die), pointers).unwrap()
+        };
+
+        assert_eq!(
+            describe(color, 0),
+            "type = enum color {RED, GREEN = 5, BLUE = -2}"
+        );
+        assert_eq!(
+            describe(structure, 1),
+            [
+                "/* offset      |    size */  type = struct s {",
+                "/*      0      |       1 */    char c;",
+                "/* XXX  3-byte hole      */",
+                "/*      4      |       4 */    int x;",
+                "/* XXX  4-byte padding   */",
+                "",
+                "                               /* total size (bytes):   12 */",
+                "                             } *",
+            ]
+            .join("\n")
+        );
+
+        assert_eq!(with_pointers("char".to_string(), "**"), "char **");
+        assert_eq!(with_pointers("char *".to_string(), "*"), "char **");
+    }
+}
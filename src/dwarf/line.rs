@@ -0,0 +1,819 @@
+use std::path::{Path, PathBuf};
+
+use super::aranges::AddressIndex;
+use super::constants::{DW_AT_COMP_DIR, DW_AT_STMT_LIST};
+use super::die::AttributeValue;
+use super::error::{DwarfError, Result};
+use super::reader::{Format, Reader};
+use super::sections::DwarfSections;
+use super::unit::Unit;
+
+const DW_LNS_COPY: u8 = 0x01;
+const DW_LNS_ADVANCE_PC: u8 = 0x02;
+const DW_LNS_ADVANCE_LINE: u8 = 0x03;
+const DW_LNS_SET_FILE: u8 = 0x04;
+const DW_LNS_SET_COLUMN: u8 = 0x05;
+const DW_LNS_NEGATE_STMT: u8 = 0x06;
+const DW_LNS_SET_BASIC_BLOCK: u8 = 0x07;
+const DW_LNS_CONST_ADD_PC: u8 = 0x08;
+const DW_LNS_FIXED_ADVANCE_PC: u8 = 0x09;
+const DW_LNS_SET_PROLOGUE_END: u8 = 0x0a;
+const DW_LNS_SET_EPILOGUE_BEGIN: u8 = 0x0b;
+const DW_LNS_SET_ISA: u8 = 0x0c;
+
+const DW_LNE_END_SEQUENCE: u8 = 0x01;
+const DW_LNE_SET_ADDRESS: u8 = 0x02;
+const DW_LNE_DEFINE_FILE: u8 = 0x03;
+const DW_LNE_SET_DISCRIMINATOR: u8 = 0x04;
+
+const DW_LNCT_PATH: u64 = 0x1;
+const DW_LNCT_DIRECTORY_INDEX: u64 = 0x2;
+const DW_LNCT_TIMESTAMP: u64 = 0x3;
+const DW_LNCT_SIZE: u64 = 0x4;
+const DW_LNCT_MD5: u64 = 0x5;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileEntry<'a> {
+    pub path: &'a str,
+    pub directory_index: u64,
+    pub modification_time: u64,
+    pub size: u64,
+    pub md5: Option<[u8; 16]>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineProgramHeader<'a> {
+    /// Offset of the line program in `.debug_line`
+    pub offset: u64,
+    pub format: Format,
+    pub version: u16,
+    pub address_size: u8,
+    pub minimum_instruction_length: u8,
+    pub maximum_operations_per_instruction: u8,
+    pub default_is_stmt: bool,
+    pub line_base: i8,
+    pub line_range: u8,
+    pub opcode_base: u8,
+    pub standard_opcode_lengths: Vec<u8>,
+    /// Before DWARF 5 the compilation directory is not part of the table,
+    /// it is implied as directory 0
+    pub include_directories: Vec<&'a str>,
+    pub file_names: Vec<FileEntry<'a>>,
+    /// The compilation directory of the unit, `DW_AT_comp_dir`
+    pub comp_dir: Option<&'a str>,
+}
+
+/// A row of the line number matrix
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineRow {
+    pub address: u64,
+    pub op_index: u64,
+    pub file: u64,
+    pub line: u64,
+    pub column: u64,
+    /// The row is a recommended breakpoint location
+    pub is_stmt: bool,
+    pub basic_block: bool,
+    /// The first address after the sequence, the row itself maps no code
+    pub end_sequence: bool,
+    /// The address where the prologue of the function ends
+    pub prologue_end: bool,
+    pub epilogue_begin: bool,
+    pub isa: u64,
+    /// Tells apart the blocks of code on the same line
+    pub discriminator: u64,
+}
+
+impl LineRow {
+    fn new(header: &LineProgramHeader) -> Self {
+        Self {
+            address: 0,
+            op_index: 0,
+            // Files are numbered from 1 before DWARF 5
+            file: 1,
+            line: 1,
+            column: 0,
+            is_stmt: header.default_is_stmt,
+            basic_block: false,
+            end_sequence: false,
+            prologue_end: false,
+            epilogue_begin: false,
+            isa: 0,
+            discriminator: 0,
+        }
+    }
+}
+
+/// A source position, lines and columns start at 1 and are 0 if unknown
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub path: PathBuf,
+    pub line: u64,
+    pub column: u64,
+}
+
+/// The line number program of a unit, run into its rows
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineProgram<'a> {
+    pub header: LineProgramHeader<'a>,
+    /// The rows of all sequences, each sequence ends with an `end_sequence` row
+    pub rows: Vec<LineRow>,
+}
+
+impl<'a> LineProgram<'a> {
+    /// The line program referred to by `DW_AT_stmt_list` of the unit, if it has one
+    pub fn for_unit(unit: &Unit<'a>) -> Result<Option<Self>> {
+        // Split units share the line program of their skeleton unit
+        if let Some(skeleton) = unit.skeleton() {
+            return Self::for_unit(skeleton);
+        }
+        let root = unit.root()?;
+        let offset = match root.attr(DW_AT_STMT_LIST).and_then(AttributeValue::offset) {
+            Some(offset) => offset,
+            None => return Ok(None),
+        };
+        let comp_dir = root.attr(DW_AT_COMP_DIR).and_then(AttributeValue::string);
+        Self::parse(unit, offset, comp_dir).map(Some)
+    }
+
+    /// Parses the line program at `offset` of `.debug_line`, `unit` is used to
+    /// decode the string forms of DWARF 5 entry formats
+    pub fn parse(unit: &Unit<'a>, offset: u64, comp_dir: Option<&'a str>) -> Result<Self> {
+        let sections = unit.sections();
+        let mut reader = Reader::at(sections.debug_line, offset as usize)?;
+        let (length, format) = reader.read_initial_length()?;
+        let end_offset = reader.offset() + length as usize;
+        let version = reader.read_u16()?;
+        if !(2..=5).contains(&version) {
+            return Err(DwarfError::UnsupportedVersion(version));
+        }
+
+        let mut address_size = unit.address_size;
+        if version >= 5 {
+            address_size = reader.read_u8()?;
+            let _segment_selector_size = reader.read_u8()?;
+        }
+        let header_length = reader.read_offset(format)?;
+        let program_offset = reader.offset() + header_length as usize;
+
+        let minimum_instruction_length = reader.read_u8()?;
+        let maximum_operations_per_instruction = match version {
+            4.. => reader.read_u8()?,
+            _ => 1,
+        };
+        let default_is_stmt = reader.read_u8()? != 0;
+        let line_base = reader.read_i8()?;
+        let line_range = reader.read_u8()?;
+        let opcode_base = reader.read_u8()?;
+        let standard_opcode_lengths = reader
+            .read_bytes(opcode_base.saturating_sub(1) as usize)?
+            .to_vec();
+
+        let mut header = LineProgramHeader {
+            offset,
+            format,
+            version,
+            address_size,
+            minimum_instruction_length,
+            maximum_operations_per_instruction,
+            default_is_stmt,
+            line_base,
+            line_range,
+            opcode_base,
+            standard_opcode_lengths,
+            include_directories: Vec::new(),
+            file_names: Vec::new(),
+            comp_dir,
+        };
+
+        if version >= 5 {
+            for entry in read_entries(&mut reader, unit)? {
+                header.include_directories.push(entry.path);
+            }
+            header.file_names = read_entries(&mut reader, unit)?;
+        } else {
+            loop {
+                let directory = reader.read_cstr()?;
+                if directory.is_empty() {
+                    break;
+                }
+                header.include_directories.push(to_str(directory));
+            }
+            loop {
+                let path = reader.read_cstr()?;
+                if path.is_empty() {
+                    break;
+                }
+                header.file_names.push(read_file_entry(&mut reader, path)?);
+            }
+        }
+
+        let program = sections
+            .debug_line
+            .get(program_offset..end_offset)
+            .ok_or(DwarfError::UnexpectedEof)?;
+        let rows = run_program(&mut header, program)?;
+        Ok(Self { header, rows })
+    }
+
+    /// The path of the file with `index` in the file table, joined with its directory
+    pub fn file_path(&self, index: u64) -> Option<PathBuf> {
+        let header = &self.header;
+        let file = if header.version >= 5 {
+            header.file_names.get(index as usize)?
+        } else {
+            header.file_names.get((index as usize).checked_sub(1)?)?
+        };
+
+        let directory = if header.version >= 5 {
+            header
+                .include_directories
+                .get(file.directory_index as usize)
+                .copied()
+        } else {
+            match file.directory_index {
+                0 => header.comp_dir,
+                index => header.include_directories.get(index as usize - 1).copied(),
+            }
+        };
+
+        let mut path = PathBuf::new();
+        // Relative directories are relative to the compilation directory
+        if let Some(comp_dir) = header.comp_dir {
+            path.push(comp_dir);
+        }
+        if let Some(directory) = directory {
+            path.push(directory);
+        }
+        path.push(file.path);
+        Some(path)
+    }
+
+    /// The sequences of rows, each covering a contiguous range of addresses
+    pub fn sequences(&self) -> impl Iterator<Item = &[LineRow]> {
+        self.rows
+            .split_inclusive(|row| row.end_sequence)
+            .filter(|sequence| sequence.len() > 1)
+    }
+
+    /// The row describing the instruction at `pc`
+    pub fn find_row(&self, pc: u64) -> Option<&LineRow> {
+        self.sequences()
+            .filter(|sequence| {
+                sequence[0].address <= pc && pc < sequence[sequence.len() - 1].address
+            })
+            .find_map(|sequence| {
+                let idx = sequence.partition_point(|row| row.address <= pc);
+                idx.checked_sub(1).map(|idx| &sequence[idx])
+            })
+    }
+
+    /// Whether a statement begins at `pc`, with a row marked as one at its address. Of the
+    /// rows at one address, as there are in optimized code, it need not be the last.
+    pub fn is_statement_start(&self, pc: u64) -> bool {
+        self.sequences()
+            .filter(|sequence| {
+                sequence[0].address <= pc && pc < sequence[sequence.len() - 1].address
+            })
+            .any(|sequence| {
+                let end = sequence.partition_point(|row| row.address <= pc);
+                sequence[..end]
+                    .iter()
+                    .rev()
+                    .take_while(|row| row.address == pc)
+                    .any(|row| row.is_stmt && row.line != 0)
+            })
+    }
+
+    pub fn find_location(&self, pc: u64) -> Option<SourceLocation> {
+        let row = self.find_row(pc)?;
+        Some(SourceLocation {
+            path: self.file_path(row.file)?,
+            line: row.line,
+            column: row.column,
+        })
+    }
+
+    /// Addresses where the code of `line` in the file at `file_index` begins. Only rows
+    /// marked as statements that enter the line from another line are considered.
+    pub fn line_addresses(&self, file_index: u64, line: u64) -> Vec<u64> {
+        let mut addresses = Vec::new();
+        for sequence in self.sequences() {
+            let mut previous: Option<&LineRow> = None;
+            for row in sequence.iter().filter(|row| !row.end_sequence) {
+                let entered = previous
+                    .map(|previous| previous.line != row.line || previous.file != row.file)
+                    .unwrap_or(true);
+                if entered && row.is_stmt && row.file == file_index && row.line == line {
+                    addresses.push(row.address);
+                }
+                previous = Some(row);
+            }
+        }
+        addresses.sort_unstable();
+        addresses.dedup();
+        addresses
+    }
+
+    /// The address where the prologue of the function whose code is from `low_pc` to
+    /// `high_pc` ends and its body begins. That is the row marked as the end of the
+    /// prologue, or else the second statement of the function as most compilers do not
+    /// mark it. `None` if the line program has no statement after the first.
+    pub fn prologue_end(&self, low_pc: u64, high_pc: u64) -> Option<u64> {
+        let rows = self
+            .rows
+            .iter()
+            .filter(|row| !row.end_sequence && (low_pc..high_pc).contains(&row.address));
+        if let Some(row) = rows.clone().find(|row| row.prologue_end) {
+            return Some(row.address);
+        }
+        rows.filter(|row| row.is_stmt && row.address > low_pc)
+            .map(|row| row.address)
+            .min()
+    }
+
+    /// Indexes of the files in the file table whose path ends with `path`
+    pub fn matching_files(&self, path: &Path) -> Vec<u64> {
+        let first = if self.header.version >= 5 { 0 } else { 1 };
+        (first..first + self.header.file_names.len() as u64)
+            .filter(|index| {
+                self.file_path(*index)
+                    .map(|file_path| file_path.ends_with(path))
+                    .unwrap_or(false)
+            })
+            .collect()
+    }
+}
+
+/// The line programs of all units of a file
+#[derive(Debug, Clone, Default)]
+pub struct LineTables<'a> {
+    programs: Vec<LineProgram<'a>>,
+    /// Offsets of the units in `.debug_info`, ascending, with the index of their program
+    units: Vec<(u64, usize)>,
+}
+
+impl<'a> LineTables<'a> {
+    pub fn new(sections: &DwarfSections<'a>) -> Result<Self> {
+        let mut programs = Vec::new();
+        let mut units = Vec::new();
+        for unit in sections.units() {
+            let unit = unit?;
+            if let Some(program) = LineProgram::for_unit(&unit)? {
+                units.push((unit.id(), programs.len()));
+                programs.push(program);
+            }
+        }
+        Ok(Self { programs, units })
+    }
+
+    pub fn programs(&self) -> &[LineProgram<'a>] {
+        &self.programs
+    }
+
+    /// The line program of the unit at `unit_offset` of `.debug_info`, see [`Unit::id`]
+    pub fn program_of_unit(&self, unit_offset: u64) -> Option<&LineProgram<'a>> {
+        let index = self
+            .units
+            .binary_search_by_key(&unit_offset, |(offset, _)| *offset)
+            .ok()?;
+        self.programs.get(self.units[index].1)
+    }
+
+    /// The source location of the instruction at `pc`, looked up in the line program
+    /// of the unit `index` maps `pc` to
+    pub fn find_indexed_location(&self, index: &AddressIndex, pc: u64) -> Option<SourceLocation> {
+        self.program_of_unit(index.find_unit_offset(pc)?)?
+            .find_location(pc)
+    }
+
+    /// The row of the instruction at `pc` with the line program it is in, looked up like
+    /// [`Self::find_indexed_location`]
+    pub fn find_indexed_row(
+        &self,
+        index: &AddressIndex,
+        pc: u64,
+    ) -> Option<(&LineProgram<'a>, &LineRow)> {
+        let program = self.program_of_unit(index.find_unit_offset(pc)?)?;
+        Some((program, program.find_row(pc)?))
+    }
+
+    /// Where the body of the function whose code is from `low_pc` to `high_pc` begins, see
+    /// [`LineProgram::prologue_end`]. The line program is that of the unit `index` maps
+    /// `low_pc` to.
+    pub fn find_prologue_end(
+        &self,
+        index: &AddressIndex,
+        low_pc: u64,
+        high_pc: u64,
+    ) -> Option<u64> {
+        self.program_of_unit(index.find_unit_offset(low_pc)?)?
+            .prologue_end(low_pc, high_pc)
+    }
+
+    /// The source location of the instruction at `pc`, searching the programs of all units
+    pub fn find_location(&self, pc: u64) -> Option<SourceLocation> {
+        self.programs
+            .iter()
+            .find_map(|program| program.find_location(pc))
+    }
+
+    /// The full path of a file of the line programs whose path ends with `path`
+    pub fn find_file(&self, path: &Path) -> Option<PathBuf> {
+        self.programs.iter().find_map(|program| {
+            let index = *program.matching_files(path).first()?;
+            program.file_path(index)
+        })
+    }
+
+    /// Addresses of the code for `line` of the files whose path ends with `path`. If the line
+    /// has no code, e.g. it is blank, the next line with code is used instead. Returns the
+    /// line that was used with its addresses, `None` if no code follows `line` in the file.
+    pub fn find_addresses(&self, path: &Path, line: u64) -> Option<(u64, Vec<u64>)> {
+        let mut best: Option<(u64, Vec<u64>)> = None;
+        for program in &self.programs {
+            for file in program.matching_files(path) {
+                let code_line = program
+                    .rows
+                    .iter()
+                    .filter(|row| row.file == file && row.is_stmt && !row.end_sequence)
+                    .map(|row| row.line)
+                    .filter(|row_line| *row_line >= line)
+                    .min();
+                let code_line = match code_line {
+                    Some(code_line) => code_line,
+                    None => continue,
+                };
+                let addresses = program.line_addresses(file, code_line);
+                match &mut best {
+                    Some((best_line, _)) if code_line > *best_line => {}
+                    Some((best_line, best_addresses)) if code_line == *best_line => {
+                        best_addresses.extend(addresses)
+                    }
+                    _ => best = Some((code_line, addresses)),
+                }
+            }
+        }
+
+        if let Some((_, addresses)) = &mut best {
+            addresses.sort_unstable();
+            addresses.dedup();
+        }
+        best
+    }
+}
+
+fn to_str(bytes: &[u8]) -> &str {
+    std::str::from_utf8(bytes).unwrap_or_default()
+}
+
+/// Reads the attributes following the path of a DWARF 2-4 file entry
+fn read_file_entry<'a>(reader: &mut Reader<'a>, path: &'a [u8]) -> Result<FileEntry<'a>> {
+    Ok(FileEntry {
+        path: to_str(path),
+        directory_index: reader.read_uleb128()?,
+        modification_time: reader.read_uleb128()?,
+        size: reader.read_uleb128()?,
+        md5: None,
+    })
+}
+
+/// Reads a DWARF 5 directory or file name table, described by its entry format
+fn read_entries<'a>(reader: &mut Reader<'a>, unit: &Unit<'a>) -> Result<Vec<FileEntry<'a>>> {
+    let format_count = reader.read_u8()?;
+    let mut formats = Vec::with_capacity(format_count as usize);
+    for _ in 0..format_count {
+        let content_type = reader.read_uleb128()?;
+        let form = reader.read_uleb128()? as u16;
+        formats.push((content_type, form));
+    }
+
+    let count = reader.read_uleb128()?;
+    let mut entries = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let mut entry = FileEntry::default();
+        for (content_type, form) in &formats {
+            let value = unit.read_form(reader, *form)?;
+            match *content_type {
+                DW_LNCT_PATH => entry.path = value.string().unwrap_or_default(),
+                DW_LNCT_DIRECTORY_INDEX => entry.directory_index = value.udata().unwrap_or(0),
+                DW_LNCT_TIMESTAMP => entry.modification_time = value.udata().unwrap_or(0),
+                DW_LNCT_SIZE => entry.size = value.udata().unwrap_or(0),
+                DW_LNCT_MD5 => {
+                    if let AttributeValue::Block(bytes) = value {
+                        entry.md5 = bytes.try_into().ok();
+                    }
+                }
+                // Vendor defined content is skipped
+                _ => {}
+            }
+        }
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// Runs the line number state machine over the opcodes of `program`
+fn run_program<'a>(header: &mut LineProgramHeader<'a>, program: &'a [u8]) -> Result<Vec<LineRow>> {
+    let mut reader = Reader::new(program);
+    let mut rows = Vec::new();
+    let mut state = LineRow::new(header);
+
+    let min_inst_length = header.minimum_instruction_length as u64;
+    let max_ops = header.maximum_operations_per_instruction.max(1) as u64;
+    let advance = |state: &mut LineRow, operation_advance: u64| {
+        let op_index = state.op_index + operation_advance;
+        state.address = state
+            .address
+            .wrapping_add(min_inst_length * (op_index / max_ops));
+        state.op_index = op_index % max_ops;
+    };
+
+    while !reader.is_empty() {
+        let opcode = reader.read_u8()?;
+        if opcode >= header.opcode_base {
+            let adjusted_opcode = (opcode - header.opcode_base) as u64;
+            let line_range = header.line_range.max(1) as u64;
+            advance(&mut state, adjusted_opcode / line_range);
+            let line_advance = header.line_base as i64 + (adjusted_opcode % line_range) as i64;
+            state.line = state.line.wrapping_add(line_advance as u64);
+            rows.push(state.clone());
+            state.basic_block = false;
+            state.prologue_end = false;
+            state.epilogue_begin = false;
+            state.discriminator = 0;
+            continue;
+        }
+
+        match opcode {
+            0 => {
+                let len = reader.read_uleb128()? as usize;
+                let mut extended = reader.split(len)?;
+                if len == 0 {
+                    continue;
+                }
+                match extended.read_u8()? {
+                    DW_LNE_END_SEQUENCE => {
+                        state.end_sequence = true;
+                        rows.push(state);
+                        state = LineRow::new(header);
+                    }
+                    DW_LNE_SET_ADDRESS => {
+                        state.address = extended.read_sized((len - 1) as u8)?;
+                        state.op_index = 0;
+                    }
+                    DW_LNE_DEFINE_FILE => {
+                        let path = extended.read_cstr()?;
+                        let entry = read_file_entry(&mut extended, path)?;
+                        header.file_names.push(entry);
+                    }
+                    DW_LNE_SET_DISCRIMINATOR => state.discriminator = extended.read_uleb128()?,
+                    // The length allows skipping unknown extended opcodes
+                    _ => {}
+                }
+            }
+            DW_LNS_COPY => {
+                rows.push(state.clone());
+                state.discriminator = 0;
+                state.basic_block = false;
+                state.prologue_end = false;
+                state.epilogue_begin = false;
+            }
+            DW_LNS_ADVANCE_PC => {
+                let operation_advance = reader.read_uleb128()?;
+                advance(&mut state, operation_advance);
+            }
+            DW_LNS_ADVANCE_LINE => {
+                let line_advance = reader.read_sleb128()?;
+                state.line = state.line.wrapping_add(line_advance as u64);
+            }
+            DW_LNS_SET_FILE => state.file = reader.read_uleb128()?,
+            DW_LNS_SET_COLUMN => state.column = reader.read_uleb128()?,
+            DW_LNS_NEGATE_STMT => state.is_stmt = !state.is_stmt,
+            DW_LNS_SET_BASIC_BLOCK => state.basic_block = true,
+            DW_LNS_CONST_ADD_PC => {
+                let adjusted_opcode = (255 - header.opcode_base) as u64;
+                advance(
+                    &mut state,
+                    adjusted_opcode / header.line_range.max(1) as u64,
+                );
+            }
+            DW_LNS_FIXED_ADVANCE_PC => {
+                state.address = state.address.wrapping_add(reader.read_u16()? as u64);
+                state.op_index = 0;
+            }
+            DW_LNS_SET_PROLOGUE_END => state.prologue_end = true,
+            DW_LNS_SET_EPILOGUE_BEGIN => state.epilogue_begin = true,
+            DW_LNS_SET_ISA => state.isa = reader.read_uleb128()?,
+            _ => {
+                // Unknown standard opcodes are skipped using their number of operands
+                let operands = header.standard_opcode_lengths[opcode as usize - 1];
+                for _ in 0..operands {
+                    reader.read_uleb128()?;
+                }
+            }
+        }
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEBUG_LINE_STR: &[u8] = b"/tmp\0src\0main.c\0util.h\0/usr/include\0";
+    const ABBREV: &[u8] = &[0x01, 0x11, 0x00, 0x00, 0x00, 0x00];
+
+    /// A DWARF 5 compile unit without attributes, needed to decode the line table forms
+    fn info_bytes() -> Vec<u8> {
+        let mut info = Vec::new();
+        info.extend_from_slice(&8u32.to_le_bytes());
+        info.extend_from_slice(&5u16.to_le_bytes());
+        info.extend_from_slice(&[0x01, 8]);
+        info.extend_from_slice(&0u32.to_le_bytes());
+        info.push(0x01);
+        info
+    }
+
+    fn sections<'a>(info: &'a [u8], line: &'a [u8]) -> DwarfSections<'a> {
+        DwarfSections {
+            debug_info: info,
+            debug_abbrev: ABBREV,
+            debug_line: line,
+            debug_line_str: DEBUG_LINE_STR,
+            address_size: 8,
+            ..Default::default()
+        }
+    }
+
+    /// Opcodes for a function at 0x1139 in file `main` with its body on lines 1 to 4,
+    /// and a line of the file `main + 1` inlined into it
+    fn program(main: u8) -> Vec<u8> {
+        let mut program = vec![DW_LNS_SET_FILE, main];
+        // set_address 0x1139
+        program.extend_from_slice(&[0x00, 0x09, DW_LNE_SET_ADDRESS]);
+        program.extend_from_slice(&0x1139u64.to_le_bytes());
+        // column 12, copy: line 1
+        program.extend_from_slice(&[DW_LNS_SET_COLUMN, 12, DW_LNS_COPY]);
+        // prologue_end, special opcode: address += 4, line += 1
+        program.extend_from_slice(&[DW_LNS_SET_PROLOGUE_END, 13 + 4 * 14 + 6]);
+        // discriminator 3, special opcode: address += 3, line += 1
+        program.extend_from_slice(&[0x00, 0x02, DW_LNE_SET_DISCRIMINATOR, 3, 13 + 3 * 14 + 6]);
+        // other file, line 10, not a statement, advance_pc 2, copy
+        program.extend_from_slice(&[DW_LNS_SET_FILE, main + 1, DW_LNS_ADVANCE_LINE, 7]);
+        program.extend_from_slice(&[DW_LNS_NEGATE_STMT, DW_LNS_ADVANCE_PC, 2, DW_LNS_COPY]);
+        // back to line 4 as statement, const_add_pc: address += 17, special opcode: copy
+        program.extend_from_slice(&[DW_LNS_SET_FILE, main, DW_LNS_NEGATE_STMT]);
+        program.extend_from_slice(&[DW_LNS_ADVANCE_LINE, 0x7a, DW_LNS_CONST_ADD_PC, 13 + 5]);
+        // fixed_advance_pc 5, end_sequence
+        program.extend_from_slice(&[DW_LNS_FIXED_ADVANCE_PC, 5, 0]);
+        program.extend_from_slice(&[0x00, 0x01, DW_LNE_END_SEQUENCE]);
+        program
+    }
+
+    fn line_program(version: u16, tables: &[u8], main: u8) -> Vec<u8> {
+        // min_inst_length, max_ops, default_is_stmt, line_base -5, line_range 14, opcode_base 13
+        let mut header = vec![1, 1, 1, 0xfb, 14, 13];
+        if version < 4 {
+            header.remove(1);
+        }
+        header.extend_from_slice(&[0, 1, 1, 1, 1, 0, 0, 0, 1, 0, 0, 1]);
+        header.extend_from_slice(tables);
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&version.to_le_bytes());
+        if version >= 5 {
+            bytes.extend_from_slice(&[8, 0]);
+        }
+        bytes.extend_from_slice(&(header.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&header);
+        bytes.extend_from_slice(&program(main));
+
+        let mut line = (bytes.len() as u32).to_le_bytes().to_vec();
+        line.extend_from_slice(&bytes);
+        line
+    }
+
+    fn check_program(program: &LineProgram, main: u64) {
+        let rows: Vec<_> = program
+            .rows
+            .iter()
+            .map(|row| (row.address, row.file, row.line, row.is_stmt))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                (0x1139, main, 1, true),
+                (0x113d, main, 2, true),
+                (0x1140, main, 3, true),
+                (0x1142, main + 1, 10, false),
+                (0x1153, main, 4, true),
+                (0x1158, main, 4, true),
+            ]
+        );
+        assert!(program.rows[1].prologue_end);
+        assert_eq!(program.rows[2].discriminator, 3);
+        assert_eq!(program.rows[3].discriminator, 0);
+        assert!(program.rows[5].end_sequence);
+
+        let location = program.find_location(0x1141).unwrap();
+        assert_eq!(location.path, PathBuf::from("/tmp/src/main.c"));
+        assert_eq!((location.line, location.column), (3, 12));
+        let location = program.find_location(0x1142).unwrap();
+        assert_eq!(location.path, PathBuf::from("/usr/include/util.h"));
+        assert_eq!(location.line, 10);
+        assert!(program.find_location(0x1158).is_none());
+        assert!(program.find_location(0x1138).is_none());
+
+        assert_eq!(program.matching_files(Path::new("main.c")), vec![main]);
+        assert_eq!(program.matching_files(Path::new("src/main.c")), vec![main]);
+        assert!(program.matching_files(Path::new("ain.c")).is_empty());
+        assert_eq!(program.line_addresses(main, 4), vec![0x1153]);
+        assert!(program.line_addresses(main, 10).is_empty());
+
+        assert_eq!(program.prologue_end(0x1139, 0x1158), Some(0x113d));
+        // Without a marked end, the next statement not in the first row
+        assert_eq!(program.prologue_end(0x1140, 0x1158), Some(0x1153));
+        assert_eq!(program.prologue_end(0x1153, 0x1158), None);
+
+        assert!(program.is_statement_start(0x113d));
+        assert!(!program.is_statement_start(0x1141));
+        assert!(!program.is_statement_start(0x1142));
+        assert!(!program.is_statement_start(0x1158));
+    }
+
+    #[test]
+    fn test_line_program_v4() {
+        let mut tables = Vec::new();
+        tables.extend_from_slice(b"src\0/usr/include\0\0");
+        tables.extend_from_slice(b"main.c\0\x01\0\0util.h\0\x02\0\0\0");
+        let (info, line) = (info_bytes(), line_program(4, &tables, 1));
+        let sections = sections(&info, &line);
+        let unit = sections.unit_at(0).unwrap();
+
+        let program = LineProgram::parse(&unit, 0, Some("/tmp")).unwrap();
+        assert_eq!(
+            program.header.include_directories,
+            vec!["src", "/usr/include"]
+        );
+        check_program(&program, 1);
+    }
+
+    #[test]
+    fn test_line_program_v5() {
+        let mut tables = Vec::new();
+        // Directories with their path as line_strp
+        tables.extend_from_slice(&[1, 0x01, 0x1f, 3]);
+        for offset in [0u32, 5, 23] {
+            tables.extend_from_slice(&offset.to_le_bytes());
+        }
+        // Files with path line_strp, directory_index udata and md5 data16
+        tables.extend_from_slice(&[3, 0x01, 0x1f, 0x02, 0x0f, 0x05, 0x1e, 2]);
+        for (offset, directory, md5) in [(9u32, 1u8, 0xaa), (16, 2, 0xbb)] {
+            tables.extend_from_slice(&offset.to_le_bytes());
+            tables.push(directory);
+            tables.extend_from_slice(&[md5; 16]);
+        }
+        let (info, line) = (info_bytes(), line_program(5, &tables, 0));
+        let sections = sections(&info, &line);
+        let unit = sections.unit_at(0).unwrap();
+
+        let program = LineProgram::parse(&unit, 0, Some("/tmp")).unwrap();
+        assert_eq!(
+            program.header.include_directories,
+            vec!["/tmp", "src", "/usr/include"]
+        );
+        assert_eq!(program.header.file_names[1].path, "util.h");
+        assert_eq!(program.header.file_names[1].md5, Some([0xbb; 16]));
+        check_program(&program, 0);
+    }
+
+    #[test]
+    fn test_find_addresses() {
+        let mut tables = Vec::new();
+        tables.extend_from_slice(b"src\0/usr/include\0\0");
+        tables.extend_from_slice(b"main.c\0\x01\0\0util.h\0\x02\0\0\0");
+        let (info, line) = (info_bytes(), line_program(4, &tables, 1));
+        let sections = sections(&info, &line);
+        let unit = sections.unit_at(0).unwrap();
+        let tables = LineTables {
+            programs: vec![LineProgram::parse(&unit, 0, Some("/tmp")).unwrap()],
+            units: vec![(0, 0)],
+        };
+
+        assert_eq!(
+            tables.find_addresses(Path::new("main.c"), 2),
+            Some((2, vec![0x113d]))
+        );
+        // Line 10 of util.h is not a statement, and nothing follows it
+        assert_eq!(tables.find_addresses(Path::new("util.h"), 10), None);
+        assert_eq!(
+            tables.find_addresses(Path::new("main.c"), 0),
+            Some((1, vec![0x1139]))
+        );
+        assert_eq!(tables.find_addresses(Path::new("main.c"), 5), None);
+        assert_eq!(tables.find_location(0x1153).unwrap().line, 4);
+        assert_eq!(tables.program_of_unit(0), tables.programs().first());
+        assert_eq!(tables.program_of_unit(0x10), None);
+    }
+}
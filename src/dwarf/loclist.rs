@@ -0,0 +1,323 @@
+use super::constants::*;
+use super::die::AttributeValue;
+use super::error::{DwarfError, Result};
+use super::reader::{Format, Reader};
+use super::unit::Unit;
+
+/// The location of a value over the addresses from `begin` up to `end`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocationListEntry<'a> {
+    pub begin: u64,
+    pub end: u64,
+    pub expression: &'a [u8],
+}
+
+/// A location list of `.debug_loc` or `.debug_loclists`, describing a value whose
+/// location changes over the code of its scope. Addresses are those of the file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LocationList<'a> {
+    pub entries: Vec<LocationListEntry<'a>>,
+    /// Location used for addresses no entry covers, DWARF 5
+    pub default: Option<&'a [u8]>,
+}
+
+impl<'a> LocationList<'a> {
+    /// Reads the location list an attribute of `unit` refers to. Returns `None` if the
+    /// attribute is not a location list, e.g. a single location expression.
+    pub fn parse(unit: &Unit<'a>, value: &AttributeValue<'a>) -> Result<Option<Self>> {
+        let sections = unit.sections();
+        let offset = match *value {
+            AttributeValue::LoclistsIndex(index) => {
+                // Without a base, the offsets follow the header of the only contribution
+                let base = unit.loclists_base.unwrap_or(match unit.format {
+                    Format::Dwarf32 => 12,
+                    Format::Dwarf64 => 20,
+                });
+                let entry = base + index * unit.format.offset_size() as u64;
+                let mut reader = Reader::at(sections.debug_loclists, entry as usize)?;
+                base + reader.read_offset(unit.format)?
+            }
+            _ => match value.offset() {
+                Some(offset) if value.expression().is_none() => offset,
+                _ => return Ok(None),
+            },
+        };
+
+        let list = if unit.version >= 5 {
+            let reader = Reader::at(sections.debug_loclists, offset as usize)?;
+            read_loclists(reader, unit.base_address, unit.address_size, |index| {
+                unit.address_by_index(index)
+            })?
+        } else if unit.unit_type == DW_UT_SPLIT_COMPILE {
+            let reader = Reader::at(sections.debug_loc, offset as usize)?;
+            read_split_loc(reader, |index| unit.address_by_index(index))?
+        } else {
+            let reader = Reader::at(sections.debug_loc, offset as usize)?;
+            read_loc(reader, unit.base_address, unit.address_size)?
+        };
+        Ok(Some(list))
+    }
+
+    /// The location expression at `pc`
+    pub fn find(&self, pc: u64) -> Option<&'a [u8]> {
+        self.entries
+            .iter()
+            .find(|entry| entry.begin <= pc && pc < entry.end)
+            .map(|entry| entry.expression)
+            .or(self.default)
+    }
+}
+
+/// The location expression of a `DW_AT_location` or `DW_AT_frame_base` value at `pc`,
+/// which is either an expression or a location list. Returns `None` where the value
+/// has no location.
+pub fn location_expression<'a>(
+    unit: &Unit<'a>,
+    value: &AttributeValue<'a>,
+    pc: u64,
+) -> Result<Option<&'a [u8]>> {
+    if let Some(expression) = value.expression() {
+        return Ok(Some(expression));
+    }
+    Ok(LocationList::parse(unit, value)?.and_then(|list| list.find(pc)))
+}
+
+/// Reads a list of `.debug_loc`, used before DWARF 5
+fn read_loc<'a>(
+    mut reader: Reader<'a>,
+    mut base_address: u64,
+    address_size: u8,
+) -> Result<LocationList<'a>> {
+    let max_address = u64::MAX >> (64 - 8 * address_size as u32);
+    let mut list = LocationList::default();
+
+    loop {
+        let begin = reader.read_address(address_size)?;
+        let end = reader.read_address(address_size)?;
+        if begin == 0 && end == 0 {
+            break;
+        }
+        if begin == max_address {
+            base_address = end;
+            continue;
+        }
+        let len = reader.read_u16()? as usize;
+        list.entries.push(LocationListEntry {
+            begin: base_address.wrapping_add(begin),
+            end: base_address.wrapping_add(end),
+            expression: reader.read_bytes(len)?,
+        });
+    }
+    Ok(list)
+}
+
+/// Reads a list of `.debug_loc.dwo` of the GNU split DWARF extension before DWARF 5,
+/// whose addresses are indexes resolved by `address_by_index`
+fn read_split_loc<'a, F>(mut reader: Reader<'a>, address_by_index: F) -> Result<LocationList<'a>>
+where
+    F: Fn(u64) -> Option<u64>,
+{
+    let address = |index| address_by_index(index).ok_or(DwarfError::Unavailable("indexed address"));
+    let mut list = LocationList::default();
+
+    loop {
+        let kind = reader.read_u8()?;
+        let (begin, end) = match kind {
+            DW_LLE_GNU_END_OF_LIST_ENTRY => break,
+            // The other entries have indexes of absolute addresses, so no base is needed
+            DW_LLE_GNU_BASE_ADDRESS_SELECTION_ENTRY => {
+                reader.read_uleb128()?;
+                continue;
+            }
+            DW_LLE_GNU_START_END_ENTRY => {
+                let begin = address(reader.read_uleb128()?)?;
+                (begin, address(reader.read_uleb128()?)?)
+            }
+            DW_LLE_GNU_START_LENGTH_ENTRY => {
+                let begin = address(reader.read_uleb128()?)?;
+                (begin, begin.wrapping_add(reader.read_u32()? as u64))
+            }
+            _ => return Err(DwarfError::UnknownLocationListEntry(kind)),
+        };
+        let len = reader.read_u16()? as usize;
+        list.entries.push(LocationListEntry {
+            begin,
+            end,
+            expression: reader.read_bytes(len)?,
+        });
+    }
+    Ok(list)
+}
+
+/// Reads a list of `.debug_loclists`, whose indexed addresses are resolved by `address_by_index`
+fn read_loclists<'a, F>(
+    mut reader: Reader<'a>,
+    mut base_address: u64,
+    address_size: u8,
+    address_by_index: F,
+) -> Result<LocationList<'a>>
+where
+    F: Fn(u64) -> Option<u64>,
+{
+    let address = |index| address_by_index(index).ok_or(DwarfError::Unavailable("indexed address"));
+    let mut list = LocationList::default();
+
+    loop {
+        let kind = reader.read_u8()?;
+        let (begin, end) = match kind {
+            DW_LLE_END_OF_LIST => break,
+            DW_LLE_BASE_ADDRESSX => {
+                base_address = address(reader.read_uleb128()?)?;
+                continue;
+            }
+            DW_LLE_BASE_ADDRESS => {
+                base_address = reader.read_address(address_size)?;
+                continue;
+            }
+            // Location views, which tell apart locations at the same address, are not used
+            DW_LLE_GNU_VIEW_PAIR => {
+                reader.read_uleb128()?;
+                reader.read_uleb128()?;
+                continue;
+            }
+            DW_LLE_DEFAULT_LOCATION => {
+                let len = reader.read_uleb128()? as usize;
+                list.default = Some(reader.read_bytes(len)?);
+                continue;
+            }
+            DW_LLE_STARTX_ENDX => {
+                let begin = address(reader.read_uleb128()?)?;
+                (begin, address(reader.read_uleb128()?)?)
+            }
+            DW_LLE_STARTX_LENGTH => {
+                let begin = address(reader.read_uleb128()?)?;
+                (begin, begin.wrapping_add(reader.read_uleb128()?))
+            }
+            DW_LLE_OFFSET_PAIR => {
+                let begin = base_address.wrapping_add(reader.read_uleb128()?);
+                (begin, base_address.wrapping_add(reader.read_uleb128()?))
+            }
+            DW_LLE_START_END => {
+                let begin = reader.read_address(address_size)?;
+                (begin, reader.read_address(address_size)?)
+            }
+            DW_LLE_START_LENGTH => {
+                let begin = reader.read_address(address_size)?;
+                (begin, begin.wrapping_add(reader.read_uleb128()?))
+            }
+            _ => return Err(DwarfError::UnknownLocationListEntry(kind)),
+        };
+        let len = reader.read_uleb128()? as usize;
+        list.entries.push(LocationListEntry {
+            begin,
+            end,
+            expression: reader.read_bytes(len)?,
+        });
+    }
+    Ok(list)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_loc() {
+        let mut bytes = Vec::new();
+        // 0x10..0x20: DW_OP_reg5
+        bytes.extend_from_slice(&0x10u64.to_le_bytes());
+        bytes.extend_from_slice(&0x20u64.to_le_bytes());
+        bytes.extend_from_slice(&[0x01, 0x00, 0x55]);
+        // Base address selection of 0x2000
+        bytes.extend_from_slice(&u64::MAX.to_le_bytes());
+        bytes.extend_from_slice(&0x2000u64.to_le_bytes());
+        // 0x2004..0x2008: DW_OP_fbreg -20
+        bytes.extend_from_slice(&0x4u64.to_le_bytes());
+        bytes.extend_from_slice(&0x8u64.to_le_bytes());
+        bytes.extend_from_slice(&[0x02, 0x00, 0x91, 0x6c]);
+        // End of the list
+        bytes.extend_from_slice(&[0; 16]);
+
+        let list = read_loc(Reader::new(&bytes), 0x1000, 8).unwrap();
+        assert_eq!(
+            list.entries,
+            vec![
+                LocationListEntry {
+                    begin: 0x1010,
+                    end: 0x1020,
+                    expression: &[0x55],
+                },
+                LocationListEntry {
+                    begin: 0x2004,
+                    end: 0x2008,
+                    expression: &[0x91, 0x6c],
+                },
+            ]
+        );
+        assert_eq!(list.find(0x101f), Some(&[0x55][..]));
+        assert_eq!(list.find(0x1020), None);
+    }
+
+    #[test]
+    fn test_read_split_loc() {
+        let mut bytes = vec![
+            // Start index 0, end index 1: DW_OP_reg5
+            0x02, 0x00, 0x01, 0x01, 0x00, 0x55,
+            // Start index 1, length 0x10: DW_OP_fbreg -20
+            0x03, 0x01, 0x10, 0x00, 0x00, 0x00, 0x02, 0x00, 0x91, 0x6c,
+        ];
+        // End of the list
+        bytes.push(0x00);
+
+        let addresses = [0x1000, 0x1010];
+        let list = read_split_loc(Reader::new(&bytes), |index| {
+            addresses.get(index as usize).copied()
+        })
+        .unwrap();
+        assert_eq!(
+            list.entries,
+            vec![
+                LocationListEntry {
+                    begin: 0x1000,
+                    end: 0x1010,
+                    expression: &[0x55],
+                },
+                LocationListEntry {
+                    begin: 0x1010,
+                    end: 0x1020,
+                    expression: &[0x91, 0x6c],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_loclists() {
+        let bytes = [
+            // DW_LLE_base_addressx 1
+            0x01, 0x01, //
+            // DW_LLE_offset_pair 0x4 0x10: DW_OP_reg0
+            0x04, 0x04, 0x10, 0x01, 0x50, //
+            // DW_LLE_startx_length 0 0x8: DW_OP_lit1; DW_OP_stack_value
+            0x03, 0x00, 0x08, 0x02, 0x31, 0x9f, //
+            // DW_LLE_default_location: DW_OP_reg3
+            0x05, 0x01, 0x53, //
+            // DW_LLE_end_of_list
+            0x00,
+        ];
+        let addresses = [0x500, 0x1000];
+        let list = read_loclists(Reader::new(&bytes), 0, 8, |index| {
+            addresses.get(index as usize).copied()
+        })
+        .unwrap();
+
+        assert_eq!(list.entries.len(), 2);
+        assert_eq!(list.find(0x1004), Some(&[0x50][..]));
+        assert_eq!(list.find(0x507), Some(&[0x31, 0x9f][..]));
+        assert_eq!(list.find(0x1010), Some(&[0x53][..]));
+        assert_eq!(
+            read_loclists(Reader::new(&[0x0a]), 0, 8, |_| None),
+            Err(DwarfError::UnknownLocationListEntry(0x0a))
+        );
+    }
+}
@@ -0,0 +1,24 @@
+pub mod abbrev;
+pub mod abi;
+pub mod aranges;
+pub mod constants;
+pub mod die;
+pub mod error;
+pub mod expression;
+pub mod frame;
+pub mod layout;
+pub mod line;
+pub mod loclist;
+pub mod process;
+pub mod reader;
+pub mod rnglist;
+mod rust;
+pub mod scope;
+pub mod sections;
+pub mod split;
+#[cfg(test)]
+mod testing;
+pub mod types;
+pub mod unit;
+pub mod unwind;
+pub mod value;
@@ -0,0 +1,187 @@
+use nix::sys::ptrace;
+use nix::sys::ptrace::AddressType;
+use nix::unistd::Pid;
+
+use super::constants::*;
+use super::die::Die;
+use super::error::{DwarfError, Result};
+use super::expression::{EvaluationContext, Evaluator, Location};
+use super::frame::{CallFrameInfo, CfaRule};
+use super::loclist::location_expression;
+use super::unit::Unit;
+use super::unwind::RETURN_ADDRESS_REGISTER;
+use crate::backtrace::Frame;
+use crate::elf::file::ElfFile;
+use crate::elf::program::SegmentType;
+use crate::register::{self, RegisterKind};
+
+/// Evaluates expressions against the registers and memory of a stopped process.
+/// What depends on the current function, the frame base and the CFA, is only
+/// available once it has been set.
+#[derive(Debug, Clone)]
+pub struct ProcessContext {
+    pid: Pid,
+    load_bias: u64,
+    /// Registers of an outer frame, recovered by unwinding. The registers of the
+    /// innermost frame are read from the process.
+    frame: Option<Frame>,
+    frame_base: Option<u64>,
+    cfa: Option<u64>,
+    /// Size of the TLS block of the executable, which ends at the thread pointer
+    tls_size: Option<u64>,
+    /// Whether the process is stopped at the first instruction of the current function
+    at_entry: bool,
+}
+
+impl ProcessContext {
+    /// `load_bias` is the load address of a position independent executable, or 0
+    pub fn new(pid: Pid, load_bias: u64) -> Self {
+        Self {
+            pid,
+            load_bias,
+            frame: None,
+            frame_base: None,
+            cfa: None,
+            tls_size: None,
+            at_entry: false,
+        }
+    }
+
+    /// Evaluates in `frame` instead of the innermost frame
+    pub fn with_frame(mut self, frame: &Frame) -> Self {
+        self.cfa = frame.cfa;
+        self.frame = Some(frame.clone());
+        self
+    }
+
+    pub fn pc(&self) -> u64 {
+        match &self.frame {
+            Some(frame) => frame.pc,
+            None => register::get_register_value(self.pid, RegisterKind::Rip),
+        }
+    }
+
+    /// The pc as an address of the file, like the addresses of the debug information.
+    /// In outer frames it is the address of the call, as the return address may
+    /// already be in the next scope.
+    pub fn file_pc(&self) -> u64 {
+        let pc = match &self.frame {
+            Some(frame) => frame.lookup_pc(),
+            None => self.pc(),
+        };
+        pc.wrapping_sub(self.load_bias)
+    }
+
+    /// Computes the CFA of the current frame from its unwind row
+    pub fn set_cfa(&mut self, cfi: &CallFrameInfo) -> Result<()> {
+        let row = cfi
+            .unwind_row(self.file_pc())?
+            .ok_or(DwarfError::Unavailable("unwind information"))?;
+        let cfa = match row.cfa {
+            CfaRule::RegisterOffset { register, offset } => {
+                self.register(register)?.wrapping_add(offset as u64)
+            }
+            CfaRule::Expression(expression) => {
+                match Evaluator::new(self, 8).evaluate(expression)? {
+                    Location::Memory(address) | Location::Value(address) => address,
+                    _ => return Err(DwarfError::Unavailable("call frame address")),
+                }
+            }
+        };
+        self.cfa = Some(cfa);
+        Ok(())
+    }
+
+    /// Computes the frame base of `function`, the subprogram the process is stopped in
+    pub fn set_function(&mut self, unit: &Unit, function: &Die) -> Result<()> {
+        let pc = self.file_pc();
+        self.at_entry = function
+            .attr(DW_AT_LOW_PC)
+            .and_then(|value| value.address())
+            == Some(pc);
+
+        let expression = match function.attr(DW_AT_FRAME_BASE) {
+            Some(value) => location_expression(unit, value, pc)?,
+            None => None,
+        };
+        self.frame_base = match expression {
+            Some(expression) => match Evaluator::for_unit(self, unit).evaluate(expression)? {
+                Location::Register(register) => Some(self.register(register)?),
+                Location::Memory(address) | Location::Value(address) => Some(address),
+                _ => None,
+            },
+            None => None,
+        };
+        Ok(())
+    }
+
+    /// Reads the size of the TLS block of the executable from its `PT_TLS` segment
+    pub fn set_tls_segment(&mut self, elf_file: &ElfFile) {
+        self.tls_size = elf_file
+            .get_program_header_by_type(SegmentType::Tls)
+            .map(|segment| {
+                let align = segment.align().max(1);
+                segment.memsz().div_ceil(align) * align
+            });
+    }
+}
+
+impl EvaluationContext for ProcessContext {
+    /// Registers an outer frame has no value for are not available, apart from those
+    /// beyond the general purpose registers, which are the same in all frames
+    fn register(&self, register: u16) -> Result<u64> {
+        match &self.frame {
+            Some(frame) if register <= RETURN_ADDRESS_REGISTER => frame
+                .registers
+                .get(register)
+                .ok_or(DwarfError::Unavailable("register of the outer frame")),
+            _ => register::get_register_value_from_dwarf_register(self.pid, register as i32)
+                .ok_or(DwarfError::UnknownRegister(register)),
+        }
+    }
+
+    fn read_memory(&self, address: u64, size: u8) -> Result<u64> {
+        let word = ptrace::read(self.pid, address as AddressType)
+            .map_err(|_| DwarfError::InvalidMemoryAccess(address))? as u64;
+        Ok(match size {
+            0 => 0,
+            1..=7 => word & ((1 << (size * 8)) - 1),
+            _ => word,
+        })
+    }
+
+    fn load_bias(&self) -> u64 {
+        self.load_bias
+    }
+
+    fn frame_base(&self) -> Result<u64> {
+        self.frame_base.ok_or(DwarfError::Unavailable("frame base"))
+    }
+
+    fn call_frame_cfa(&self) -> Result<u64> {
+        self.cfa
+            .ok_or(DwarfError::Unavailable("call frame address"))
+    }
+
+    /// x86-64 places the TLS block of the executable right below the thread pointer
+    fn tls_address(&self, offset: u64) -> Result<u64> {
+        let tls_size = self
+            .tls_size
+            .ok_or(DwarfError::Unavailable("thread local storage"))?;
+        let thread_pointer = register::get_register_value(self.pid, RegisterKind::FsBase);
+        Ok(thread_pointer.wrapping_sub(tls_size).wrapping_add(offset))
+    }
+
+    /// Entry values are only known while the registers still hold them, at the
+    /// first instruction of the function
+    fn entry_value(&self, expression: &[u8]) -> Result<u64> {
+        if !self.at_entry {
+            return Err(DwarfError::Unavailable("entry value"));
+        }
+        match Evaluator::new(self, 8).evaluate(expression)? {
+            Location::Register(register) => self.register(register),
+            Location::Memory(value) | Location::Value(value) => Ok(value),
+            _ => Err(DwarfError::Unavailable("entry value")),
+        }
+    }
+}
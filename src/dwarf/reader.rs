@@ -0,0 +1,251 @@
+use super::error::{DwarfError, Result};
+
+/// Whether a unit or frame entry uses the 32-bit or the 64-bit DWARF format,
+/// which decides the size of lengths and section offsets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Dwarf32,
+    Dwarf64,
+}
+
+impl Format {
+    pub fn offset_size(&self) -> u8 {
+        match self {
+            Format::Dwarf32 => 4,
+            Format::Dwarf64 => 8,
+        }
+    }
+}
+
+/// A cursor over little endian DWARF data
+#[derive(Debug, Clone, Copy)]
+pub struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, offset: 0 }
+    }
+
+    /// A reader positioned at `offset` of `bytes`
+    pub fn at(bytes: &'a [u8], offset: usize) -> Result<Self> {
+        if offset > bytes.len() {
+            return Err(DwarfError::UnexpectedEof);
+        }
+        Ok(Self { bytes, offset })
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn set_offset(&mut self, offset: usize) {
+        self.offset = offset;
+    }
+
+    pub fn bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.offset >= self.bytes.len()
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.bytes.len().saturating_sub(self.offset)
+    }
+
+    pub fn read_bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self
+            .offset
+            .checked_add(len)
+            .ok_or(DwarfError::UnexpectedEof)?;
+        let bytes = self
+            .bytes
+            .get(self.offset..end)
+            .ok_or(DwarfError::UnexpectedEof)?;
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    /// Splits off the next `len` bytes into a reader of their own
+    pub fn split(&mut self, len: usize) -> Result<Reader<'a>> {
+        Ok(Reader::new(self.read_bytes(len)?))
+    }
+
+    pub fn skip(&mut self, len: usize) -> Result<()> {
+        self.read_bytes(len).map(|_| ())
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    pub fn read_i8(&mut self) -> Result<i8> {
+        Ok(self.read_u8()? as i8)
+    }
+
+    pub fn read_u16(&mut self) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.read_bytes(2)?.try_into().unwrap()))
+    }
+
+    pub fn read_i16(&mut self) -> Result<i16> {
+        Ok(self.read_u16()? as i16)
+    }
+
+    pub fn read_u24(&mut self) -> Result<u32> {
+        let bytes = self.read_bytes(3)?;
+        Ok(bytes[0] as u32 | (bytes[1] as u32) << 8 | (bytes[2] as u32) << 16)
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.read_bytes(4)?.try_into().unwrap()))
+    }
+
+    pub fn read_i32(&mut self) -> Result<i32> {
+        Ok(self.read_u32()? as i32)
+    }
+
+    pub fn read_u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.read_bytes(8)?.try_into().unwrap()))
+    }
+
+    pub fn read_i64(&mut self) -> Result<i64> {
+        Ok(self.read_u64()? as i64)
+    }
+
+    /// Reads an unsigned integer of `size` bytes
+    pub fn read_sized(&mut self, size: u8) -> Result<u64> {
+        match size {
+            1 => self.read_u8().map(u64::from),
+            2 => self.read_u16().map(u64::from),
+            4 => self.read_u32().map(u64::from),
+            8 => self.read_u64(),
+            _ => Err(DwarfError::UnsupportedAddressSize(size)),
+        }
+    }
+
+    pub fn read_address(&mut self, address_size: u8) -> Result<u64> {
+        self.read_sized(address_size)
+    }
+
+    /// Reads a section offset, which is 4 bytes in 32-bit DWARF and 8 bytes in 64-bit DWARF
+    pub fn read_offset(&mut self, format: Format) -> Result<u64> {
+        self.read_sized(format.offset_size())
+    }
+
+    /// Reads an initial length field, which also determines the format of what follows
+    pub fn read_initial_length(&mut self) -> Result<(u64, Format)> {
+        let length = self.read_u32()?;
+        if length == 0xffffffff {
+            Ok((self.read_u64()?, Format::Dwarf64))
+        } else {
+            Ok((length as u64, Format::Dwarf32))
+        }
+    }
+
+    pub fn read_uleb128(&mut self) -> Result<u64> {
+        let mut result = 0u64;
+        let mut shift = 0;
+        loop {
+            let byte = self.read_u8()?;
+            if shift < 64 {
+                let bits = (byte & 0x7f) as u64;
+                if shift == 63 && bits > 1 {
+                    return Err(DwarfError::Leb128Overflow);
+                }
+                result |= bits << shift;
+            } else if byte & 0x7f != 0 {
+                return Err(DwarfError::Leb128Overflow);
+            }
+            shift += 7;
+            if byte & 0x80 == 0 {
+                return Ok(result);
+            }
+        }
+    }
+
+    pub fn read_sleb128(&mut self) -> Result<i64> {
+        let mut result = 0i64;
+        let mut shift = 0;
+        loop {
+            let byte = self.read_u8()?;
+            if shift < 64 {
+                result |= ((byte & 0x7f) as i64) << shift;
+            }
+            shift += 7;
+            if byte & 0x80 == 0 {
+                if shift < 64 && byte & 0x40 != 0 {
+                    result |= -1i64 << shift;
+                }
+                return Ok(result);
+            }
+        }
+    }
+
+    /// Reads a NUL terminated string, without the terminator
+    pub fn read_cstr(&mut self) -> Result<&'a [u8]> {
+        let rest = &self.bytes[self.offset.min(self.bytes.len())..];
+        let len = rest
+            .iter()
+            .position(|byte| *byte == 0)
+            .ok_or(DwarfError::UnexpectedEof)?;
+        self.offset += len + 1;
+        Ok(&rest[..len])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_leb128() {
+        let bytes = [0x02, 0x7f, 0x80, 0x01, 0xe5, 0x8e, 0x26, 0xc0, 0xbb, 0x78];
+        let mut reader = Reader::new(&bytes);
+        assert_eq!(reader.read_uleb128(), Ok(2));
+        assert_eq!(reader.read_uleb128(), Ok(127));
+        assert_eq!(reader.read_uleb128(), Ok(128));
+        assert_eq!(reader.read_uleb128(), Ok(624485));
+        assert_eq!(reader.read_sleb128(), Ok(-123456));
+        assert!(reader.is_empty());
+
+        let bytes = [0x7f, 0x80, 0x7f, 0x02];
+        let mut reader = Reader::new(&bytes);
+        assert_eq!(reader.read_sleb128(), Ok(-1));
+        assert_eq!(reader.read_sleb128(), Ok(-128));
+        assert_eq!(reader.read_sleb128(), Ok(2));
+
+        let bytes = [0xff; 11];
+        assert_eq!(
+            Reader::new(&bytes).read_uleb128(),
+            Err(DwarfError::Leb128Overflow)
+        );
+    }
+
+    #[test]
+    fn test_read_fixed_size_values() {
+        let bytes = [
+            0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, b'h', b'i', 0, 0xff,
+        ];
+        let mut reader = Reader::new(&bytes);
+        assert_eq!(reader.read_u16(), Ok(0x0201));
+        assert_eq!(reader.read_u24(), Ok(0x050403));
+        assert_eq!(reader.read_u32(), Ok(0x09080706));
+        assert_eq!(reader.read_cstr(), Ok(&b"hi"[..]));
+        assert_eq!(reader.read_i8(), Ok(-1));
+        assert_eq!(reader.read_u8(), Err(DwarfError::UnexpectedEof));
+    }
+
+    #[test]
+    fn test_read_initial_length() {
+        let mut bytes = vec![0xff, 0xff, 0xff, 0xff];
+        bytes.extend_from_slice(&0x1234u64.to_le_bytes());
+        bytes.extend_from_slice(&0x20u32.to_le_bytes());
+        let mut reader = Reader::new(&bytes);
+        assert_eq!(reader.read_initial_length(), Ok((0x1234, Format::Dwarf64)));
+        assert_eq!(reader.read_initial_length(), Ok((0x20, Format::Dwarf32)));
+    }
+}
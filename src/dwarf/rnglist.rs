@@ -0,0 +1,209 @@
+use super::constants::*;
+use super::die::{AttributeValue, Die};
+use super::error::{DwarfError, Result};
+use super::reader::{Format, Reader};
+use super::unit::Unit;
+
+/// The address ranges of the code of `die`, from its `DW_AT_low_pc` and `DW_AT_high_pc`
+/// or the range list of its `DW_AT_ranges`. Addresses are those of the file, empty
+/// ranges are left out.
+pub fn pc_ranges(unit: &Unit, die: &Die) -> Result<Vec<(u64, u64)>> {
+    if let Some(value) = die.attr(DW_AT_RANGES) {
+        let mut ranges = read_range_list(unit, die, value)?;
+        ranges.retain(|(begin, end)| begin < end);
+        return Ok(ranges);
+    }
+
+    let low_pc = match die.attr(DW_AT_LOW_PC).and_then(|value| value.address()) {
+        Some(low_pc) => low_pc,
+        None => return Ok(Vec::new()),
+    };
+    let high_pc = match die.attr(DW_AT_HIGH_PC) {
+        Some(AttributeValue::Address(high_pc)) => *high_pc,
+        // Since DWARF 4 the high pc may be the size of the code
+        Some(value) => match value.udata() {
+            Some(size) => low_pc.wrapping_add(size),
+            None => return Ok(Vec::new()),
+        },
+        None => return Ok(Vec::new()),
+    };
+    Ok(if low_pc < high_pc {
+        vec![(low_pc, high_pc)]
+    } else {
+        Vec::new()
+    })
+}
+
+/// Whether the code of `die` contains `pc`
+pub fn contains_pc(unit: &Unit, die: &Die, pc: u64) -> Result<bool> {
+    Ok(pc_ranges(unit, die)?
+        .iter()
+        .any(|(begin, end)| *begin <= pc && pc < *end))
+}
+
+/// Reads the range list the `DW_AT_ranges` value of `die` refers to
+fn read_range_list(unit: &Unit, die: &Die, value: &AttributeValue) -> Result<Vec<(u64, u64)>> {
+    let sections = unit.sections();
+    let offset = match *value {
+        AttributeValue::RnglistsIndex(index) => {
+            // Without a base, the offsets follow the header of the only contribution
+            let base = unit.rnglists_base.unwrap_or(match unit.format {
+                Format::Dwarf32 => 12,
+                Format::Dwarf64 => 20,
+            });
+            let entry = base + index * unit.format.offset_size() as u64;
+            let mut reader = Reader::at(sections.debug_rnglists, entry as usize)?;
+            base + reader.read_offset(unit.format)?
+        }
+        _ => match value.offset() {
+            // The GNU split DWARF extension makes offsets below the unit DIE relative
+            // to the `DW_AT_GNU_ranges_base` of the unit
+            Some(offset) if unit.version < 5 && die.tag != DW_TAG_COMPILE_UNIT => {
+                offset + unit.rnglists_base.unwrap_or(0)
+            }
+            Some(offset) => offset,
+            None => return Ok(Vec::new()),
+        },
+    };
+
+    if unit.version >= 5 {
+        let reader = Reader::at(sections.debug_rnglists, offset as usize)?;
+        read_rnglists(reader, unit.base_address, unit.address_size, |index| {
+            unit.address_by_index(index)
+        })
+    } else {
+        let reader = Reader::at(sections.debug_ranges, offset as usize)?;
+        read_ranges(reader, unit.base_address, unit.address_size)
+    }
+}
+
+/// Reads a list of `.debug_ranges`, used before DWARF 5
+fn read_ranges(
+    mut reader: Reader,
+    mut base_address: u64,
+    address_size: u8,
+) -> Result<Vec<(u64, u64)>> {
+    let max_address = u64::MAX >> (64 - 8 * address_size as u32);
+    let mut ranges = Vec::new();
+
+    loop {
+        let begin = reader.read_address(address_size)?;
+        let end = reader.read_address(address_size)?;
+        if begin == 0 && end == 0 {
+            break;
+        }
+        if begin == max_address {
+            base_address = end;
+            continue;
+        }
+        ranges.push((
+            base_address.wrapping_add(begin),
+            base_address.wrapping_add(end),
+        ));
+    }
+    Ok(ranges)
+}
+
+/// Reads a list of `.debug_rnglists`, whose indexed addresses are resolved by `address_by_index`
+fn read_rnglists<F>(
+    mut reader: Reader,
+    mut base_address: u64,
+    address_size: u8,
+    address_by_index: F,
+) -> Result<Vec<(u64, u64)>>
+where
+    F: Fn(u64) -> Option<u64>,
+{
+    let address = |index| address_by_index(index).ok_or(DwarfError::Unavailable("indexed address"));
+    let mut ranges = Vec::new();
+
+    loop {
+        let kind = reader.read_u8()?;
+        let range = match kind {
+            DW_RLE_END_OF_LIST => break,
+            DW_RLE_BASE_ADDRESSX => {
+                base_address = address(reader.read_uleb128()?)?;
+                continue;
+            }
+            DW_RLE_BASE_ADDRESS => {
+                base_address = reader.read_address(address_size)?;
+                continue;
+            }
+            DW_RLE_STARTX_ENDX => {
+                let begin = address(reader.read_uleb128()?)?;
+                (begin, address(reader.read_uleb128()?)?)
+            }
+            DW_RLE_STARTX_LENGTH => {
+                let begin = address(reader.read_uleb128()?)?;
+                (begin, begin.wrapping_add(reader.read_uleb128()?))
+            }
+            DW_RLE_OFFSET_PAIR => {
+                let begin = base_address.wrapping_add(reader.read_uleb128()?);
+                (begin, base_address.wrapping_add(reader.read_uleb128()?))
+            }
+            DW_RLE_START_END => {
+                let begin = reader.read_address(address_size)?;
+                (begin, reader.read_address(address_size)?)
+            }
+            DW_RLE_START_LENGTH => {
+                let begin = reader.read_address(address_size)?;
+                (begin, begin.wrapping_add(reader.read_uleb128()?))
+            }
+            _ => return Err(DwarfError::UnknownRangeListEntry(kind)),
+        };
+        ranges.push(range);
+    }
+    Ok(ranges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_ranges() {
+        let mut bytes = Vec::new();
+        // 0x10..0x20 relative to the base address of the unit
+        bytes.extend_from_slice(&0x10u64.to_le_bytes());
+        bytes.extend_from_slice(&0x20u64.to_le_bytes());
+        // Base address selection of 0x2000
+        bytes.extend_from_slice(&u64::MAX.to_le_bytes());
+        bytes.extend_from_slice(&0x2000u64.to_le_bytes());
+        bytes.extend_from_slice(&0x4u64.to_le_bytes());
+        bytes.extend_from_slice(&0x8u64.to_le_bytes());
+        // End of the list
+        bytes.extend_from_slice(&[0; 16]);
+
+        let ranges = read_ranges(Reader::new(&bytes), 0x1000, 8).unwrap();
+        assert_eq!(ranges, vec![(0x1010, 0x1020), (0x2004, 0x2008)]);
+    }
+
+    #[test]
+    fn test_read_rnglists() {
+        let mut bytes = vec![
+            // DW_RLE_base_addressx 1
+            0x01, 0x01, //
+            // DW_RLE_offset_pair 0x4 0x10
+            0x04, 0x04, 0x10, //
+            // DW_RLE_startx_length 0 0x8
+            0x03, 0x00, 0x08, //
+            // DW_RLE_start_length 0x3000 0x20
+            0x07,
+        ];
+        bytes.extend_from_slice(&0x3000u64.to_le_bytes());
+        bytes.extend_from_slice(&[0x20, 0x00]);
+
+        let addresses = [0x1000, 0x2000];
+        let address_by_index = |index| addresses.get(index as usize).copied();
+        let ranges = read_rnglists(Reader::new(&bytes), 0, 8, address_by_index).unwrap();
+        assert_eq!(
+            ranges,
+            vec![(0x2004, 0x2010), (0x1000, 0x1008), (0x3000, 0x3020)]
+        );
+
+        assert_eq!(
+            read_rnglists(Reader::new(&[0x09]), 0, 8, address_by_index),
+            Err(DwarfError::UnknownRangeListEntry(0x09))
+        );
+    }
+}
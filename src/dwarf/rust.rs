@@ -0,0 +1,1146 @@
+use super::constants::*;
+use super::die::Die;
+use super::error::Result;
+use super::expression::EvaluationContext;
+use super::types::{byte_size, strip_typedefs, type_of};
+use super::value::{escape_char, read_memory, unsigned, ValueFormatter, PRINT_LIMIT};
+
+/// Members followed from a smart pointer or collection to the pointer it wraps, like
+/// `String::vec`, `Vec::buf`, `RawVec::inner`, `RawVecInner::ptr` and `NonNull::pointer`
+const POINTER_MEMBERS: [&str; 5] = ["vec", "buf", "inner", "ptr", "pointer"];
+/// Levels of structures searched for a member of a collection, like `items` of a `HashMap`
+const MAX_MEMBER_DEPTH: usize = 8;
+/// Levels of nodes of a `BTreeMap` followed at most, more are a corrupted map
+const MAX_BTREE_HEIGHT: u64 = 32;
+/// Keys a node of a `BTreeMap` holds at most, `CAPACITY` of the standard library
+const BTREE_CAPACITY: u64 = 11;
+
+/// A member found in a structure, its type and bytes
+type Member<'a, 'b> = Option<(Option<Die<'a>>, &'b [u8])>;
+
+impl<'u, 'a, C: EvaluationContext> ValueFormatter<'u, 'a, C> {
+    /// Formats values of Rust units the way Rust's `Debug` prints them, e.g. `vec![1, 2]`
+    /// or `Some("text")`. Returns `None` for types printed like those of C.
+    pub(super) fn format_rust(&self, die: &Die<'a>, bytes: &[u8]) -> Result<Option<String>> {
+        let name = die.name().unwrap_or_default();
+        match die.tag {
+            DW_TAG_BASE_TYPE if name == "()" => Ok(Some("()".to_string())),
+            // `Box<T>` of a sized `T` is a pointer, of others a structure like slices
+            DW_TAG_POINTER_TYPE if short_name(name) == "Box" => self.format_box(die, bytes),
+            DW_TAG_STRUCTURE_TYPE => {
+                let value = match short_name(name) {
+                    _ if name.starts_with('(') => None,
+                    "String" => self.format_string(die, bytes)?,
+                    "Vec" => self.format_vec(die, bytes)?,
+                    "Rc" | "Arc" => self.format_rc(die, bytes)?,
+                    "HashMap" | "HashSet" => self.format_hash_map(die, bytes)?,
+                    "BTreeMap" | "BTreeSet" => self.format_btree_map(die, bytes)?,
+                    _ => self.format_fat_pointer(die, bytes)?,
+                };
+                match value {
+                    Some(value) => Ok(Some(value)),
+                    None => self.format_rust_struct(die, bytes).map(Some),
+                }
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Formats structures, enums and tuples, e.g. `Point { x: 1, y: 2 }`, `Some(5)` or
+    /// `(1, -2)`. An enum is a structure with a variant part, its value is the variant.
+    fn format_rust_struct(&self, die: &Die<'a>, bytes: &[u8]) -> Result<String> {
+        let mut fields = Vec::new();
+        for child in self.unit.children(die)? {
+            match child.tag {
+                DW_TAG_VARIANT_PART => {
+                    return match self.active_variant(&child, bytes)? {
+                        Some(member) => match self.member_value(&member, bytes)? {
+                            (variant_type, Some(bytes)) => {
+                                self.format(variant_type.as_ref(), bytes)
+                            }
+                            _ => Ok("<unavailable>".to_string()),
+                        },
+                        None => Ok("<invalid discriminant>".to_string()),
+                    };
+                }
+                DW_TAG_MEMBER => {
+                    let value = match self.member_value(&child, bytes)? {
+                        (member_type, Some(bytes)) => self.format(member_type.as_ref(), bytes)?,
+                        _ => "<unavailable>".to_string(),
+                    };
+                    fields.push((child.name().unwrap_or_default(), value));
+                }
+                _ => {}
+            }
+        }
+
+        let name = die.name().unwrap_or_default();
+        let values = || {
+            fields
+                .iter()
+                .map(|(_, value)| value.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        // Fields of tuples and tuple structs are named `__0`, `__1`, ...
+        let is_tuple = fields.iter().all(|(name, _)| name.starts_with("__"));
+        Ok(match fields.len() {
+            1 if name.starts_with('(') => format!("({},)", values()),
+            _ if name.starts_with('(') => format!("({})", values()),
+            0 => short_name(name).to_string(),
+            _ if is_tuple => format!("{}({})", short_name(name), values()),
+            _ => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|(name, value)| format!("{}: {}", name, value))
+                    .collect();
+                format!("{} {{ {} }}", short_name(name), fields.join(", "))
+            }
+        })
+    }
+
+    fn format_box(&self, die: &Die<'a>, bytes: &[u8]) -> Result<Option<String>> {
+        let address = unsigned(bytes) as u64;
+        let target = type_of(self.unit, die)?;
+        let size = match &target {
+            Some(target) => byte_size(self.unit, target)?,
+            None => None,
+        };
+        let size = match size {
+            Some(size) => size as usize,
+            None => return Ok(None),
+        };
+        Ok(Some(match read_memory(self.context, address, size) {
+            Ok(bytes) => format!("Box({})", self.format(target.as_ref(), &bytes)?),
+            Err(err) => format!("Box(0x{:x} <error: {}>)", address, err),
+        }))
+    }
+
+    fn format_string(&self, die: &Die<'a>, bytes: &[u8]) -> Result<Option<String>> {
+        match (
+            self.pointer(Some(die), bytes)?,
+            self.integer_member(die, bytes, "len")?,
+        ) {
+            (Some((address, _)), Some(len)) => Ok(Some(self.format_str(address, len))),
+            _ => Ok(None),
+        }
+    }
+
+    /// Formats `&str` and slices, which are pointers with a length, e.g. `&[1, 2]`
+    fn format_fat_pointer(&self, die: &Die<'a>, bytes: &[u8]) -> Result<Option<String>> {
+        let (address, element) = match self.member(die, bytes, "data_ptr")? {
+            Some((pointer_type, bytes)) => match self.pointer(pointer_type.as_ref(), bytes)? {
+                Some(pointer) => pointer,
+                None => return Ok(None),
+            },
+            None => return Ok(None),
+        };
+        let len = match self.integer_member(die, bytes, "length")? {
+            Some(len) => len,
+            None => return Ok(None),
+        };
+
+        let name = die.name().unwrap_or_default();
+        match name.find('[') {
+            Some(start) => {
+                let elements = self.format_sequence(element.as_ref(), address, len)?;
+                Ok(Some(format!("{}[{}]", &name[..start], elements.join(", "))))
+            }
+            None if name.contains("str") => Ok(Some(self.format_str(address, len))),
+            None => Ok(None),
+        }
+    }
+
+    fn format_vec(&self, die: &Die<'a>, bytes: &[u8]) -> Result<Option<String>> {
+        let element = self.template_parameter(die, "T")?;
+        match (
+            self.pointer(Some(die), bytes)?,
+            self.integer_member(die, bytes, "len")?,
+        ) {
+            (Some((address, _)), Some(len)) => {
+                let elements = self.format_sequence(element.as_ref(), address, len)?;
+                Ok(Some(format!("vec![{}]", elements.join(", "))))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// The address and length of the elements of a `Vec` or slice, with their type, for
+    /// indexing them. `None` for other types.
+    pub fn sequence(
+        &self,
+        die: &Die<'a>,
+        bytes: &[u8],
+    ) -> Result<Option<(u64, u64, Option<Die<'a>>)>> {
+        let name = die.name().unwrap_or_default();
+        if short_name(name) == "Vec" {
+            let element = self.template_parameter(die, "T")?;
+            return match (
+                self.pointer(Some(die), bytes)?,
+                self.integer_member(die, bytes, "len")?,
+            ) {
+                (Some((address, _)), Some(len)) => Ok(Some((address, len, element))),
+                _ => Ok(None),
+            };
+        }
+        if !name.starts_with('&') || !name.contains('[') {
+            return Ok(None);
+        }
+        let (address, element) = match self.member(die, bytes, "data_ptr")? {
+            Some((pointer_type, bytes)) => match self.pointer(pointer_type.as_ref(), bytes)? {
+                Some(pointer) => pointer,
+                None => return Ok(None),
+            },
+            None => return Ok(None),
+        };
+        match self.integer_member(die, bytes, "length")? {
+            Some(len) => Ok(Some((address, len, element))),
+            None => Ok(None),
+        }
+    }
+
+    /// Formats `Rc` and `Arc` with their reference counts
+    fn format_rc(&self, die: &Die<'a>, bytes: &[u8]) -> Result<Option<String>> {
+        let (address, inner) = match self.pointer(Some(die), bytes)? {
+            Some((address, Some(inner))) => (address, inner),
+            _ => return Ok(None),
+        };
+        let size = byte_size(self.unit, &inner)?.unwrap_or(0) as usize;
+        let name = short_name(die.name().unwrap_or_default());
+        let inner_bytes = match read_memory(self.context, address, size) {
+            Ok(bytes) => bytes,
+            Err(err) => return Ok(Some(format!("{}(0x{:x} <error: {}>)", name, address, err))),
+        };
+
+        let strong = self.integer_member(&inner, &inner_bytes, "strong")?;
+        let weak = self.integer_member(&inner, &inner_bytes, "weak")?;
+        // `Rc` calls its value `value`, `Arc` `data`
+        let value = match self.member(&inner, &inner_bytes, "value")? {
+            Some(value) => Some(value),
+            None => self.member(&inner, &inner_bytes, "data")?,
+        };
+        match (strong, weak, value) {
+            (Some(strong), Some(weak), Some((value_type, value))) => {
+                // All strong references together hold one weak reference
+                let weak = if strong > 0 {
+                    weak.saturating_sub(1)
+                } else {
+                    weak
+                };
+                Ok(Some(format!(
+                    "{} {{ strong: {}, weak: {}, value: {} }}",
+                    name,
+                    strong,
+                    weak,
+                    self.format(value_type.as_ref(), value)?
+                )))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Formats the `HashMap` and `HashSet` of the standard library, which are those of
+    /// hashbrown. Their entries are stored below the control bytes of the table, one
+    /// per bucket, and a control byte with the top bit clear marks a full bucket.
+    fn format_hash_map(&self, die: &Die<'a>, bytes: &[u8]) -> Result<Option<String>> {
+        let is_set = short_name(die.name().unwrap_or_default()) == "HashSet";
+        let (items, bucket_mask) = match (
+            self.integer_member(die, bytes, "items")?,
+            self.integer_member(die, bytes, "bucket_mask")?,
+        ) {
+            (Some(items), Some(bucket_mask)) => (items, bucket_mask),
+            _ => return Ok(None),
+        };
+        let ctrl = match self.find_member(die, bytes, "ctrl", 0)? {
+            Some((ctrl_type, bytes)) => match self.pointer(ctrl_type.as_ref(), bytes)? {
+                Some((ctrl, _)) => ctrl,
+                None => return Ok(None),
+            },
+            None => return Ok(None),
+        };
+        let entry = match self.find_member(die, bytes, "table", 0)? {
+            Some((Some(table), _))
+                if short_name(table.name().unwrap_or_default()) == "RawTable" =>
+            {
+                self.template_parameter(&table, "T")?
+            }
+            _ => return Ok(None),
+        };
+        let entry_size = match &entry {
+            Some(entry) => byte_size(self.unit, entry)?.unwrap_or(0),
+            None => 0,
+        };
+        // Before hashbrown 0.9 the entries were stored in an array of their own
+        let data = match self.find_member(die, bytes, "data", 0)? {
+            Some((data_type, bytes)) => self
+                .pointer(data_type.as_ref(), bytes)?
+                .map(|(data, _)| data),
+            None => None,
+        };
+
+        let mut entries = Vec::new();
+        let buckets = if items == 0 { 0 } else { bucket_mask + 1 };
+        let mut control = Vec::new();
+        for bucket in 0..buckets {
+            if entries.len() as u64 >= items.min(PRINT_LIMIT as u64) {
+                break;
+            }
+            if bucket.is_multiple_of(8) {
+                control = match self.context.read_memory(ctrl + bucket, 8) {
+                    Ok(word) => word.to_le_bytes().to_vec(),
+                    Err(err) => {
+                        entries.push(format!("<error: {}>", err));
+                        break;
+                    }
+                };
+            }
+            if control[bucket as usize % 8] & 0x80 != 0 {
+                continue;
+            }
+            let address = match data {
+                Some(data) => data + bucket * entry_size,
+                None => ctrl.wrapping_sub((bucket + 1) * entry_size),
+            };
+            entries.push(
+                match read_memory(self.context, address, entry_size as usize) {
+                    Ok(bytes) => self.format_entry(entry.as_ref(), &bytes, is_set)?,
+                    Err(err) => format!("<error: {}>", err),
+                },
+            );
+        }
+        if items > PRINT_LIMIT as u64 {
+            entries.push("...".to_string());
+        }
+        Ok(Some(format!("{{{}}}", entries.join(", "))))
+    }
+
+    /// Formats an entry of a map, a `(key, value)` tuple, as `key: value`, and only the
+    /// key for sets
+    fn format_entry(&self, entry: Option<&Die<'a>>, bytes: &[u8], is_set: bool) -> Result<String> {
+        let entry = match strip_typedefs(self.unit, entry.cloned())? {
+            Some(entry) => entry,
+            None => return self.format(None, bytes),
+        };
+        let key = self.member(&entry, bytes, "__0")?;
+        let value = self.member(&entry, bytes, "__1")?;
+        match (key, value) {
+            (Some((key_type, key)), _) if is_set => self.format(key_type.as_ref(), key),
+            (Some((key_type, key)), Some((value_type, value))) => Ok(format!(
+                "{}: {}",
+                self.format(key_type.as_ref(), key)?,
+                self.format(value_type.as_ref(), value)?
+            )),
+            _ => self.format(Some(&entry), bytes),
+        }
+    }
+
+    /// Formats `BTreeMap` and `BTreeSet` by walking the nodes of the tree in order. The
+    /// nodes below the root are internal nodes, a leaf node followed by its edges,
+    /// down to the leaves at height 0.
+    fn format_btree_map(&self, die: &Die<'a>, bytes: &[u8]) -> Result<Option<String>> {
+        let is_set = short_name(die.name().unwrap_or_default()) == "BTreeSet";
+        let length = match self.integer_member(die, bytes, "length")? {
+            Some(length) => length,
+            None => return Ok(None),
+        };
+        let root = match self.find_member(die, bytes, "node", 0)? {
+            Some((node_type, bytes)) => self.pointer(node_type.as_ref(), bytes)?,
+            None => None,
+        };
+        let height = self.integer_member(die, bytes, "height")?;
+        let mut entries = Vec::new();
+        match (root, height) {
+            (Some((_, Some(_))), Some(height)) if length > 0 && height > MAX_BTREE_HEIGHT => {
+                entries.push(format!("<invalid height {}>", height));
+            }
+            (Some((node, Some(leaf))), Some(height)) if length > 0 => {
+                let map = if is_set {
+                    // A set is a map of its keys, with values of zero size
+                    self.find_member(die, bytes, "map", 0)?
+                        .and_then(|(map, _)| map)
+                        .unwrap_or_else(|| die.clone())
+                } else {
+                    die.clone()
+                };
+                let types = (
+                    self.template_parameter(&map, "K")?,
+                    self.template_parameter(&map, "V")?,
+                );
+                self.btree_entries(node, height, &leaf, &types, is_set, &mut entries)?;
+            }
+            _ => {}
+        }
+        if length as usize > entries.len() && entries.len() >= PRINT_LIMIT {
+            entries.push("...".to_string());
+        }
+        Ok(Some(format!("{{{}}}", entries.join(", "))))
+    }
+
+    /// Formats the entries of the node at `address` and of the nodes below it
+    fn btree_entries(
+        &self,
+        address: u64,
+        height: u64,
+        leaf: &Die<'a>,
+        types: &(Option<Die<'a>>, Option<Die<'a>>),
+        is_set: bool,
+        entries: &mut Vec<String>,
+    ) -> Result<()> {
+        let (key_type, value_type) = types;
+        let leaf_size = byte_size(self.unit, leaf)?.unwrap_or(0);
+        let node = match read_memory(self.context, address, leaf_size as usize) {
+            Ok(node) => node,
+            Err(err) => {
+                entries.push(format!("<error: {}>", err));
+                return Ok(());
+            }
+        };
+        // Only the root of an empty map has no keys, the edges of a corrupted node without
+        // any are not followed
+        let len = self.integer_member(leaf, &node, "len")?.unwrap_or(0);
+        if len == 0 {
+            return Ok(());
+        }
+        let len = len.min(BTREE_CAPACITY);
+        let keys = self.member(leaf, &node, "keys")?;
+        let values = self.member(leaf, &node, "vals")?;
+
+        // The edges of an internal node follow its leaf node, aligned for pointers
+        let pointer_size = self.unit.address_size as u64;
+        let edges = if height > 0 {
+            let edges = address + leaf_size.next_multiple_of(pointer_size);
+            match read_memory(self.context, edges, ((len + 1) * pointer_size) as usize) {
+                Ok(edges) => edges,
+                Err(err) => {
+                    entries.push(format!("<error: {}>", err));
+                    return Ok(());
+                }
+            }
+        } else {
+            Vec::new()
+        };
+        let edge = |index: u64| {
+            let start = (index * pointer_size) as usize;
+            unsigned(&edges[start..start + pointer_size as usize]) as u64
+        };
+
+        for index in 0..=len {
+            if entries.len() >= PRINT_LIMIT {
+                return Ok(());
+            }
+            if height > 0 {
+                self.btree_entries(edge(index), height - 1, leaf, types, is_set, entries)?;
+            }
+            if index == len || entries.len() >= PRINT_LIMIT {
+                break;
+            }
+            let key = self.array_element(keys.as_ref(), key_type.as_ref(), index)?;
+            let value = self.array_element(values.as_ref(), value_type.as_ref(), index)?;
+            entries.push(match (key, value) {
+                (Some(key), _) if is_set => key,
+                (Some(key), Some(value)) => format!("{}: {}", key, value),
+                _ => "<unavailable>".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Formats the element `index`, of the type `element`, of an array member like the
+    /// `[MaybeUninit<K>; 11]` keys of a B-tree node
+    fn array_element(
+        &self,
+        array: Option<&(Option<Die<'a>>, &[u8])>,
+        element: Option<&Die<'a>>,
+        index: u64,
+    ) -> Result<Option<String>> {
+        let (array_type, bytes) = match array {
+            Some((Some(array_type), bytes)) => (array_type, bytes),
+            _ => return Ok(None),
+        };
+        let stride = match type_of(self.unit, array_type)? {
+            Some(slot) => byte_size(self.unit, &slot)?.unwrap_or(0),
+            None => 0,
+        };
+        let size = match element {
+            Some(element) => byte_size(self.unit, element)?.unwrap_or(0),
+            None => 0,
+        };
+        let start = (index * stride) as usize;
+        match bytes.get(start..start + size as usize) {
+            Some(bytes) => self.format(element, bytes).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Formats the `count` values of the type `element` stored one after another at `address`
+    fn format_sequence(
+        &self,
+        element: Option<&Die<'a>>,
+        address: u64,
+        count: u64,
+    ) -> Result<Vec<String>> {
+        let size = match element {
+            Some(element) => byte_size(self.unit, element)?.unwrap_or(0),
+            None => 0,
+        } as usize;
+        let shown = count.min(PRINT_LIMIT as u64) as usize;
+        let bytes = match read_memory(self.context, address, size * shown) {
+            Ok(bytes) => bytes,
+            Err(err) => return Ok(vec![format!("<error: {}>", err)]),
+        };
+        let mut values = Vec::new();
+        for index in 0..shown {
+            values.push(self.format(element, &bytes[index * size..(index + 1) * size])?);
+        }
+        if count > shown as u64 {
+            values.push("...".to_string());
+        }
+        Ok(values)
+    }
+
+    /// Reads the UTF-8 string of `len` bytes at `address` as a literal
+    fn format_str(&self, address: u64, len: u64) -> String {
+        let shown = len.min(PRINT_LIMIT as u64) as usize;
+        match read_memory(self.context, address, shown) {
+            Ok(bytes) if len > shown as u64 => {
+                format!("\"{}\"...", str_literal(&String::from_utf8_lossy(&bytes)))
+            }
+            Ok(bytes) => format!("\"{}\"", str_literal(&String::from_utf8_lossy(&bytes))),
+            Err(err) => format!("0x{:x} <error: {}>", address, err),
+        }
+    }
+
+    /// The member `name` of the structure `die`
+    fn member<'b>(&self, die: &Die<'a>, bytes: &'b [u8], name: &str) -> Result<Member<'a, 'b>> {
+        for child in self.unit.children(die)? {
+            if child.tag == DW_TAG_MEMBER && child.name() == Some(name) {
+                return Ok(match self.member_value(&child, bytes)? {
+                    (member_type, Some(bytes)) => Some((member_type, bytes)),
+                    (_, None) => None,
+                });
+            }
+        }
+        Ok(None)
+    }
+
+    /// The member `name` of the structure `die` or, depth first, of the structures and
+    /// active enum variants in it, for members buried in the layers of a collection
+    fn find_member<'b>(
+        &self,
+        die: &Die<'a>,
+        bytes: &'b [u8],
+        name: &str,
+        depth: usize,
+    ) -> Result<Member<'a, 'b>> {
+        if let Some(member) = self.member(die, bytes, name)? {
+            return Ok(Some(member));
+        }
+        if depth >= MAX_MEMBER_DEPTH {
+            return Ok(None);
+        }
+        for child in self.unit.children(die)? {
+            let member = match child.tag {
+                DW_TAG_MEMBER => child,
+                DW_TAG_VARIANT_PART => match self.active_variant(&child, bytes)? {
+                    Some(member) => member,
+                    None => continue,
+                },
+                _ => continue,
+            };
+            let (member_type, member_bytes) = match self.member_value(&member, bytes)? {
+                (member_type, Some(bytes)) => (member_type, bytes),
+                (_, None) => continue,
+            };
+            match strip_typedefs(self.unit, member_type)? {
+                Some(member_type) if member_type.tag == DW_TAG_STRUCTURE_TYPE => {
+                    if let Some(found) =
+                        self.find_member(&member_type, member_bytes, name, depth + 1)?
+                    {
+                        return Ok(Some(found));
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(None)
+    }
+
+    /// The address a pointer, or a structure wrapping one like `NonNull` or `Vec`, points
+    /// to, with the type it points to
+    fn pointer(
+        &self,
+        die: Option<&Die<'a>>,
+        bytes: &[u8],
+    ) -> Result<Option<(u64, Option<Die<'a>>)>> {
+        let die = match strip_typedefs(self.unit, die.cloned())? {
+            Some(die) => die,
+            None => return Ok(None),
+        };
+        match die.tag {
+            DW_TAG_POINTER_TYPE => Ok(Some((unsigned(bytes) as u64, type_of(self.unit, &die)?))),
+            DW_TAG_STRUCTURE_TYPE => {
+                for name in POINTER_MEMBERS {
+                    if let Some((member_type, bytes)) = self.member(&die, bytes, name)? {
+                        return self.pointer(member_type.as_ref(), bytes);
+                    }
+                }
+                Ok(None)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// The value of an integer, or of a structure wrapping one like `Cell<usize>` or
+    /// `AtomicUsize`
+    fn integer(&self, die: Option<&Die<'a>>, bytes: &[u8]) -> Result<Option<u64>> {
+        let die = match strip_typedefs(self.unit, die.cloned())? {
+            Some(die) => die,
+            None => return Ok(None),
+        };
+        match die.tag {
+            DW_TAG_BASE_TYPE => Ok(Some(unsigned(bytes) as u64)),
+            DW_TAG_STRUCTURE_TYPE | DW_TAG_UNION_TYPE => {
+                let first = self
+                    .unit
+                    .children(&die)?
+                    .into_iter()
+                    .find(|child| child.tag == DW_TAG_MEMBER);
+                match first {
+                    Some(member) => match self.member_value(&member, bytes)? {
+                        (member_type, Some(bytes)) => self.integer(member_type.as_ref(), bytes),
+                        (_, None) => Ok(None),
+                    },
+                    None => Ok(None),
+                }
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn integer_member(&self, die: &Die<'a>, bytes: &[u8], name: &str) -> Result<Option<u64>> {
+        match self.find_member(die, bytes, name, 0)? {
+            Some((member_type, bytes)) => self.integer(member_type.as_ref(), bytes),
+            None => Ok(None),
+        }
+    }
+
+    /// The type of the generic parameter `name` of `die`, e.g. `T` of `Vec<T>`
+    fn template_parameter(&self, die: &Die<'a>, name: &str) -> Result<Option<Die<'a>>> {
+        for child in self.unit.children(die)? {
+            if child.tag == DW_TAG_TEMPLATE_TYPE_PARAMETER && child.name() == Some(name) {
+                return type_of(self.unit, &child);
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// The name of a type without its path and generic arguments, e.g. `Box` of
+/// `alloc::boxed::Box<i32, alloc::alloc::Global>`
+fn short_name(name: &str) -> &str {
+    let name = name.split('<').next().unwrap_or(name);
+    name.rsplit("::").next().unwrap_or(name)
+}
+
+/// Escapes a string for a literal, keeping the characters that are not ASCII
+fn str_literal(string: &str) -> String {
+    string
+        .chars()
+        .map(|character| match character as u32 {
+            0x80..=0xff => character.to_string(),
+            code => escape_char(code, '"'),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dwarf::testing::{abbrev_table, reference, sections, Memory, UnitBuilder};
+    use crate::dwarf::unit::Unit;
+
+    fn abbrev_bytes() -> Vec<u8> {
+        abbrev_table(&[
+            // 2: base type, name string, byte_size data1, encoding data1
+            0x02, 0x24, 0x00, 0x03, 0x08, 0x0b, 0x0b, 0x3e, 0x0b, 0x00, 0x00,
+            // 3: structure type with children, name string, byte_size data1
+            0x03, 0x13, 0x01, 0x03, 0x08, 0x0b, 0x0b, 0x00, 0x00,
+            // 4: member, name string, type ref4, data_member_location data1
+            0x04, 0x0d, 0x00, 0x03, 0x08, 0x49, 0x13, 0x38, 0x0b, 0x00, 0x00,
+            // 5: pointer type, name string, byte_size data1, type ref4
+            0x05, 0x0f, 0x00, 0x03, 0x08, 0x0b, 0x0b, 0x49, 0x13, 0x00, 0x00,
+            // 6: template type parameter, name string, type ref4
+            0x06, 0x2f, 0x00, 0x03, 0x08, 0x49, 0x13, 0x00, 0x00,
+            // 7: variant part with children, discr ref4
+            0x07, 0x33, 0x01, 0x15, 0x13, 0x00, 0x00,
+            // 8: variant with children, discr_value data1
+            0x08, 0x19, 0x01, 0x16, 0x0b, 0x00, 0x00, // 9: variant with children
+            0x09, 0x19, 0x01, 0x00, 0x00, // 10: array type with children, type ref4
+            0x0a, 0x01, 0x01, 0x49, 0x13, 0x00, 0x00,
+            // 11: subrange type, upper_bound data1
+            0x0b, 0x21, 0x00, 0x2f, 0x0b, 0x00, 0x00,
+        ])
+    }
+
+    /// The bytes of a DIE with the abbreviation `code` and a name, followed by `attributes`
+    fn named(code: u8, name: &str, attributes: &[u8]) -> Vec<u8> {
+        [&[code][..], name.as_bytes(), &[0], attributes].concat()
+    }
+
+    fn member(unit: &mut UnitBuilder, name: &str, member_type: u64, offset: u8) -> u64 {
+        unit.die(&named(
+            0x04,
+            name,
+            &[&reference(member_type)[..], &[offset]].concat(),
+        ))
+    }
+
+    fn pointer(unit: &mut UnitBuilder, name: &str, target: u64) -> u64 {
+        unit.die(&named(0x05, name, &[&[8][..], &reference(target)].concat()))
+    }
+
+    /// Appends a structure with the members and generic parameters, returning its offset
+    fn structure(
+        unit: &mut UnitBuilder,
+        name: &str,
+        size: u8,
+        members: &[(&str, u64, u8)],
+        parameters: &[(&str, u64)],
+    ) -> u64 {
+        let offset = unit.die(&named(0x03, name, &[size]));
+        for &(name, member_type, member_offset) in members {
+            member(unit, name, member_type, member_offset);
+        }
+        for &(name, parameter) in parameters {
+            unit.die(&named(0x06, name, &reference(parameter)));
+        }
+        unit.end_children();
+        offset
+    }
+
+    /// Appends `Vec<T>` the way the standard library lays it out, a `RawVec` with the
+    /// `NonNull` pointer and the capacity followed by the length
+    fn vec(unit: &mut UnitBuilder, element: u64, element_name: &str, usize: u64) -> u64 {
+        let element_pointer = pointer(unit, &format!("*const {}", element_name), element);
+        let non_null = structure(
+            unit,
+            &format!("NonNull<{}>", element_name),
+            8,
+            &[("pointer", element_pointer, 0)],
+            &[],
+        );
+        let raw_vec = structure(
+            unit,
+            &format!("RawVec<{}>", element_name),
+            16,
+            &[("ptr", non_null, 0), ("cap", usize, 8)],
+            &[],
+        );
+        structure(
+            unit,
+            &format!("Vec<{}, alloc::alloc::Global>", element_name),
+            24,
+            &[("buf", raw_vec, 0), ("len", usize, 16)],
+            &[("T", element)],
+        )
+    }
+
+    /// Offsets of the type DIEs of the test unit
+    struct Types {
+        option: u64,
+        tagged: u64,
+        vec: u64,
+        string: u64,
+        slice: u64,
+        str: u64,
+        hash_map: u64,
+        btree_map: u64,
+        rc: u64,
+        arc: u64,
+    }
+
+    /// A DWARF 4 unit of a Rust program with the types of `Types`
+    fn info_bytes() -> (Vec<u8>, Types) {
+        let mut unit = UnitBuilder::new(DW_LANG_RUST as u8);
+        let u8 = unit.die(&named(0x02, "u8", &[1, DW_ATE_UNSIGNED]));
+        let u16 = unit.die(&named(0x02, "u16", &[2, DW_ATE_UNSIGNED]));
+        let u32 = unit.die(&named(0x02, "u32", &[4, DW_ATE_UNSIGNED]));
+        let i32 = unit.die(&named(0x02, "i32", &[4, DW_ATE_SIGNED]));
+        let usize = unit.die(&named(0x02, "usize", &[8, DW_ATE_UNSIGNED]));
+
+        // `Option<NonZeroU32>`, whose `None` is the niche 0 of the `NonZeroU32`
+        let none = structure(&mut unit, "None", 4, &[], &[]);
+        let some = structure(&mut unit, "Some", 4, &[("__0", u32, 0)], &[]);
+        let option = unit.die(&named(0x03, "Option<core::num::nonzero::NonZeroU32>", &[4]));
+        let discriminant = unit.die(&[0x07, 0, 0, 0, 0]);
+        let niche = member(&mut unit, "", u32, 0);
+        unit.die(&[0x08, 0]);
+        member(&mut unit, "None", none, 0);
+        unit.end_children();
+        unit.die(&[0x09]);
+        member(&mut unit, "Some", some, 0);
+        unit.end_children();
+        unit.end_children();
+        unit.end_children();
+
+        // `enum Tagged { A(u8), B(u16) }`, with a tag byte
+        let a = structure(&mut unit, "A", 4, &[("__0", u8, 1)], &[]);
+        let b = structure(&mut unit, "B", 4, &[("__0", u16, 2)], &[]);
+        let tagged = unit.die(&named(0x03, "r::Tagged", &[4]));
+        let tagged_discriminant = unit.die(&[0x07, 0, 0, 0, 0]);
+        let tag = member(&mut unit, "", u8, 0);
+        for (value, name, variant) in [(0, "A", a), (1, "B", b)] {
+            unit.die(&[0x08, value]);
+            member(&mut unit, name, variant, 0);
+            unit.end_children();
+        }
+        unit.end_children();
+        unit.end_children();
+
+        let vec_i32 = vec(&mut unit, i32, "i32", usize);
+        let vec_u8 = vec(&mut unit, u8, "u8", usize);
+        let string = structure(
+            &mut unit,
+            "alloc::string::String",
+            24,
+            &[("vec", vec_u8, 0)],
+            &[],
+        );
+        let i32_pointer = pointer(&mut unit, "*const i32", i32);
+        let slice = structure(
+            &mut unit,
+            "&[i32]",
+            16,
+            &[("data_ptr", i32_pointer, 0), ("length", usize, 8)],
+            &[],
+        );
+        let u8_pointer = pointer(&mut unit, "*const u8", u8);
+        let str = structure(
+            &mut unit,
+            "&str",
+            16,
+            &[("data_ptr", u8_pointer, 0), ("length", usize, 8)],
+            &[],
+        );
+
+        // `HashMap<u32, u32>`, with the layers of hashbrown reduced to the raw table
+        let entry = structure(
+            &mut unit,
+            "(u32, u32)",
+            8,
+            &[("__0", u32, 0), ("__1", u32, 4)],
+            &[],
+        );
+        let ctrl_pointer = pointer(&mut unit, "*const u8", u8);
+        let ctrl = structure(
+            &mut unit,
+            "NonNull<u8>",
+            8,
+            &[("pointer", ctrl_pointer, 0)],
+            &[],
+        );
+        let raw_table_inner = structure(
+            &mut unit,
+            "RawTableInner",
+            32,
+            &[
+                ("bucket_mask", usize, 0),
+                ("ctrl", ctrl, 8),
+                ("growth_left", usize, 16),
+                ("items", usize, 24),
+            ],
+            &[],
+        );
+        let raw_table = structure(
+            &mut unit,
+            "RawTable<(u32, u32), alloc::alloc::Global>",
+            32,
+            &[("table", raw_table_inner, 0)],
+            &[("T", entry)],
+        );
+        let hash_map = structure(
+            &mut unit,
+            "std::collections::hash::map::HashMap<u32, u32, std::hash::random::RandomState>",
+            32,
+            &[("table", raw_table, 0)],
+            &[],
+        );
+
+        // `BTreeMap<u32, u32>`, whose leaf nodes hold up to 11 keys and values
+        let keys = unit.die(&[&[0x0a][..], &reference(u32)].concat());
+        unit.die(&[0x0b, 10]);
+        unit.end_children();
+        let leaf = unit.die(&named(0x03, "LeafNode<u32, u32>", &[104]));
+        let parent = pointer(&mut unit, "*const InternalNode<u32, u32>", leaf);
+        member(&mut unit, "parent", parent, 0);
+        member(&mut unit, "keys", keys, 8);
+        member(&mut unit, "vals", keys, 52);
+        member(&mut unit, "parent_idx", u16, 96);
+        member(&mut unit, "len", u16, 98);
+        unit.end_children();
+        let leaf_pointer = pointer(&mut unit, "*const LeafNode<u32, u32>", leaf);
+        let node = structure(
+            &mut unit,
+            "NonNull<LeafNode<u32, u32>>",
+            8,
+            &[("pointer", leaf_pointer, 0)],
+            &[],
+        );
+        let root = structure(
+            &mut unit,
+            "NodeRef<Owned, u32, u32, LeafOrInternal>",
+            16,
+            &[("node", node, 0), ("height", usize, 8)],
+            &[],
+        );
+        let btree_map = structure(
+            &mut unit,
+            "BTreeMap<u32, u32, alloc::alloc::Global>",
+            24,
+            &[("root", root, 0), ("length", usize, 16)],
+            &[("K", u32), ("V", u32)],
+        );
+
+        // `Rc<i32>` and `Arc<i32>`, whose counts wrap the `usize` in a `Cell` or an atomic
+        let cell = structure(&mut unit, "Cell<usize>", 8, &[("value", usize, 0)], &[]);
+        let rc_inner = structure(
+            &mut unit,
+            "RcInner<i32>",
+            24,
+            &[("strong", cell, 0), ("weak", cell, 8), ("value", i32, 16)],
+            &[],
+        );
+        let rc_pointer = pointer(&mut unit, "*const RcInner<i32>", rc_inner);
+        let rc_non_null = structure(
+            &mut unit,
+            "NonNull<RcInner<i32>>",
+            8,
+            &[("pointer", rc_pointer, 0)],
+            &[],
+        );
+        let rc = structure(
+            &mut unit,
+            "alloc::rc::Rc<i32, alloc::alloc::Global>",
+            8,
+            &[("ptr", rc_non_null, 0)],
+            &[],
+        );
+        let atomic = structure(&mut unit, "AtomicUsize", 8, &[("v", usize, 0)], &[]);
+        let arc_inner = structure(
+            &mut unit,
+            "ArcInner<i32>",
+            24,
+            &[
+                ("strong", atomic, 0),
+                ("weak", atomic, 8),
+                ("data", i32, 16),
+            ],
+            &[],
+        );
+        let arc_pointer = pointer(&mut unit, "*const ArcInner<i32>", arc_inner);
+        let arc_non_null = structure(
+            &mut unit,
+            "NonNull<ArcInner<i32>>",
+            8,
+            &[("pointer", arc_pointer, 0)],
+            &[],
+        );
+        let arc = structure(
+            &mut unit,
+            "alloc::sync::Arc<i32, alloc::alloc::Global>",
+            8,
+            &[("ptr", arc_non_null, 0)],
+            &[],
+        );
+
+        let mut info = unit.finish();
+        // The discriminants are members of the variant parts, which precede them
+        for (variant_part, member) in [(discriminant, niche), (tagged_discriminant, tag)] {
+            let start = variant_part as usize + 1;
+            info[start..start + 4].copy_from_slice(&reference(member));
+        }
+        let types = Types {
+            option,
+            tagged,
+            vec: vec_i32,
+            string,
+            slice,
+            str,
+            hash_map,
+            btree_map,
+            rc,
+            arc,
+        };
+        (info, types)
+    }
+
+    /// Formats `bytes` as a value of the type at `offset`
+    fn format(unit: &Unit, memory: &Memory, offset: u64, bytes: &[u8]) -> String {
+        let die = unit.entry(offset).unwrap();
+        ValueFormatter::new(unit, memory)
+            .format(Some(&die), bytes)
+            .unwrap()
+    }
+
+    /// The bytes of the `usize` words of a value
+    fn words(words: &[u64]) -> Vec<u8> {
+        words.iter().flat_map(|word| word.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn test_enums() {
+        let (info, types) = info_bytes();
+        let abbrev = abbrev_bytes();
+        let sections = sections(&info, &abbrev);
+        let unit = sections.unit_at(0).unwrap();
+        let memory = Memory::default();
+        let format = |offset, bytes: &[u8]| format(&unit, &memory, offset, bytes);
+
+        assert_eq!(format(types.option, &[0, 0, 0, 0]), "None");
+        assert_eq!(format(types.option, &[7, 0, 0, 0]), "Some(7)");
+        assert_eq!(format(types.tagged, &[0, 5, 0, 0]), "A(5)");
+        assert_eq!(format(types.tagged, &[1, 0, 0x2c, 0x01]), "B(300)");
+        assert_eq!(
+            format(types.tagged, &[2, 0, 0, 0]),
+            "<invalid discriminant>"
+        );
+    }
+
+    #[test]
+    fn test_sequences() {
+        let (info, types) = info_bytes();
+        let abbrev = abbrev_bytes();
+        let sections = sections(&info, &abbrev);
+        let unit = sections.unit_at(0).unwrap();
+        let mut memory = Memory::default();
+        memory.write(0x1000, &[1, 0, 0, 0, 2, 0, 0, 0, 0xfd, 0xff, 0xff, 0xff]);
+        memory.write(0x2000, b"hi\n\"");
+        let format = |offset, bytes: &[u8]| format(&unit, &memory, offset, bytes);
+
+        // The pointer and capacity of the buffer, then the length
+        assert_eq!(format(types.vec, &words(&[0x1000, 4, 3])), "vec![1, 2, -3]");
+        assert_eq!(format(types.vec, &words(&[0x1000, 4, 0])), "vec![]");
+        assert_eq!(
+            format(types.string, &words(&[0x2000, 8, 4])),
+            "\"hi\\n\\\"\""
+        );
+        assert_eq!(format(types.slice, &words(&[0x1004, 2])), "&[2, -3]");
+        assert_eq!(format(types.str, &words(&[0x2000, 2])), "\"hi\"");
+        assert_eq!(
+            format(types.vec, &words(&[0x3000, 4, 1])),
+            "vec![<error: cannot access memory at address 0x3000>]"
+        );
+    }
+
+    #[test]
+    fn test_hash_map() {
+        let (info, types) = info_bytes();
+        let abbrev = abbrev_bytes();
+        let sections = sections(&info, &abbrev);
+        let unit = sections.unit_at(0).unwrap();
+        let mut memory = Memory::default();
+        // Four buckets, the entries are stored below the control bytes from the last
+        // bucket down to the first. Buckets 1 and 3 are full.
+        let ctrl = 0x3000;
+        memory.write(ctrl - 32, &[3, 0, 0, 0, 30, 0, 0, 0]);
+        memory.write(ctrl - 16, &[1, 0, 0, 0, 10, 0, 0, 0]);
+        memory.write(ctrl, &[0xff, 0x12, 0xff, 0x34, 0xff, 0xff, 0xff, 0xff]);
+        let format = |offset, bytes: &[u8]| format(&unit, &memory, offset, bytes);
+
+        // The bucket mask, control bytes, growth left and items
+        assert_eq!(
+            format(types.hash_map, &words(&[3, ctrl, 1, 2])),
+            "{1: 10, 3: 30}"
+        );
+        assert_eq!(format(types.hash_map, &words(&[0, 0, 0, 0])), "{}");
+    }
+
+    #[test]
+    fn test_btree_map() {
+        let (info, types) = info_bytes();
+        let abbrev = abbrev_bytes();
+        let sections = sections(&info, &abbrev);
+        let unit = sections.unit_at(0).unwrap();
+        let node = |keys: &[u32], values: &[u32], edges: &[u64]| {
+            let mut node = vec![0; 104];
+            for (index, (key, value)) in keys.iter().zip(values).enumerate() {
+                node[8 + index * 4..12 + index * 4].copy_from_slice(&key.to_le_bytes());
+                node[52 + index * 4..56 + index * 4].copy_from_slice(&value.to_le_bytes());
+            }
+            node[98..100].copy_from_slice(&(keys.len() as u16).to_le_bytes());
+            node.extend(words(edges));
+            node
+        };
+        let mut memory = Memory::default();
+        // An internal root with a key between those of its two leaves
+        memory.write(0x4000, &node(&[2], &[20], &[0x5000, 0x6000]));
+        memory.write(0x5000, &node(&[1], &[10], &[]));
+        memory.write(0x6000, &node(&[3, 4], &[30, 40], &[]));
+        // A leaf whose length is more than a node holds
+        let mut corrupted = node(&[5], &[50], &[]);
+        corrupted[98..100].copy_from_slice(&u16::MAX.to_le_bytes());
+        memory.write(0x9000, &corrupted);
+        let format = |offset, bytes: &[u8]| format(&unit, &memory, offset, bytes);
+
+        // The root node and its height, then the length
+        assert_eq!(
+            format(types.btree_map, &words(&[0x4000, 1, 4])),
+            "{1: 10, 2: 20, 3: 30, 4: 40}"
+        );
+        assert_eq!(
+            format(types.btree_map, &words(&[0x6000, 0, 2])),
+            "{3: 30, 4: 40}"
+        );
+        assert_eq!(format(types.btree_map, &words(&[0, 0, 0])), "{}");
+        // The height is read from the process, a corrupted one is not followed
+        assert_eq!(
+            format(types.btree_map, &words(&[0x4000, u64::MAX, 4])),
+            format!("{{<invalid height {}>}}", u64::MAX)
+        );
+        let entries = format(types.btree_map, &words(&[0x9000, 0, 1]));
+        assert!(entries.starts_with("{5: 50, 0: 0, "));
+        assert_eq!(entries.matches(':').count(), BTREE_CAPACITY as usize);
+        // Leaves taken for internal nodes have no edges to read
+        assert_eq!(
+            format(types.btree_map, &words(&[0x4000, 2, 4])),
+            "{<error: cannot access memory at address 0x5068>, 2: 20, \
+             <error: cannot access memory at address 0x6068>}"
+        );
+    }
+
+    #[test]
+    fn test_rc_and_arc() {
+        let (info, types) = info_bytes();
+        let abbrev = abbrev_bytes();
+        let sections = sections(&info, &abbrev);
+        let unit = sections.unit_at(0).unwrap();
+        let mut memory = Memory::default();
+        // Two strong references, which hold one weak reference together
+        memory.write(0x7000, &words(&[2, 1, 7]));
+        let format = |offset, bytes: &[u8]| format(&unit, &memory, offset, bytes);
+
+        assert_eq!(
+            format(types.rc, &words(&[0x7000])),
+            "Rc { strong: 2, weak: 0, value: 7 }"
+        );
+        assert_eq!(
+            format(types.arc, &words(&[0x7000])),
+            "Arc { strong: 2, weak: 0, value: 7 }"
+        );
+        assert_eq!(
+            format(types.rc, &words(&[0x8000])),
+            "Rc(0x8000 <error: cannot access memory at address 0x8000>)"
+        );
+    }
+
+    #[test]
+    fn test_names_and_literals() {
+        assert_eq!(short_name("Vec<i32, alloc::alloc::Global>"), "Vec");
+        assert_eq!(short_name("String"), "String");
+        assert_eq!(
+            short_name("alloc::boxed::Box<r::Point, alloc::alloc::Global>"),
+            "Box"
+        );
+        assert_eq!(str_literal("é\"\n"), "é\\\"\\n");
+    }
+}
@@ -0,0 +1,456 @@
+use std::iter;
+
+use super::aranges::AddressIndex;
+use super::constants::*;
+use super::die::{AttributeValue, Die};
+use super::error::Result;
+use super::rnglist::{contains_pc, pc_ranges};
+use super::sections::DwarfSections;
+use super::unit::Unit;
+
+/// The function a pc is in, and the lexical blocks and inlined subroutines within it
+/// that contain the pc
+#[derive(Debug, Clone)]
+pub struct Scope<'a> {
+    pub unit: Unit<'a>,
+    pub function: Die<'a>,
+    /// Lexical blocks and inlined subroutines containing the pc, outermost first
+    pub blocks: Vec<Die<'a>>,
+}
+
+impl<'a> Scope<'a> {
+    /// Finds the scope of `pc`, an address of the file, in the unit `index` maps it to
+    pub fn find(index: &AddressIndex<'a>, pc: u64) -> Result<Option<Self>> {
+        match index.find_unit(pc)? {
+            Some(unit) => Self::find_in_unit(unit, pc),
+            None => Ok(None),
+        }
+    }
+
+    /// Finds the scope of `pc` in the DIEs of `unit`
+    pub fn find_in_unit(unit: Unit<'a>, pc: u64) -> Result<Option<Self>> {
+        let mut function = None;
+        for entry in unit.entries() {
+            let (_, die) = entry?;
+            if die.tag == DW_TAG_SUBPROGRAM && contains_pc(&unit, &die, pc)? {
+                function = Some(die);
+                break;
+            }
+        }
+        let function = match function {
+            Some(function) => function,
+            None => return Ok(None),
+        };
+
+        let mut blocks: Vec<Die<'a>> = Vec::new();
+        loop {
+            let parent = blocks.last().unwrap_or(&function);
+            let mut block = None;
+            for child in unit.children(parent)? {
+                if matches!(child.tag, DW_TAG_LEXICAL_BLOCK | DW_TAG_INLINED_SUBROUTINE)
+                    && contains_pc(&unit, &child, pc)?
+                {
+                    block = Some(child);
+                    break;
+                }
+            }
+            match block {
+                Some(block) => blocks.push(block),
+                None => break,
+            }
+        }
+
+        Ok(Some(Self {
+            unit,
+            function,
+            blocks,
+        }))
+    }
+
+    /// Number of inlined subroutines containing the pc, the calls of functions inlined
+    /// into each other between `function` and the code at the pc
+    pub fn inline_depth(&self) -> usize {
+        self.inlined_subroutines().count()
+    }
+
+    /// The scope of the code the innermost `depth` inlined subroutines were inlined into,
+    /// as if the pc was at their call site
+    pub fn outer(&self, depth: usize) -> Self {
+        let mut scope = self.clone();
+        if depth > 0 {
+            let position = self
+                .blocks
+                .iter()
+                .enumerate()
+                .filter(|(_, block)| block.tag == DW_TAG_INLINED_SUBROUTINE)
+                .map(|(position, _)| position)
+                .rev()
+                .nth(depth - 1)
+                .unwrap_or(0);
+            scope.blocks.truncate(position);
+        }
+        scope
+    }
+
+    /// The innermost inlined subroutine, whose call site is in the code of the scope
+    /// around it
+    pub fn inlined_subroutine(&self) -> Option<&Die<'a>> {
+        self.inlined_subroutines().last()
+    }
+
+    /// The DIE of the function whose code the pc is in: the innermost inlined
+    /// subroutine, or `function` if the pc is not in inlined code
+    pub fn frame_function(&self) -> &Die<'a> {
+        self.inlined_subroutine().unwrap_or(&self.function)
+    }
+
+    /// The file index, line and column of the call of the innermost inlined subroutine,
+    /// the column is 0 if it is unknown
+    pub fn call_site(&self) -> Option<(u64, u64, u64)> {
+        let subroutine = self.inlined_subroutine()?;
+        let file = subroutine.attr(DW_AT_CALL_FILE)?.udata()?;
+        let line = subroutine.attr(DW_AT_CALL_LINE)?.udata()?;
+        let column = subroutine
+            .attr(DW_AT_CALL_COLUMN)
+            .and_then(|value| value.udata())
+            .unwrap_or(0);
+        Some((file, line, column))
+    }
+
+    fn inlined_subroutines(&self) -> impl DoubleEndedIterator<Item = &Die<'a>> {
+        self.blocks
+            .iter()
+            .filter(|block| block.tag == DW_TAG_INLINED_SUBROUTINE)
+    }
+
+    /// The variables and parameters visible at the pc, those of the innermost block first.
+    /// The variables of the code an inlined subroutine was inlined into are not visible
+    /// in it.
+    pub fn variables(&self) -> Result<Vec<Die<'a>>> {
+        let mut variables = Vec::new();
+        for scope in self.blocks.iter().rev().chain(iter::once(&self.function)) {
+            for child in self.unit.children(scope)? {
+                if matches!(child.tag, DW_TAG_VARIABLE | DW_TAG_FORMAL_PARAMETER) {
+                    variables.push(child);
+                }
+            }
+            if scope.tag == DW_TAG_INLINED_SUBROUTINE {
+                break;
+            }
+        }
+        Ok(variables)
+    }
+
+    /// The local variables visible at the pc, those of the innermost block first
+    pub fn locals(&self) -> Result<Vec<Die<'a>>> {
+        let mut locals = self.variables()?;
+        // Declarations of variables are those of globals, like `extern int x;` in a function
+        locals.retain(|variable| {
+            variable.tag == DW_TAG_VARIABLE
+                && !variable
+                    .attr(DW_AT_DECLARATION)
+                    .is_some_and(|value| value.flag())
+        });
+        Ok(locals)
+    }
+
+    /// The parameters of the function the pc is in, of the innermost inlined subroutine
+    /// in inlined code
+    pub fn parameters(&self) -> Result<Vec<Die<'a>>> {
+        let mut parameters = self.variables()?;
+        parameters.retain(|variable| variable.tag == DW_TAG_FORMAL_PARAMETER);
+        Ok(parameters)
+    }
+
+    pub fn find_variable(&self, name: &str) -> Result<Option<Die<'a>>> {
+        for variable in self.variables()? {
+            if self.unit.name_of(&variable)? == Some(name) {
+                return Ok(Some(variable));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Where the code of a function is entered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FunctionEntry {
+    /// The address in the file
+    pub address: u64,
+    /// The end of the range of code starting at the entry
+    pub end: u64,
+    /// Inlined instances have no prologue, their code starts with the body of the function
+    pub inlined: bool,
+}
+
+/// Where the code of the function `name` is entered in the file: the out-of-line copies of
+/// the function, of each instance of it if it is generic, and every instance of it inlined
+/// into other functions, ordered by address
+pub fn function_entries(sections: &DwarfSections, name: &str) -> Result<Vec<FunctionEntry>> {
+    let mut entries = Vec::new();
+    for unit in sections.units() {
+        let unit = unit?;
+        for entry in unit.entries() {
+            let (_, die) = entry?;
+            if !matches!(die.tag, DW_TAG_SUBPROGRAM | DW_TAG_INLINED_SUBROUTINE) {
+                continue;
+            }
+            // Abstract instances and declarations have no code
+            let ranges = pc_ranges(&unit, &die)?;
+            let low_pc = match ranges.iter().map(|(begin, _)| *begin).min() {
+                Some(low_pc) => low_pc,
+                None => continue,
+            };
+            // Instances of generic functions are named with their arguments, like `f<i32>`
+            let matches = unit.name_of(&die)?.is_some_and(|die_name| {
+                die_name == name
+                    || die_name
+                        .strip_prefix(name)
+                        .is_some_and(|arguments| arguments.starts_with('<'))
+            });
+            if !matches {
+                continue;
+            }
+            // Code of inlined instances is often scheduled before the entry point, which
+            // is then given by the entry pc, an offset from the low pc since DWARF 5
+            let entry_pc = match die.attr(DW_AT_ENTRY_PC) {
+                Some(AttributeValue::Address(address)) => *address,
+                Some(value) => match value.udata() {
+                    Some(offset) => low_pc.wrapping_add(offset),
+                    None => low_pc,
+                },
+                None => low_pc,
+            };
+            let end = ranges
+                .iter()
+                .find(|(begin, end)| (*begin..*end).contains(&entry_pc))
+                .map_or(entry_pc, |(_, end)| *end);
+            entries.push(FunctionEntry {
+                address: entry_pc,
+                end,
+                inlined: die.tag == DW_TAG_INLINED_SUBROUTINE,
+            });
+        }
+    }
+    entries.sort_unstable_by_key(|entry| entry.address);
+    entries.dedup_by_key(|entry| entry.address);
+    Ok(entries)
+}
+
+/// Finds a variable defined outside of functions, looking in the unit at `first_unit`
+/// before the others, as its static variables hide those of other units
+pub fn find_global_variable<'a>(
+    sections: &DwarfSections<'a>,
+    name: &str,
+    first_unit: Option<u64>,
+) -> Result<Option<(Unit<'a>, Die<'a>)>> {
+    for unit in units_from(sections, first_unit)? {
+        let mut scopes = vec![unit.root()?];
+        while let Some(scope) = scopes.pop() {
+            for child in unit.children(&scope)? {
+                match child.tag {
+                    DW_TAG_NAMESPACE => scopes.push(child),
+                    DW_TAG_VARIABLE
+                        if (child.has_attr(DW_AT_LOCATION)
+                            || child.has_attr(DW_AT_CONST_VALUE))
+                            && unit.name_of(&child)? == Some(name) =>
+                    {
+                        return Ok(Some((unit, child)));
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Finds the type `name` of the debug information, a structure, union or enumeration if
+/// it is prefixed with its keyword like `struct point`. The unit at `first_unit` is looked
+/// in before the others. Declarations are only returned if there is no definition.
+pub fn find_type<'a>(
+    sections: &DwarfSections<'a>,
+    name: &str,
+    first_unit: Option<u64>,
+) -> Result<Option<(Unit<'a>, Die<'a>)>> {
+    let (tags, name): (&[u16], &str) = match name.split_once(' ') {
+        Some(("struct", name)) => (&[DW_TAG_STRUCTURE_TYPE, DW_TAG_CLASS_TYPE], name),
+        Some(("union", name)) => (&[DW_TAG_UNION_TYPE], name),
+        Some(("enum", name)) => (&[DW_TAG_ENUMERATION_TYPE], name),
+        _ => (
+            &[
+                DW_TAG_BASE_TYPE,
+                DW_TAG_TYPEDEF,
+                DW_TAG_STRUCTURE_TYPE,
+                DW_TAG_CLASS_TYPE,
+                DW_TAG_UNION_TYPE,
+                DW_TAG_ENUMERATION_TYPE,
+            ],
+            name,
+        ),
+    };
+
+    let mut declaration = None;
+    for unit in units_from(sections, first_unit)? {
+        for entry in unit.entries() {
+            let (_, die) = entry?;
+            if !tags.contains(&die.tag) || die.name() != Some(name) {
+                continue;
+            }
+            if die
+                .attr(DW_AT_DECLARATION)
+                .is_some_and(|value| value.flag())
+            {
+                declaration = declaration.or_else(|| Some((unit.clone(), die)));
+                continue;
+            }
+            return Ok(Some((unit, die)));
+        }
+    }
+    Ok(declaration)
+}
+
+/// Finds the function `name`, whose entry also gives its type like `int (int, int)`. The
+/// unit at `first_unit` is looked in before the others.
+pub fn find_function<'a>(
+    sections: &DwarfSections<'a>,
+    name: &str,
+    first_unit: Option<u64>,
+) -> Result<Option<(Unit<'a>, Die<'a>)>> {
+    let mut declaration = None;
+    for unit in units_from(sections, first_unit)? {
+        for entry in unit.entries() {
+            let (_, die) = entry?;
+            if die.tag != DW_TAG_SUBPROGRAM || unit.name_of(&die)? != Some(name) {
+                continue;
+            }
+            if die
+                .attr(DW_AT_DECLARATION)
+                .is_some_and(|value| value.flag())
+            {
+                declaration = declaration.or_else(|| Some((unit.clone(), die)));
+                continue;
+            }
+            return Ok(Some((unit, die)));
+        }
+    }
+    Ok(declaration)
+}
+
+/// The units of `sections`, the one at `first_unit` first
+fn units_from<'a>(sections: &DwarfSections<'a>, first_unit: Option<u64>) -> Result<Vec<Unit<'a>>> {
+    let mut units = Vec::new();
+    if let Some(offset) = first_unit {
+        units.push(sections.unit_at(offset)?);
+    }
+    for unit in sections.units() {
+        let unit = unit?;
+        if Some(unit.id()) != first_unit {
+            units.push(unit);
+        }
+    }
+    Ok(units)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dwarf::testing::{abbrev_table, sections, UnitBuilder};
+
+    fn abbrev_bytes() -> Vec<u8> {
+        abbrev_table(&[
+            // 2: subprogram with children, name string, low_pc addr, high_pc data1
+            0x02, 0x2e, 0x01, 0x03, 0x08, 0x11, 0x01, 0x12, 0x0b, 0x00, 0x00,
+            // 3: formal parameter, name string
+            0x03, 0x05, 0x00, 0x03, 0x08, 0x00, 0x00, // 4: variable, name string
+            0x04, 0x34, 0x00, 0x03, 0x08, 0x00, 0x00,
+            // 5: variable, name string, declaration flag_present
+            0x05, 0x34, 0x00, 0x03, 0x08, 0x3c, 0x19, 0x00, 0x00,
+            // 6: lexical block with children, low_pc addr, high_pc data1
+            0x06, 0x0b, 0x01, 0x11, 0x01, 0x12, 0x0b, 0x00, 0x00,
+        ])
+    }
+
+    /// A DWARF 4 unit of a C program with the function below, returning the offsets of
+    /// the two `x`
+    ///
+    /// ```c
+    /// void f(int a, int b) {   // 0x1000 to 0x1080
+    ///     int x;
+    ///     extern int g;
+    ///     {                    // 0x1010 to 0x1020
+    ///         int x, y;
+    ///     }
+    ///     {                    // 0x1040 to 0x1050
+    ///         int z;
+    ///     }
+    /// }
+    /// ```
+    fn info_bytes() -> (Vec<u8>, u64, u64) {
+        let with_range = |bytes: &[u8], low_pc: u64, length: u8| {
+            [bytes, &low_pc.to_le_bytes(), &[length]].concat()
+        };
+
+        // Compile unit in C99
+        let mut unit = UnitBuilder::new(0x0c);
+        unit.die(&with_range(&[0x02, b'f', 0], 0x1000, 0x80));
+        unit.die(&[0x03, b'a', 0]);
+        unit.die(&[0x03, b'b', 0]);
+        let outer_x = unit.die(&[0x04, b'x', 0]);
+        unit.die(&[0x05, b'g', 0]);
+        unit.die(&with_range(&[0x06], 0x1010, 0x10));
+        let inner_x = unit.die(&[0x04, b'x', 0]);
+        unit.die(&[0x04, b'y', 0]);
+        unit.end_children();
+        unit.die(&with_range(&[0x06], 0x1040, 0x10));
+        unit.die(&[0x04, b'z', 0]);
+        unit.end_children();
+        unit.end_children();
+        (unit.finish(), outer_x, inner_x)
+    }
+
+    #[test]
+    fn test_locals_and_parameters() {
+        let (info, outer_x, inner_x) = info_bytes();
+        let abbrev = abbrev_bytes();
+        let sections = sections(&info, &abbrev);
+        let scope = |pc| {
+            Scope::find_in_unit(sections.unit_at(0).unwrap(), pc)
+                .unwrap()
+                .unwrap()
+        };
+        let names = |scope: &Scope, variables: Vec<Die>| -> Vec<String> {
+            variables
+                .iter()
+                .map(|variable| scope.unit.name_of(variable).unwrap().unwrap().to_string())
+                .collect()
+        };
+
+        // In the first block, whose variables come first and shadow those of the function
+        let inner = scope(0x1018);
+        assert_eq!(inner.blocks.len(), 1);
+        assert_eq!(names(&inner, inner.locals().unwrap()), ["x", "y", "x"]);
+        assert_eq!(inner.locals().unwrap()[0].offset, inner_x);
+        assert_eq!(inner.find_variable("x").unwrap().unwrap().offset, inner_x);
+        assert_eq!(names(&inner, inner.parameters().unwrap()), ["a", "b"]);
+
+        // Outside the blocks, the declaration of the global is not a local either
+        let outer = scope(0x1030);
+        assert!(outer.blocks.is_empty());
+        assert_eq!(names(&outer, outer.locals().unwrap()), ["x"]);
+        assert_eq!(outer.find_variable("x").unwrap().unwrap().offset, outer_x);
+        assert_eq!(names(&outer, outer.parameters().unwrap()), ["a", "b"]);
+        assert_eq!(
+            names(&outer, outer.variables().unwrap()),
+            ["a", "b", "x", "g"]
+        );
+
+        // In the second block, the variables of the first are not visible
+        let second = scope(0x1048);
+        assert_eq!(second.blocks.len(), 1);
+        assert_eq!(names(&second, second.locals().unwrap()), ["z", "x"]);
+
+        let unit = sections.unit_at(0).unwrap();
+        assert!(Scope::find_in_unit(unit, 0x1080).unwrap().is_none());
+    }
+}
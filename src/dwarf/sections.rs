@@ -0,0 +1,144 @@
+use super::constants::*;
+use super::error::{DwarfError, Result};
+use super::split::SplitDwarf;
+use super::unit::{Unit, Units};
+use crate::elf::file::ElfFile;
+use crate::elf::header::ElfHeader;
+
+/// The DWARF sections of a file. Sections missing from the file are empty.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DwarfSections<'a> {
+    pub debug_info: &'a [u8],
+    pub debug_abbrev: &'a [u8],
+    pub debug_str: &'a [u8],
+    pub debug_line_str: &'a [u8],
+    pub debug_str_offsets: &'a [u8],
+    pub debug_addr: &'a [u8],
+    pub debug_line: &'a [u8],
+    pub debug_loc: &'a [u8],
+    pub debug_loclists: &'a [u8],
+    pub debug_ranges: &'a [u8],
+    pub debug_rnglists: &'a [u8],
+    pub debug_aranges: &'a [u8],
+    /// Address size of the file, used where the DWARF data does not state it
+    pub address_size: u8,
+    /// The split units the skeleton units of `.debug_info` stand for
+    pub split: Option<&'a SplitDwarf<'a>>,
+}
+
+impl<'a> DwarfSections<'a> {
+    pub fn new(elf_file: &ElfFile<'a>) -> Self {
+        let address_size = match elf_file.header {
+            ElfHeader::Elf32(_) => 4,
+            ElfHeader::Elf64(_) => 8,
+        };
+        Self::load(
+            |name| {
+                elf_file
+                    .get_section_by_name(name)
+                    .map(|section| section.bytes())
+                    .unwrap_or_default()
+            },
+            address_size,
+        )
+    }
+
+    /// The `.dwo` sections of a split DWARF object or package
+    pub fn for_split_file(elf_file: &ElfFile<'a>) -> Self {
+        let address_size = Self::new(elf_file).address_size;
+        Self::load(
+            |name| {
+                elf_file
+                    .get_section_by_name(&format!("{}.dwo", name))
+                    .map(|section| section.bytes())
+                    .unwrap_or_default()
+            },
+            address_size,
+        )
+    }
+
+    /// Makes the units of `.debug_info` the split units of `split` where they are skeletons
+    pub fn with_split_dwarf(mut self, split: &'a SplitDwarf<'a>) -> Self {
+        self.split = Some(split);
+        self
+    }
+
+    /// Loads the sections by name through `section`, which returns the bytes of a section
+    pub fn load<F>(section: F, address_size: u8) -> Self
+    where
+        F: Fn(&str) -> &'a [u8],
+    {
+        Self {
+            debug_info: section(".debug_info"),
+            debug_abbrev: section(".debug_abbrev"),
+            debug_str: section(".debug_str"),
+            debug_line_str: section(".debug_line_str"),
+            debug_str_offsets: section(".debug_str_offsets"),
+            debug_addr: section(".debug_addr"),
+            debug_line: section(".debug_line"),
+            debug_loc: section(".debug_loc"),
+            debug_loclists: section(".debug_loclists"),
+            debug_ranges: section(".debug_ranges"),
+            debug_rnglists: section(".debug_rnglists"),
+            debug_aranges: section(".debug_aranges"),
+            address_size,
+            split: None,
+        }
+    }
+
+    pub fn has_debug_info(&self) -> bool {
+        !self.debug_info.is_empty()
+    }
+
+    /// Iterates over the units of `.debug_info`, parsing only their headers. Skeleton
+    /// units are replaced by their split units.
+    pub fn units(&self) -> Units<'a> {
+        Units::new(*self)
+    }
+
+    /// The unit whose header is at `offset` of `.debug_info`, or its split unit if it is
+    /// a skeleton unit
+    pub fn unit_at(&self, offset: u64) -> Result<Unit<'a>> {
+        self.resolve_skeleton(Unit::parse(*self, offset)?)
+    }
+
+    /// The unit containing the DIE at `offset` of `.debug_info`
+    pub fn unit_containing(&self, offset: u64) -> Result<Unit<'a>> {
+        let mut unit_offset = 0;
+        while (unit_offset as usize) < self.debug_info.len() {
+            let unit = Unit::parse(*self, unit_offset)?;
+            if unit.contains(offset) {
+                return Ok(unit);
+            }
+            unit_offset = unit.end_offset();
+        }
+        Err(DwarfError::InvalidDieOffset(offset))
+    }
+
+    /// The split unit completing `unit` if it is a skeleton unit whose split unit was
+    /// loaded, otherwise `unit`
+    pub(crate) fn resolve_skeleton(&self, unit: Unit<'a>) -> Result<Unit<'a>> {
+        let split = match self.split {
+            Some(split) if unit.unit_type == DW_UT_SKELETON => split,
+            _ => return Ok(unit),
+        };
+        let (sections, offset) = match unit.dwo_id.and_then(|dwo_id| split.split_unit(dwo_id)) {
+            Some(split_unit) => split_unit,
+            None => return Ok(unit),
+        };
+        // Addresses and, before DWARF 5, range lists stay in the executable
+        let sections = DwarfSections {
+            debug_addr: self.debug_addr,
+            debug_ranges: self.debug_ranges,
+            ..sections
+        };
+        Ok(Unit::parse(sections, offset)?.with_skeleton(unit))
+    }
+
+    /// Reads a NUL terminated string at `offset` of `section`
+    pub(crate) fn string_at(section: &'a [u8], offset: u64) -> Option<&'a str> {
+        let bytes = section.get(offset as usize..)?;
+        let end = bytes.iter().position(|byte| *byte == 0)?;
+        std::str::from_utf8(&bytes[..end]).ok()
+    }
+}
@@ -0,0 +1,322 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::constants::*;
+use super::die::AttributeValue;
+use super::error::{DwarfError, Result};
+use super::reader::Reader;
+use super::sections::DwarfSections;
+use super::unit::Unit;
+use crate::elf::file::ElfFile;
+
+/// The contribution of a unit to a section of a package
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SectionContribution {
+    /// One of the `DW_SECT_*` values
+    pub section: u32,
+    pub offset: u64,
+    pub size: u64,
+}
+
+/// A `.debug_cu_index` or `.debug_tu_index` table of a `.dwp` package, which tells where
+/// the units of the package are in its sections
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UnitIndex {
+    /// 5, or 2 for the GNU extension before DWARF 5, which numbers sections differently
+    pub version: u16,
+    /// The DWO id or type signature of each unit with its contributions
+    pub units: Vec<(u64, Vec<SectionContribution>)>,
+}
+
+impl UnitIndex {
+    pub fn parse(section: &[u8]) -> Result<Self> {
+        if section.is_empty() {
+            return Ok(Self::default());
+        }
+        let mut reader = Reader::new(section);
+        // DWARF 5 has a 2 byte version and 2 bytes of padding, version 2 a 4 byte version
+        let version = reader.read_u32()? as u16;
+        if !matches!(version, 2 | 5) {
+            return Err(DwarfError::UnsupportedVersion(version));
+        }
+        let section_count = reader.read_u32()? as usize;
+        let unit_count = reader.read_u32()? as usize;
+        let slot_count = reader.read_u32()? as usize;
+
+        let mut signatures = Vec::with_capacity(slot_count);
+        for _ in 0..slot_count {
+            signatures.push(reader.read_u64()?);
+        }
+        let mut rows = Vec::with_capacity(slot_count);
+        for _ in 0..slot_count {
+            rows.push(reader.read_u32()? as usize);
+        }
+        let mut sections = Vec::with_capacity(section_count);
+        for _ in 0..section_count {
+            sections.push(reader.read_u32()?);
+        }
+        let mut offsets = Vec::with_capacity(unit_count * section_count);
+        for _ in 0..unit_count * section_count {
+            offsets.push(reader.read_u32()? as u64);
+        }
+        let mut sizes = Vec::with_capacity(unit_count * section_count);
+        for _ in 0..unit_count * section_count {
+            sizes.push(reader.read_u32()? as u64);
+        }
+
+        // The hash table is only needed to look up single units, empty slots have row 0
+        let mut units = Vec::with_capacity(unit_count);
+        for (signature, row) in signatures.into_iter().zip(rows) {
+            if row == 0 || row > unit_count {
+                continue;
+            }
+            let first = (row - 1) * section_count;
+            let contributions = sections
+                .iter()
+                .enumerate()
+                .map(|(column, section)| SectionContribution {
+                    section: *section,
+                    offset: offsets[first + column],
+                    size: sizes[first + column],
+                })
+                .collect();
+            units.push((signature, contributions));
+        }
+        Ok(Self { version, units })
+    }
+}
+
+/// The split units of an executable built with split DWARF, whose skeleton units only
+/// tell where the rest of their debug info is: in a `.dwo` file per unit or in a `.dwp`
+/// package of all of them
+#[derive(Debug, Clone, Default)]
+pub struct SplitDwarf<'a> {
+    /// Split compile units by DWO id, with the sections and header offset of each
+    units: HashMap<u64, (DwarfSections<'a>, u64)>,
+    /// Split type units by type signature
+    type_units: HashMap<u64, (DwarfSections<'a>, u64)>,
+}
+
+impl<'a> SplitDwarf<'a> {
+    /// Adds the units of a `.dwo` file or `.dwp` package. Units already known are kept.
+    pub fn add_file(&mut self, elf_file: &ElfFile<'a>) -> Result<()> {
+        let section = |name: &str| {
+            elf_file
+                .get_section_by_name(name)
+                .map(|section| section.bytes())
+                .unwrap_or_default()
+        };
+        let sections = DwarfSections::for_split_file(elf_file);
+
+        let cu_index = UnitIndex::parse(section(".debug_cu_index"))?;
+        let tu_index = UnitIndex::parse(section(".debug_tu_index"))?;
+        if cu_index.units.is_empty() && tu_index.units.is_empty() {
+            for unit in sections.units() {
+                let unit = unit?;
+                match (unit.unit_type, unit.dwo_id, unit.type_signature) {
+                    (DW_UT_SPLIT_COMPILE, Some(dwo_id), _) => {
+                        self.units.entry(dwo_id).or_insert((sections, unit.offset));
+                    }
+                    (DW_UT_SPLIT_TYPE, _, Some(signature)) => {
+                        self.type_units
+                            .entry(signature)
+                            .or_insert((sections, unit.offset));
+                    }
+                    _ => {}
+                }
+            }
+            return Ok(());
+        }
+
+        for (index, units) in [
+            (&cu_index, &mut self.units),
+            (&tu_index, &mut self.type_units),
+        ] {
+            for (signature, contributions) in &index.units {
+                // Type units of version 2 packages are in `.debug_types.dwo`, which is not read
+                if let Some(sections) =
+                    contribution_sections(&sections, index.version, contributions)?
+                {
+                    units.entry(*signature).or_insert((sections, 0));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.units.is_empty() && self.type_units.is_empty()
+    }
+
+    /// The sections of the split unit with `dwo_id`, and the offset of its header
+    pub fn split_unit(&self, dwo_id: u64) -> Option<(DwarfSections<'a>, u64)> {
+        self.units.get(&dwo_id).copied()
+    }
+
+    /// The split type unit with the type signature `signature`
+    pub fn type_unit(&self, signature: u64) -> Option<Result<Unit<'a>>> {
+        let (sections, offset) = self.type_units.get(&signature)?;
+        Some(sections.unit_at(*offset))
+    }
+}
+
+/// Narrows the sections of a package to the contributions of one unit. Returns `None`
+/// if the unit has no contribution to `.debug_info.dwo`.
+fn contribution_sections<'a>(
+    sections: &DwarfSections<'a>,
+    version: u16,
+    contributions: &[SectionContribution],
+) -> Result<Option<DwarfSections<'a>>> {
+    let mut unit_sections = *sections;
+    let mut has_info = false;
+    for contribution in contributions {
+        let start = contribution.offset as usize;
+        let end = start + contribution.size as usize;
+        let slice = |section: &'a [u8]| section.get(start..end).ok_or(DwarfError::UnexpectedEof);
+        match (contribution.section, version) {
+            (DW_SECT_INFO, _) => {
+                unit_sections.debug_info = slice(sections.debug_info)?;
+                has_info = true;
+            }
+            (DW_SECT_ABBREV, _) => unit_sections.debug_abbrev = slice(sections.debug_abbrev)?,
+            (DW_SECT_LINE, _) => unit_sections.debug_line = slice(sections.debug_line)?,
+            (DW_SECT_STR_OFFSETS, _) => {
+                unit_sections.debug_str_offsets = slice(sections.debug_str_offsets)?
+            }
+            (DW_SECT_LOCLISTS, 5) => unit_sections.debug_loclists = slice(sections.debug_loclists)?,
+            (DW_SECT_RNGLISTS, 5) => unit_sections.debug_rnglists = slice(sections.debug_rnglists)?,
+            (DW_SECT_GNU_LOC, 2) => unit_sections.debug_loc = slice(sections.debug_loc)?,
+            _ => {}
+        }
+    }
+    Ok(has_info.then_some(unit_sections))
+}
+
+/// Paths of the files holding the split units of the executable at `executable`, whose
+/// DWARF sections are `sections`. A package named like the executable with a `.dwp`
+/// extension is used instead of the `.dwo` files of the units if it exists. The `.dwo`
+/// file of a unit is looked up at the name its skeleton unit gives, relative to the
+/// compilation directory, and else next to the executable.
+pub fn locate_split_files(executable: &Path, sections: &DwarfSections) -> Vec<PathBuf> {
+    let mut package = executable.as_os_str().to_owned();
+    package.push(".dwp");
+    let package = PathBuf::from(package);
+    if package.is_file() {
+        return vec![package];
+    }
+
+    let executable_dir = executable.parent().unwrap_or_else(|| Path::new("."));
+    let mut paths = Vec::new();
+    for unit in sections.units().flatten() {
+        if unit.unit_type != DW_UT_SKELETON {
+            continue;
+        }
+        let root = match unit.root() {
+            Ok(root) => root,
+            Err(_) => continue,
+        };
+        let name = match root
+            .attr(DW_AT_DWO_NAME)
+            .or_else(|| root.attr(DW_AT_GNU_DWO_NAME))
+            .and_then(AttributeValue::string)
+        {
+            Some(name) => Path::new(name),
+            None => continue,
+        };
+        let comp_dir = root.attr(DW_AT_COMP_DIR).and_then(AttributeValue::string);
+
+        let mut candidates =
+            vec![comp_dir.map_or(name.to_path_buf(), |dir| Path::new(dir).join(name))];
+        if let Some(file_name) = name.file_name() {
+            candidates.push(executable_dir.join(file_name));
+        }
+        if let Some(path) = candidates.into_iter().find(|path| path.is_file()) {
+            if !paths.contains(&path) {
+                paths.push(path);
+            }
+        }
+    }
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_unit_index() {
+        let mut bytes = Vec::new();
+        // Version 5, 2 sections, 1 unit, 2 slots
+        for value in [5u32, 2, 1, 2] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        // The unit is in the second slot
+        bytes.extend_from_slice(&0u64.to_le_bytes());
+        bytes.extend_from_slice(&0x1234u64.to_le_bytes());
+        // Rows, section identifiers, offsets and sizes
+        for value in [
+            0u32,
+            1,
+            DW_SECT_INFO,
+            DW_SECT_ABBREV,
+            0x40,
+            0x10,
+            0x30,
+            0x20,
+        ] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+
+        let index = UnitIndex::parse(&bytes).unwrap();
+        assert_eq!(index.version, 5);
+        assert_eq!(
+            index.units,
+            vec![(
+                0x1234,
+                vec![
+                    SectionContribution {
+                        section: DW_SECT_INFO,
+                        offset: 0x40,
+                        size: 0x30,
+                    },
+                    SectionContribution {
+                        section: DW_SECT_ABBREV,
+                        offset: 0x10,
+                        size: 0x20,
+                    },
+                ]
+            )]
+        );
+
+        let (info, abbrev) = ([0xaa; 0x80], [0xbb; 0x40]);
+        let sections = DwarfSections {
+            debug_info: &info,
+            debug_abbrev: &abbrev,
+            ..Default::default()
+        };
+        let contributions = &index.units[0].1;
+        let unit_sections = contribution_sections(&sections, 5, contributions)
+            .unwrap()
+            .unwrap();
+        assert_eq!(unit_sections.debug_info.len(), 0x30);
+        assert_eq!(unit_sections.debug_abbrev.len(), 0x20);
+        // Units without a contribution to `.debug_info.dwo` are left out
+        assert!(contribution_sections(&sections, 5, &contributions[1..])
+            .unwrap()
+            .is_none());
+        // Contributions must be within their section
+        assert_eq!(
+            contribution_sections(
+                &sections,
+                5,
+                &[SectionContribution {
+                    section: DW_SECT_INFO,
+                    offset: 0x60,
+                    size: 0x30,
+                }]
+            )
+            .err(),
+            Some(DwarfError::UnexpectedEof)
+        );
+    }
+}
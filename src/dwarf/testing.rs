@@ -0,0 +1,113 @@
+use super::error::{DwarfError, Result};
+use super::expression::EvaluationContext;
+use super::sections::DwarfSections;
+
+/// Abbreviation 1 of the test tables: compile unit with children, language data1
+const COMPILE_UNIT_ABBREV: [u8; 7] = [0x01, 0x11, 0x01, 0x13, 0x0b, 0x00, 0x00];
+
+/// An abbreviation table with the compile unit as code 1, followed by `abbreviations`
+pub fn abbrev_table(abbreviations: &[u8]) -> Vec<u8> {
+    let mut table = COMPILE_UNIT_ABBREV.to_vec();
+    table.extend_from_slice(abbreviations);
+    // End of the table
+    table.push(0);
+    table
+}
+
+/// A DWARF 4 unit with 8-byte addresses whose DIEs are appended one by one
+pub struct UnitBuilder {
+    info: Vec<u8>,
+}
+
+impl UnitBuilder {
+    /// Starts a unit whose compile unit DIE has the `DW_LANG_*` `language`
+    pub fn new(language: u8) -> Self {
+        let mut info = Vec::new();
+        // The length is set by `finish`
+        info.extend_from_slice(&0u32.to_le_bytes());
+        info.extend_from_slice(&4u16.to_le_bytes());
+        info.extend_from_slice(&0u32.to_le_bytes());
+        info.push(8);
+        info.extend_from_slice(&[0x01, language]);
+        Self { info }
+    }
+
+    /// Appends a DIE with the abbreviation code and attributes of `bytes`, returning its
+    /// offset
+    pub fn die(&mut self, bytes: &[u8]) -> u64 {
+        let offset = self.info.len() as u64;
+        self.info.extend_from_slice(bytes);
+        offset
+    }
+
+    /// Ends the children of the last DIE with children
+    pub fn end_children(&mut self) {
+        self.info.push(0);
+    }
+
+    /// Ends the children of the compile unit and returns the `.debug_info` bytes
+    pub fn finish(mut self) -> Vec<u8> {
+        self.end_children();
+        let length = self.info.len() as u32 - 4;
+        self.info[..4].copy_from_slice(&length.to_le_bytes());
+        self.info
+    }
+}
+
+/// A `DW_FORM_ref4` reference to the DIE at `offset`
+pub fn reference(offset: u64) -> [u8; 4] {
+    (offset as u32).to_le_bytes()
+}
+
+/// The sections of a file with the unit `info` and its abbreviations
+pub fn sections<'a>(info: &'a [u8], abbrev: &'a [u8]) -> DwarfSections<'a> {
+    DwarfSections {
+        debug_info: info,
+        debug_abbrev: abbrev,
+        address_size: 8,
+        ..Default::default()
+    }
+}
+
+/// A context without a process, for types and values that need not read it
+pub struct NoProcess;
+
+impl EvaluationContext for NoProcess {
+    fn register(&self, register: u16) -> Result<u64> {
+        Err(DwarfError::UnknownRegister(register))
+    }
+
+    fn read_memory(&self, address: u64, _size: u8) -> Result<u64> {
+        Err(DwarfError::InvalidMemoryAccess(address))
+    }
+}
+
+/// A process whose memory is the regions written to it, for values that point into it
+#[derive(Default)]
+pub struct Memory {
+    regions: Vec<(u64, Vec<u8>)>,
+}
+
+impl Memory {
+    pub fn write(&mut self, address: u64, bytes: &[u8]) {
+        self.regions.push((address, bytes.to_vec()));
+    }
+}
+
+impl EvaluationContext for Memory {
+    fn register(&self, register: u16) -> Result<u64> {
+        Err(DwarfError::UnknownRegister(register))
+    }
+
+    fn read_memory(&self, address: u64, size: u8) -> Result<u64> {
+        for (start, bytes) in self.regions.iter().filter(|(start, _)| *start <= address) {
+            let offset = (address - start) as usize;
+            if let Some(bytes) = bytes.get(offset..offset + size as usize) {
+                let mut word = [0; 8];
+                word[..bytes.len()].copy_from_slice(bytes);
+                return Ok(u64::from_le_bytes(word));
+            }
+        }
+        Err(DwarfError::InvalidMemoryAccess(address))
+    }
+}
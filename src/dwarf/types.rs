@@ -0,0 +1,413 @@
+use super::constants::*;
+use super::die::Die;
+use super::error::Result;
+use super::unit::Unit;
+
+/// The type of `die`, `None` for `void`. The type of a variable or function may
+/// be stored in the declaration it completes.
+pub fn type_of<'a>(unit: &Unit<'a>, die: &Die<'a>) -> Result<Option<Die<'a>>> {
+    match unit
+        .inherited_attr(die, DW_AT_TYPE)?
+        .and_then(|value| unit.reference(&value))
+    {
+        Some(offset) => unit.entry(offset).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Follows typedefs and cv-qualifiers to the type deciding how a value is stored
+pub fn strip_typedefs<'a>(unit: &Unit<'a>, die: Option<Die<'a>>) -> Result<Option<Die<'a>>> {
+    let mut die = die;
+    while let Some(current) = &die {
+        match current.tag {
+            DW_TAG_TYPEDEF | DW_TAG_CONST_TYPE | DW_TAG_VOLATILE_TYPE | DW_TAG_RESTRICT_TYPE
+            | DW_TAG_ATOMIC_TYPE => die = type_of(unit, current)?,
+            _ => break,
+        }
+    }
+    Ok(die)
+}
+
+/// The size of a value of the type `die`, `None` for types without a size like functions
+pub fn byte_size(unit: &Unit, die: &Die) -> Result<Option<u64>> {
+    if let Some(size) = die.attr(DW_AT_BYTE_SIZE).and_then(|value| value.udata()) {
+        return Ok(Some(size));
+    }
+    if let Some(bits) = die.attr(DW_AT_BIT_SIZE).and_then(|value| value.udata()) {
+        return Ok(Some(bits.div_ceil(8)));
+    }
+
+    match die.tag {
+        DW_TAG_POINTER_TYPE
+        | DW_TAG_REFERENCE_TYPE
+        | DW_TAG_RVALUE_REFERENCE_TYPE
+        | DW_TAG_PTR_TO_MEMBER_TYPE => Ok(Some(unit.address_size as u64)),
+        DW_TAG_TYPEDEF
+        | DW_TAG_CONST_TYPE
+        | DW_TAG_VOLATILE_TYPE
+        | DW_TAG_RESTRICT_TYPE
+        | DW_TAG_ATOMIC_TYPE
+        | DW_TAG_ENUMERATION_TYPE => match type_of(unit, die)? {
+            Some(target) => byte_size(unit, &target),
+            None => Ok(None),
+        },
+        DW_TAG_ARRAY_TYPE => {
+            let element = match type_of(unit, die)? {
+                Some(element) => element,
+                None => return Ok(None),
+            };
+            let mut size = byte_size(unit, &element)?;
+            for count in array_dimensions(unit, die)? {
+                size = match (size, count) {
+                    (Some(size), Some(count)) => Some(size * count),
+                    _ => None,
+                };
+            }
+            Ok(size)
+        }
+        _ => Ok(None),
+    }
+}
+
+/// The number of elements of each dimension of an array, `None` where it is not
+/// known statically, like for flexible array members and variable length arrays
+pub fn array_dimensions(unit: &Unit, die: &Die) -> Result<Vec<Option<u64>>> {
+    let mut dimensions = Vec::new();
+    for child in unit.children(die)? {
+        if child.tag != DW_TAG_SUBRANGE_TYPE {
+            continue;
+        }
+        let count = match child.attr(DW_AT_COUNT).and_then(|value| value.udata()) {
+            Some(count) => Some(count),
+            None => {
+                let lower = child
+                    .attr(DW_AT_LOWER_BOUND)
+                    .and_then(|value| value.sdata())
+                    .unwrap_or(0);
+                child
+                    .attr(DW_AT_UPPER_BOUND)
+                    .and_then(|value| value.sdata())
+                    .map(|upper| (upper - lower + 1).max(0) as u64)
+            }
+        };
+        dimensions.push(count);
+    }
+    Ok(dimensions)
+}
+
+/// Whether the unit is written in C, where struct, union and enum names are
+/// prefixed with their keyword
+fn is_c_language(unit: &Unit) -> Result<bool> {
+    let language = unit
+        .root()?
+        .attr(DW_AT_LANGUAGE)
+        .and_then(|value| value.udata());
+    Ok(matches!(
+        language.map(|language| language as u16),
+        Some(DW_LANG_C89 | DW_LANG_C | DW_LANG_C99 | DW_LANG_C11 | DW_LANG_C17)
+    ))
+}
+
+/// Whether the unit is written in Rust, whose values are printed the way Rust's `Debug` does
+pub fn is_rust_language(unit: &Unit) -> Result<bool> {
+    let language = unit
+        .root()?
+        .attr(DW_AT_LANGUAGE)
+        .and_then(|value| value.udata());
+    Ok(language == Some(DW_LANG_RUST as u64))
+}
+
+/// The name of the type `die` as it is written in C, e.g. `const char *` or
+/// `int (*)(int, char)`. `None` is `void`.
+pub fn type_name(unit: &Unit, die: Option<&Die>) -> Result<String> {
+    if is_rust_language(unit)? {
+        return rust_type_name(unit, die);
+    }
+    let (prefix, suffix) = declarator(unit, die, is_c_language(unit)?)?;
+    let ends_with_operator = prefix.ends_with('*') || prefix.ends_with('&');
+    if (suffix.starts_with('[') || suffix.starts_with('(')) && !ends_with_operator {
+        Ok(format!("{} {}", prefix, suffix))
+    } else {
+        Ok(format!("{}{}", prefix, suffix))
+    }
+}
+
+/// The declaration of `name` with the type `die` as it is written in C, e.g.
+/// `char *name` or `int (*name)[3]`
+pub fn declaration(unit: &Unit, die: Option<&Die>, name: &str) -> Result<String> {
+    let (prefix, suffix) = declarator(unit, die, is_c_language(unit)?)?;
+    if prefix.ends_with('*') || prefix.ends_with('&') {
+        Ok(format!("{}{}{}", prefix, name, suffix))
+    } else {
+        Ok(format!("{} {}{}", prefix, name, suffix))
+    }
+}
+
+/// The name of the type `die` as it is written in Rust, e.g. `&str` or `[u8; 4]`.
+/// Rust names most types itself, except arrays and raw function pointers.
+fn rust_type_name(unit: &Unit, die: Option<&Die>) -> Result<String> {
+    let die = match die {
+        Some(die) => die,
+        None => return Ok("()".to_string()),
+    };
+    if die.tag != DW_TAG_SUBPROGRAM {
+        if let Some(name) = unit.name_of(die)? {
+            return Ok(name.to_string());
+        }
+    }
+    let target = type_of(unit, die)?;
+
+    Ok(match die.tag {
+        DW_TAG_CONST_TYPE | DW_TAG_VOLATILE_TYPE | DW_TAG_TYPEDEF => {
+            rust_type_name(unit, target.as_ref())?
+        }
+        DW_TAG_POINTER_TYPE => format!("*const {}", rust_type_name(unit, target.as_ref())?),
+        DW_TAG_ARRAY_TYPE => {
+            let mut name = rust_type_name(unit, target.as_ref())?;
+            for count in array_dimensions(unit, die)?.iter().rev() {
+                name = match count {
+                    Some(count) => format!("[{}; {}]", name, count),
+                    None => format!("[{}]", name),
+                };
+            }
+            name
+        }
+        DW_TAG_SUBROUTINE_TYPE | DW_TAG_SUBPROGRAM => {
+            let mut parameters = Vec::new();
+            for child in unit.children(die)? {
+                if child.tag == DW_TAG_FORMAL_PARAMETER {
+                    let parameter = type_of(unit, &child)?;
+                    parameters.push(rust_type_name(unit, parameter.as_ref())?);
+                }
+            }
+            match target {
+                Some(target) => format!(
+                    "fn({}) -> {}",
+                    parameters.join(", "),
+                    rust_type_name(unit, Some(&target))?
+                ),
+                None => format!("fn({})", parameters.join(", ")),
+            }
+        }
+        _ => "<unknown type>".to_string(),
+    })
+}
+
+/// Splits a type name around the place of the declared name, e.g. `int (*` and `)[3]`
+fn declarator(unit: &Unit, die: Option<&Die>, is_c: bool) -> Result<(String, String)> {
+    let die = match die {
+        Some(die) => die,
+        None => return Ok(("void".to_string(), String::new())),
+    };
+    let target = type_of(unit, die)?;
+    let name = unit.name_of(die)?;
+
+    Ok(match die.tag {
+        DW_TAG_STRUCTURE_TYPE | DW_TAG_CLASS_TYPE | DW_TAG_UNION_TYPE | DW_TAG_ENUMERATION_TYPE => {
+            let keyword = match die.tag {
+                DW_TAG_STRUCTURE_TYPE => "struct",
+                DW_TAG_CLASS_TYPE => "class",
+                DW_TAG_UNION_TYPE => "union",
+                _ => "enum",
+            };
+            let name = match name {
+                Some(name) if is_c => format!("{} {}", keyword, name),
+                Some(name) => name.to_string(),
+                None => format!("{} {{...}}", keyword),
+            };
+            (name, String::new())
+        }
+        DW_TAG_CONST_TYPE | DW_TAG_VOLATILE_TYPE | DW_TAG_RESTRICT_TYPE | DW_TAG_ATOMIC_TYPE => {
+            let qualifier = match die.tag {
+                DW_TAG_CONST_TYPE => "const",
+                DW_TAG_VOLATILE_TYPE => "volatile",
+                DW_TAG_RESTRICT_TYPE => "restrict",
+                _ => "_Atomic",
+            };
+            let (prefix, suffix) = declarator(unit, target.as_ref(), is_c)?;
+            // Qualifiers of pointers follow the `*`
+            if prefix.ends_with('*') {
+                (format!("{} {}", prefix, qualifier), suffix)
+            } else {
+                (format!("{} {}", qualifier, prefix), suffix)
+            }
+        }
+        DW_TAG_POINTER_TYPE
+        | DW_TAG_REFERENCE_TYPE
+        | DW_TAG_RVALUE_REFERENCE_TYPE
+        | DW_TAG_PTR_TO_MEMBER_TYPE => {
+            let operator = match die.tag {
+                DW_TAG_POINTER_TYPE => "*".to_string(),
+                DW_TAG_REFERENCE_TYPE => "&".to_string(),
+                DW_TAG_RVALUE_REFERENCE_TYPE => "&&".to_string(),
+                _ => {
+                    let containing = unit.entry_by_attr(die, DW_AT_CONTAINING_TYPE)?;
+                    format!("{}::*", type_name(unit, containing.as_ref())?)
+                }
+            };
+            let (prefix, suffix) = declarator(unit, target.as_ref(), is_c)?;
+            if !suffix.is_empty() && !suffix.starts_with(')') {
+                // Pointers to arrays and functions need parentheses, e.g. `int (*)[3]`,
+                // which pointers to those pointers share, e.g. `int (**)[3]`
+                (format!("{} ({}", prefix, operator), format!("){}", suffix))
+            } else if prefix.ends_with('*') || prefix.ends_with('&') {
+                (format!("{}{}", prefix, operator), suffix)
+            } else {
+                (format!("{} {}", prefix, operator), suffix)
+            }
+        }
+        DW_TAG_ARRAY_TYPE => {
+            let (prefix, suffix) = declarator(unit, target.as_ref(), is_c)?;
+            let dimensions: String = array_dimensions(unit, die)?
+                .iter()
+                .map(|count| match count {
+                    Some(count) => format!("[{}]", count),
+                    None => "[]".to_string(),
+                })
+                .collect();
+            (prefix, format!("{}{}", dimensions, suffix))
+        }
+        // Functions have the type of their parameters and return value
+        DW_TAG_SUBROUTINE_TYPE | DW_TAG_SUBPROGRAM => {
+            let mut parameters = Vec::new();
+            for child in unit.children(die)? {
+                match child.tag {
+                    DW_TAG_FORMAL_PARAMETER => {
+                        let parameter = type_of(unit, &child)?;
+                        parameters.push(type_name(unit, parameter.as_ref())?);
+                    }
+                    DW_TAG_UNSPECIFIED_PARAMETERS => parameters.push("...".to_string()),
+                    _ => {}
+                }
+            }
+            if parameters.is_empty()
+                && is_c
+                && die.attr(DW_AT_PROTOTYPED).is_some_and(|value| value.flag())
+            {
+                parameters.push("void".to_string());
+            }
+            let (prefix, suffix) = declarator(unit, target.as_ref(), is_c)?;
+            (prefix, format!("({}){}", parameters.join(", "), suffix))
+        }
+        _ => (name.unwrap_or("<unknown type>").to_string(), String::new()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dwarf::testing::{abbrev_table, reference, sections, UnitBuilder};
+
+    fn abbrev_bytes() -> Vec<u8> {
+        abbrev_table(&[
+            // 2: base type, name string, byte_size data1, encoding data1
+            0x02, 0x24, 0x00, 0x03, 0x08, 0x0b, 0x0b, 0x3e, 0x0b, 0x00, 0x00,
+            // 3: pointer type, byte_size data1, type ref4
+            0x03, 0x0f, 0x00, 0x0b, 0x0b, 0x49, 0x13, 0x00, 0x00,
+            // 4: const type, type ref4
+            0x04, 0x26, 0x00, 0x49, 0x13, 0x00, 0x00,
+            // 5: array type with children, type ref4
+            0x05, 0x01, 0x01, 0x49, 0x13, 0x00, 0x00,
+            // 6: subrange type, upper_bound data1
+            0x06, 0x21, 0x00, 0x2f, 0x0b, 0x00, 0x00,
+            // 7: structure type with children, name string, byte_size data1
+            0x07, 0x13, 0x01, 0x03, 0x08, 0x0b, 0x0b, 0x00, 0x00,
+            // 8: member, name string, type ref4, data_member_location data1
+            0x08, 0x0d, 0x00, 0x03, 0x08, 0x49, 0x13, 0x38, 0x0b, 0x00, 0x00,
+            // 9: subroutine type with children, type ref4, prototyped flag_present
+            0x09, 0x15, 0x01, 0x49, 0x13, 0x27, 0x19, 0x00, 0x00,
+            // 10: formal parameter, type ref4
+            0x0a, 0x05, 0x00, 0x49, 0x13, 0x00, 0x00,
+        ])
+    }
+
+    /// Offsets of the type DIEs of the test unit
+    struct Types {
+        int: u64,
+        const_char: u64,
+        string: u64,
+        array: u64,
+        array_pointer: u64,
+        point: u64,
+        function_pointer: u64,
+        function_pointer_pointer: u64,
+    }
+
+    /// A DWARF 4 unit of a C program with the types of `Types`
+    fn info_bytes() -> (Vec<u8>, Types) {
+        // Compile unit in C99
+        let mut unit = UnitBuilder::new(0x0c);
+        let int = unit.die(&[0x02, b'i', b'n', b't', 0, 4, DW_ATE_SIGNED]);
+        let char = unit.die(&[0x02, b'c', b'h', b'a', b'r', 0, 1, DW_ATE_SIGNED_CHAR]);
+        let const_char = unit.die(&[&[0x04][..], &reference(char)].concat());
+        let string = unit.die(&[&[0x03, 8][..], &reference(const_char)].concat());
+        let array = unit.die(&[&[0x05][..], &reference(int), &[0x06, 2, 0]].concat());
+        let array_pointer = unit.die(&[&[0x03, 8][..], &reference(array)].concat());
+        let point = unit.die(&[0x07, b'p', b'o', b'i', b'n', b't', 0, 8]);
+        for (name, offset) in [(b'x', 0), (b'y', 4)] {
+            unit.die(&[&[0x08, name, 0][..], &reference(int), &[offset]].concat());
+        }
+        unit.end_children();
+        let function = unit.die(&[&[0x09][..], &reference(int)].concat());
+        unit.die(&[&[0x0a][..], &reference(int)].concat());
+        unit.die(&[&[0x0a][..], &reference(string)].concat());
+        unit.end_children();
+        let function_pointer = unit.die(&[&[0x03, 8][..], &reference(function)].concat());
+        let function_pointer_pointer =
+            unit.die(&[&[0x03, 8][..], &reference(function_pointer)].concat());
+
+        let types = Types {
+            int,
+            const_char,
+            string,
+            array,
+            array_pointer,
+            point,
+            function_pointer,
+            function_pointer_pointer,
+        };
+        (unit.finish(), types)
+    }
+
+    #[test]
+    fn test_type_names_and_sizes() {
+        let (info, types) = info_bytes();
+        let abbrev = abbrev_bytes();
+        let sections = sections(&info, &abbrev);
+        let unit = sections.unit_at(0).unwrap();
+        let name = |offset| type_name(&unit, Some(&unit.entry(offset).unwrap())).unwrap();
+        let size = |offset| byte_size(&unit, &unit.entry(offset).unwrap()).unwrap();
+
+        assert_eq!(name(types.int), "int");
+        assert_eq!(name(types.const_char), "const char");
+        assert_eq!(name(types.string), "const char *");
+        assert_eq!(name(types.array), "int [3]");
+        assert_eq!(name(types.array_pointer), "int (*)[3]");
+        assert_eq!(name(types.point), "struct point");
+        assert_eq!(name(types.function_pointer), "int (*)(int, const char *)");
+        assert_eq!(type_name(&unit, None).unwrap(), "void");
+
+        let declaration =
+            |offset, name| declaration(&unit, Some(&unit.entry(offset).unwrap()), name).unwrap();
+        assert_eq!(declaration(types.string, "s"), "const char *s");
+        assert_eq!(declaration(types.array, "a"), "int a[3]");
+        assert_eq!(declaration(types.array_pointer, "p"), "int (*p)[3]");
+        assert_eq!(
+            declaration(types.function_pointer, "f"),
+            "int (*f)(int, const char *)"
+        );
+        assert_eq!(
+            declaration(types.function_pointer_pointer, "f"),
+            "int (**f)(int, const char *)"
+        );
+
+        assert_eq!(size(types.const_char), Some(1));
+        assert_eq!(size(types.array), Some(12));
+        assert_eq!(size(types.point), Some(8));
+        assert_eq!(size(types.function_pointer), Some(8));
+
+        let const_char = unit.entry(types.const_char).unwrap();
+        let char = strip_typedefs(&unit, Some(const_char)).unwrap().unwrap();
+        assert_eq!(char.name(), Some("char"));
+    }
+}
@@ -0,0 +1,757 @@
+use std::rc::Rc;
+
+use super::abbrev::Abbreviations;
+use super::constants::*;
+use super::die::{Attribute, AttributeValue, Die};
+use super::error::{DwarfError, Result};
+use super::reader::{Format, Reader};
+use super::sections::DwarfSections;
+
+/// A unit of `.debug_info`, usually a compilation unit. Its DIEs are only read when asked for.
+#[derive(Debug, Clone)]
+pub struct Unit<'a> {
+    /// Offset of the unit header in `.debug_info`
+    pub offset: u64,
+    pub format: Format,
+    pub version: u16,
+    /// One of the `DW_UT_*` values, units before DWARF 5 are compile units
+    pub unit_type: u8,
+    pub address_size: u8,
+    pub abbrev_offset: u64,
+    /// Id linking a skeleton unit and its split unit
+    pub dwo_id: Option<u64>,
+    pub type_signature: Option<u64>,
+    pub type_offset: Option<u64>,
+    pub str_offsets_base: u64,
+    pub addr_base: u64,
+    pub rnglists_base: Option<u64>,
+    pub loclists_base: Option<u64>,
+    /// The `DW_AT_low_pc` of the unit, the base address of its location and range lists
+    pub base_address: u64,
+    /// Offset of the first DIE in `.debug_info`
+    entries_offset: u64,
+    /// Offset after the last byte of the unit
+    end_offset: u64,
+    abbreviations: Rc<Abbreviations>,
+    sections: DwarfSections<'a>,
+    /// The skeleton unit in the executable a split unit was found through
+    skeleton: Option<Box<Unit<'a>>>,
+}
+
+impl<'a> Unit<'a> {
+    /// Parses the header of the unit at `offset` of `.debug_info` and the unit level
+    /// attributes of its root DIE needed to decode the other DIEs
+    pub fn parse(sections: DwarfSections<'a>, offset: u64) -> Result<Self> {
+        let mut reader = Reader::at(sections.debug_info, offset as usize)?;
+        let (length, format) = reader.read_initial_length()?;
+        let end_offset = reader.offset() as u64 + length;
+        let version = reader.read_u16()?;
+        if !(2..=5).contains(&version) {
+            return Err(DwarfError::UnsupportedVersion(version));
+        }
+
+        let mut dwo_id = None;
+        let mut type_signature = None;
+        let mut type_offset = None;
+        let (unit_type, address_size, abbrev_offset) = if version >= 5 {
+            let unit_type = reader.read_u8()?;
+            let address_size = reader.read_u8()?;
+            let abbrev_offset = reader.read_offset(format)?;
+            match unit_type {
+                DW_UT_SKELETON | DW_UT_SPLIT_COMPILE => dwo_id = Some(reader.read_u64()?),
+                DW_UT_TYPE | DW_UT_SPLIT_TYPE => {
+                    type_signature = Some(reader.read_u64()?);
+                    type_offset = Some(reader.read_offset(format)?);
+                }
+                _ => {}
+            }
+            (unit_type, address_size, abbrev_offset)
+        } else {
+            let abbrev_offset = reader.read_offset(format)?;
+            let address_size = reader.read_u8()?;
+            (DW_UT_COMPILE, address_size, abbrev_offset)
+        };
+        if !matches!(address_size, 1 | 2 | 4 | 8) {
+            return Err(DwarfError::UnsupportedAddressSize(address_size));
+        }
+
+        let abbreviations = Abbreviations::parse(sections.debug_abbrev, abbrev_offset)?;
+        let mut unit = Self {
+            offset,
+            format,
+            version,
+            unit_type,
+            address_size,
+            abbrev_offset,
+            dwo_id,
+            type_signature,
+            type_offset,
+            // Split units have no base attributes, their contributions start after the header
+            str_offsets_base: if version >= 5 {
+                2 * format.offset_size() as u64
+            } else {
+                0
+            },
+            addr_base: 0,
+            rnglists_base: None,
+            loclists_base: None,
+            base_address: 0,
+            entries_offset: reader.offset() as u64,
+            end_offset,
+            abbreviations: Rc::new(abbreviations),
+            sections,
+            skeleton: None,
+        };
+
+        let root = unit.read_die(unit.entries_offset, false)?;
+        let mut low_pc = None;
+        // Before DWARF 5, split units are a GNU extension told apart by their attributes
+        if version < 5 && root.has_attr(DW_AT_GNU_DWO_ID) {
+            unit.unit_type = if root.has_attr(DW_AT_GNU_DWO_NAME) {
+                DW_UT_SKELETON
+            } else {
+                DW_UT_SPLIT_COMPILE
+            };
+        }
+        for attribute in &root.attributes {
+            if attribute.name == DW_AT_LOW_PC {
+                low_pc = Some(attribute.value.clone());
+            }
+            match (attribute.name, attribute.value.offset()) {
+                (DW_AT_STR_OFFSETS_BASE, Some(base)) => unit.str_offsets_base = base,
+                (DW_AT_ADDR_BASE | DW_AT_GNU_ADDR_BASE, Some(base)) => unit.addr_base = base,
+                (DW_AT_RNGLISTS_BASE | DW_AT_GNU_RANGES_BASE, Some(base)) => {
+                    unit.rnglists_base = Some(base)
+                }
+                (DW_AT_LOCLISTS_BASE, Some(base)) => unit.loclists_base = Some(base),
+                (DW_AT_GNU_DWO_ID, _) => unit.dwo_id = attribute.value.udata(),
+                _ => {}
+            }
+        }
+        // The address base may follow the low pc, so its index is resolved last
+        if let Some(AttributeValue::Address(address)) = low_pc.map(|value| unit.resolve(value)) {
+            unit.base_address = address;
+        }
+        Ok(unit)
+    }
+
+    /// Completes a split unit with what its skeleton unit holds: the base of its indexed
+    /// addresses, its base address and, before DWARF 5, the base of its range lists
+    pub(crate) fn with_skeleton(mut self, skeleton: Unit<'a>) -> Self {
+        self.addr_base = skeleton.addr_base;
+        self.base_address = skeleton.base_address;
+        if self.version < 5 {
+            self.rnglists_base = skeleton.rnglists_base;
+        }
+        self.skeleton = Some(Box::new(skeleton));
+        self
+    }
+
+    /// The skeleton unit of a split unit, which has its line program
+    pub fn skeleton(&self) -> Option<&Unit<'a>> {
+        self.skeleton.as_deref()
+    }
+
+    /// Identifies the unit among those of the executable: the offset of its header, or of
+    /// the header of its skeleton unit for a split unit, in `.debug_info` of the executable
+    pub fn id(&self) -> u64 {
+        self.skeleton
+            .as_ref()
+            .map_or(self.offset, |skeleton| skeleton.offset)
+    }
+
+    pub fn sections(&self) -> &DwarfSections<'a> {
+        &self.sections
+    }
+
+    /// Offset of the next unit in `.debug_info`
+    pub fn end_offset(&self) -> u64 {
+        self.end_offset
+    }
+
+    /// Whether the DIE at `offset` of `.debug_info` belongs to this unit
+    pub fn contains(&self, offset: u64) -> bool {
+        self.entries_offset <= offset && offset < self.end_offset
+    }
+
+    /// The first DIE of the unit, e.g. the `DW_TAG_compile_unit`
+    pub fn root(&self) -> Result<Die<'a>> {
+        self.entry(self.entries_offset)
+    }
+
+    /// The DIE at `offset` of `.debug_info`
+    pub fn entry(&self, offset: u64) -> Result<Die<'a>> {
+        if !self.contains(offset) {
+            return Err(DwarfError::InvalidDieOffset(offset));
+        }
+        self.read_die(offset, true)
+    }
+
+    /// The section offset of the DIE an attribute value refers to
+    pub fn reference(&self, value: &AttributeValue) -> Option<u64> {
+        match *value {
+            AttributeValue::UnitRef(offset) => Some(self.offset + offset),
+            AttributeValue::DebugInfoRef(offset) => Some(offset),
+            _ => None,
+        }
+    }
+
+    /// Follows a reference attribute, like `DW_AT_type`, to the DIE it refers to
+    pub fn entry_by_attr(&self, die: &Die, name: u16) -> Result<Option<Die<'a>>> {
+        match die.attr(name).and_then(|value| self.reference(value)) {
+            Some(offset) => self.entry(offset).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// The attribute `name` of `die`, or of the DIE it completes through `DW_AT_abstract_origin`
+    /// or `DW_AT_specification`, like the declaration of a function holding its name and type
+    pub fn inherited_attr(&self, die: &Die<'a>, name: u16) -> Result<Option<AttributeValue<'a>>> {
+        let mut die = die.clone();
+        // Origins may be in another unit, referred to with `DW_FORM_ref_addr`
+        let mut other_unit: Option<Unit<'a>> = None;
+        // An origin may itself have a specification, more levels do not occur
+        for _ in 0..3 {
+            let unit = other_unit.as_ref().unwrap_or(self);
+            if let Some(value) = die.attr(name) {
+                return Ok(Some(match *value {
+                    // References within the other unit are kept pointing into it
+                    AttributeValue::UnitRef(offset) if other_unit.is_some() => {
+                        AttributeValue::DebugInfoRef(unit.offset + offset)
+                    }
+                    _ => value.clone(),
+                }));
+            }
+            let offset = match die
+                .attr(DW_AT_ABSTRACT_ORIGIN)
+                .or_else(|| die.attr(DW_AT_SPECIFICATION))
+                .and_then(|value| unit.reference(value))
+            {
+                Some(offset) => offset,
+                None => return Ok(None),
+            };
+            if !unit.contains(offset) {
+                other_unit = Some(self.sections.unit_containing(offset)?);
+            }
+            die = other_unit.as_ref().unwrap_or(self).entry(offset)?;
+        }
+        Ok(None)
+    }
+
+    /// The name of `die`, looked up like `inherited_attr`
+    pub fn name_of(&self, die: &Die<'a>) -> Result<Option<&'a str>> {
+        Ok(self
+            .inherited_attr(die, DW_AT_NAME)?
+            .and_then(|value| value.string()))
+    }
+
+    /// The direct children of a DIE, their own children are skipped
+    pub fn children(&self, die: &Die) -> Result<Vec<Die<'a>>> {
+        let mut children = Vec::new();
+        if !die.has_children {
+            return Ok(children);
+        }
+
+        let mut offset = die.next_offset;
+        while let Some(child) = self.read_entry(offset)? {
+            offset = match child.attr(DW_AT_SIBLING).and_then(|v| self.reference(v)) {
+                Some(sibling) if child.has_children && sibling > offset => sibling,
+                _ if child.has_children => self.skip_children(child.next_offset)?,
+                _ => child.next_offset,
+            };
+            children.push(child);
+        }
+        Ok(children)
+    }
+
+    /// Walks all DIEs of the unit in order, with their depth below the root
+    pub fn entries(&self) -> Entries<'_, 'a> {
+        Entries {
+            unit: self,
+            offset: self.entries_offset,
+            depth: 0,
+            done: false,
+        }
+    }
+
+    /// Reads the DIE at `offset`, `None` for the null entry ending a list of children
+    fn read_entry(&self, offset: u64) -> Result<Option<Die<'a>>> {
+        if offset >= self.end_offset {
+            return Ok(None);
+        }
+        let mut reader = Reader::at(self.sections.debug_info, offset as usize)?;
+        if reader.read_uleb128()? == 0 {
+            return Ok(None);
+        }
+        self.read_die(offset, true).map(Some)
+    }
+
+    /// Returns the offset after the null entry ending the children starting at `offset`
+    fn skip_children(&self, mut offset: u64) -> Result<u64> {
+        let mut depth = 1;
+        while depth > 0 {
+            if offset >= self.end_offset {
+                return Ok(self.end_offset);
+            }
+            let mut reader = Reader::at(self.sections.debug_info, offset as usize)?;
+            let code = reader.read_uleb128()?;
+            if code == 0 {
+                depth -= 1;
+                offset = reader.offset() as u64;
+                continue;
+            }
+            let die = self.read_die(offset, false)?;
+            if die.has_children {
+                depth += 1;
+            }
+            offset = die.next_offset;
+        }
+        Ok(offset)
+    }
+
+    fn read_die(&self, offset: u64, resolve: bool) -> Result<Die<'a>> {
+        let mut reader = Reader::at(self.sections.debug_info, offset as usize)?;
+        let code = reader.read_uleb128()?;
+        let abbreviation = self.abbreviations.get(code)?;
+
+        let mut attributes = Vec::with_capacity(abbreviation.attributes.len());
+        for spec in &abbreviation.attributes {
+            let (form, value) =
+                self.read_attribute_value(&mut reader, spec.form, spec.implicit_const)?;
+            let value = if resolve { self.resolve(value) } else { value };
+            attributes.push(Attribute {
+                name: spec.name,
+                form,
+                value,
+            });
+        }
+
+        Ok(Die {
+            offset,
+            tag: abbreviation.tag,
+            has_children: abbreviation.has_children,
+            attributes,
+            next_offset: reader.offset() as u64,
+        })
+    }
+
+    fn read_attribute_value(
+        &self,
+        reader: &mut Reader<'a>,
+        form: u16,
+        implicit_const: Option<i64>,
+    ) -> Result<(u16, AttributeValue<'a>)> {
+        let format = self.format;
+        let value = match form {
+            DW_FORM_ADDR => AttributeValue::Address(reader.read_address(self.address_size)?),
+            DW_FORM_BLOCK1 => {
+                let len = reader.read_u8()? as usize;
+                AttributeValue::Block(reader.read_bytes(len)?)
+            }
+            DW_FORM_BLOCK2 => {
+                let len = reader.read_u16()? as usize;
+                AttributeValue::Block(reader.read_bytes(len)?)
+            }
+            DW_FORM_BLOCK4 => {
+                let len = reader.read_u32()? as usize;
+                AttributeValue::Block(reader.read_bytes(len)?)
+            }
+            DW_FORM_BLOCK => {
+                let len = reader.read_uleb128()? as usize;
+                AttributeValue::Block(reader.read_bytes(len)?)
+            }
+            DW_FORM_EXPRLOC => {
+                let len = reader.read_uleb128()? as usize;
+                AttributeValue::Exprloc(reader.read_bytes(len)?)
+            }
+            DW_FORM_DATA1 => AttributeValue::Udata(reader.read_u8()? as u64),
+            DW_FORM_DATA2 => AttributeValue::Udata(reader.read_u16()? as u64),
+            DW_FORM_DATA4 => AttributeValue::Udata(reader.read_u32()? as u64),
+            DW_FORM_DATA8 => AttributeValue::Udata(reader.read_u64()?),
+            DW_FORM_DATA16 => AttributeValue::Block(reader.read_bytes(16)?),
+            DW_FORM_UDATA => AttributeValue::Udata(reader.read_uleb128()?),
+            DW_FORM_SDATA => AttributeValue::Sdata(reader.read_sleb128()?),
+            DW_FORM_IMPLICIT_CONST => AttributeValue::Sdata(implicit_const.unwrap_or(0)),
+            DW_FORM_FLAG => AttributeValue::Flag(reader.read_u8()? != 0),
+            DW_FORM_FLAG_PRESENT => AttributeValue::Flag(true),
+            DW_FORM_STRING => {
+                let bytes = reader.read_cstr()?;
+                AttributeValue::String(std::str::from_utf8(bytes).unwrap_or_default())
+            }
+            DW_FORM_STRP => {
+                let offset = reader.read_offset(format)?;
+                let string = DwarfSections::string_at(self.sections.debug_str, offset);
+                AttributeValue::String(string.ok_or(DwarfError::UnexpectedEof)?)
+            }
+            DW_FORM_LINE_STRP => {
+                let offset = reader.read_offset(format)?;
+                let string = DwarfSections::string_at(self.sections.debug_line_str, offset);
+                AttributeValue::String(string.ok_or(DwarfError::UnexpectedEof)?)
+            }
+            DW_FORM_STRX | DW_FORM_GNU_STR_INDEX => {
+                AttributeValue::StrIndex(reader.read_uleb128()?)
+            }
+            DW_FORM_STRX1 => AttributeValue::StrIndex(reader.read_u8()? as u64),
+            DW_FORM_STRX2 => AttributeValue::StrIndex(reader.read_u16()? as u64),
+            DW_FORM_STRX3 => AttributeValue::StrIndex(reader.read_u24()? as u64),
+            DW_FORM_STRX4 => AttributeValue::StrIndex(reader.read_u32()? as u64),
+            DW_FORM_ADDRX | DW_FORM_GNU_ADDR_INDEX => {
+                AttributeValue::AddrIndex(reader.read_uleb128()?)
+            }
+            DW_FORM_ADDRX1 => AttributeValue::AddrIndex(reader.read_u8()? as u64),
+            DW_FORM_ADDRX2 => AttributeValue::AddrIndex(reader.read_u16()? as u64),
+            DW_FORM_ADDRX3 => AttributeValue::AddrIndex(reader.read_u24()? as u64),
+            DW_FORM_ADDRX4 => AttributeValue::AddrIndex(reader.read_u32()? as u64),
+            DW_FORM_REF1 => AttributeValue::UnitRef(reader.read_u8()? as u64),
+            DW_FORM_REF2 => AttributeValue::UnitRef(reader.read_u16()? as u64),
+            DW_FORM_REF4 => AttributeValue::UnitRef(reader.read_u32()? as u64),
+            DW_FORM_REF8 => AttributeValue::UnitRef(reader.read_u64()?),
+            DW_FORM_REF_UDATA => AttributeValue::UnitRef(reader.read_uleb128()?),
+            // DWARF 2 uses the address size for references to other units
+            DW_FORM_REF_ADDR if self.version == 2 => {
+                AttributeValue::DebugInfoRef(reader.read_address(self.address_size)?)
+            }
+            DW_FORM_REF_ADDR => AttributeValue::DebugInfoRef(reader.read_offset(format)?),
+            DW_FORM_REF_SIG8 => AttributeValue::TypeSignature(reader.read_u64()?),
+            DW_FORM_SEC_OFFSET => AttributeValue::SecOffset(reader.read_offset(format)?),
+            DW_FORM_LOCLISTX => AttributeValue::LoclistsIndex(reader.read_uleb128()?),
+            DW_FORM_RNGLISTX => AttributeValue::RnglistsIndex(reader.read_uleb128()?),
+            DW_FORM_REF_SUP4 => AttributeValue::Supplementary(reader.read_u32()? as u64),
+            DW_FORM_REF_SUP8 => AttributeValue::Supplementary(reader.read_u64()?),
+            DW_FORM_STRP_SUP | DW_FORM_GNU_REF_ALT | DW_FORM_GNU_STRP_ALT => {
+                AttributeValue::Supplementary(reader.read_offset(format)?)
+            }
+            DW_FORM_INDIRECT => {
+                let form = reader.read_uleb128()? as u16;
+                return self.read_attribute_value(reader, form, implicit_const);
+            }
+            _ => return Err(DwarfError::UnknownForm(form)),
+        };
+        Ok((form, value))
+    }
+
+    /// Reads a value of `form` outside of a DIE, e.g. in the line table header
+    pub(crate) fn read_form(
+        &self,
+        reader: &mut Reader<'a>,
+        form: u16,
+    ) -> Result<AttributeValue<'a>> {
+        let (_, value) = self.read_attribute_value(reader, form, None)?;
+        Ok(self.resolve(value))
+    }
+
+    /// Resolves string and address indexes through `.debug_str_offsets` and `.debug_addr`
+    fn resolve(&self, value: AttributeValue<'a>) -> AttributeValue<'a> {
+        match value {
+            AttributeValue::StrIndex(index) => self
+                .string_by_index(index)
+                .map_or(value, AttributeValue::String),
+            AttributeValue::AddrIndex(index) => self
+                .address_by_index(index)
+                .map_or(value, AttributeValue::Address),
+            value => value,
+        }
+    }
+
+    pub fn string_by_index(&self, index: u64) -> Option<&'a str> {
+        let offset_size = self.format.offset_size();
+        let entry = self.str_offsets_base + index * offset_size as u64;
+        let mut reader = Reader::at(self.sections.debug_str_offsets, entry as usize).ok()?;
+        let offset = reader.read_sized(offset_size).ok()?;
+        DwarfSections::string_at(self.sections.debug_str, offset)
+    }
+
+    pub fn address_by_index(&self, index: u64) -> Option<u64> {
+        let entry = self.addr_base + index * self.address_size as u64;
+        let mut reader = Reader::at(self.sections.debug_addr, entry as usize).ok()?;
+        reader.read_address(self.address_size).ok()
+    }
+}
+
+/// Iterator over the units of `.debug_info`
+pub struct Units<'a> {
+    sections: DwarfSections<'a>,
+    offset: u64,
+}
+
+impl<'a> Units<'a> {
+    pub(crate) fn new(sections: DwarfSections<'a>) -> Self {
+        Self {
+            sections,
+            offset: 0,
+        }
+    }
+}
+
+impl<'a> Iterator for Units<'a> {
+    type Item = Result<Unit<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.offset as usize >= self.sections.debug_info.len() {
+            return None;
+        }
+        match Unit::parse(self.sections, self.offset) {
+            Ok(unit) => {
+                self.offset = unit.end_offset;
+                Some(self.sections.resolve_skeleton(unit))
+            }
+            Err(err) => {
+                // A broken header leaves no way to find the next unit
+                self.offset = self.sections.debug_info.len() as u64;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+/// Depth first walk over the DIEs of a unit, see [`Unit::entries`]
+pub struct Entries<'u, 'a> {
+    unit: &'u Unit<'a>,
+    offset: u64,
+    depth: usize,
+    done: bool,
+}
+
+impl<'u, 'a> Iterator for Entries<'u, 'a> {
+    type Item = Result<(usize, Die<'a>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done && self.offset < self.unit.end_offset {
+            let mut reader = match Reader::at(self.unit.sections.debug_info, self.offset as usize) {
+                Ok(reader) => reader,
+                Err(err) => return Some(Err(err)),
+            };
+            match reader.read_uleb128() {
+                Ok(0) => {
+                    self.offset = reader.offset() as u64;
+                    if self.depth == 0 {
+                        self.done = true;
+                    } else {
+                        self.depth -= 1;
+                    }
+                    continue;
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(err));
+                }
+            }
+
+            let die = match self.unit.read_die(self.offset, true) {
+                Ok(die) => die,
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(err));
+                }
+            };
+            let depth = self.depth;
+            self.offset = die.next_offset;
+            if die.has_children {
+                self.depth += 1;
+            }
+            return Some(Ok((depth, die)));
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEBUG_STR: &[u8] = b"\0main\0hello.c\0";
+    const DEBUG_LINE_STR: &[u8] = b"/tmp\0";
+
+    fn abbrev_bytes() -> Vec<u8> {
+        vec![
+            // 1: compile unit, name strx1, comp_dir line_strp, low_pc addrx, high_pc data4,
+            // str_offsets_base and addr_base sec_offset
+            0x01, 0x11, 0x01, 0x03, 0x25, 0x1b, 0x1f, 0x11, 0x1b, 0x12, 0x06, 0x72, 0x17, 0x73,
+            0x17, 0x00, 0x00,
+            // 2: base type, name string, byte_size data1, encoding data1
+            0x02, 0x24, 0x00, 0x03, 0x08, 0x0b, 0x0b, 0x3e, 0x0b, 0x00, 0x00,
+            // 3: subprogram with children, name strp, type ref4, external flag_present
+            0x03, 0x2e, 0x01, 0x03, 0x0e, 0x49, 0x13, 0x3f, 0x19, 0x00, 0x00,
+            // 4: variable, name string, type ref4, decl_file implicit_const 1, location exprloc
+            0x04, 0x34, 0x00, 0x03, 0x08, 0x49, 0x13, 0x3a, 0x21, 0x01, 0x02, 0x18, 0x00, 0x00,
+            0x00,
+            // Second table at offset 0x36
+            // 1: compile unit, name strp
+            0x01, 0x11, 0x01, 0x03, 0x0e, 0x00, 0x00,
+            // 2: typedef, name string, type ref_addr
+            0x02, 0x16, 0x00, 0x03, 0x08, 0x49, 0x10, 0x00, 0x00, 0x00,
+        ]
+    }
+
+    /// A DWARF 5 unit followed by a 64-bit DWARF 4 unit
+    fn info_bytes() -> Vec<u8> {
+        let mut info = Vec::new();
+        info.extend_from_slice(&55u32.to_le_bytes());
+        info.extend_from_slice(&5u16.to_le_bytes());
+        info.extend_from_slice(&[DW_UT_COMPILE, 8]);
+        info.extend_from_slice(&0u32.to_le_bytes());
+        // 0xc: compile unit
+        info.extend_from_slice(&[0x01, 0x00]);
+        info.extend_from_slice(&0u32.to_le_bytes());
+        info.push(0x00);
+        info.extend_from_slice(&0x20u32.to_le_bytes());
+        info.extend_from_slice(&8u32.to_le_bytes());
+        info.extend_from_slice(&8u32.to_le_bytes());
+        // 0x1f: base type int, 4 bytes, DW_ATE_signed
+        info.extend_from_slice(&[0x02, b'i', b'n', b't', 0x00, 0x04, 0x05]);
+        // 0x26: subprogram main returning int
+        info.push(0x03);
+        info.extend_from_slice(&1u32.to_le_bytes());
+        info.extend_from_slice(&0x1fu32.to_le_bytes());
+        // 0x2f: variable x of type int at fbreg -20
+        info.extend_from_slice(&[0x04, b'x', 0x00]);
+        info.extend_from_slice(&0x1fu32.to_le_bytes());
+        info.extend_from_slice(&[0x02, 0x91, 0x6c]);
+        // End of the children of main and of the compile unit
+        info.extend_from_slice(&[0x00, 0x00]);
+        assert_eq!(info.len(), 59);
+
+        // 0x3b: 64-bit DWARF 4 unit
+        info.extend_from_slice(&0xffffffffu32.to_le_bytes());
+        info.extend_from_slice(&32u64.to_le_bytes());
+        info.extend_from_slice(&4u16.to_le_bytes());
+        info.extend_from_slice(&0x36u64.to_le_bytes());
+        info.push(8);
+        // 0x52: compile unit named main
+        info.push(0x01);
+        info.extend_from_slice(&1u64.to_le_bytes());
+        // 0x5b: typedef t of the base type of the first unit
+        info.extend_from_slice(&[0x02, b't', 0x00]);
+        info.extend_from_slice(&0x1fu64.to_le_bytes());
+        info.push(0x00);
+        assert_eq!(info.len(), 0x3b + 12 + 32);
+        info
+    }
+
+    fn str_offsets_bytes() -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&12u32.to_le_bytes());
+        bytes.extend_from_slice(&5u16.to_le_bytes());
+        bytes.extend_from_slice(&0u16.to_le_bytes());
+        bytes.extend_from_slice(&6u32.to_le_bytes());
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes
+    }
+
+    fn addr_bytes() -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&12u32.to_le_bytes());
+        bytes.extend_from_slice(&5u16.to_le_bytes());
+        bytes.extend_from_slice(&[8, 0]);
+        bytes.extend_from_slice(&0x1139u64.to_le_bytes());
+        bytes
+    }
+
+    fn sections<'a>(
+        info: &'a [u8],
+        abbrev: &'a [u8],
+        str_offsets: &'a [u8],
+        addr: &'a [u8],
+    ) -> DwarfSections<'a> {
+        DwarfSections {
+            debug_info: info,
+            debug_abbrev: abbrev,
+            debug_str: DEBUG_STR,
+            debug_line_str: DEBUG_LINE_STR,
+            debug_str_offsets: str_offsets,
+            debug_addr: addr,
+            address_size: 8,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_dwarf5_unit() {
+        let (info, abbrev) = (info_bytes(), abbrev_bytes());
+        let (str_offsets, addr) = (str_offsets_bytes(), addr_bytes());
+        let sections = sections(&info, &abbrev, &str_offsets, &addr);
+
+        let unit = sections.unit_at(0).unwrap();
+        assert_eq!(unit.version, 5);
+        assert_eq!(unit.format, Format::Dwarf32);
+        assert_eq!(unit.str_offsets_base, 8);
+        assert_eq!(unit.addr_base, 8);
+
+        let root = unit.root().unwrap();
+        assert_eq!(root.tag, DW_TAG_COMPILE_UNIT);
+        assert_eq!(root.name(), Some("hello.c"));
+        assert_eq!(
+            root.attr(DW_AT_COMP_DIR),
+            Some(&AttributeValue::String("/tmp"))
+        );
+        assert_eq!(
+            root.attr(DW_AT_LOW_PC),
+            Some(&AttributeValue::Address(0x1139))
+        );
+        assert_eq!(root.attr(DW_AT_HIGH_PC), Some(&AttributeValue::Udata(0x20)));
+
+        let children = unit.children(&root).unwrap();
+        let names: Vec<_> = children.iter().map(|die| die.name().unwrap()).collect();
+        assert_eq!(names, vec!["int", "main"]);
+        assert!(children[1].attr(DW_AT_EXTERNAL).unwrap().flag());
+
+        let variables = unit.children(&children[1]).unwrap();
+        assert_eq!(variables.len(), 1);
+        let variable = &variables[0];
+        assert_eq!(
+            variable.attr(DW_AT_DECL_FILE),
+            Some(&AttributeValue::Sdata(1))
+        );
+        assert_eq!(
+            variable.attr(DW_AT_LOCATION).unwrap().expression(),
+            Some(&[0x91, 0x6c][..])
+        );
+        let r#type = unit.entry_by_attr(variable, DW_AT_TYPE).unwrap().unwrap();
+        assert_eq!(r#type, children[0]);
+
+        let entries: Vec<_> = unit
+            .entries()
+            .map(|entry| entry.map(|(depth, die)| (depth, die.tag)).unwrap())
+            .collect();
+        assert_eq!(
+            entries,
+            vec![
+                (0, DW_TAG_COMPILE_UNIT),
+                (1, DW_TAG_BASE_TYPE),
+                (1, DW_TAG_SUBPROGRAM),
+                (2, DW_TAG_VARIABLE),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_dwarf4_64bit_unit() {
+        let (info, abbrev) = (info_bytes(), abbrev_bytes());
+        let sections = sections(&info, &abbrev, &[], &[]);
+
+        let units: Vec<_> = sections.units().map(Result::unwrap).collect();
+        assert_eq!(units.len(), 2);
+        let unit = &units[1];
+        assert_eq!(unit.offset, 0x3b);
+        assert_eq!(unit.version, 4);
+        assert_eq!(unit.format, Format::Dwarf64);
+        assert_eq!(unit.root().unwrap().name(), Some("main"));
+
+        // The typedef refers to the base type of the first unit
+        let typedef = unit.children(&unit.root().unwrap()).unwrap().remove(0);
+        assert_eq!(
+            typedef.attr(DW_AT_TYPE),
+            Some(&AttributeValue::DebugInfoRef(0x1f))
+        );
+        assert!(unit.entry_by_attr(&typedef, DW_AT_TYPE).is_err());
+        let type_unit = sections.unit_containing(0x1f).unwrap();
+        assert_eq!(type_unit.offset, 0);
+        assert_eq!(type_unit.entry(0x1f).unwrap().name(), Some("int"));
+
+        // Without .debug_str_offsets and .debug_addr the indexes stay unresolved
+        let root = units[0].root().unwrap();
+        assert_eq!(root.attr(DW_AT_NAME), Some(&AttributeValue::StrIndex(0)));
+        assert_eq!(root.attr(DW_AT_LOW_PC), Some(&AttributeValue::AddrIndex(0)));
+    }
+}
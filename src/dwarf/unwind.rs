@@ -0,0 +1,229 @@
+use std::collections::BTreeMap;
+
+use super::error::{DwarfError, Result};
+use super::expression::{EvaluationContext, Evaluator, Location};
+use super::frame::{CfaRule, RegisterRule, UnwindRow};
+
+/// DWARF number of the stack pointer on x86-64
+pub const STACK_POINTER_REGISTER: u16 = 7;
+/// DWARF number of the frame pointer on x86-64
+pub const FRAME_POINTER_REGISTER: u16 = 6;
+/// DWARF number of the return address column on x86-64, which holds the pc of a frame
+pub const RETURN_ADDRESS_REGISTER: u16 = 16;
+/// Registers a function has to preserve on x86-64, their values are kept in the caller
+/// where the CFI has no rule for them
+const CALLEE_SAVED_REGISTERS: [u16; 6] = [3, 6, 12, 13, 14, 15];
+
+/// Values of the registers of a frame by DWARF register number. Registers whose value
+/// could not be recovered while unwinding are missing.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RegisterSet {
+    values: BTreeMap<u16, u64>,
+}
+
+impl RegisterSet {
+    pub fn get(&self, register: u16) -> Option<u64> {
+        self.values.get(&register).copied()
+    }
+
+    pub fn set(&mut self, register: u16, value: u64) {
+        self.values.insert(register, value);
+    }
+
+    pub fn remove(&mut self, register: u16) {
+        self.values.remove(&register);
+    }
+
+    /// The pc of the frame
+    pub fn pc(&self) -> Option<u64> {
+        self.get(RETURN_ADDRESS_REGISTER)
+    }
+
+    pub fn sp(&self) -> Option<u64> {
+        self.get(STACK_POINTER_REGISTER)
+    }
+}
+
+/// Evaluates the CFI expressions of a frame against its registers
+struct FrameContext<'r, F> {
+    registers: &'r RegisterSet,
+    read_memory: F,
+}
+
+impl<'r, F> EvaluationContext for FrameContext<'r, F>
+where
+    F: Fn(u64) -> Result<u64>,
+{
+    fn register(&self, register: u16) -> Result<u64> {
+        self.registers
+            .get(register)
+            .ok_or(DwarfError::UnknownRegister(register))
+    }
+
+    fn read_memory(&self, address: u64, size: u8) -> Result<u64> {
+        let word = (self.read_memory)(address)?;
+        Ok(match size {
+            0 => 0,
+            1..=7 => word & ((1 << (size * 8)) - 1),
+            _ => word,
+        })
+    }
+}
+
+/// Computes the CFA of a frame from its unwind row
+pub fn frame_cfa<F>(row: &UnwindRow, registers: &RegisterSet, read_memory: F) -> Result<u64>
+where
+    F: Fn(u64) -> Result<u64>,
+{
+    match row.cfa {
+        CfaRule::RegisterOffset { register, offset } => registers
+            .get(register)
+            .map(|value| value.wrapping_add(offset as u64))
+            .ok_or(DwarfError::UnknownRegister(register)),
+        CfaRule::Expression(expression) => {
+            let context = FrameContext {
+                registers,
+                read_memory,
+            };
+            match Evaluator::new(&context, 8).evaluate(expression)? {
+                Location::Memory(address) | Location::Value(address) => Ok(address),
+                _ => Err(DwarfError::Unavailable("call frame address")),
+            }
+        }
+    }
+}
+
+/// Recovers the registers of the caller of a frame from the unwind row of the frame.
+/// Returns the CFA of the frame and the registers of the caller, whose pc is missing
+/// if the frame is the outermost one.
+pub fn unwind_frame<F>(
+    row: &UnwindRow,
+    return_address_register: u16,
+    registers: &RegisterSet,
+    read_memory: F,
+) -> Result<(u64, RegisterSet)>
+where
+    F: Fn(u64) -> Result<u64>,
+{
+    let cfa = frame_cfa(row, registers, &read_memory)?;
+    let context = FrameContext {
+        registers,
+        read_memory: &read_memory,
+    };
+    let evaluate = |expression| {
+        let mut evaluator = Evaluator::new(&context, 8);
+        evaluator.push(cfa);
+        evaluator.evaluate(expression)
+    };
+
+    let mut caller = RegisterSet::default();
+    for register in CALLEE_SAVED_REGISTERS {
+        if let Some(value) = registers.get(register) {
+            caller.set(register, value);
+        }
+    }
+    // The CFA is the stack pointer of the caller before the call
+    caller.set(STACK_POINTER_REGISTER, cfa);
+
+    for (&register, rule) in &row.registers {
+        let value = match rule {
+            RegisterRule::Undefined => None,
+            RegisterRule::SameValue => registers.get(register),
+            RegisterRule::Offset(offset) => Some(read_memory(cfa.wrapping_add(*offset as u64))?),
+            RegisterRule::ValOffset(offset) => Some(cfa.wrapping_add(*offset as u64)),
+            RegisterRule::Register(other) => registers.get(*other),
+            RegisterRule::Expression(expression) => match evaluate(expression)? {
+                Location::Memory(address) | Location::Value(address) => Some(read_memory(address)?),
+                _ => None,
+            },
+            RegisterRule::ValExpression(expression) => match evaluate(expression)? {
+                Location::Memory(value) | Location::Value(value) => Some(value),
+                _ => None,
+            },
+        };
+        match value {
+            Some(value) => caller.set(register, value),
+            None => caller.remove(register),
+        }
+    }
+
+    // The pc of the caller is the return address, unless the frame has no caller
+    match caller.get(return_address_register) {
+        Some(return_address) if row.register(return_address_register).is_some() => {
+            caller.set(RETURN_ADDRESS_REGISTER, return_address)
+        }
+        _ => caller.remove(RETURN_ADDRESS_REGISTER),
+    }
+    Ok((cfa, caller))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_memory(address: u64) -> Result<u64> {
+        match address {
+            0x7ff0 => Ok(0x7fff_0000),
+            0x7ff8 => Ok(0x401234),
+            _ => Err(DwarfError::InvalidMemoryAccess(address)),
+        }
+    }
+
+    #[test]
+    fn test_unwind_frame() {
+        // After `push rbp; mov rbp, rsp`: the CFA is rbp + 16, the return address is
+        // at CFA - 8 and the saved rbp at CFA - 16
+        let row = UnwindRow {
+            start_address: 0x1000,
+            end_address: 0x1010,
+            cfa: CfaRule::RegisterOffset {
+                register: FRAME_POINTER_REGISTER,
+                offset: 16,
+            },
+            registers: BTreeMap::from([
+                (FRAME_POINTER_REGISTER, RegisterRule::Offset(-16)),
+                (RETURN_ADDRESS_REGISTER, RegisterRule::Offset(-8)),
+                (0, RegisterRule::Undefined),
+            ]),
+        };
+        let mut registers = RegisterSet::default();
+        registers.set(0, 42);
+        registers.set(3, 7);
+        registers.set(5, 1);
+        registers.set(FRAME_POINTER_REGISTER, 0x7ff0);
+        registers.set(STACK_POINTER_REGISTER, 0x7fe0);
+        registers.set(RETURN_ADDRESS_REGISTER, 0x1004);
+
+        let (cfa, caller) =
+            unwind_frame(&row, RETURN_ADDRESS_REGISTER, &registers, read_memory).unwrap();
+        assert_eq!(cfa, 0x8000);
+        assert_eq!(caller.pc(), Some(0x401234));
+        assert_eq!(caller.sp(), Some(0x8000));
+        assert_eq!(caller.get(FRAME_POINTER_REGISTER), Some(0x7fff_0000));
+        // rbx is callee saved, rax has no value in the caller and rdi is not preserved
+        assert_eq!(caller.get(3), Some(7));
+        assert_eq!(caller.get(0), None);
+        assert_eq!(caller.get(5), None);
+    }
+
+    #[test]
+    fn test_unwind_outermost_frame() {
+        let row = UnwindRow {
+            start_address: 0x1000,
+            end_address: 0x1010,
+            cfa: CfaRule::RegisterOffset {
+                register: STACK_POINTER_REGISTER,
+                offset: 8,
+            },
+            registers: BTreeMap::from([(RETURN_ADDRESS_REGISTER, RegisterRule::Undefined)]),
+        };
+        let mut registers = RegisterSet::default();
+        registers.set(STACK_POINTER_REGISTER, 0x7fe0);
+        registers.set(RETURN_ADDRESS_REGISTER, 0x1004);
+
+        let (cfa, caller) =
+            unwind_frame(&row, RETURN_ADDRESS_REGISTER, &registers, read_memory).unwrap();
+        assert_eq!(cfa, 0x7fe8);
+        assert_eq!(caller.pc(), None);
+    }
+}
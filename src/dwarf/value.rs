@@ -0,0 +1,873 @@
+use super::constants::*;
+use super::die::{AttributeValue, Die};
+use super::error::{DwarfError, Result};
+use super::expression::{EvaluationContext, Evaluator, Location};
+use super::loclist::location_expression;
+use super::reader::Reader;
+use super::types::{
+    array_dimensions, byte_size, is_rust_language, strip_typedefs, type_name, type_of,
+};
+use super::unit::Unit;
+
+/// Elements of arrays and characters of strings printed before the rest is elided
+pub(super) const PRINT_LIMIT: usize = 200;
+/// Identical consecutive array elements from which on they are printed only once
+const REPEATS_THRESHOLD: usize = 10;
+
+/// The value of a variable, with the address it is stored at unless it is not in memory
+#[derive(Debug, Clone)]
+pub struct VariableValue<'a> {
+    /// `None` is `void`
+    pub variable_type: Option<Die<'a>>,
+    pub bytes: Vec<u8>,
+    pub address: Option<u64>,
+}
+
+/// Reads the value of a variable or parameter at `pc`, an address of the file,
+/// and formats it by its type
+pub fn format_variable<'a, C: EvaluationContext>(
+    unit: &Unit<'a>,
+    variable: &Die<'a>,
+    context: &C,
+    pc: u64,
+) -> Result<String> {
+    if let Some(AttributeValue::String(string)) =
+        unit.inherited_attr(variable, DW_AT_CONST_VALUE)?
+    {
+        return Ok(format!("\"{}\"", string_literal(string.as_bytes())));
+    }
+    match read_variable(unit, variable, context, pc)? {
+        Some(value) => {
+            ValueFormatter::new(unit, context).format(value.variable_type.as_ref(), &value.bytes)
+        }
+        None => Ok("<optimized out>".to_string()),
+    }
+}
+
+/// Reads the value of a variable or parameter at `pc`, an address of the file.
+/// `None` if it is optimized out there.
+pub fn read_variable<'a, C: EvaluationContext>(
+    unit: &Unit<'a>,
+    variable: &Die<'a>,
+    context: &C,
+    pc: u64,
+) -> Result<Option<VariableValue<'a>>> {
+    let variable_type = type_of(unit, variable)?;
+    let location = match unit.inherited_attr(variable, DW_AT_CONST_VALUE)? {
+        Some(AttributeValue::Block(bytes)) => Location::Bytes(bytes),
+        Some(AttributeValue::String(string)) => Location::Bytes(string.as_bytes()),
+        Some(value) => value.udata().map_or(Location::Empty, Location::Value),
+        None => match variable.attr(DW_AT_LOCATION) {
+            Some(value) => match location_expression(unit, value, pc)? {
+                Some(expression) => Evaluator::for_unit(context, unit).evaluate(expression)?,
+                None => Location::Empty,
+            },
+            None => Location::Empty,
+        },
+    };
+    if location == Location::Empty {
+        return Ok(None);
+    }
+
+    let size = match &variable_type {
+        Some(variable_type) => byte_size(unit, variable_type)?.unwrap_or(0),
+        None => 0,
+    };
+    let bytes = read_location(context, &location, size)?;
+    let address = match location {
+        Location::Memory(address) => Some(address),
+        _ => None,
+    };
+    Ok(Some(VariableValue {
+        variable_type,
+        bytes,
+        address,
+    }))
+}
+
+/// Reads the `size` bytes of a value stored at `location`
+pub fn read_location<C: EvaluationContext>(
+    context: &C,
+    location: &Location,
+    size: u64,
+) -> Result<Vec<u8>> {
+    let size = size as usize;
+    let mut bytes = match location {
+        Location::Empty => return Err(DwarfError::Unavailable("optimized out value")),
+        Location::Memory(address) => read_memory(context, *address, size)?,
+        Location::Register(register) => context.register(*register)?.to_le_bytes().to_vec(),
+        Location::Value(value) => value.to_le_bytes().to_vec(),
+        Location::Bytes(bytes) => bytes.to_vec(),
+        Location::ImplicitPointer { .. } => {
+            return Err(DwarfError::Unavailable("optimized out pointer"))
+        }
+        Location::Pieces(pieces) => {
+            let mut bytes = vec![0; size];
+            let mut position = 0;
+            for piece in pieces {
+                let bit_offset = piece.bit_offset as usize;
+                let size_in_bits = piece.size_in_bits as usize;
+                let source_size = (bit_offset + size_in_bits).div_ceil(8);
+                // Empty pieces are usually padding, they are left zeroed
+                let source = match piece.location {
+                    Location::Empty => vec![0; source_size],
+                    _ => read_location(context, &piece.location, source_size as u64)?,
+                };
+                for bit in 0..size_in_bits {
+                    let (from, to) = (bit_offset + bit, position + bit);
+                    if to / 8 >= size {
+                        break;
+                    }
+                    bytes[to / 8] |= (source[from / 8] >> (from % 8) & 1) << (to % 8);
+                }
+                position += size_in_bits;
+            }
+            bytes
+        }
+    };
+    bytes.resize(size, 0);
+    Ok(bytes)
+}
+
+/// Reads `size` bytes of memory at `address`
+pub fn read_memory<C: EvaluationContext>(
+    context: &C,
+    address: u64,
+    size: usize,
+) -> Result<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(size);
+    while bytes.len() < size {
+        let chunk = (size - bytes.len()).min(8);
+        let value = context.read_memory(address + bytes.len() as u64, chunk as u8)?;
+        bytes.extend_from_slice(&value.to_le_bytes()[..chunk]);
+    }
+    Ok(bytes)
+}
+
+/// Reads a NUL terminated string of at most `PRINT_LIMIT` characters at `address`.
+/// Returns whether the string was complete.
+fn read_c_string<C: EvaluationContext>(context: &C, address: u64) -> Result<(Vec<u8>, bool)> {
+    let mut bytes = Vec::new();
+    while bytes.len() < PRINT_LIMIT {
+        let word = context.read_memory(address + bytes.len() as u64, 8)?;
+        for byte in word.to_le_bytes() {
+            if byte == 0 {
+                return Ok((bytes, true));
+            }
+            bytes.push(byte);
+        }
+    }
+    bytes.truncate(PRINT_LIMIT);
+    Ok((bytes, false))
+}
+
+/// Formats a pointer to characters with the NUL terminated string it points to,
+/// e.g. `0x4006f4 "text"`
+pub fn format_c_string<C: EvaluationContext>(context: &C, address: u64) -> String {
+    match read_c_string(context, address) {
+        Ok((string, true)) => format!("0x{:x} \"{}\"", address, string_literal(&string)),
+        Ok((string, false)) => format!("0x{:x} \"{}\"...", address, string_literal(&string)),
+        Err(err) => format!("0x{:x} <error: {}>", address, err),
+    }
+}
+
+pub fn unsigned(bytes: &[u8]) -> u128 {
+    let mut value = [0; 16];
+    let len = bytes.len().min(16);
+    value[..len].copy_from_slice(&bytes[..len]);
+    u128::from_le_bytes(value)
+}
+
+pub fn signed(bytes: &[u8]) -> i128 {
+    let bits = (bytes.len().min(16) * 8) as u32;
+    match bits {
+        0 => 0,
+        // Shifts the sign bit of the value to the top and back
+        _ => (unsigned(bytes) << (128 - bits)) as i128 >> (128 - bits),
+    }
+}
+
+/// Converts an x87 80-bit extended precision float, used for `long double`
+fn extended_to_f64(bytes: &[u8]) -> f64 {
+    let mantissa = u64::from_le_bytes(bytes[..8].try_into().unwrap());
+    let sign_exponent = u16::from_le_bytes([bytes[8], bytes[9]]);
+    let sign = if sign_exponent & 0x8000 != 0 {
+        -1.0
+    } else {
+        1.0
+    };
+    let exponent = (sign_exponent & 0x7fff) as i32;
+
+    let magnitude = match exponent {
+        0x7fff if mantissa << 1 == 0 => f64::INFINITY,
+        0x7fff => f64::NAN,
+        _ => mantissa as f64 * 2f64.powi(exponent - 16383 - 63),
+    };
+    sign * magnitude
+}
+
+/// Escapes a character for a literal quoted with `quote`, like gdb does
+pub fn escape_char(character: u32, quote: char) -> String {
+    match character {
+        0x07 => "\\a".to_string(),
+        0x08 => "\\b".to_string(),
+        0x09 => "\\t".to_string(),
+        0x0a => "\\n".to_string(),
+        0x0b => "\\v".to_string(),
+        0x0c => "\\f".to_string(),
+        0x0d => "\\r".to_string(),
+        0x5c => "\\\\".to_string(),
+        _ if character == quote as u32 => format!("\\{}", quote),
+        0x20..=0x7e => char::from(character as u8).to_string(),
+        0..=0xff => format!("\\{:03o}", character),
+        _ => char::from_u32(character)
+            .map_or_else(|| format!("\\x{:x}", character), |c| c.to_string()),
+    }
+}
+
+fn string_literal(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| escape_char(*byte as u32, '"'))
+        .collect()
+}
+
+pub(super) fn hex(bytes: &[u8]) -> String {
+    format!("0x{:x}", unsigned(bytes))
+}
+
+fn encoding(die: &Die) -> Option<u8> {
+    die.attr(DW_AT_ENCODING)
+        .and_then(|value| value.udata())
+        .map(|encoding| encoding as u8)
+}
+
+/// Whether values of the type `die` are characters, printed as strings in arrays and pointers
+pub fn is_char_type(unit: &Unit, die: Option<&Die>) -> Result<bool> {
+    let die = match strip_typedefs(unit, die.cloned())? {
+        Some(die) if die.tag == DW_TAG_BASE_TYPE => die,
+        _ => return Ok(false),
+    };
+    Ok(byte_size(unit, &die)? == Some(1)
+        && matches!(
+            encoding(&die),
+            Some(DW_ATE_SIGNED_CHAR | DW_ATE_UNSIGNED_CHAR | DW_ATE_UTF | DW_ATE_ASCII)
+        ))
+}
+
+/// Whether values of the type `die` are sign extended, like those of `int` and of enums
+/// with negative enumerators
+pub fn is_signed_type(unit: &Unit, die: Option<&Die>) -> Result<bool> {
+    let die = match strip_typedefs(unit, die.cloned())? {
+        Some(die) => die,
+        None => return Ok(false),
+    };
+    match die.tag {
+        DW_TAG_BASE_TYPE => Ok(matches!(
+            encoding(&die),
+            Some(DW_ATE_SIGNED | DW_ATE_SIGNED_CHAR)
+        )),
+        DW_TAG_ENUMERATION_TYPE => match type_of(unit, &die)? {
+            Some(underlying) => is_signed_type(unit, Some(&underlying)),
+            // Without an underlying type, negative enumerators make the enum signed
+            None => Ok(unit.children(&die)?.iter().any(|enumerator| {
+                matches!(
+                    enumerator.attr(DW_AT_CONST_VALUE),
+                    Some(AttributeValue::Sdata(value)) if *value < 0
+                )
+            })),
+        },
+        _ => Ok(false),
+    }
+}
+
+/// The type and value of a member found in a structure, with its offset in it
+pub type MemberValue<'a> = (Option<Die<'a>>, Vec<u8>, Option<u64>);
+
+/// Formats values by their DWARF type, the way gdb prints them, e.g. `{a = 1, p = 0x0}`
+pub struct ValueFormatter<'u, 'a, C> {
+    pub(super) unit: &'u Unit<'a>,
+    pub(super) context: &'u C,
+    /// Values of Rust units are printed like Rust's `Debug` does, see `super::rust`
+    is_rust: bool,
+}
+
+impl<'u, 'a, C: EvaluationContext> ValueFormatter<'u, 'a, C> {
+    /// Pointers and references are followed through `context`
+    pub fn new(unit: &'u Unit<'a>, context: &'u C) -> Self {
+        let is_rust = is_rust_language(unit).unwrap_or(false);
+        Self {
+            unit,
+            context,
+            is_rust,
+        }
+    }
+
+    /// Formats `bytes`, a value of the type `die`
+    pub fn format(&self, die: Option<&Die<'a>>, bytes: &[u8]) -> Result<String> {
+        let die = match strip_typedefs(self.unit, die.cloned())? {
+            Some(die) => die,
+            None => return Ok("void".to_string()),
+        };
+        if self.is_rust {
+            if let Some(value) = self.format_rust(&die, bytes)? {
+                return Ok(value);
+            }
+        }
+
+        match die.tag {
+            DW_TAG_BASE_TYPE => Ok(self.format_base(&die, bytes)),
+            DW_TAG_POINTER_TYPE => self.format_pointer(&die, bytes),
+            DW_TAG_REFERENCE_TYPE | DW_TAG_RVALUE_REFERENCE_TYPE => {
+                self.format_reference(&die, bytes)
+            }
+            DW_TAG_ARRAY_TYPE => self.format_array(&die, bytes),
+            DW_TAG_STRUCTURE_TYPE | DW_TAG_CLASS_TYPE | DW_TAG_UNION_TYPE => {
+                self.format_struct(&die, bytes)
+            }
+            DW_TAG_ENUMERATION_TYPE => self.format_enum(&die, bytes),
+            _ => Ok(hex(bytes)),
+        }
+    }
+
+    fn format_base(&self, die: &Die, bytes: &[u8]) -> String {
+        let character = |value: u32| escape_char(value, '\'');
+        match encoding(die) {
+            Some(DW_ATE_BOOLEAN) => match unsigned(bytes) {
+                0 => "false".to_string(),
+                1 => "true".to_string(),
+                value => value.to_string(),
+            },
+            Some(DW_ATE_FLOAT) => format_float(bytes),
+            Some(DW_ATE_COMPLEX_FLOAT) => {
+                let (real, imaginary) = bytes.split_at(bytes.len() / 2);
+                format!("{} + {}i", format_float(real), format_float(imaginary))
+            }
+            Some(DW_ATE_SIGNED) => signed(bytes).to_string(),
+            Some(DW_ATE_UNSIGNED) => unsigned(bytes).to_string(),
+            Some(DW_ATE_SIGNED_CHAR) => {
+                let value = signed(bytes);
+                format!("{} '{}'", value, character(value as u8 as u32))
+            }
+            Some(DW_ATE_UNSIGNED_CHAR | DW_ATE_UTF | DW_ATE_ASCII) => {
+                let value = unsigned(bytes);
+                format!("{} '{}'", value, character(value as u32))
+            }
+            _ => hex(bytes),
+        }
+    }
+
+    fn format_pointer(&self, die: &Die<'a>, bytes: &[u8]) -> Result<String> {
+        let address = unsigned(bytes) as u64;
+        let target = type_of(self.unit, die)?;
+        if address == 0 || !is_char_type(self.unit, target.as_ref())? {
+            return Ok(format!("0x{:x}", address));
+        }
+
+        Ok(format_c_string(self.context, address))
+    }
+
+    fn format_reference(&self, die: &Die<'a>, bytes: &[u8]) -> Result<String> {
+        let address = unsigned(bytes) as u64;
+        let target = type_of(self.unit, die)?;
+        let size = match &target {
+            Some(target) => byte_size(self.unit, target)?.unwrap_or(0),
+            None => 0,
+        };
+        let value = match read_memory(self.context, address, size as usize) {
+            Ok(bytes) => self.format(target.as_ref(), &bytes)?,
+            Err(err) => format!("<error: {}>", err),
+        };
+        Ok(format!("@0x{:x}: {}", address, value))
+    }
+
+    fn format_array(&self, die: &Die<'a>, bytes: &[u8]) -> Result<String> {
+        let element = type_of(self.unit, die)?;
+        let element_size = match &element {
+            Some(element) => byte_size(self.unit, element)?.unwrap_or(0),
+            None => 0,
+        };
+        let dimensions = array_dimensions(self.unit, die)?;
+        self.format_elements(element.as_ref(), element_size, &dimensions, bytes)
+    }
+
+    /// Formats the elements of an array with the `dimensions`, innermost last
+    pub fn format_elements(
+        &self,
+        element: Option<&Die<'a>>,
+        element_size: u64,
+        dimensions: &[Option<u64>],
+        bytes: &[u8],
+    ) -> Result<String> {
+        let (count, inner) = match dimensions.split_first() {
+            Some((count, inner)) => (count.unwrap_or(0) as usize, inner),
+            None => return self.format(element, bytes),
+        };
+        let stride = inner
+            .iter()
+            .fold(element_size, |size, count| size * count.unwrap_or(0))
+            as usize;
+
+        if inner.is_empty() && is_char_type(self.unit, element)? {
+            let len = count.min(bytes.len());
+            let string = &bytes[..len];
+            let end = string.iter().position(|byte| *byte == 0).unwrap_or(len);
+            return Ok(format!("\"{}\"", string_literal(&string[..end])));
+        }
+
+        let mut values: Vec<String> = Vec::new();
+        for index in 0..count.min(PRINT_LIMIT) {
+            let value = match bytes.get(index * stride..(index + 1) * stride) {
+                Some(bytes) => self.format_elements(element, element_size, inner, bytes)?,
+                None => "<unavailable>".to_string(),
+            };
+            values.push(value);
+        }
+
+        let mut elements = Vec::new();
+        let mut start = 0;
+        while start < values.len() {
+            let repeats = values[start..]
+                .iter()
+                .take_while(|value| **value == values[start])
+                .count();
+            if repeats >= REPEATS_THRESHOLD {
+                elements.push(format!("{} <repeats {} times>", values[start], repeats));
+                start += repeats;
+            } else {
+                elements.push(values[start].clone());
+                start += 1;
+            }
+        }
+        if count > PRINT_LIMIT {
+            elements.push("...".to_string());
+        }
+        if self.is_rust {
+            Ok(format!("[{}]", elements.join(", ")))
+        } else {
+            Ok(format!("{{{}}}", elements.join(", ")))
+        }
+    }
+
+    /// The offset of a member or base class in the structure containing it
+    pub fn member_offset(&self, member: &Die<'a>) -> Result<u64> {
+        match member.attr(DW_AT_DATA_MEMBER_LOCATION) {
+            None => Ok(0),
+            Some(value) => match value.expression() {
+                Some(expression) => {
+                    // The expression moves the address of the structure, pushed first, to the member
+                    let mut evaluator = Evaluator::for_unit(self.context, self.unit);
+                    evaluator.push(0);
+                    match evaluator.evaluate(expression)? {
+                        Location::Memory(offset) => Ok(offset),
+                        _ => Err(DwarfError::Unavailable("member location")),
+                    }
+                }
+                None => Ok(value.udata().unwrap_or(0)),
+            },
+        }
+    }
+
+    fn format_struct(&self, die: &Die<'a>, bytes: &[u8]) -> Result<String> {
+        if die
+            .attr(DW_AT_DECLARATION)
+            .is_some_and(|value| value.flag())
+        {
+            return Ok("<incomplete type>".to_string());
+        }
+
+        let mut fields = Vec::new();
+        for child in self.unit.children(die)? {
+            if child.tag == DW_TAG_VARIANT_PART {
+                if let Some(member) = self.active_variant(&child, bytes)? {
+                    let (member_type, value) = self.member_value(&member, bytes)?;
+                    let value = match value {
+                        Some(bytes) => self.format(member_type.as_ref(), bytes)?,
+                        None => "<unavailable>".to_string(),
+                    };
+                    fields.push(format!("{} = {}", member.name().unwrap_or_default(), value));
+                }
+                continue;
+            }
+            let is_static = child.attr(DW_AT_EXTERNAL).is_some_and(|value| value.flag())
+                || child
+                    .attr(DW_AT_DECLARATION)
+                    .is_some_and(|value| value.flag());
+            if !matches!(child.tag, DW_TAG_MEMBER | DW_TAG_INHERITANCE) || is_static {
+                continue;
+            }
+
+            let member_type = type_of(self.unit, &child)?;
+            let value = match child.attr(DW_AT_BIT_SIZE).and_then(|value| value.udata()) {
+                Some(bit_size) => {
+                    let value =
+                        self.bitfield_value(&child, member_type.as_ref(), bit_size, bytes)?;
+                    self.format(member_type.as_ref(), &value)?
+                }
+                None => match self.member_value(&child, bytes)?.1 {
+                    Some(bytes) => self.format(member_type.as_ref(), bytes)?,
+                    None => "<unavailable>".to_string(),
+                },
+            };
+
+            fields.push(match (child.tag, child.name()) {
+                (DW_TAG_INHERITANCE, _) => {
+                    format!(
+                        "<{}> = {}",
+                        type_name(self.unit, member_type.as_ref())?,
+                        value
+                    )
+                }
+                (_, Some(name)) => format!("{} = {}", name, value),
+                // Members of anonymous structs and unions are accessed as if they were in this one
+                (_, None) => value,
+            });
+        }
+        Ok(format!("{{{}}}", fields.join(", ")))
+    }
+
+    /// The type of a member and its bytes in `bytes`, the value of the structure containing
+    /// it. The bytes are `None` if the member is outside of them.
+    pub(super) fn member_value<'b>(
+        &self,
+        member: &Die<'a>,
+        bytes: &'b [u8],
+    ) -> Result<(Option<Die<'a>>, Option<&'b [u8]>)> {
+        let member_type = type_of(self.unit, member)?;
+        let offset = self.member_offset(member)? as usize;
+        let size = match &member_type {
+            Some(member_type) => byte_size(self.unit, member_type)?.unwrap_or(0),
+            None => 0,
+        } as usize;
+        Ok((member_type, bytes.get(offset..offset + size)))
+    }
+
+    /// The member of the variant of `variant_part` that holds the value `bytes` of the
+    /// structure containing the variant part, chosen by the discriminant. The variant
+    /// without a discriminant value is used if no other matches, `None` if there is none.
+    pub(super) fn active_variant(
+        &self,
+        variant_part: &Die<'a>,
+        bytes: &[u8],
+    ) -> Result<Option<Die<'a>>> {
+        let discriminant = match self.unit.entry_by_attr(variant_part, DW_AT_DISCR)? {
+            Some(member) => match self.member_value(&member, bytes)? {
+                (member_type, Some(value)) => Some((
+                    unsigned(value),
+                    is_signed_type(self.unit, member_type.as_ref())?,
+                    value.len(),
+                )),
+                _ => None,
+            },
+            None => None,
+        };
+
+        let mut default = None;
+        for variant in self.unit.children(variant_part)? {
+            if variant.tag != DW_TAG_VARIANT {
+                continue;
+            }
+            let selected = match (
+                variant.attr(DW_AT_DISCR_VALUE),
+                variant.attr(DW_AT_DISCR_LIST),
+                discriminant,
+            ) {
+                (Some(value), _, Some((discriminant, _, size))) => discriminant_value(value)
+                    .is_some_and(|value| truncate(value, size) == discriminant),
+                (None, Some(list), Some((discriminant, signed, size))) => match list.expression() {
+                    Some(list) => in_discriminant_list(list, discriminant, signed, size)?,
+                    None => false,
+                },
+                (None, None, _) => {
+                    default = default.or_else(|| Some(variant.clone()));
+                    false
+                }
+                _ => false,
+            };
+            if selected {
+                return self.variant_member(&variant);
+            }
+        }
+        match default {
+            Some(variant) => self.variant_member(&variant),
+            None => Ok(None),
+        }
+    }
+
+    fn variant_member(&self, variant: &Die<'a>) -> Result<Option<Die<'a>>> {
+        Ok(self
+            .unit
+            .children(variant)?
+            .into_iter()
+            .find(|child| child.tag == DW_TAG_MEMBER))
+    }
+
+    /// The member `name` of the structure `die` whose value is `bytes`, also looking in
+    /// anonymous structures and unions, base classes and the active variant of a Rust enum.
+    /// Returns the type and value of the member with its offset in the structure, which
+    /// bitfields have none.
+    pub fn member_named(
+        &self,
+        die: &Die<'a>,
+        bytes: &[u8],
+        name: &str,
+    ) -> Result<Option<MemberValue<'a>>> {
+        for child in self.unit.children(die)? {
+            let nested = match child.tag {
+                DW_TAG_MEMBER if child.name() == Some(name) => {
+                    let member_type = type_of(self.unit, &child)?;
+                    if let Some(bit_size) = child.attr(DW_AT_BIT_SIZE).and_then(|v| v.udata()) {
+                        let value =
+                            self.bitfield_value(&child, member_type.as_ref(), bit_size, bytes)?;
+                        return Ok(Some((member_type, value, None)));
+                    }
+                    let offset = self.member_offset(&child)?;
+                    return match self.member_value(&child, bytes)? {
+                        (member_type, Some(value)) => {
+                            Ok(Some((member_type, value.to_vec(), Some(offset))))
+                        }
+                        (_, None) => {
+                            Err(DwarfError::Unavailable("member outside of its structure"))
+                        }
+                    };
+                }
+                DW_TAG_MEMBER if child.name().is_none() => child,
+                DW_TAG_INHERITANCE => child,
+                DW_TAG_VARIANT_PART => match self.active_variant(&child, bytes)? {
+                    Some(member) => member,
+                    None => continue,
+                },
+                _ => continue,
+            };
+
+            let offset = self.member_offset(&nested)?;
+            let (nested_type, nested_bytes) = match self.member_value(&nested, bytes)? {
+                (nested_type, Some(nested_bytes)) => (nested_type, nested_bytes),
+                (_, None) => continue,
+            };
+            let nested_type = match strip_typedefs(self.unit, nested_type)? {
+                Some(nested_type) => nested_type,
+                None => continue,
+            };
+            if let Some((member_type, value, member_offset)) =
+                self.member_named(&nested_type, nested_bytes, name)?
+            {
+                let member_offset = member_offset.map(|member_offset| offset + member_offset);
+                return Ok(Some((member_type, value, member_offset)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// The offset in bits of the bitfield `member` from the start of its structure
+    pub fn bit_position(
+        &self,
+        member: &Die<'a>,
+        member_type: Option<&Die<'a>>,
+        bit_size: u64,
+    ) -> Result<u64> {
+        Ok(
+            match member.attr(DW_AT_DATA_BIT_OFFSET).and_then(|v| v.udata()) {
+                Some(position) => position,
+                None => {
+                    let offset = self.member_offset(member)?;
+                    match member
+                        .attr(DW_AT_BIT_OFFSET)
+                        .and_then(|value| value.udata())
+                    {
+                        // Before DWARF 4 the offset counts from the most significant bit of the storage
+                        Some(bit_offset) => {
+                            let storage_size = match member.attr(DW_AT_BYTE_SIZE) {
+                                Some(size) => size.udata().unwrap_or(0),
+                                None => match member_type {
+                                    Some(member_type) => {
+                                        byte_size(self.unit, member_type)?.unwrap_or(0)
+                                    }
+                                    None => 0,
+                                },
+                            };
+                            (offset + storage_size) * 8 - bit_offset - bit_size
+                        }
+                        None => offset * 8,
+                    }
+                }
+            },
+        )
+    }
+
+    /// The value of a bitfield, sign extended into the bytes of its type
+    fn bitfield_value(
+        &self,
+        member: &Die<'a>,
+        member_type: Option<&Die<'a>>,
+        bit_size: u64,
+        bytes: &[u8],
+    ) -> Result<Vec<u8>> {
+        let position = self.bit_position(member, member_type, bit_size)?;
+        let mut value: u128 = 0;
+        for bit in 0..bit_size.min(128) {
+            let from = (position + bit) as usize;
+            let set = bytes.get(from / 8).map_or(0, |byte| byte >> (from % 8) & 1);
+            value |= (set as u128) << bit;
+        }
+        if bit_size < 128
+            && is_signed_type(self.unit, member_type)?
+            && value >> (bit_size - 1) & 1 == 1
+        {
+            value |= u128::MAX << bit_size;
+        }
+
+        let size = match member_type {
+            Some(member_type) => byte_size(self.unit, member_type)?.unwrap_or(0),
+            None => 0,
+        } as usize;
+        Ok(value.to_le_bytes()[..size.min(16)].to_vec())
+    }
+
+    fn format_enum(&self, die: &Die<'a>, bytes: &[u8]) -> Result<String> {
+        let value = if is_signed_type(self.unit, Some(die))? {
+            signed(bytes)
+        } else {
+            unsigned(bytes) as i128
+        };
+
+        for enumerator in self.unit.children(die)? {
+            let enumerator_value = match enumerator.attr(DW_AT_CONST_VALUE) {
+                Some(AttributeValue::Sdata(value)) => *value as i128,
+                Some(AttributeValue::Udata(value)) => *value as i128,
+                _ => continue,
+            };
+            if enumerator_value != value {
+                continue;
+            }
+            let name = enumerator.name().unwrap_or_default();
+            return Ok(match die.name() {
+                // Enumerators of C++ `enum class` are scoped
+                Some(enum_name) if die.attr(DW_AT_ENUM_CLASS).is_some_and(|v| v.flag()) => {
+                    format!("{}::{}", enum_name, name)
+                }
+                _ => name.to_string(),
+            });
+        }
+        Ok(value.to_string())
+    }
+}
+
+/// A `DW_AT_discr_value`, as the bits of the discriminant
+fn discriminant_value(value: &AttributeValue) -> Option<u128> {
+    match value {
+        AttributeValue::Sdata(value) => Some(*value as i128 as u128),
+        // Discriminants wider than 64 bits are given as blocks
+        AttributeValue::Block(bytes) => Some(unsigned(bytes)),
+        value => value.udata().map(u128::from),
+    }
+}
+
+/// Keeps the low `size` bytes of `value`
+fn truncate(value: u128, size: usize) -> u128 {
+    match size {
+        0..=15 => value & ((1 << (size * 8)) - 1),
+        _ => value,
+    }
+}
+
+/// Whether `discriminant`, of `size` bytes, is one of the labels or in one of the ranges
+/// of a `DW_AT_discr_list`
+fn in_discriminant_list(
+    list: &[u8],
+    discriminant: u128,
+    is_signed: bool,
+    size: usize,
+) -> Result<bool> {
+    let mut reader = Reader::new(list);
+    let read_value = |reader: &mut Reader| -> Result<i128> {
+        Ok(if is_signed {
+            reader.read_sleb128()? as i128
+        } else {
+            reader.read_uleb128()? as i128
+        })
+    };
+    let discriminant = if is_signed {
+        signed(&discriminant.to_le_bytes()[..size.min(16)])
+    } else {
+        discriminant as i128
+    };
+    while !reader.is_empty() {
+        let descriptor = reader.read_u8()?;
+        let low = read_value(&mut reader)?;
+        let high = match descriptor {
+            DW_DSC_RANGE => read_value(&mut reader)?,
+            _ => low,
+        };
+        if (low..=high).contains(&discriminant) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+pub fn format_float(bytes: &[u8]) -> String {
+    match float_value(bytes) {
+        // Single precision floats are printed with the digits they have
+        Some(_) if bytes.len() == 4 => f32::from_le_bytes(bytes.try_into().unwrap()).to_string(),
+        Some(value) => value.to_string(),
+        None => hex(bytes),
+    }
+}
+
+/// The value of a float of 4, 8 or, for `long double`, 10 or 16 bytes
+pub fn float_value(bytes: &[u8]) -> Option<f64> {
+    match bytes.len() {
+        4 => Some(f32::from_le_bytes(bytes.try_into().unwrap()) as f64),
+        8 => Some(f64::from_le_bytes(bytes.try_into().unwrap())),
+        10 | 16 => Some(extended_to_f64(bytes)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_integers() {
+        assert_eq!(signed(&[0xff]), -1);
+        assert_eq!(signed(&[0xfe, 0xff, 0xff, 0xff]), -2);
+        assert_eq!(signed(&[0x7f, 0x00]), 127);
+        assert_eq!(unsigned(&[0xff, 0xff]), 0xffff);
+    }
+
+    #[test]
+    fn test_extended_to_f64() {
+        // 1.5 and -2.0 as x87 extended precision values
+        let one_and_a_half = [0, 0, 0, 0, 0, 0, 0, 0xc0, 0xff, 0x3f];
+        let minus_two = [0, 0, 0, 0, 0, 0, 0, 0x80, 0x00, 0xc0];
+        assert_eq!(extended_to_f64(&one_and_a_half), 1.5);
+        assert_eq!(extended_to_f64(&minus_two), -2.0);
+    }
+
+    #[test]
+    fn test_discriminants() {
+        assert_eq!(
+            discriminant_value(&AttributeValue::Sdata(-1)).map(|v| truncate(v, 1)),
+            Some(0xff)
+        );
+        assert_eq!(truncate(0x1234, 1), 0x34);
+        // A label 2 and the range 5 to 7
+        let list = [DW_DSC_LABEL, 2, DW_DSC_RANGE, 5, 7];
+        assert!(in_discriminant_list(&list, 2, false, 4).unwrap());
+        assert!(in_discriminant_list(&list, 6, false, 4).unwrap());
+        assert!(!in_discriminant_list(&list, 4, false, 4).unwrap());
+        // The range -2 to -1 of a signed byte
+        let list = [DW_DSC_RANGE, 0x7e, 0x7f];
+        assert!(in_discriminant_list(&list, 0xff, true, 1).unwrap());
+        assert!(!in_discriminant_list(&list, 0x01, true, 1).unwrap());
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(string_literal(b"a\"b\n\x01"), "a\\\"b\\n\\001");
+        assert_eq!(escape_char('\'' as u32, '\''), "\\'");
+        assert_eq!(escape_char(0, '\''), "\\000");
+    }
+}
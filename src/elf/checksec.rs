@@ -0,0 +1,295 @@
+use std::fmt::Display;
+
+use super::dynamic::{
+    dynamic_entries, dynamic_strings, DynamicEntry, DF_1_NOW, DF_1_PIE, DF_BIND_NOW, DT_BIND_NOW,
+    DT_FLAGS, DT_FLAGS_1, DT_RPATH, DT_RUNPATH,
+};
+use super::file::ElfFile;
+use super::header::ElfType;
+use super::note::{parse_notes, Note, GNU_NOTE_NAME, NT_GNU_PROPERTY_TYPE_0};
+use super::program::{SegmentFlags, SegmentType};
+
+const STACK_CHK_FAIL: &str = "__stack_chk_fail";
+const FORTIFY_SUFFIX: &str = "_chk";
+const GNU_PROPERTY_SECTION: &str = ".note.gnu.property";
+const GNU_PROPERTY_X86_FEATURE_1_AND: u32 = 0xc0000002;
+const GNU_PROPERTY_X86_FEATURE_1_IBT: u32 = 0x1;
+const GNU_PROPERTY_X86_FEATURE_1_SHSTK: u32 = 0x2;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pie {
+    /// Loaded at a fixed address
+    Disabled,
+    Enabled,
+    /// A shared object, which is always position independent
+    SharedObject,
+    /// A relocatable object file, not yet linked
+    Relocatable,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Relro {
+    None,
+    /// The GOT is made read-only after relocation, except the lazily bound PLT slots
+    Partial,
+    /// All relocations are processed at load time, so the whole GOT is read-only
+    Full,
+}
+
+/// The exploit mitigations an ELF file is built with, like the `checksec` script reports them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HardeningReport {
+    pub pie: Pie,
+    /// The stack is not executable, `PT_GNU_STACK` without the execute flag
+    pub nx: bool,
+    pub relro: Relro,
+    /// `__stack_chk_fail` is referenced
+    pub stack_canary: bool,
+    /// The fortified `*_chk` variants of libc functions that are referenced
+    pub fortified_functions: Vec<String>,
+    /// Indirect branch tracking, from the GNU property note
+    pub ibt: bool,
+    /// Shadow stack, from the GNU property note
+    pub shstk: bool,
+    pub rpath: Vec<String>,
+    pub runpath: Vec<String>,
+    /// The file has no `.symtab`
+    pub stripped: bool,
+    /// The file has `.debug_info`
+    pub debug_info: bool,
+}
+
+/// Creates the hardening report of the file
+pub fn checksec(elf_file: &ElfFile) -> HardeningReport {
+    let dynamic = dynamic_entries(elf_file);
+
+    let pie = match elf_file.elf_type() {
+        ElfType::Rel => Pie::Relocatable,
+        ElfType::Dyn if is_executable(elf_file, &dynamic) => Pie::Enabled,
+        ElfType::Dyn => Pie::SharedObject,
+        _ => Pie::Disabled,
+    };
+
+    // Without PT_GNU_STACK the kernel falls back to an executable stack
+    let nx = elf_file
+        .get_program_header_by_type(SegmentType::GnuStack)
+        .map(|header| !header.flags().contains(SegmentFlags::EXECUTE))
+        .unwrap_or(false);
+
+    let has_gnu_relro = elf_file
+        .get_program_header_by_type(SegmentType::GnuRelro)
+        .is_some();
+    let relro = relro(has_gnu_relro, &dynamic);
+
+    let symbol_names: Vec<&str> = elf_file
+        .dynamic_symbols
+        .iter()
+        .chain(elf_file.symbols.iter())
+        .map(|symbol| symbol.name)
+        .collect();
+    let stack_canary = symbol_names.contains(&STACK_CHK_FAIL);
+    let fortified_functions = fortified_functions(&symbol_names);
+
+    let (ibt, shstk) = match elf_file.get_section_by_name(GNU_PROPERTY_SECTION) {
+        Some(section) => x86_features(&parse_notes(section.bytes(), section.addr_align())),
+        None => (false, false),
+    };
+
+    HardeningReport {
+        pie,
+        nx,
+        relro,
+        stack_canary,
+        fortified_functions,
+        ibt,
+        shstk,
+        rpath: to_strings(dynamic_strings(elf_file, DT_RPATH)),
+        runpath: to_strings(dynamic_strings(elf_file, DT_RUNPATH)),
+        stripped: elf_file.get_section_by_name(".symtab").is_none(),
+        debug_info: elf_file.get_section_by_name(".debug_info").is_some(),
+    }
+}
+
+/// Position independent executables are marked with `DF_1_PIE` by modern linkers,
+/// older ones can only be told apart from shared objects by having an interpreter.
+fn is_executable(elf_file: &ElfFile, dynamic: &[DynamicEntry]) -> bool {
+    let pie_flag = dynamic
+        .iter()
+        .any(|entry| entry.tag == DT_FLAGS_1 && entry.value & DF_1_PIE != 0);
+    pie_flag
+        || elf_file
+            .get_program_header_by_type(SegmentType::Interp)
+            .is_some()
+}
+
+fn relro(has_gnu_relro: bool, dynamic: &[DynamicEntry]) -> Relro {
+    if !has_gnu_relro {
+        return Relro::None;
+    }
+    let bind_now = dynamic.iter().any(|entry| match entry.tag {
+        DT_BIND_NOW => true,
+        DT_FLAGS => entry.value & DF_BIND_NOW != 0,
+        DT_FLAGS_1 => entry.value & DF_1_NOW != 0,
+        _ => false,
+    });
+    if bind_now {
+        Relro::Full
+    } else {
+        Relro::Partial
+    }
+}
+
+/// The fortified variants are named like `__printf_chk`, excluding `__stack_chk_fail`
+fn fortified_functions(symbol_names: &[&str]) -> Vec<String> {
+    let mut functions: Vec<String> = symbol_names
+        .iter()
+        .filter(|name| name.starts_with("__") && name.ends_with(FORTIFY_SUFFIX))
+        .map(|name| name.to_string())
+        .collect();
+    functions.sort();
+    functions.dedup();
+    functions
+}
+
+/// Returns whether IBT and SHSTK are enabled according to the
+/// `GNU_PROPERTY_X86_FEATURE_1_AND` property of the GNU property notes.
+fn x86_features(notes: &[Note]) -> (bool, bool) {
+    let mut features = 0;
+    for note in notes
+        .iter()
+        .filter(|note| note.name == GNU_NOTE_NAME && note.n_type == NT_GNU_PROPERTY_TYPE_0)
+    {
+        // Each property is a type, the size of its data and the data padded to 8 bytes
+        let mut offset = 0;
+        while offset + 8 <= note.desc.len() {
+            let pr_type = u32::from_le_bytes(note.desc[offset..offset + 4].try_into().unwrap());
+            let pr_datasz =
+                u32::from_le_bytes(note.desc[offset + 4..offset + 8].try_into().unwrap()) as usize;
+            let data = match note.desc.get(offset + 8..offset + 8 + pr_datasz) {
+                Some(data) => data,
+                None => break,
+            };
+            if pr_type == GNU_PROPERTY_X86_FEATURE_1_AND && data.len() >= 4 {
+                features |= u32::from_le_bytes(data[..4].try_into().unwrap());
+            }
+            offset += 8 + ((pr_datasz + 7) & !7);
+        }
+    }
+    (
+        features & GNU_PROPERTY_X86_FEATURE_1_IBT != 0,
+        features & GNU_PROPERTY_X86_FEATURE_1_SHSTK != 0,
+    )
+}
+
+fn to_strings(strings: Vec<&str>) -> Vec<String> {
+    strings.into_iter().map(String::from).collect()
+}
+
+fn enabled(value: bool) -> &'static str {
+    if value {
+        "enabled"
+    } else {
+        "disabled"
+    }
+}
+
+impl Display for HardeningReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let pie = match self.pie {
+            Pie::Disabled => "disabled",
+            Pie::Enabled => "enabled",
+            Pie::SharedObject => "shared object",
+            Pie::Relocatable => "relocatable object",
+        };
+        let relro = match self.relro {
+            Relro::None => "none",
+            Relro::Partial => "partial",
+            Relro::Full => "full",
+        };
+        let fortify = if self.fortified_functions.is_empty() {
+            String::from("disabled")
+        } else {
+            format!("enabled ({})", self.fortified_functions.join(", "))
+        };
+        let paths = |paths: &[String]| {
+            if paths.is_empty() {
+                String::from("none")
+            } else {
+                paths.join(":")
+            }
+        };
+
+        writeln!(f, "PIE:          {}", pie)?;
+        writeln!(f, "NX:           {}", enabled(self.nx))?;
+        writeln!(f, "RELRO:        {}", relro)?;
+        writeln!(f, "Stack canary: {}", enabled(self.stack_canary))?;
+        writeln!(f, "FORTIFY:      {}", fortify)?;
+        writeln!(f, "IBT:          {}", enabled(self.ibt))?;
+        writeln!(f, "SHSTK:        {}", enabled(self.shstk))?;
+        writeln!(f, "RPATH:        {}", paths(&self.rpath))?;
+        writeln!(f, "RUNPATH:      {}", paths(&self.runpath))?;
+        writeln!(
+            f,
+            "Stripped:     {}",
+            if self.stripped { "yes" } else { "no" }
+        )?;
+        write!(
+            f,
+            "Debug info:   {}",
+            if self.debug_info { "yes" } else { "no" }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relro() {
+        let entry = |tag, value| DynamicEntry { tag, value };
+        assert_eq!(relro(false, &[entry(DT_BIND_NOW, 0)]), Relro::None);
+        assert_eq!(relro(true, &[]), Relro::Partial);
+        assert_eq!(relro(true, &[entry(DT_FLAGS, DF_BIND_NOW)]), Relro::Full);
+        assert_eq!(relro(true, &[entry(DT_FLAGS_1, DF_1_NOW)]), Relro::Full);
+        assert_eq!(relro(true, &[entry(DT_FLAGS_1, DF_1_PIE)]), Relro::Partial);
+    }
+
+    #[test]
+    fn test_fortified_functions() {
+        let names = [
+            "__printf_chk",
+            "__stack_chk_fail",
+            "__memcpy_chk",
+            "puts",
+            "__printf_chk",
+        ];
+        assert_eq!(
+            fortified_functions(&names),
+            vec![String::from("__memcpy_chk"), String::from("__printf_chk")]
+        );
+    }
+
+    #[test]
+    fn test_x86_features() {
+        // A GNU_PROPERTY_X86_ISA_1_NEEDED property followed by FEATURE_1_AND with IBT and SHSTK
+        let mut desc = Vec::new();
+        desc.extend_from_slice(&0xc0008002u32.to_le_bytes());
+        desc.extend_from_slice(&4u32.to_le_bytes());
+        desc.extend_from_slice(&[1, 0, 0, 0, 0, 0, 0, 0]);
+        desc.extend_from_slice(&GNU_PROPERTY_X86_FEATURE_1_AND.to_le_bytes());
+        desc.extend_from_slice(&4u32.to_le_bytes());
+        desc.extend_from_slice(&[3, 0, 0, 0, 0, 0, 0, 0]);
+        let note = |desc| Note {
+            name: GNU_NOTE_NAME,
+            n_type: NT_GNU_PROPERTY_TYPE_0,
+            desc,
+        };
+        assert_eq!(x86_features(&[note(&desc)]), (true, true));
+
+        let mut ibt_only = desc.clone();
+        ibt_only[24] = GNU_PROPERTY_X86_FEATURE_1_IBT as u8;
+        assert_eq!(x86_features(&[note(&ibt_only)]), (true, false));
+        assert_eq!(x86_features(&[]), (false, false));
+    }
+}
@@ -0,0 +1,244 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::file::ElfFile;
+use super::note::{parse_notes, GNU_NOTE_NAME, NT_GNU_BUILD_ID};
+use super::parser::ElfParser;
+
+/// The directory distributions install separate debug info into
+pub const DEFAULT_DEBUG_FILE_DIRECTORY: &str = "/usr/lib/debug";
+
+const BUILD_ID_DIRECTORY: &str = ".build-id";
+const DEBUG_FILE_SUFFIX: &str = ".debug";
+const DEBUGLINK_SECTION: &str = ".gnu_debuglink";
+const BUILD_ID_SECTION: &str = ".note.gnu.build-id";
+
+/// Contents of the `.gnu_debuglink` section, the file name of the separate
+/// debug file and the CRC32 checksum of its contents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebugLink<'a> {
+    pub file_name: &'a str,
+    pub crc: u32,
+}
+
+/// Returns the build-id of the file from its NT_GNU_BUILD_ID note, if it has one.
+pub fn build_id<'a>(elf_file: &ElfFile<'a>) -> Option<&'a [u8]> {
+    let section = elf_file.get_section_by_name(BUILD_ID_SECTION)?;
+    parse_notes(section.bytes(), section.addr_align())
+        .into_iter()
+        .find(|note| note.name == GNU_NOTE_NAME && note.n_type == NT_GNU_BUILD_ID)
+        .map(|note| note.desc)
+}
+
+/// Returns the parsed `.gnu_debuglink` section of the file, if it has one.
+pub fn debug_link<'a>(elf_file: &ElfFile<'a>) -> Option<DebugLink<'a>> {
+    let section = elf_file.get_section_by_name(DEBUGLINK_SECTION)?;
+    parse_debug_link(section.bytes())
+}
+
+/// The section holds a NUL terminated file name, padded to a 4 byte boundary,
+/// followed by the CRC32 of the debug file.
+fn parse_debug_link(bytes: &[u8]) -> Option<DebugLink<'_>> {
+    let name_end = bytes.iter().position(|byte| *byte == 0)?;
+    let file_name = std::str::from_utf8(&bytes[..name_end]).ok()?;
+    let crc_offset = (name_end + 1 + 3) & !3;
+    let crc_bytes = bytes.get(crc_offset..crc_offset + 4)?;
+    Some(DebugLink {
+        file_name,
+        crc: u32::from_le_bytes(crc_bytes.try_into().unwrap()),
+    })
+}
+
+/// Path of the debug file for a build-id relative to a debug file directory,
+/// e.g. `.build-id/ab/cdef0123.debug`
+pub fn build_id_path(build_id: &[u8]) -> Option<PathBuf> {
+    let (first, rest) = build_id.split_first()?;
+    if rest.is_empty() {
+        return None;
+    }
+    let rest: String = rest.iter().map(|byte| format!("{:02x}", byte)).collect();
+    Some(
+        Path::new(BUILD_ID_DIRECTORY)
+            .join(format!("{:02x}", first))
+            .join(format!("{}{}", rest, DEBUG_FILE_SUFFIX)),
+    )
+}
+
+const fn make_crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                0xedb88320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+const CRC32_TABLE: [u32; 256] = make_crc32_table();
+
+/// The CRC32 used for `.gnu_debuglink`, which is the same as the one used by zlib
+pub fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0u32, |crc, byte| {
+        CRC32_TABLE[((crc ^ *byte as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+/// Locates the separate debug file of a stripped executable, searching the same places as gdb.
+/// 1. `<dir>/.build-id/xx/yyyy.debug` for each search directory, using the build-id note
+/// 2. `.gnu_debuglink`, looked up next to the executable, in its `.debug` subdirectory and
+///    in `<dir>/<executable directory>` for each search directory, verified by CRC32
+#[derive(Debug, Clone)]
+pub struct DebugFileLocator {
+    search_dirs: Vec<PathBuf>,
+}
+
+impl Default for DebugFileLocator {
+    fn default() -> Self {
+        Self::new(vec![PathBuf::from(DEFAULT_DEBUG_FILE_DIRECTORY)])
+    }
+}
+
+impl DebugFileLocator {
+    pub fn new(search_dirs: Vec<PathBuf>) -> Self {
+        Self { search_dirs }
+    }
+
+    pub fn search_dirs(&self) -> &[PathBuf] {
+        &self.search_dirs
+    }
+
+    pub fn add_search_dir(&mut self, dir: PathBuf) {
+        self.search_dirs.push(dir);
+    }
+
+    pub fn locate(&self, executable_path: &Path, elf_file: &ElfFile) -> Option<PathBuf> {
+        if let Some(build_id) = build_id(elf_file) {
+            if let Some(path) = self.locate_by_build_id(build_id) {
+                return Some(path);
+            }
+        }
+
+        let debug_link = debug_link(elf_file)?;
+        self.locate_by_debug_link(executable_path, &debug_link)
+    }
+
+    pub fn locate_by_build_id(&self, build_id: &[u8]) -> Option<PathBuf> {
+        let relative_path = build_id_path(build_id)?;
+        self.search_dirs
+            .iter()
+            .map(|dir| dir.join(&relative_path))
+            .find(|path| path.is_file() && has_build_id(path, build_id))
+    }
+
+    pub fn locate_by_debug_link(
+        &self,
+        executable_path: &Path,
+        debug_link: &DebugLink,
+    ) -> Option<PathBuf> {
+        let executable_path =
+            fs::canonicalize(executable_path).unwrap_or_else(|_| executable_path.to_path_buf());
+        let executable_dir = executable_path.parent().unwrap_or_else(|| Path::new("/"));
+
+        let mut candidates = vec![
+            executable_dir.join(debug_link.file_name),
+            executable_dir.join(".debug").join(debug_link.file_name),
+        ];
+        // Joining an absolute path would replace the search directory
+        let relative_executable_dir = executable_dir.strip_prefix("/").unwrap_or(executable_dir);
+        for dir in &self.search_dirs {
+            candidates.push(dir.join(relative_executable_dir).join(debug_link.file_name));
+        }
+
+        candidates.into_iter().find(|path| {
+            // The executable itself may be named like its debug link
+            path != &executable_path
+                && fs::read(path)
+                    .map(|bytes| crc32(&bytes) == debug_link.crc)
+                    .unwrap_or(false)
+        })
+    }
+}
+
+fn has_build_id(path: &Path, expected: &[u8]) -> bool {
+    let mut parser = ElfParser::new(path);
+    let elf_file = parser.parse_elf_file();
+    build_id(&elf_file) == Some(expected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf43926);
+        assert_eq!(
+            crc32(b"The quick brown fox jumps over the lazy dog"),
+            0x414fa339
+        );
+    }
+
+    #[test]
+    fn test_parse_debug_link() {
+        let mut bytes = b"entry_point.debug\0".to_vec();
+        bytes.extend_from_slice(&[0, 0]);
+        bytes.extend_from_slice(&0x12345678u32.to_le_bytes());
+
+        let debug_link = parse_debug_link(&bytes).unwrap();
+        assert_eq!(debug_link.file_name, "entry_point.debug");
+        assert_eq!(debug_link.crc, 0x12345678);
+
+        assert_eq!(parse_debug_link(b"truncated\0"), None);
+    }
+
+    #[test]
+    fn test_build_id_path() {
+        let path = build_id_path(&[0xab, 0xcd, 0xef, 0x01]).unwrap();
+        assert_eq!(path, Path::new(".build-id/ab/cdef01.debug"));
+        assert_eq!(build_id_path(&[0xab]), None);
+    }
+
+    #[test]
+    fn test_locate_by_debug_link_verifies_crc() {
+        let dir = std::env::temp_dir().join(format!("tamandua-debuglink-{}", std::process::id()));
+        let executable_dir = dir.join("bin");
+        fs::create_dir_all(executable_dir.join(".debug")).unwrap();
+        let executable_path = executable_dir.join("program");
+        fs::write(&executable_path, b"stripped").unwrap();
+        fs::write(executable_dir.join(".debug/program.debug"), b"debug info").unwrap();
+
+        let locator = DebugFileLocator::new(Vec::new());
+        let found = locator.locate_by_debug_link(
+            &executable_path,
+            &DebugLink {
+                file_name: "program.debug",
+                crc: crc32(b"debug info"),
+            },
+        );
+        let mismatch = locator.locate_by_debug_link(
+            &executable_path,
+            &DebugLink {
+                file_name: "program.debug",
+                crc: crc32(b"other contents"),
+            },
+        );
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            found.unwrap().file_name().unwrap(),
+            Path::new("program.debug")
+        );
+        assert_eq!(mismatch, None);
+    }
+}
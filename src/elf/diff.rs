@@ -0,0 +1,557 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+use super::dynamic::needed_libraries;
+use super::file::ElfFile;
+use super::header::ElfHeader;
+use super::symbol::{SymType, Symbol};
+
+/// A header field with different values in the two files
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub old: String,
+    pub new: String,
+}
+
+/// A change of a section, segment or symbol between the two files
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    Added {
+        name: String,
+        size: u64,
+    },
+    Removed {
+        name: String,
+        size: u64,
+    },
+    Resized {
+        name: String,
+        old_size: u64,
+        new_size: u64,
+    },
+    /// The attributes changed, e.g. the flags of a segment or the binding of a symbol.
+    /// If the size changed as well, `old` and `new` include it.
+    Modified {
+        name: String,
+        old: String,
+        new: String,
+    },
+}
+
+/// The shared libraries only needed by one of the two files
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DependencyChanges {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+/// The structural differences between two ELF files, e.g. two releases of the same program
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ElfDiff {
+    pub header: Vec<FieldChange>,
+    pub sections: Vec<Change>,
+    pub segments: Vec<Change>,
+    pub symbols: Vec<Change>,
+    pub dependencies: DependencyChanges,
+}
+
+/// The properties of a section, segment or symbol that are compared
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Item {
+    size: u64,
+    attributes: String,
+}
+
+/// Compares the structure of two files. Contents of sections are not compared,
+/// only what they are, how big they are and what they export.
+pub fn diff(old: &ElfFile, new: &ElfFile) -> ElfDiff {
+    ElfDiff {
+        header: diff_fields(&header_fields(old), &header_fields(new)),
+        sections: diff_items(&sections(old), &sections(new)),
+        segments: diff_items(&segments(old), &segments(new)),
+        symbols: diff_items(&symbols(old), &symbols(new)),
+        dependencies: diff_dependencies(&needed_libraries(old), &needed_libraries(new)),
+    }
+}
+
+impl ElfDiff {
+    pub fn is_empty(&self) -> bool {
+        self.header.is_empty()
+            && self.sections.is_empty()
+            && self.segments.is_empty()
+            && self.symbols.is_empty()
+            && self.dependencies.added.is_empty()
+            && self.dependencies.removed.is_empty()
+    }
+
+    /// Formats the differences as a JSON object
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("{\n  \"header\": [");
+        let header: Vec<String> = self
+            .header
+            .iter()
+            .map(|change| {
+                format!(
+                    "{{\"field\": {}, \"old\": {}, \"new\": {}}}",
+                    json_string(change.field),
+                    json_string(&change.old),
+                    json_string(&change.new)
+                )
+            })
+            .collect();
+        write_json_list(&mut json, &header);
+
+        for (name, changes) in [
+            ("sections", &self.sections),
+            ("segments", &self.segments),
+            ("symbols", &self.symbols),
+        ] {
+            write!(json, ",\n  \"{}\": [", name).unwrap();
+            let changes: Vec<String> = changes.iter().map(Change::to_json).collect();
+            write_json_list(&mut json, &changes);
+        }
+
+        let libraries = |libraries: &[String]| {
+            let libraries: Vec<String> = libraries.iter().map(|lib| json_string(lib)).collect();
+            libraries.join(", ")
+        };
+        write!(
+            json,
+            ",\n  \"dependencies\": {{\"added\": [{}], \"removed\": [{}]}}\n}}",
+            libraries(&self.dependencies.added),
+            libraries(&self.dependencies.removed)
+        )
+        .unwrap();
+        json
+    }
+}
+
+impl Change {
+    fn to_json(&self) -> String {
+        match self {
+            Change::Added { name, size } => format!(
+                "{{\"change\": \"added\", \"name\": {}, \"size\": {}}}",
+                json_string(name),
+                size
+            ),
+            Change::Removed { name, size } => format!(
+                "{{\"change\": \"removed\", \"name\": {}, \"size\": {}}}",
+                json_string(name),
+                size
+            ),
+            Change::Resized {
+                name,
+                old_size,
+                new_size,
+            } => format!(
+                "{{\"change\": \"resized\", \"name\": {}, \"old_size\": {}, \"new_size\": {}}}",
+                json_string(name),
+                old_size,
+                new_size
+            ),
+            Change::Modified { name, old, new } => format!(
+                "{{\"change\": \"modified\", \"name\": {}, \"old\": {}, \"new\": {}}}",
+                json_string(name),
+                json_string(old),
+                json_string(new)
+            ),
+        }
+    }
+}
+
+fn write_json_list(json: &mut String, items: &[String]) {
+    if items.is_empty() {
+        json.push(']');
+        return;
+    }
+    for (idx, item) in items.iter().enumerate() {
+        let separator = if idx == 0 { "" } else { "," };
+        write!(json, "{}\n    {}", separator, item).unwrap();
+    }
+    json.push_str("\n  ]");
+}
+
+fn json_string(value: &str) -> String {
+    let mut json = String::with_capacity(value.len() + 2);
+    json.push('"');
+    for character in value.chars() {
+        match character {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            character if (character as u32) < 0x20 => {
+                write!(json, "\\u{:04x}", character as u32).unwrap()
+            }
+            character => json.push(character),
+        }
+    }
+    json.push('"');
+    json
+}
+
+fn header_fields(elf_file: &ElfFile) -> Vec<(&'static str, String)> {
+    let (class, machine, entry, flags, ph_num, sh_num) = match elf_file.header {
+        ElfHeader::Elf32(header) => (
+            "ELF32",
+            format!("{:?}", header.machine),
+            header.entry as u64,
+            header.flags,
+            header.ph_num,
+            header.sh_num,
+        ),
+        ElfHeader::Elf64(header) => (
+            "ELF64",
+            format!("{:?}", header.machine),
+            header.entry,
+            header.flags,
+            header.ph_num,
+            header.sh_num,
+        ),
+    };
+    vec![
+        ("class", class.to_string()),
+        ("type", format!("{:?}", elf_file.elf_type())),
+        ("machine", machine),
+        ("entry", format!("0x{:x}", entry)),
+        ("flags", format!("0x{:x}", flags)),
+        ("program_headers", ph_num.to_string()),
+        ("section_headers", sh_num.to_string()),
+    ]
+}
+
+fn sections(elf_file: &ElfFile) -> BTreeMap<String, Item> {
+    let mut sections = BTreeMap::new();
+    for section in elf_file
+        .sections
+        .iter()
+        .filter(|section| !section.name.is_empty())
+    {
+        sections
+            .entry(section.name.to_string())
+            .or_insert_with(|| Item {
+                size: section.size(),
+                attributes: format!("{:?} {:?}", section.r#type(), section.flags()),
+            });
+    }
+    sections
+}
+
+/// Segments have no names, they are told apart by their type and their
+/// position among the segments of the same type, e.g. `Load[1]`.
+fn segments(elf_file: &ElfFile) -> BTreeMap<String, Item> {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    let mut segments = BTreeMap::new();
+    for header in &elf_file.program_headers {
+        let r#type = match header.r#type() {
+            Some(r#type) => format!("{:?}", r#type),
+            None => String::from("Unknown"),
+        };
+        let count = counts.entry(r#type.clone()).or_default();
+        segments.insert(
+            format!("{}[{}]", r#type, count),
+            Item {
+                size: header.memsz(),
+                attributes: format!("{:?}", header.flags()),
+            },
+        );
+        *count += 1;
+    }
+    segments
+}
+
+/// Named symbols of `.symtab` and `.dynsym`, without section and file symbols. A symbol
+/// in both tables is counted once. Local symbols may share a name, e.g. static functions
+/// of different files, so the later ones are told apart by their position among the
+/// symbols of the same name, e.g. `helper[1]`.
+fn symbols(elf_file: &ElfFile) -> BTreeMap<String, Item> {
+    symbol_items(
+        elf_file
+            .symbols
+            .iter()
+            .chain(elf_file.dynamic_symbols.iter()),
+    )
+}
+
+fn symbol_items<'a>(symbols: impl Iterator<Item = &'a Symbol<'a>>) -> BTreeMap<String, Item> {
+    let mut seen = BTreeSet::new();
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut items = BTreeMap::new();
+    for symbol in symbols
+        .filter(|symbol| !symbol.name.is_empty())
+        .filter(|symbol| !matches!(symbol.r#type(), Some(SymType::Section | SymType::File)))
+        .filter(|symbol| seen.insert((symbol.name, symbol.shndx(), symbol.value())))
+    {
+        let bind = match symbol.bind() {
+            Some(bind) => format!("{:?}", bind),
+            None => String::from("Unknown"),
+        };
+        let r#type = match symbol.r#type() {
+            Some(r#type) => format!("{:?}", r#type),
+            None => String::from("Unknown"),
+        };
+        let count = counts.entry(symbol.name).or_default();
+        let name = match *count {
+            0 => symbol.name.to_string(),
+            count => format!("{}[{}]", symbol.name, count),
+        };
+        items.insert(
+            name,
+            Item {
+                size: symbol.size(),
+                attributes: format!("{} {}", bind, r#type),
+            },
+        );
+        *count += 1;
+    }
+    items
+}
+
+fn diff_fields(old: &[(&'static str, String)], new: &[(&'static str, String)]) -> Vec<FieldChange> {
+    old.iter()
+        .zip(new.iter())
+        .filter(|((_, old), (_, new))| old != new)
+        .map(|((field, old), (_, new))| FieldChange {
+            field,
+            old: old.clone(),
+            new: new.clone(),
+        })
+        .collect()
+}
+
+fn diff_items(old: &BTreeMap<String, Item>, new: &BTreeMap<String, Item>) -> Vec<Change> {
+    let mut changes = Vec::new();
+    for (name, old_item) in old {
+        match new.get(name) {
+            None => changes.push(Change::Removed {
+                name: name.clone(),
+                size: old_item.size,
+            }),
+            Some(new_item) if old_item.attributes != new_item.attributes => {
+                let (old, new) = if old_item.size != new_item.size {
+                    (
+                        format!("{}, size {}", old_item.attributes, old_item.size),
+                        format!("{}, size {}", new_item.attributes, new_item.size),
+                    )
+                } else {
+                    (old_item.attributes.clone(), new_item.attributes.clone())
+                };
+                changes.push(Change::Modified {
+                    name: name.clone(),
+                    old,
+                    new,
+                });
+            }
+            Some(new_item) if old_item.size != new_item.size => changes.push(Change::Resized {
+                name: name.clone(),
+                old_size: old_item.size,
+                new_size: new_item.size,
+            }),
+            Some(_) => {}
+        }
+    }
+    for (name, new_item) in new.iter().filter(|(name, _)| !old.contains_key(*name)) {
+        changes.push(Change::Added {
+            name: name.clone(),
+            size: new_item.size,
+        });
+    }
+    changes
+}
+
+fn diff_dependencies(old: &[&str], new: &[&str]) -> DependencyChanges {
+    DependencyChanges {
+        added: new
+            .iter()
+            .filter(|lib| !old.contains(lib))
+            .map(|lib| lib.to_string())
+            .collect(),
+        removed: old
+            .iter()
+            .filter(|lib| !new.contains(lib))
+            .map(|lib| lib.to_string())
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elf::symbol::{Elf64Sym, ElfSym, Info, SymBinding};
+
+    fn items(items: &[(&str, u64, &str)]) -> BTreeMap<String, Item> {
+        items
+            .iter()
+            .map(|(name, size, attributes)| {
+                (
+                    name.to_string(),
+                    Item {
+                        size: *size,
+                        attributes: attributes.to_string(),
+                    },
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_diff_items() {
+        let old = items(&[
+            (".text", 0x100, "ProgBits ALLOC | EXECINSTR"),
+            (".data", 0x10, "ProgBits WRITE | ALLOC"),
+            (".comment", 0x2b, "ProgBits (empty)"),
+        ]);
+        let new = items(&[
+            (".text", 0x180, "ProgBits ALLOC | EXECINSTR"),
+            (".data", 0x10, "ProgBits WRITE | ALLOC | EXECINSTR"),
+            (".rodata", 0x20, "ProgBits ALLOC"),
+        ]);
+
+        assert_eq!(
+            diff_items(&old, &new),
+            vec![
+                Change::Removed {
+                    name: String::from(".comment"),
+                    size: 0x2b
+                },
+                Change::Modified {
+                    name: String::from(".data"),
+                    old: String::from("ProgBits WRITE | ALLOC"),
+                    new: String::from("ProgBits WRITE | ALLOC | EXECINSTR"),
+                },
+                Change::Resized {
+                    name: String::from(".text"),
+                    old_size: 0x100,
+                    new_size: 0x180
+                },
+                Change::Added {
+                    name: String::from(".rodata"),
+                    size: 0x20
+                },
+            ]
+        );
+        assert!(diff_items(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn test_diff_items_resized_and_modified() {
+        let old = items(&[(".data", 0x10, "ProgBits WRITE | ALLOC")]);
+        let new = items(&[(".data", 0x20, "ProgBits WRITE | ALLOC | EXECINSTR")]);
+
+        assert_eq!(
+            diff_items(&old, &new),
+            vec![Change::Modified {
+                name: String::from(".data"),
+                old: String::from("ProgBits WRITE | ALLOC, size 16"),
+                new: String::from("ProgBits WRITE | ALLOC | EXECINSTR, size 32"),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_symbol_items() {
+        let sym = |info, shndx, value, size| Elf64Sym {
+            name: 0,
+            info,
+            other: 0,
+            shndx,
+            value,
+            size,
+        };
+        let local_func = Elf64Sym::info(SymBinding::Local as u8, SymType::Func as u8);
+        let global_func = Elf64Sym::info(SymBinding::Global as u8, SymType::Func as u8);
+        let helper_a = sym(local_func, 14, 0x1139, 0x10);
+        let helper_b = sym(local_func, 14, 0x1149, 0x20);
+        let main = sym(global_func, 14, 0x1159, 0x30);
+        let symbols = [
+            Symbol::new("helper", ElfSym::Sym64(&helper_a)),
+            Symbol::new("helper", ElfSym::Sym64(&helper_b)),
+            Symbol::new("main", ElfSym::Sym64(&main)),
+            // The same symbol in .dynsym
+            Symbol::new("main", ElfSym::Sym64(&main)),
+        ];
+
+        let items = symbol_items(symbols.iter());
+        let sizes: Vec<(&str, u64)> = items
+            .iter()
+            .map(|(name, item)| (name.as_str(), item.size))
+            .collect();
+        assert_eq!(
+            sizes,
+            vec![("helper", 0x10), ("helper[1]", 0x20), ("main", 0x30)]
+        );
+        assert_eq!(items["main"].attributes, "Global Func");
+    }
+
+    #[test]
+    fn test_diff_fields_and_dependencies() {
+        let old = vec![
+            ("entry", String::from("0x1040")),
+            ("class", String::from("ELF64")),
+        ];
+        let new = vec![
+            ("entry", String::from("0x1050")),
+            ("class", String::from("ELF64")),
+        ];
+        assert_eq!(
+            diff_fields(&old, &new),
+            vec![FieldChange {
+                field: "entry",
+                old: String::from("0x1040"),
+                new: String::from("0x1050"),
+            }]
+        );
+
+        let dependencies =
+            diff_dependencies(&["libc.so.6", "libm.so.6"], &["libc.so.6", "libz.so.1"]);
+        assert_eq!(dependencies.added, vec![String::from("libz.so.1")]);
+        assert_eq!(dependencies.removed, vec![String::from("libm.so.6")]);
+    }
+
+    #[test]
+    fn test_to_json() {
+        let diff = ElfDiff {
+            header: vec![FieldChange {
+                field: "entry",
+                old: String::from("0x1040"),
+                new: String::from("0x1050"),
+            }],
+            symbols: vec![
+                Change::Added {
+                    name: String::from("\"quoted\""),
+                    size: 8,
+                },
+                Change::Resized {
+                    name: String::from("main"),
+                    old_size: 16,
+                    new_size: 32,
+                },
+            ],
+            dependencies: DependencyChanges {
+                added: vec![String::from("libz.so.1")],
+                removed: Vec::new(),
+            },
+            ..Default::default()
+        };
+
+        assert_eq!(
+            diff.to_json(),
+            r#"{
+  "header": [
+    {"field": "entry", "old": "0x1040", "new": "0x1050"}
+  ],
+  "sections": [],
+  "segments": [],
+  "symbols": [
+    {"change": "added", "name": "\"quoted\"", "size": 8},
+    {"change": "resized", "name": "main", "old_size": 16, "new_size": 32}
+  ],
+  "dependencies": {"added": ["libz.so.1"], "removed": []}
+}"#
+        );
+        assert!(!diff.is_empty());
+        assert!(ElfDiff::default().is_empty());
+    }
+}
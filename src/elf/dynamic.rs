@@ -0,0 +1,140 @@
+use super::file::ElfFile;
+use super::section::{ElfSectionHeader, Section};
+
+pub const DT_NULL: i64 = 0;
+pub const DT_NEEDED: i64 = 1;
+pub const DT_SONAME: i64 = 14;
+pub const DT_RPATH: i64 = 15;
+pub const DT_BIND_NOW: i64 = 24;
+pub const DT_RUNPATH: i64 = 29;
+pub const DT_FLAGS: i64 = 30;
+pub const DT_FLAGS_1: i64 = 0x6ffffffb;
+
+/// `DT_FLAGS` flag requesting all relocations to be processed at load time
+pub const DF_BIND_NOW: u64 = 0x8;
+/// `DT_FLAGS_1` flag requesting all relocations to be processed at load time
+pub const DF_1_NOW: u64 = 0x1;
+/// `DT_FLAGS_1` flag marking position independent executables
+pub const DF_1_PIE: u64 = 0x08000000;
+
+const DYNAMIC_SECTION: &str = ".dynamic";
+const DYNAMIC_STRING_TABLE: &str = ".dynstr";
+
+/// An entry of the `.dynamic` section. Depending on the tag, the value is either
+/// an integer, an address or an offset into the dynamic string table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DynamicEntry {
+    pub tag: i64,
+    pub value: u64,
+}
+
+/// Parses the entries of the dynamic section up to the terminating `DT_NULL`
+pub fn parse_dynamic_entries(section: &Section) -> Vec<DynamicEntry> {
+    let (entry_size, word_size) = match section.section_header() {
+        ElfSectionHeader::Section32(_) => (8, 4),
+        ElfSectionHeader::Section64(_) => (16, 8),
+    };
+
+    section
+        .bytes()
+        .chunks_exact(entry_size)
+        .map(|entry| {
+            let (tag, value) = entry.split_at(word_size);
+            match word_size {
+                4 => DynamicEntry {
+                    tag: i32::from_le_bytes(tag.try_into().unwrap()) as i64,
+                    value: u32::from_le_bytes(value.try_into().unwrap()) as u64,
+                },
+                _ => DynamicEntry {
+                    tag: i64::from_le_bytes(tag.try_into().unwrap()),
+                    value: u64::from_le_bytes(value.try_into().unwrap()),
+                },
+            }
+        })
+        .take_while(|entry| entry.tag != DT_NULL)
+        .collect()
+}
+
+/// The entries of the dynamic section of the file, empty for static executables
+pub fn dynamic_entries(elf_file: &ElfFile) -> Vec<DynamicEntry> {
+    elf_file
+        .get_section_by_name(DYNAMIC_SECTION)
+        .map(parse_dynamic_entries)
+        .unwrap_or_default()
+}
+
+/// Values of all the entries with `tag` that refer to strings, e.g. `DT_NEEDED` or `DT_RUNPATH`
+pub fn dynamic_strings<'a>(elf_file: &ElfFile<'a>, tag: i64) -> Vec<&'a str> {
+    let string_table = match elf_file.get_section_by_name(DYNAMIC_STRING_TABLE) {
+        Some(section) => section.bytes(),
+        None => return Vec::new(),
+    };
+
+    dynamic_entries(elf_file)
+        .iter()
+        .filter(|entry| entry.tag == tag)
+        .filter_map(|entry| {
+            let bytes = string_table.get(entry.value as usize..)?;
+            let end = bytes.iter().position(|byte| *byte == 0)?;
+            std::str::from_utf8(&bytes[..end]).ok()
+        })
+        .collect()
+}
+
+/// The shared libraries the file depends on
+pub fn needed_libraries<'a>(elf_file: &ElfFile<'a>) -> Vec<&'a str> {
+    dynamic_strings(elf_file, DT_NEEDED)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elf::section::{Elf64SectionFlags, Elf64Shdr, ElfSectionType};
+
+    #[test]
+    fn test_parse_dynamic_entries() {
+        let mut bytes = Vec::new();
+        for (tag, value) in [
+            (DT_NEEDED, 1u64),
+            (DT_FLAGS_1, DF_1_NOW | DF_1_PIE),
+            (DT_NULL, 0),
+            (DT_NULL, 0),
+        ] {
+            bytes.extend_from_slice(&tag.to_le_bytes());
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+
+        let header = Elf64Shdr {
+            name: 0,
+            sh_type: ElfSectionType::Dynamic,
+            flags: Elf64SectionFlags::ALLOC | Elf64SectionFlags::WRITE,
+            addr: 0x3dc8,
+            offset: 0x2dc8,
+            size: bytes.len() as u64,
+            link: 0,
+            info: 0,
+            addr_align: 8,
+            ent_size: 16,
+        };
+        let section = Section::new(
+            DYNAMIC_SECTION,
+            &header.sh_type,
+            &bytes,
+            ElfSectionHeader::Section64(&header),
+        );
+
+        assert_eq!(
+            parse_dynamic_entries(&section),
+            vec![
+                DynamicEntry {
+                    tag: DT_NEEDED,
+                    value: 1
+                },
+                DynamicEntry {
+                    tag: DT_FLAGS_1,
+                    value: DF_1_NOW | DF_1_PIE
+                },
+            ]
+        );
+    }
+}
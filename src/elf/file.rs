@@ -1,23 +1,82 @@
+use super::header::{ElfHeader, ElfType};
+use super::program::{ElfProgramheader, SegmentType};
 use super::{section::Section, symbol::Symbol};
 
 #[derive(Debug)]
 pub struct ElfFile<'a> {
+    pub header: ElfHeader<'a>,
+    pub program_headers: Vec<ElfProgramheader<'a>>,
     pub sections: Vec<Section<'a>>,
+    /// Symbols from `.symtab`, empty for stripped files
     pub symbols: Vec<Symbol<'a>>,
+    /// Symbols from `.dynsym`, the ones imported and exported by the dynamic linker
+    pub dynamic_symbols: Vec<Symbol<'a>>,
 }
 
 impl<'a> ElfFile<'a> {
-    pub fn new(sections: Vec<Section<'a>>, symbols: Vec<Symbol<'a>>) -> Self {
-        ElfFile { sections, symbols }
+    pub fn new(
+        header: ElfHeader<'a>,
+        program_headers: Vec<ElfProgramheader<'a>>,
+        sections: Vec<Section<'a>>,
+        symbols: Vec<Symbol<'a>>,
+        dynamic_symbols: Vec<Symbol<'a>>,
+    ) -> Self {
+        ElfFile {
+            header,
+            program_headers,
+            sections,
+            symbols,
+            dynamic_symbols,
+        }
+    }
+
+    pub fn elf_type(&self) -> &ElfType {
+        match self.header {
+            ElfHeader::Elf32(header) => &header.elf_type,
+            ElfHeader::Elf64(header) => &header.elf_type,
+        }
+    }
+
+    /// Position independent executables and shared objects are relocated by the
+    /// dynamic linker, so their addresses are relative to the load address.
+    pub fn is_position_independent(&self) -> bool {
+        *self.elf_type() == ElfType::Dyn
+    }
+
+    pub fn get_program_header_by_type(&self, r#type: SegmentType) -> Option<&ElfProgramheader<'a>> {
+        self.program_headers
+            .iter()
+            .find(|header| header.r#type().as_ref() == Some(&r#type))
     }
 
     #[allow(dead_code, unused_variables)]
-    pub fn get_section_by_name(&self, name: &str) -> Option<&Section> {
-        for section in &self.sections {
-            if name == section.name {
-                return Some(section);
+    pub fn get_section_by_name(&self, name: &str) -> Option<&Section<'a>> {
+        self.sections.iter().find(|section| name == section.name)
+    }
+
+    /// Merges the contents of a separate debug file into this, usually stripped, file.
+    /// Sections missing here, or only present as `NoBits` placeholders, are taken from
+    /// the debug file, e.g. `.debug_info` and `.symtab`. Symbols not already known are added.
+    pub fn merge_debug_file(&mut self, debug_file: ElfFile<'a>) {
+        for section in debug_file.sections {
+            if section.is_no_bits() || section.name.is_empty() {
+                continue;
+            }
+            match self.sections.iter().position(|s| s.name == section.name) {
+                Some(idx) if self.sections[idx].is_no_bits() => self.sections[idx] = section,
+                Some(_) => {}
+                None => self.sections.push(section),
+            }
+        }
+
+        for symbol in debug_file.symbols {
+            let known = self
+                .symbols
+                .iter()
+                .any(|s| s.name == symbol.name && s.value() == symbol.value());
+            if !known {
+                self.symbols.push(symbol);
             }
         }
-        None
     }
 }
@@ -1,9 +1,16 @@
+pub mod checksec;
+pub mod debuglink;
+pub mod diff;
+pub mod dynamic;
 pub mod error;
 pub mod file;
 pub mod header;
+pub mod note;
 pub mod parse;
 pub mod parser;
+pub mod plt;
 pub mod program;
+pub mod relocation;
 pub mod section;
 pub mod symbol;
 pub mod types;
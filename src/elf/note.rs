@@ -0,0 +1,108 @@
+use std::mem::size_of;
+
+use super::types::Elf64Word;
+
+/// Note type of the build-id note, owned by "GNU"
+pub const NT_GNU_BUILD_ID: Elf64Word = 3;
+/// Note type of the program property note, owned by "GNU"
+pub const NT_GNU_PROPERTY_TYPE_0: Elf64Word = 5;
+
+pub const GNU_NOTE_NAME: &str = "GNU";
+
+/// A single entry of a note section or segment.
+/// Each entry consists of a header with the sizes of the owner name and the descriptor,
+/// a type, the owner name itself and the descriptor, where the latter two are padded
+/// to the alignment of the containing section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Note<'a> {
+    pub name: &'a str,
+    pub n_type: Elf64Word,
+    pub desc: &'a [u8],
+}
+
+/// Parses all the notes contained in the bytes of a note section.
+/// `align` is the alignment of the section, which is 4 for most notes and 8 for
+/// `.note.gnu.property` in 64-bit files. Malformed trailing entries are ignored.
+pub fn parse_notes(bytes: &[u8], align: u64) -> Vec<Note<'_>> {
+    let align = if align < 4 { 4 } else { align as usize };
+    let header_size = 3 * size_of::<Elf64Word>();
+
+    let mut notes = Vec::new();
+    let mut offset = 0;
+    while offset + header_size <= bytes.len() {
+        let name_size = read_word(bytes, offset) as usize;
+        let desc_size = read_word(bytes, offset + 4) as usize;
+        let n_type = read_word(bytes, offset + 8);
+
+        let name_start = offset + header_size;
+        let desc_start = align_up(name_start + name_size, align);
+        let desc_end = desc_start + desc_size;
+        if desc_end > bytes.len() {
+            break;
+        }
+
+        // The name is NUL terminated, and the size includes the terminator
+        let name_bytes = &bytes[name_start..name_start + name_size];
+        let name_bytes = match name_bytes.iter().position(|byte| *byte == 0) {
+            Some(end) => &name_bytes[..end],
+            None => name_bytes,
+        };
+        let name = std::str::from_utf8(name_bytes).unwrap_or("");
+
+        notes.push(Note {
+            name,
+            n_type,
+            desc: &bytes[desc_start..desc_end],
+        });
+
+        offset = align_up(desc_end, align);
+    }
+    notes
+}
+
+fn read_word(bytes: &[u8], offset: usize) -> Elf64Word {
+    Elf64Word::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn align_up(value: usize, align: usize) -> usize {
+    (value + align - 1) & !(align - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_build_id_note() {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&4u32.to_le_bytes());
+        bytes.extend_from_slice(&6u32.to_le_bytes());
+        bytes.extend_from_slice(&NT_GNU_BUILD_ID.to_le_bytes());
+        bytes.extend_from_slice(b"GNU\0");
+        bytes.extend_from_slice(&[0xde, 0xad, 0xbe, 0xef, 0x01, 0x02, 0, 0]);
+
+        let notes = parse_notes(&bytes, 4);
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].name, GNU_NOTE_NAME);
+        assert_eq!(notes[0].n_type, NT_GNU_BUILD_ID);
+        assert_eq!(notes[0].desc, &[0xde, 0xad, 0xbe, 0xef, 0x01, 0x02]);
+    }
+
+    #[test]
+    fn test_parse_notes_with_8_byte_alignment() {
+        let mut bytes = Vec::new();
+        for n_type in [NT_GNU_PROPERTY_TYPE_0, 1] {
+            bytes.extend_from_slice(&4u32.to_le_bytes());
+            bytes.extend_from_slice(&4u32.to_le_bytes());
+            bytes.extend_from_slice(&n_type.to_le_bytes());
+            bytes.extend_from_slice(b"GNU\0");
+            bytes.extend_from_slice(&[1, 2, 3, 4, 0, 0, 0, 0]);
+        }
+
+        let notes = parse_notes(&bytes, 8);
+        assert_eq!(notes.len(), 2);
+        assert_eq!(notes[0].n_type, NT_GNU_PROPERTY_TYPE_0);
+        assert_eq!(notes[0].desc, &[1, 2, 3, 4]);
+        assert_eq!(notes[1].n_type, 1);
+    }
+}
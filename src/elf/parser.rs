@@ -3,6 +3,7 @@
 #![allow(unused_imports)]
 use super::file::ElfFile;
 use super::header::*;
+use super::program::{Elf32Phdr, Elf64Phdr, ElfProgramheader};
 use super::section::{
     Elf32Shdr, Elf64SectionFlags, Elf64Shdr, ElfSectionHeader, ElfSectionType, Section,
 };
@@ -34,67 +35,92 @@ impl<'a> ElfParser<'a> {
         file.read_to_end(&mut self.file_bytes).unwrap();
     }
 
-    pub fn parse_elf_file(&mut self) -> ElfFile {
+    pub fn parse_elf_file(&mut self) -> ElfFile<'_> {
         self.read_elf_file_into_buffer();
 
         let elf_header = self.parse_header();
+        let program_headers = self.parse_program_headers(&elf_header);
         let section_headers = self.parse_section_headers(&elf_header);
         let sh_string_table_slice = self.get_sh_string_table_slice(&elf_header, &section_headers);
-        let string_table_slice = self.get_string_table_slice(&section_headers).unwrap();
 
         let mut sections = Vec::<Section>::new();
         let mut symbols = Vec::<Symbol>::new();
-        for section_header in section_headers {
+        let mut dynamic_symbols = Vec::<Symbol>::new();
+        for section_header in &section_headers {
+            let section_bytes = self.get_section_bytes(section_header);
             match section_header {
                 ElfSectionHeader::Section32(header) => {
                     let section_name = self.parse_name(header.name as usize, sh_string_table_slice);
-                    let section_bytes = &self.file_bytes
-                        [header.offset as usize..(header.offset + header.size) as usize];
-
-                    let section_header_clone = section_header.clone();
                     sections.push(Section::new(
                         section_name,
                         &header.sh_type,
                         section_bytes,
-                        section_header,
+                        section_header.clone(),
                     ));
 
-                    if let ElfSectionType::SymTab = header.sh_type {
-                        symbols = self.parse_symbols(
-                            &section_header_clone,
-                            section_bytes,
-                            string_table_slice,
-                        )
+                    if let ElfSectionType::SymTab | ElfSectionType::DynSym = header.sh_type {
+                        let string_table_slice =
+                            self.get_section_bytes(&section_headers[header.link as usize]);
+                        let parsed_symbols =
+                            self.parse_symbols(section_header, section_bytes, string_table_slice);
+                        match header.sh_type {
+                            ElfSectionType::SymTab => symbols = parsed_symbols,
+                            _ => dynamic_symbols = parsed_symbols,
+                        }
                     }
                 }
                 ElfSectionHeader::Section64(header) => {
                     let section_name = self.parse_name(header.name as usize, sh_string_table_slice);
-                    let section_bytes = &self.file_bytes
-                        [header.offset as usize..(header.offset + header.size) as usize];
-
-                    let section_header_clone = section_header.clone();
                     sections.push(Section::new(
                         section_name,
                         &header.sh_type,
                         section_bytes,
-                        section_header,
+                        section_header.clone(),
                     ));
 
-                    if let ElfSectionType::SymTab = header.sh_type {
-                        symbols = self.parse_symbols(
-                            &section_header_clone,
-                            section_bytes,
-                            string_table_slice,
-                        )
+                    if let ElfSectionType::SymTab | ElfSectionType::DynSym = header.sh_type {
+                        let string_table_slice =
+                            self.get_section_bytes(&section_headers[header.link as usize]);
+                        let parsed_symbols =
+                            self.parse_symbols(section_header, section_bytes, string_table_slice);
+                        match header.sh_type {
+                            ElfSectionType::SymTab => symbols = parsed_symbols,
+                            _ => dynamic_symbols = parsed_symbols,
+                        }
                     }
                 }
             }
         }
 
-        ElfFile::new(sections, symbols)
+        ElfFile::new(
+            elf_header,
+            program_headers,
+            sections,
+            symbols,
+            dynamic_symbols,
+        )
     }
 
-    pub fn parse_header(&self) -> ElfHeader {
+    /// The bytes a section occupies in the file. `NoBits` sections, e.g. `.bss` or
+    /// the stubs left in separate debug files, occupy no space in the file.
+    pub fn get_section_bytes(&self, section_header: &ElfSectionHeader) -> &[u8] {
+        match section_header {
+            ElfSectionHeader::Section32(header) => match header.sh_type {
+                ElfSectionType::NoBits => &[],
+                _ => {
+                    &self.file_bytes[header.offset as usize..(header.offset + header.size) as usize]
+                }
+            },
+            ElfSectionHeader::Section64(header) => match header.sh_type {
+                ElfSectionType::NoBits => &[],
+                _ => {
+                    &self.file_bytes[header.offset as usize..(header.offset + header.size) as usize]
+                }
+            },
+        }
+    }
+
+    pub fn parse_header(&self) -> ElfHeader<'_> {
         let class_byte = self.file_bytes[EI_CLASS_IDX];
         let class = Class::try_from(class_byte).unwrap();
 
@@ -122,7 +148,40 @@ impl<'a> ElfParser<'a> {
         }
     }
 
-    pub fn parse_section_headers(&self, elf_header: &ElfHeader) -> Vec<ElfSectionHeader> {
+    pub fn parse_program_headers(&self, elf_header: &ElfHeader) -> Vec<ElfProgramheader<'_>> {
+        let mut program_header_entries;
+        match elf_header {
+            ElfHeader::Elf32(header) => {
+                program_header_entries = Vec::with_capacity(header.ph_num as usize);
+                let mut entry_offset: usize = header.ph_off.try_into().unwrap();
+                for _entry in 1..=header.ph_num {
+                    let entry_slice =
+                        &self.file_bytes[entry_offset..entry_offset + header.ph_ent_size as usize];
+                    let (head, body, _tail) = unsafe { entry_slice.align_to::<Elf32Phdr>() };
+                    assert!(head.is_empty(), "Data was not aligned");
+                    program_header_entries.push(ElfProgramheader::ProgramHeader32(&body[0]));
+
+                    entry_offset += header.ph_ent_size as usize;
+                }
+            }
+            ElfHeader::Elf64(header) => {
+                program_header_entries = Vec::with_capacity(header.ph_num as usize);
+                let mut entry_offset: usize = header.ph_off.try_into().unwrap();
+                for _entry in 1..=header.ph_num {
+                    let entry_slice =
+                        &self.file_bytes[entry_offset..entry_offset + header.ph_ent_size as usize];
+                    let (head, body, _tail) = unsafe { entry_slice.align_to::<Elf64Phdr>() };
+                    assert!(head.is_empty(), "Data was not aligned");
+                    program_header_entries.push(ElfProgramheader::ProgramHeader64(&body[0]));
+
+                    entry_offset += header.ph_ent_size as usize;
+                }
+            }
+        }
+        program_header_entries
+    }
+
+    pub fn parse_section_headers(&self, elf_header: &ElfHeader) -> Vec<ElfSectionHeader<'_>> {
         let mut section_header_entries;
         match elf_header {
             ElfHeader::Elf32(header) => {
@@ -164,7 +223,7 @@ impl<'a> ElfParser<'a> {
         section_header: &ElfSectionHeader,
         section_bytes: &'a [u8],
         string_table_slice: &'a [u8],
-    ) -> Vec<Symbol> {
+    ) -> Vec<Symbol<'_>> {
         let mut symbols = Vec::<Symbol>::new();
         match section_header {
             ElfSectionHeader::Section32(header) => {
@@ -292,7 +351,7 @@ mod test {
     };
 
     use super::*;
-    use std::{mem::size_of, path::Path, usize};
+    use std::{mem::size_of, path::Path};
 
     #[test]
     fn new_parser() {
@@ -0,0 +1,281 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::file::ElfFile;
+use super::parser::ElfParser;
+use super::relocation::{
+    parse_relocations, R_X86_64_GLOB_DAT, R_X86_64_IRELATIVE, R_X86_64_JUMP_SLOT,
+};
+use super::section::ElfSectionType;
+use super::symbol::SymType;
+
+/// Sections holding PLT stubs. `.plt.sec` holds the stubs that are called when the
+/// binary is built with IBT, with `.plt` only doing the lazy binding, and `.plt.got`
+/// holds stubs for functions that also have their address taken.
+pub const PLT_SECTIONS: [&str; 3] = [".plt", ".plt.sec", ".plt.got"];
+
+const PLT_ENTRY_SIZE: u64 = 16;
+const PLT_SUFFIX: &str = "@plt";
+
+/// `jmp *disp32(%rip)`
+const INDIRECT_JUMP_OPCODE: [u8; 2] = [0xff, 0x25];
+const INDIRECT_JUMP_SIZE: u64 = 6;
+
+/// A GOT slot that is filled in by the dynamic linker
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GotEntry {
+    /// Link time address of the slot
+    pub address: u64,
+    pub r_type: u32,
+    /// Name of the symbol the slot is resolved to
+    pub symbol: String,
+}
+
+/// A stub in one of the PLT sections, jumping through `got_entry`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PltStub {
+    /// Synthesized name of the stub, e.g. `puts@plt`
+    pub name: String,
+    pub address: u64,
+    pub size: u64,
+    pub section: String,
+    pub got_entry: GotEntry,
+}
+
+impl PltStub {
+    pub fn contains(&self, address: u64) -> bool {
+        self.address <= address && address < self.address + self.size
+    }
+}
+
+/// Returns the GOT slots of the file that are bound by the dynamic linker,
+/// i.e. the `JUMP_SLOT`, `GLOB_DAT` and `IRELATIVE` relocations.
+pub fn got_entries(elf_file: &ElfFile) -> Vec<GotEntry> {
+    let mut entries = Vec::new();
+    for section in &elf_file.sections {
+        if !matches!(section.r#type(), ElfSectionType::Rela | ElfSectionType::Rel) {
+            continue;
+        }
+        for relocation in parse_relocations(section) {
+            let symbol = match relocation.r_type {
+                R_X86_64_JUMP_SLOT | R_X86_64_GLOB_DAT => elf_file
+                    .dynamic_symbols
+                    .get(relocation.symbol_index as usize)
+                    .map(|symbol| symbol.name.to_string()),
+                // Resolved by calling the resolver function at the addend, e.g. in static binaries
+                R_X86_64_IRELATIVE => Some(format!("*ABS*+{:#x}", relocation.addend)),
+                _ => None,
+            };
+            if let Some(symbol) = symbol {
+                entries.push(GotEntry {
+                    address: relocation.offset,
+                    r_type: relocation.r_type,
+                    symbol,
+                });
+            }
+        }
+    }
+    entries.sort_by_key(|entry| entry.address);
+    entries
+}
+
+/// Synthesizes `foo@plt` symbols for every stub in the PLT sections, by decoding
+/// the indirect jump of each stub and matching the GOT slot it jumps through
+/// with the relocations of the file. The first entry of `.plt`, which calls the
+/// lazy binding resolver, has no relocation and is skipped.
+pub fn plt_stubs(elf_file: &ElfFile) -> Vec<PltStub> {
+    let got_entries: HashMap<u64, GotEntry> = got_entries(elf_file)
+        .into_iter()
+        .map(|entry| (entry.address, entry))
+        .collect();
+
+    let mut stubs = Vec::new();
+    for section_name in PLT_SECTIONS {
+        let section = match elf_file.get_section_by_name(section_name) {
+            Some(section) => section,
+            None => continue,
+        };
+        let entry_size = plt_entry_size(section_name, section.bytes());
+        for (idx, entry) in section.bytes().chunks(entry_size as usize).enumerate() {
+            let address = section.address() + idx as u64 * entry_size;
+            let got_entry = decode_indirect_jump(entry, address)
+                .and_then(|got_address| got_entries.get(&got_address));
+            if let Some(got_entry) = got_entry {
+                stubs.push(PltStub {
+                    name: format!("{}{}", got_entry.symbol, PLT_SUFFIX),
+                    address,
+                    size: entry_size,
+                    section: section_name.to_string(),
+                    got_entry: got_entry.clone(),
+                });
+            }
+        }
+    }
+    stubs.sort_by_key(|stub| stub.address);
+    stubs
+}
+
+/// Finds the PLT stub named `name`, with or without the `@plt` suffix.
+pub fn find_plt_stub<'a>(stubs: &'a [PltStub], name: &str) -> Option<&'a PltStub> {
+    let name = name.strip_suffix(PLT_SUFFIX).unwrap_or(name);
+    stubs.iter().find(|stub| stub.got_entry.symbol == name)
+}
+
+/// `.plt.got` entries are 8 bytes, `jmp *disp32(%rip)` and a 2 byte nop,
+/// unless the binary is built with IBT, where they are prefixed by `endbr64`.
+fn plt_entry_size(section_name: &str, bytes: &[u8]) -> u64 {
+    const ENDBR64: [u8; 4] = [0xf3, 0x0f, 0x1e, 0xfa];
+    if section_name == ".plt.got" && !bytes.starts_with(&ENDBR64) {
+        8
+    } else {
+        PLT_ENTRY_SIZE
+    }
+}
+
+/// Returns the target of the first `jmp *disp32(%rip)` in a PLT entry at `address`,
+/// which is the address of the GOT slot the stub jumps through.
+fn decode_indirect_jump(entry: &[u8], address: u64) -> Option<u64> {
+    let opcode_idx = entry
+        .windows(INDIRECT_JUMP_OPCODE.len())
+        .position(|window| window == INDIRECT_JUMP_OPCODE)?;
+    let disp_bytes = entry.get(opcode_idx + 2..opcode_idx + 6)?;
+    let disp = i32::from_le_bytes(disp_bytes.try_into().unwrap());
+    let next_instruction = address + opcode_idx as u64 + INDIRECT_JUMP_SIZE;
+    Some(next_instruction.wrapping_add(disp as i64 as u64))
+}
+
+/// The state of a GOT slot in a running process
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GotSlotState {
+    /// Still points back into the PLT, the symbol is resolved on the first call
+    Lazy,
+    /// Still holds the link-time address of its PLT stub, before the dynamic loader
+    /// relocated the GOT of a position independent executable
+    Unrelocated,
+    /// Points into a mapped file. `symbols` are the names of the dynamic symbols at the
+    /// address, which is empty if it is not the start of an exported symbol, e.g. for IFUNCs.
+    Resolved {
+        library: String,
+        offset: u64,
+        symbols: Vec<String>,
+    },
+    /// An undefined weak symbol, e.g. `__gmon_start__`, resolves to 0
+    Null,
+    /// Points outside any mapped file
+    Unmapped,
+}
+
+impl GotSlotState {
+    /// A resolved slot pointing to an exported symbol with a different name than
+    /// the one it was bound for is a strong sign of the GOT being overwritten.
+    pub fn is_suspicious(&self, expected_symbol: &str) -> bool {
+        match self {
+            GotSlotState::Resolved { symbols, .. } => {
+                !symbols.is_empty() && !symbols.iter().any(|symbol| symbol == expected_symbol)
+            }
+            GotSlotState::Unmapped => true,
+            GotSlotState::Lazy | GotSlotState::Unrelocated | GotSlotState::Null => false,
+        }
+    }
+}
+
+/// Reads the dynamic symbols that are defined in the shared object at `path`, keyed by
+/// their value, which is the address relative to the load address of the object.
+pub fn defined_dynamic_symbols(path: &Path) -> HashMap<u64, Vec<String>> {
+    let mut parser = ElfParser::new(path);
+    let elf_file = parser.parse_elf_file();
+
+    let mut symbols: HashMap<u64, Vec<String>> = HashMap::new();
+    for symbol in &elf_file.dynamic_symbols {
+        let defined = symbol.shndx() != 0
+            && matches!(
+                symbol.r#type(),
+                Some(SymType::Func) | Some(SymType::Object) | Some(SymType::NoType)
+            );
+        if defined && !symbol.name.is_empty() {
+            symbols
+                .entry(symbol.value())
+                .or_default()
+                .push(symbol.name.to_string());
+        }
+    }
+    symbols
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_indirect_jump() {
+        // .plt.sec entry: endbr64; bnd jmp *0x2f75(%rip); nopl 0x0(%rax,%rax,1)
+        let entry = [
+            0xf3, 0x0f, 0x1e, 0xfa, 0xf2, 0xff, 0x25, 0x75, 0x2f, 0x00, 0x00, 0x0f, 0x1f, 0x44,
+            0x00, 0x00,
+        ];
+        assert_eq!(
+            decode_indirect_jump(&entry, 0x1050),
+            Some(0x1050 + 11 + 0x2f75)
+        );
+
+        // Classic .plt entry: jmp *0x2fe2(%rip); push $0x0; jmp plt0
+        let entry = [
+            0xff, 0x25, 0xe2, 0x2f, 0x00, 0x00, 0x68, 0x00, 0x00, 0x00, 0x00, 0xe9, 0xe0, 0xff,
+            0xff, 0xff,
+        ];
+        assert_eq!(decode_indirect_jump(&entry, 0x1030), Some(0x1036 + 0x2fe2));
+
+        // Lazy binding entry in .plt when .plt.sec exists: endbr64; push $0x0; bnd jmp plt0
+        let entry = [
+            0xf3, 0x0f, 0x1e, 0xfa, 0x68, 0x00, 0x00, 0x00, 0x00, 0xf2, 0xe9, 0xe1, 0xff, 0xff,
+            0xff, 0x90,
+        ];
+        assert_eq!(decode_indirect_jump(&entry, 0x1030), None);
+    }
+
+    #[test]
+    fn test_plt_got_entry_size() {
+        let entry = [0xff, 0x25, 0x00, 0x00, 0x00, 0x00, 0x66, 0x90];
+        assert_eq!(plt_entry_size(".plt.got", &entry), 8);
+        let entry = [0xf3, 0x0f, 0x1e, 0xfa, 0xf2, 0xff, 0x25];
+        assert_eq!(plt_entry_size(".plt.got", &entry), 16);
+        assert_eq!(plt_entry_size(".plt", &[]), 16);
+    }
+
+    #[test]
+    fn test_find_plt_stub() {
+        let stubs = vec![PltStub {
+            name: "puts@plt".to_string(),
+            address: 0x1050,
+            size: 16,
+            section: ".plt.sec".to_string(),
+            got_entry: GotEntry {
+                address: 0x3fd0,
+                r_type: R_X86_64_JUMP_SLOT,
+                symbol: "puts".to_string(),
+            },
+        }];
+        assert_eq!(find_plt_stub(&stubs, "puts@plt").unwrap().address, 0x1050);
+        assert_eq!(find_plt_stub(&stubs, "puts").unwrap().address, 0x1050);
+        assert!(find_plt_stub(&stubs, "printf").is_none());
+        assert!(stubs[0].contains(0x105f));
+        assert!(!stubs[0].contains(0x1060));
+    }
+
+    #[test]
+    fn test_got_slot_state_is_suspicious() {
+        let resolved = |symbols: &[&str]| GotSlotState::Resolved {
+            library: "libc.so.6".to_string(),
+            offset: 0x80e50,
+            symbols: symbols.iter().map(|s| s.to_string()).collect(),
+        };
+        assert!(!resolved(&["_IO_puts", "puts"]).is_suspicious("puts"));
+        assert!(resolved(&["system"]).is_suspicious("puts"));
+        // IFUNCs resolve to implementations that are not exported
+        assert!(!resolved(&[]).is_suspicious("strlen"));
+        assert!(!GotSlotState::Lazy.is_suspicious("puts"));
+        assert!(!GotSlotState::Unrelocated.is_suspicious("puts"));
+        assert!(!GotSlotState::Null.is_suspicious("__gmon_start__"));
+        assert!(GotSlotState::Unmapped.is_suspicious("puts"));
+    }
+}
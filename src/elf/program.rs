@@ -1,34 +1,145 @@
+use bitflags::bitflags;
+use derive_try_from_primitive::TryFromPrimitive;
+
 use super::types::*;
 
-#[derive(Debug)]
-#[allow(dead_code)]
+/// Type of a segment. The raw value is kept in the program header, as it
+/// may hold OS or processor specific values not listed here.
+#[derive(Debug, Clone, PartialEq, Eq, TryFromPrimitive)]
+#[repr(u32)]
+pub enum SegmentType {
+    Null = 0,
+    Load = 1,
+    Dynamic = 2,
+    Interp = 3,
+    Note = 4,
+    ShLib = 5,
+    Phdr = 6,
+    Tls = 7,
+    GnuEhFrame = 0x6474e550,
+    GnuStack = 0x6474e551,
+    GnuRelro = 0x6474e552,
+    GnuProperty = 0x6474e553,
+}
+
+bitflags! {
+    pub struct SegmentFlags: Elf64Word {
+        const EXECUTE = 0x1;
+        const WRITE = 0x2;
+        const READ = 0x4;
+    }
+}
+
+#[derive(Debug, Clone)]
 pub enum ElfProgramheader<'a> {
     ProgramHeader32(&'a Elf32Phdr),
     ProgramHeader64(&'a Elf64Phdr),
 }
 
+impl<'a> ElfProgramheader<'a> {
+    pub fn r#type(&self) -> Option<SegmentType> {
+        let r#type = match self {
+            ElfProgramheader::ProgramHeader32(header) => header.r#type,
+            ElfProgramheader::ProgramHeader64(header) => header.r#type,
+        };
+        SegmentType::try_from(r#type).ok()
+    }
+
+    pub fn flags(&self) -> SegmentFlags {
+        let flags = match self {
+            ElfProgramheader::ProgramHeader32(header) => header.flags,
+            ElfProgramheader::ProgramHeader64(header) => header.flags,
+        };
+        SegmentFlags::from_bits_truncate(flags)
+    }
+
+    pub fn offset(&self) -> u64 {
+        match self {
+            ElfProgramheader::ProgramHeader32(header) => header.offset as u64,
+            ElfProgramheader::ProgramHeader64(header) => header.offset,
+        }
+    }
+
+    pub fn vaddr(&self) -> u64 {
+        match self {
+            ElfProgramheader::ProgramHeader32(header) => header.vaddr as u64,
+            ElfProgramheader::ProgramHeader64(header) => header.vaddr,
+        }
+    }
+
+    pub fn filesz(&self) -> u64 {
+        match self {
+            ElfProgramheader::ProgramHeader32(header) => header.filesz as u64,
+            ElfProgramheader::ProgramHeader64(header) => header.filesz,
+        }
+    }
+
+    pub fn memsz(&self) -> u64 {
+        match self {
+            ElfProgramheader::ProgramHeader32(header) => header.memsz as u64,
+            ElfProgramheader::ProgramHeader64(header) => header.memsz,
+        }
+    }
+
+    pub fn align(&self) -> u64 {
+        match self {
+            ElfProgramheader::ProgramHeader32(header) => header.align as u64,
+            ElfProgramheader::ProgramHeader64(header) => header.align,
+        }
+    }
+}
+
 #[derive(Debug)]
 #[repr(C)]
 pub struct Elf32Phdr {
-    r#type: Elf32Word,
-    offset: Elf32Off,
-    vaddr: Elf32Addr,
-    paddr: Elf32Addr,
-    filesz: Elf32Word,
-    memsz: Elf32Word,
-    flags: Elf32Word,
-    align: Elf32Word,
+    pub r#type: Elf32Word,
+    pub offset: Elf32Off,
+    pub vaddr: Elf32Addr,
+    pub paddr: Elf32Addr,
+    pub filesz: Elf32Word,
+    pub memsz: Elf32Word,
+    pub flags: Elf32Word,
+    pub align: Elf32Word,
 }
 
 #[derive(Debug)]
 #[repr(C)]
 pub struct Elf64Phdr {
-    r#type: Elf64Word,
-    flags: Elf64Word,
-    offset: Elf64Off,
-    vaddr: Elf64Addr,
-    paddr: Elf64Addr,
-    filesz: Elf64Xword,
-    memsz: Elf64Xword,
-    align: Elf64Xword,
+    pub r#type: Elf64Word,
+    pub flags: Elf64Word,
+    pub offset: Elf64Off,
+    pub vaddr: Elf64Addr,
+    pub paddr: Elf64Addr,
+    pub filesz: Elf64Xword,
+    pub memsz: Elf64Xword,
+    pub align: Elf64Xword,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_program_header_accessors() {
+        let phdr = Elf64Phdr {
+            r#type: 0x6474e551,
+            flags: 0x6,
+            offset: 0,
+            vaddr: 0,
+            paddr: 0,
+            filesz: 0,
+            memsz: 0,
+            align: 16,
+        };
+        let header = ElfProgramheader::ProgramHeader64(&phdr);
+        assert_eq!(header.r#type(), Some(SegmentType::GnuStack));
+        assert_eq!(header.flags(), SegmentFlags::READ | SegmentFlags::WRITE);
+        assert!(!header.flags().contains(SegmentFlags::EXECUTE));
+
+        let phdr = Elf64Phdr {
+            r#type: 0x70000001,
+            ..phdr
+        };
+        assert_eq!(ElfProgramheader::ProgramHeader64(&phdr).r#type(), None);
+    }
 }
@@ -0,0 +1,136 @@
+use super::section::{ElfSectionHeader, ElfSectionType, Section};
+
+/// Adjust by program base, used for `R_X86_64_RELATIVE`
+pub const R_X86_64_RELATIVE: u32 = 8;
+/// Set GOT entry to data address
+pub const R_X86_64_GLOB_DAT: u32 = 6;
+/// Set GOT entry to code address, the entries lazily bound through the PLT
+pub const R_X86_64_JUMP_SLOT: u32 = 7;
+/// Adjust indirectly by program base, the resolver function is called to get the address
+pub const R_X86_64_IRELATIVE: u32 = 37;
+
+/// A relocation entry independent of the file class and whether it has an explicit addend.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Relocation {
+    /// Location to apply the relocation to, a virtual address for executables and shared objects
+    pub offset: u64,
+    pub r_type: u32,
+    /// Index into the symbol table the relocation section links to
+    pub symbol_index: u32,
+    pub addend: i64,
+}
+
+/// Parses the entries of a `Rel` or `Rela` section, other sections have no relocations.
+pub fn parse_relocations(section: &Section) -> Vec<Relocation> {
+    let bytes = section.bytes();
+    let is_rela = match section.r#type() {
+        ElfSectionType::Rela => true,
+        ElfSectionType::Rel => false,
+        _ => return Vec::new(),
+    };
+
+    match section.section_header() {
+        ElfSectionHeader::Section32(_) => {
+            let entry_size = if is_rela { 12 } else { 8 };
+            bytes
+                .chunks_exact(entry_size)
+                .map(|entry| {
+                    let info = read_u32(entry, 4);
+                    Relocation {
+                        offset: read_u32(entry, 0) as u64,
+                        r_type: info & 0xff,
+                        symbol_index: info >> 8,
+                        addend: if is_rela {
+                            read_u32(entry, 8) as i32 as i64
+                        } else {
+                            0
+                        },
+                    }
+                })
+                .collect()
+        }
+        ElfSectionHeader::Section64(_) => {
+            let entry_size = if is_rela { 24 } else { 16 };
+            bytes
+                .chunks_exact(entry_size)
+                .map(|entry| {
+                    let info = read_u64(entry, 8);
+                    Relocation {
+                        offset: read_u64(entry, 0),
+                        r_type: (info & 0xffffffff) as u32,
+                        symbol_index: (info >> 32) as u32,
+                        addend: if is_rela {
+                            read_u64(entry, 16) as i64
+                        } else {
+                            0
+                        },
+                    }
+                })
+                .collect()
+        }
+    }
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elf::section::{Elf64SectionFlags, Elf64Shdr};
+
+    #[test]
+    fn test_parse_rela64_relocations() {
+        let mut bytes = Vec::new();
+        for (offset, symbol_index, addend) in [(0x3fd0u64, 2u64, 0i64), (0x3fd8, 5, -8)] {
+            bytes.extend_from_slice(&offset.to_le_bytes());
+            bytes.extend_from_slice(
+                &((symbol_index << 32) | R_X86_64_JUMP_SLOT as u64).to_le_bytes(),
+            );
+            bytes.extend_from_slice(&addend.to_le_bytes());
+        }
+
+        let header = Elf64Shdr {
+            name: 0,
+            sh_type: ElfSectionType::Rela,
+            flags: Elf64SectionFlags::ALLOC,
+            addr: 0,
+            offset: 0,
+            size: bytes.len() as u64,
+            link: 0,
+            info: 0,
+            addr_align: 8,
+            ent_size: 24,
+        };
+        let section = Section::new(
+            ".rela.plt",
+            &header.sh_type,
+            &bytes,
+            ElfSectionHeader::Section64(&header),
+        );
+
+        let relocations = parse_relocations(&section);
+        assert_eq!(
+            relocations,
+            vec![
+                Relocation {
+                    offset: 0x3fd0,
+                    r_type: R_X86_64_JUMP_SLOT,
+                    symbol_index: 2,
+                    addend: 0
+                },
+                Relocation {
+                    offset: 0x3fd8,
+                    r_type: R_X86_64_JUMP_SLOT,
+                    symbol_index: 5,
+                    addend: -8
+                },
+            ]
+        );
+    }
+}
@@ -26,8 +26,15 @@ pub enum ElfSectionType {
     Group = 17,
     SymTabShndx = 18,
     LoOs = 0x60000000,
+    GnuAttributes = 0x6ffffff5,
+    GnuHash = 0x6ffffff6,
+    GnuLibList = 0x6ffffff7,
+    Checksum = 0x6ffffff8,
+    GnuVerDef = 0x6ffffffd,
+    GnuVerNeed = 0x6ffffffe,
     HiOs = 0x6fffffff,
     LoProc = 0x70000000,
+    X86_64Unwind = 0x70000001,
     Hiproc = 0x7fffffff,
     LoUser = 0x80000000,
     HiUser = 0xffffffff,
@@ -127,4 +134,62 @@ impl<'a> Section<'a> {
             section_header,
         }
     }
+
+    pub fn r#type(&self) -> &'a ElfSectionType {
+        self.r#type
+    }
+
+    /// The raw bytes of the section as found in the file.
+    /// Sections of type `NoBits` occupy no space in the file and have no bytes.
+    pub fn bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    pub fn section_header(&self) -> &ElfSectionHeader<'a> {
+        &self.section_header
+    }
+
+    /// Virtual address of the section when loaded, 0 if it is not part of the memory image
+    pub fn address(&self) -> u64 {
+        match self.section_header {
+            ElfSectionHeader::Section32(header) => header.addr as u64,
+            ElfSectionHeader::Section64(header) => header.addr,
+        }
+    }
+
+    /// Size of the section in memory, for `NoBits` sections this differs from `bytes().len()`
+    pub fn size(&self) -> u64 {
+        match self.section_header {
+            ElfSectionHeader::Section32(header) => header.size as u64,
+            ElfSectionHeader::Section64(header) => header.size,
+        }
+    }
+
+    /// Flags of the section, the 32-bit flags use the same bits as the 64-bit ones
+    pub fn flags(&self) -> Elf64SectionFlags {
+        match self.section_header {
+            ElfSectionHeader::Section32(header) => {
+                Elf64SectionFlags::from_bits_truncate(header.flags.bits() as u64)
+            }
+            ElfSectionHeader::Section64(header) => header.flags,
+        }
+    }
+
+    pub fn link(&self) -> u32 {
+        match self.section_header {
+            ElfSectionHeader::Section32(header) => header.link,
+            ElfSectionHeader::Section64(header) => header.link,
+        }
+    }
+
+    pub fn addr_align(&self) -> u64 {
+        match self.section_header {
+            ElfSectionHeader::Section32(header) => header.addr_align as u64,
+            ElfSectionHeader::Section64(header) => header.addr_align,
+        }
+    }
+
+    pub fn is_no_bits(&self) -> bool {
+        *self.r#type == ElfSectionType::NoBits
+    }
 }
@@ -138,4 +138,39 @@ impl<'a> Symbol<'a> {
     pub fn new(name: &'a str, elf_sym: ElfSym<'a>) -> Self {
         Self { name, elf_sym }
     }
+
+    pub fn value(&self) -> u64 {
+        match self.elf_sym {
+            ElfSym::Sym32(sym) => sym.value as u64,
+            ElfSym::Sym64(sym) => sym.value,
+        }
+    }
+
+    pub fn size(&self) -> u64 {
+        match self.elf_sym {
+            ElfSym::Sym32(sym) => sym.size as u64,
+            ElfSym::Sym64(sym) => sym.size,
+        }
+    }
+
+    pub fn bind(&self) -> Option<SymBinding> {
+        match self.elf_sym {
+            ElfSym::Sym32(sym) => sym.bind(),
+            ElfSym::Sym64(sym) => sym.bind(),
+        }
+    }
+
+    pub fn r#type(&self) -> Option<SymType> {
+        match self.elf_sym {
+            ElfSym::Sym32(sym) => sym.r#type(),
+            ElfSym::Sym64(sym) => sym.r#type(),
+        }
+    }
+
+    pub fn shndx(&self) -> u16 {
+        match self.elf_sym {
+            ElfSym::Sym32(sym) => sym.shndx,
+            ElfSym::Sym64(sym) => sym.shndx,
+        }
+    }
 }
@@ -0,0 +1,1029 @@
+use std::cmp::Ordering;
+
+use crate::dwarf::constants::*;
+use crate::dwarf::die::Die;
+use crate::dwarf::expression::EvaluationContext;
+use crate::dwarf::layout::with_pointers;
+use crate::dwarf::types::{array_dimensions, byte_size, strip_typedefs, type_name, type_of};
+use crate::dwarf::unit::Unit;
+use crate::dwarf::value::{
+    escape_char, float_value, format_c_string, format_float, is_char_type, is_signed_type,
+    read_memory, signed, unsigned, ValueFormatter,
+};
+use crate::expr::{parse_expression, BinaryOp, Expr, ExpressionError, Result, TypeName, UnaryOp};
+
+/// The type of a value of an expression: a type of the debug information, or one of the
+/// types of literals and arithmetic, which exist without it
+#[derive(Debug, Clone)]
+pub enum Type<'a> {
+    /// A type of the debug information of the unit, `None` is `void`
+    Dwarf(Unit<'a>, Option<Die<'a>>),
+    /// The rows of a multidimensional array of the debug information, like `int [3]`
+    /// of `int [2][3]`
+    Array {
+        unit: Unit<'a>,
+        element: Option<Die<'a>>,
+        dimensions: Vec<Option<u64>>,
+    },
+    Void,
+    Bool,
+    Char {
+        signed: bool,
+    },
+    Int {
+        size: usize,
+        signed: bool,
+    },
+    Float {
+        size: usize,
+    },
+    Pointer(Box<Type<'a>>),
+}
+
+/// How values of a type are operated on
+enum Kind<'a> {
+    /// Integers, characters, booleans and enums
+    Int {
+        size: usize,
+        signed: bool,
+    },
+    Float,
+    /// Pointers to the type
+    Pointer(Type<'a>),
+    Reference(Type<'a>),
+    Array {
+        element: Type<'a>,
+    },
+    Struct(Unit<'a>, Die<'a>),
+    Void,
+    Other,
+}
+
+impl<'a> Type<'a> {
+    /// The size of its values, `None` for types without one like `void` and functions
+    pub fn size(&self) -> Result<Option<u64>> {
+        Ok(match self {
+            Type::Dwarf(unit, Some(die)) => byte_size(unit, die)?,
+            Type::Dwarf(_, None) | Type::Void => None,
+            Type::Array {
+                unit,
+                element,
+                dimensions,
+            } => {
+                let element_size = match element {
+                    Some(element) => byte_size(unit, element)?,
+                    None => None,
+                };
+                element_size.and_then(|size| {
+                    dimensions
+                        .iter()
+                        .try_fold(size, |size, count| count.map(|count| size * count))
+                })
+            }
+            Type::Bool | Type::Char { .. } => Some(1),
+            Type::Int { size, .. } | Type::Float { size } => Some(*size as u64),
+            Type::Pointer(_) => Some(8),
+        })
+    }
+
+    /// The name of the type as it is written in C, e.g. `int [3]` or `char *`
+    pub fn name(&self) -> Result<String> {
+        Ok(match self {
+            Type::Dwarf(unit, die) => type_name(unit, die.as_ref())?,
+            Type::Array {
+                unit,
+                element,
+                dimensions,
+            } => {
+                let element = type_name(unit, element.as_ref())?;
+                let dimensions: String = dimensions
+                    .iter()
+                    .map(|count| match count {
+                        Some(count) => format!("[{}]", count),
+                        None => "[]".to_string(),
+                    })
+                    .collect();
+                if element.ends_with('*') {
+                    format!("{}{}", element, dimensions)
+                } else {
+                    format!("{} {}", element, dimensions)
+                }
+            }
+            Type::Void => "void".to_string(),
+            Type::Bool => "_Bool".to_string(),
+            Type::Char { signed: true } => "char".to_string(),
+            Type::Char { signed: false } => "unsigned char".to_string(),
+            Type::Int { size, signed } => {
+                let name = match size {
+                    1 => "char",
+                    2 => "short",
+                    4 => "int",
+                    16 => "__int128",
+                    _ => "long",
+                };
+                match (size, signed) {
+                    (1, true) => "signed char".to_string(),
+                    (_, true) => name.to_string(),
+                    (_, false) => format!("unsigned {}", name),
+                }
+            }
+            Type::Float { size: 4 } => "float".to_string(),
+            Type::Float { .. } => "double".to_string(),
+            Type::Pointer(target) => with_pointers(target.name()?, "*"),
+        })
+    }
+
+    fn kind(&self) -> Result<Kind<'a>> {
+        let (unit, die) = match self {
+            Type::Dwarf(unit, die) => match strip_typedefs(unit, die.clone())? {
+                Some(die) => (unit, die),
+                None => return Ok(Kind::Void),
+            },
+            Type::Array {
+                unit,
+                element,
+                dimensions,
+            } => return Ok(array_kind(unit, element, dimensions)),
+            Type::Void => return Ok(Kind::Void),
+            Type::Bool => {
+                return Ok(Kind::Int {
+                    size: 1,
+                    signed: false,
+                })
+            }
+            Type::Char { signed } => {
+                return Ok(Kind::Int {
+                    size: 1,
+                    signed: *signed,
+                })
+            }
+            Type::Int { size, signed } => {
+                return Ok(Kind::Int {
+                    size: *size,
+                    signed: *signed,
+                })
+            }
+            Type::Float { .. } => return Ok(Kind::Float),
+            Type::Pointer(target) => return Ok(Kind::Pointer((**target).clone())),
+        };
+
+        let size = byte_size(unit, &die)?.unwrap_or(0) as usize;
+        Ok(match die.tag {
+            DW_TAG_BASE_TYPE => match die.attr(DW_AT_ENCODING).and_then(|v| v.udata()) {
+                Some(encoding) => match encoding as u8 {
+                    DW_ATE_FLOAT => Kind::Float,
+                    DW_ATE_SIGNED | DW_ATE_SIGNED_CHAR => Kind::Int { size, signed: true },
+                    DW_ATE_BOOLEAN | DW_ATE_UNSIGNED | DW_ATE_UNSIGNED_CHAR | DW_ATE_UTF
+                    | DW_ATE_ASCII => Kind::Int {
+                        size,
+                        signed: false,
+                    },
+                    _ => Kind::Other,
+                },
+                None => Kind::Other,
+            },
+            DW_TAG_ENUMERATION_TYPE => Kind::Int {
+                size,
+                signed: is_signed_type(unit, Some(&die))?,
+            },
+            DW_TAG_POINTER_TYPE => Kind::Pointer(Type::Dwarf(unit.clone(), type_of(unit, &die)?)),
+            DW_TAG_REFERENCE_TYPE | DW_TAG_RVALUE_REFERENCE_TYPE => {
+                Kind::Reference(Type::Dwarf(unit.clone(), type_of(unit, &die)?))
+            }
+            DW_TAG_ARRAY_TYPE => {
+                array_kind(unit, &type_of(unit, &die)?, &array_dimensions(unit, &die)?)
+            }
+            DW_TAG_STRUCTURE_TYPE | DW_TAG_CLASS_TYPE | DW_TAG_UNION_TYPE => {
+                Kind::Struct(unit.clone(), die)
+            }
+            _ => Kind::Other,
+        })
+    }
+}
+
+/// The kind of an array, whose elements are the rows of the inner dimensions if it has more
+/// than one
+fn array_kind<'a>(
+    unit: &Unit<'a>,
+    element: &Option<Die<'a>>,
+    dimensions: &[Option<u64>],
+) -> Kind<'a> {
+    let element = match dimensions {
+        [_, inner @ ..] if !inner.is_empty() => Type::Array {
+            unit: unit.clone(),
+            element: element.clone(),
+            dimensions: inner.to_vec(),
+        },
+        _ => Type::Dwarf(unit.clone(), element.clone()),
+    };
+    Kind::Array { element }
+}
+
+/// The types C and Rust have built in, for casts in programs whose debug information
+/// does not have them
+fn builtin_type(name: &str) -> Option<Type<'static>> {
+    let int = |size, signed| Some(Type::Int { size, signed });
+    match name {
+        "void" => Some(Type::Void),
+        "_Bool" | "bool" => Some(Type::Bool),
+        "char" | "signed char" => Some(Type::Char { signed: true }),
+        "unsigned char" => Some(Type::Char { signed: false }),
+        "i8" => int(1, true),
+        "u8" => int(1, false),
+        "short" | "short int" | "signed short" | "i16" => int(2, true),
+        "unsigned short" | "unsigned short int" | "u16" => int(2, false),
+        "int" | "signed" | "signed int" | "i32" => int(4, true),
+        "unsigned" | "unsigned int" | "u32" => int(4, false),
+        "long" | "long int" | "signed long" | "long long" | "long long int" | "isize" | "i64" => {
+            int(8, true)
+        }
+        "unsigned long" | "unsigned long int" | "unsigned long long" | "usize" | "u64" => {
+            int(8, false)
+        }
+        "i128" => int(16, true),
+        "u128" => int(16, false),
+        "float" | "f32" => Some(Type::Float { size: 4 }),
+        "double" | "f64" => Some(Type::Float { size: 8 }),
+        _ => None,
+    }
+}
+
+/// The value of an expression
+#[derive(Debug, Clone)]
+pub struct Value<'a> {
+    pub value_type: Type<'a>,
+    pub bytes: Vec<u8>,
+    /// Where the value is stored in the process, `None` for values computed by the
+    /// expression or held in registers
+    pub address: Option<u64>,
+}
+
+/// A value operated on by arithmetic and comparisons
+enum Scalar<'a> {
+    /// The value, its size and whether it is signed
+    Int(i128, usize, bool),
+    Float(f64),
+    /// The address and the type pointed to
+    Pointer(u64, Box<Type<'a>>),
+}
+
+impl<'a> Scalar<'a> {
+    fn to_f64(&self) -> f64 {
+        match self {
+            Scalar::Int(value, _, true) => *value as f64,
+            Scalar::Int(value, _, false) => *value as u128 as f64,
+            Scalar::Float(value) => *value,
+            Scalar::Pointer(address, _) => *address as f64,
+        }
+    }
+}
+
+impl<'a> Value<'a> {
+    pub fn new(value_type: Type<'a>, bytes: Vec<u8>, address: Option<u64>) -> Self {
+        Self {
+            value_type,
+            bytes,
+            address,
+        }
+    }
+
+    /// The value of unset convenience variables
+    pub fn void() -> Self {
+        Self::new(Type::Void, Vec::new(), None)
+    }
+
+    pub fn integer(value: i128, size: usize, signed: bool) -> Self {
+        Self::new(
+            Type::Int { size, signed },
+            value.to_le_bytes()[..size.min(16)].to_vec(),
+            None,
+        )
+    }
+
+    /// The result of comparisons and logical operators, an `int` like in C
+    fn boolean(value: bool) -> Self {
+        Self::integer(value as i128, 4, true)
+    }
+
+    fn float(value: f64) -> Self {
+        Self::new(Type::Float { size: 8 }, value.to_le_bytes().to_vec(), None)
+    }
+
+    pub fn pointer(target: Type<'a>, address: u64) -> Self {
+        Self::new(
+            Type::Pointer(Box::new(target)),
+            address.to_le_bytes().to_vec(),
+            None,
+        )
+    }
+
+    /// Formats the value by its type, pointers and references are followed through `context`
+    pub fn format<C: EvaluationContext>(&self, context: &C) -> Result<String> {
+        let bytes = &self.bytes;
+        Ok(match &self.value_type {
+            Type::Dwarf(unit, die) => {
+                ValueFormatter::new(unit, context).format(die.as_ref(), bytes)?
+            }
+            Type::Array {
+                unit,
+                element,
+                dimensions,
+            } => {
+                let element_size = match element {
+                    Some(element) => byte_size(unit, element)?.unwrap_or(0),
+                    None => 0,
+                };
+                ValueFormatter::new(unit, context).format_elements(
+                    element.as_ref(),
+                    element_size,
+                    dimensions,
+                    bytes,
+                )?
+            }
+            Type::Void => "void".to_string(),
+            Type::Bool => match unsigned(bytes) {
+                0 => "false".to_string(),
+                1 => "true".to_string(),
+                value => value.to_string(),
+            },
+            Type::Char { signed: true } => {
+                let value = signed(bytes);
+                format!("{} '{}'", value, escape_char(value as u8 as u32, '\''))
+            }
+            Type::Char { signed: false } => {
+                let value = unsigned(bytes);
+                format!("{} '{}'", value, escape_char(value as u32, '\''))
+            }
+            Type::Int { signed: true, .. } => signed(bytes).to_string(),
+            Type::Int { signed: false, .. } => unsigned(bytes).to_string(),
+            Type::Float { .. } => format_float(bytes),
+            Type::Pointer(target) => {
+                let address = unsigned(bytes) as u64;
+                let is_string = match &**target {
+                    Type::Char { .. } => true,
+                    Type::Dwarf(unit, die) => is_char_type(unit, die.as_ref())?,
+                    _ => false,
+                };
+                if is_string && address != 0 {
+                    format_c_string(context, address)
+                } else {
+                    format!("0x{:x}", address)
+                }
+            }
+        })
+    }
+
+    /// Whether the value is true as a condition, that is not zero
+    pub fn is_true(&self) -> Result<bool> {
+        Ok(match self.scalar("a condition")? {
+            Scalar::Int(value, ..) => value != 0,
+            Scalar::Float(value) => value != 0.0,
+            Scalar::Pointer(address, _) => address != 0,
+        })
+    }
+
+    /// The address the value is as an integer or pointer, or where it is stored for arrays
+    pub fn as_address(&self) -> Result<u64> {
+        match self.scalar("an address")? {
+            Scalar::Int(value, ..) => Ok(value as u64),
+            Scalar::Pointer(address, _) => Ok(address),
+            Scalar::Float(_) => Err(ExpressionError::InvalidOperand("an address")),
+        }
+    }
+
+    fn scalar(&self, operation: &'static str) -> Result<Scalar<'a>> {
+        match self.value_type.kind()? {
+            Kind::Int { size, signed: true } => Ok(Scalar::Int(signed(&self.bytes), size, true)),
+            Kind::Int { size, .. } => Ok(Scalar::Int(unsigned(&self.bytes) as i128, size, false)),
+            Kind::Float => float_value(&self.bytes)
+                .map(Scalar::Float)
+                .ok_or(ExpressionError::InvalidOperand(operation)),
+            Kind::Pointer(target) | Kind::Reference(target) => Ok(Scalar::Pointer(
+                unsigned(&self.bytes) as u64,
+                Box::new(target),
+            )),
+            // Arrays decay to pointers to their first element
+            Kind::Array { element } => match self.address {
+                Some(address) => Ok(Scalar::Pointer(address, Box::new(element))),
+                None => Err(ExpressionError::NotAnLvalue),
+            },
+            _ => Err(ExpressionError::InvalidOperand(operation)),
+        }
+    }
+}
+
+/// What expressions are evaluated against, the variables, registers and types visible
+/// in a frame of the process
+pub trait Environment<'a> {
+    type Context: EvaluationContext;
+
+    /// Reads the memory of the process, also when values are formatted
+    fn context(&self) -> &Self::Context;
+    /// The variable `name` visible at the pc, `None` if there is none
+    fn variable(&self, name: &str) -> Result<Option<Value<'a>>>;
+    /// The register `name`, like `rip` or `pc`, `None` if there is none
+    fn register(&self, name: &str) -> Result<Option<Value<'a>>>;
+    /// The type `name` of the debug information, like `struct point` or `size_t`
+    fn find_type(&self, name: &str) -> Result<Option<Type<'a>>>;
+    fn convenience_variable(&self, name: &str) -> Option<Value<'a>>;
+    fn set_convenience_variable(&mut self, name: &str, value: Value<'a>);
+}
+
+/// Evaluates expressions with the semantics of C, e.g. pointer arithmetic is scaled by
+/// the size of the type pointed to. Like in Rust, `.` follows pointers and `Vec`s and
+/// slices are indexed with a bounds check.
+pub struct ExpressionEvaluator<'e, E> {
+    environment: &'e mut E,
+}
+
+impl<'e, 'a, E: Environment<'a>> ExpressionEvaluator<'e, E> {
+    pub fn new(environment: &'e mut E) -> Self {
+        Self { environment }
+    }
+
+    /// Parses and evaluates `text`
+    pub fn evaluate_text(&mut self, text: &str) -> Result<Value<'a>> {
+        let expr = self.parse_text(text)?;
+        self.evaluate(&expr)
+    }
+
+    /// Parses `text`, telling casts from other parenthesized expressions by the types
+    /// of the environment
+    pub fn parse_text(&self, text: &str) -> Result<Expr> {
+        let environment = &*self.environment;
+        let is_type = |name: &str| {
+            builtin_type(name).is_some() || matches!(environment.find_type(name), Ok(Some(_)))
+        };
+        parse_expression(text, &is_type)
+    }
+
+    pub fn evaluate(&mut self, expr: &Expr) -> Result<Value<'a>> {
+        match expr {
+            Expr::Integer(value) => Ok(integer_literal(*value)),
+            Expr::Float(value) => Ok(Value::float(*value)),
+            Expr::Char(value) => Ok(Value::new(Type::Char { signed: true }, vec![*value], None)),
+            Expr::Variable(name) => self
+                .environment
+                .variable(name)?
+                .ok_or_else(|| ExpressionError::NoSymbol(name.clone())),
+            Expr::Dollar(name) => match self.environment.register(name)? {
+                Some(value) => Ok(value),
+                None => Ok(self
+                    .environment
+                    .convenience_variable(name)
+                    .unwrap_or_else(Value::void)),
+            },
+            Expr::Unary(op, operand) => {
+                let operand = self.evaluate(operand)?;
+                self.unary(*op, operand)
+            }
+            // Logical operators only evaluate their right operand if it decides the result
+            Expr::Binary(BinaryOp::And, left, right) => {
+                let value = self.evaluate(left)?.is_true()? && self.evaluate(right)?.is_true()?;
+                Ok(Value::boolean(value))
+            }
+            Expr::Binary(BinaryOp::Or, left, right) => {
+                let value = self.evaluate(left)?.is_true()? || self.evaluate(right)?.is_true()?;
+                Ok(Value::boolean(value))
+            }
+            Expr::Binary(op, left, right) => {
+                let left = self.evaluate(left)?;
+                let right = self.evaluate(right)?;
+                self.binary(*op, left, right)
+            }
+            Expr::Member(base, name) => {
+                let mut base = self.evaluate(base)?;
+                while let Kind::Pointer(target) | Kind::Reference(target) =
+                    base.value_type.kind()?
+                {
+                    base = self.dereference(target, &base)?;
+                }
+                self.member(base, name)
+            }
+            Expr::Arrow(base, name) => {
+                let base = self.evaluate(base)?;
+                match base.value_type.kind()? {
+                    Kind::Pointer(target) | Kind::Reference(target) => {
+                        let base = self.dereference(target, &base)?;
+                        self.member(base, name)
+                    }
+                    _ => Err(ExpressionError::InvalidOperand("->")),
+                }
+            }
+            Expr::Index(base, index) => {
+                let base = self.evaluate(base)?;
+                let index = self.evaluate(index)?;
+                self.index(base, index)
+            }
+            Expr::Cast(type_name, operand) => {
+                let target = self.resolve_type(type_name)?;
+                let operand = self.evaluate(operand)?;
+                self.cast(target, operand)
+            }
+            Expr::Conditional(condition, then, otherwise) => {
+                if self.evaluate(condition)?.is_true()? {
+                    self.evaluate(then)
+                } else {
+                    self.evaluate(otherwise)
+                }
+            }
+            Expr::Assign(target, value) => match &**target {
+                Expr::Dollar(name) if self.environment.register(name)?.is_none() => {
+                    // Convenience variables hold a copy of the value
+                    let value = Value {
+                        address: None,
+                        ..self.evaluate(value)?
+                    };
+                    self.environment
+                        .set_convenience_variable(name, value.clone());
+                    Ok(value)
+                }
+                _ => Err(ExpressionError::NotAssignable),
+            },
+        }
+    }
+
+    /// Reads a value of the type `value_type` at `address`
+    fn read(&self, value_type: Type<'a>, address: u64) -> Result<Value<'a>> {
+        let size = value_type
+            .size()?
+            .ok_or(ExpressionError::InvalidOperand("dereference"))?;
+        let bytes = read_memory(self.environment.context(), address, size as usize)?;
+        Ok(Value::new(value_type, bytes, Some(address)))
+    }
+
+    /// Reads the value of the type `target` that the pointer or reference `value` points to
+    fn dereference(&self, target: Type<'a>, value: &Value<'a>) -> Result<Value<'a>> {
+        self.read(target, unsigned(&value.bytes) as u64)
+    }
+
+    /// Follows references, which are operated on like the value they refer to
+    fn referenced(&self, mut value: Value<'a>) -> Result<Value<'a>> {
+        while let Kind::Reference(target) = value.value_type.kind()? {
+            value = self.dereference(target, &value)?;
+        }
+        Ok(value)
+    }
+
+    /// The size pointer arithmetic scales by, 1 for `void *` like gdb does
+    fn stride(&self, target: &Type<'a>) -> Result<u64> {
+        Ok(target.size()?.unwrap_or(1).max(1))
+    }
+
+    /// The type named `type_name` in the environment, or built into C and Rust
+    pub fn resolve_type(&self, type_name: &TypeName) -> Result<Type<'a>> {
+        let mut resolved = match self.environment.find_type(&type_name.name)? {
+            Some(resolved) => resolved,
+            None => builtin_type(&type_name.name)
+                .ok_or_else(|| ExpressionError::UnknownType(type_name.name.clone()))?,
+        };
+        for _ in 0..type_name.pointers {
+            resolved = Type::Pointer(Box::new(resolved));
+        }
+        Ok(resolved)
+    }
+
+    fn unary(&self, op: UnaryOp, operand: Value<'a>) -> Result<Value<'a>> {
+        if op == UnaryOp::AddressOf {
+            return match operand.address {
+                Some(address) => Ok(Value::pointer(operand.value_type, address)),
+                None => Err(ExpressionError::NotAnLvalue),
+            };
+        }
+        let operand = self.referenced(operand)?;
+        match op {
+            UnaryOp::Deref => match operand.value_type.kind()? {
+                Kind::Pointer(target) => self.dereference(target, &operand),
+                Kind::Array { element } => match operand.address {
+                    Some(address) => self.read(element, address),
+                    None => Err(ExpressionError::NotAnLvalue),
+                },
+                _ => Err(ExpressionError::InvalidOperand("unary *")),
+            },
+            UnaryOp::Not => Ok(Value::boolean(!operand.is_true()?)),
+            UnaryOp::Negate => match operand.scalar("unary -")? {
+                Scalar::Int(value, size, signed) => {
+                    let (size, signed) = promote(size, signed);
+                    Ok(Value::integer(value.wrapping_neg(), size, signed))
+                }
+                Scalar::Float(value) => Ok(Value::float(-value)),
+                Scalar::Pointer(..) => Err(ExpressionError::InvalidOperand("unary -")),
+            },
+            UnaryOp::Complement => match operand.scalar("~")? {
+                Scalar::Int(value, size, signed) => {
+                    let (size, signed) = promote(size, signed);
+                    Ok(Value::integer(!value, size, signed))
+                }
+                _ => Err(ExpressionError::InvalidOperand("~")),
+            },
+            UnaryOp::AddressOf => unreachable!(),
+        }
+    }
+
+    fn binary(&self, op: BinaryOp, left: Value<'a>, right: Value<'a>) -> Result<Value<'a>> {
+        let name = operator_name(op);
+        let left = self.referenced(left)?.scalar(name)?;
+        let right = self.referenced(right)?.scalar(name)?;
+        match (left, right) {
+            (Scalar::Pointer(address, target), Scalar::Int(offset, ..))
+                if matches!(op, BinaryOp::Add | BinaryOp::Sub) =>
+            {
+                let offset = if op == BinaryOp::Sub { -offset } else { offset };
+                let stride = self.stride(&target)? as i128;
+                let address = (address as i128).wrapping_add(offset.wrapping_mul(stride));
+                Ok(Value::pointer(*target, address as u64))
+            }
+            (Scalar::Int(offset, ..), Scalar::Pointer(address, target)) if op == BinaryOp::Add => {
+                let stride = self.stride(&target)? as i128;
+                let address = (address as i128).wrapping_add(offset.wrapping_mul(stride));
+                Ok(Value::pointer(*target, address as u64))
+            }
+            (Scalar::Pointer(left, target), Scalar::Pointer(right, _)) if op == BinaryOp::Sub => {
+                let stride = self.stride(&target)? as i128;
+                let difference = left.wrapping_sub(right) as i64 as i128;
+                Ok(Value::integer(difference / stride, 8, true))
+            }
+            (left, right) => arithmetic(op, left, right),
+        }
+    }
+
+    fn member(&self, base: Value<'a>, name: &str) -> Result<Value<'a>> {
+        let (unit, die) = match base.value_type.kind()? {
+            Kind::Struct(unit, die) => (unit, die),
+            _ => return Err(ExpressionError::InvalidOperand("member access")),
+        };
+        let formatter = ValueFormatter::new(&unit, self.environment.context());
+        match formatter.member_named(&die, &base.bytes, name)? {
+            Some((member_type, bytes, offset)) => {
+                let address = match (base.address, offset) {
+                    (Some(address), Some(offset)) => Some(address + offset),
+                    _ => None,
+                };
+                Ok(Value::new(
+                    Type::Dwarf(unit.clone(), member_type),
+                    bytes,
+                    address,
+                ))
+            }
+            None => Err(ExpressionError::NoMember(name.to_string())),
+        }
+    }
+
+    fn index(&self, base: Value<'a>, index: Value<'a>) -> Result<Value<'a>> {
+        let base = self.referenced(base)?;
+        let index = match self.referenced(index)?.scalar("[]")? {
+            Scalar::Int(index, ..) => index,
+            _ => return Err(ExpressionError::InvalidOperand("[]")),
+        };
+        match base.value_type.kind()? {
+            Kind::Array { element } => {
+                let stride = element.size()?.unwrap_or(0);
+                let offset = index.wrapping_mul(stride as i128);
+                if let Ok(start) = usize::try_from(offset) {
+                    if let Some(bytes) = base.bytes.get(start..start + stride as usize) {
+                        let address = base.address.map(|address| address + start as u64);
+                        return Ok(Value::new(element, bytes.to_vec(), address));
+                    }
+                }
+                // Like C, elements outside of the array are read from memory after it
+                match base.address {
+                    Some(address) => self.read(element, address.wrapping_add(offset as u64)),
+                    None => Err(ExpressionError::NotAnLvalue),
+                }
+            }
+            Kind::Pointer(target) => {
+                let offset = index.wrapping_mul(self.stride(&target)? as i128);
+                let address = (unsigned(&base.bytes) as u64).wrapping_add(offset as u64);
+                self.read(target, address)
+            }
+            Kind::Struct(unit, die) => {
+                let formatter = ValueFormatter::new(&unit, self.environment.context());
+                let (address, len, element) = match formatter.sequence(&die, &base.bytes)? {
+                    Some(sequence) => sequence,
+                    None => return Err(ExpressionError::InvalidOperand("[]")),
+                };
+                if index < 0 || index >= len as i128 {
+                    return Err(ExpressionError::IndexOutOfBounds { index, len });
+                }
+                let element = Type::Dwarf(unit.clone(), element);
+                let stride = element.size()?.unwrap_or(0);
+                self.read(element, address + index as u64 * stride)
+            }
+            _ => Err(ExpressionError::InvalidOperand("[]")),
+        }
+    }
+
+    fn cast(&self, target: Type<'a>, operand: Value<'a>) -> Result<Value<'a>> {
+        let operand = self.referenced(operand)?;
+        let bytes = match target.kind()? {
+            Kind::Void => Vec::new(),
+            Kind::Int { size, .. } => {
+                let value = match operand.scalar("cast")? {
+                    Scalar::Int(value, ..) => value,
+                    Scalar::Float(value) => value as i128,
+                    Scalar::Pointer(address, _) => address as i128,
+                };
+                let value = match target {
+                    Type::Bool => (value != 0) as i128,
+                    _ => value,
+                };
+                value.to_le_bytes()[..size.min(16)].to_vec()
+            }
+            Kind::Float => {
+                let value = operand.scalar("cast")?.to_f64();
+                match target.size()? {
+                    Some(4) => (value as f32).to_le_bytes().to_vec(),
+                    Some(8) => value.to_le_bytes().to_vec(),
+                    _ => return Err(ExpressionError::InvalidOperand("cast")),
+                }
+            }
+            Kind::Pointer(_) | Kind::Reference(_) => match operand.scalar("cast")? {
+                Scalar::Int(value, ..) => (value as u64).to_le_bytes().to_vec(),
+                Scalar::Pointer(address, _) => address.to_le_bytes().to_vec(),
+                Scalar::Float(_) => return Err(ExpressionError::InvalidOperand("cast")),
+            },
+            // Other types reinterpret values of their size, like a structure of its typedef
+            _ if target.size()? == Some(operand.bytes.len() as u64) => {
+                return Ok(Value::new(target, operand.bytes, operand.address));
+            }
+            _ => return Err(ExpressionError::InvalidOperand("cast")),
+        };
+        Ok(Value::new(target, bytes, None))
+    }
+}
+
+/// The value of an integer literal, an `int` if it fits like in C
+fn integer_literal(value: u128) -> Value<'static> {
+    let (size, signed) = if value <= i32::MAX as u128 {
+        (4, true)
+    } else if value <= i64::MAX as u128 {
+        (8, true)
+    } else if value <= u64::MAX as u128 {
+        (8, false)
+    } else {
+        (16, false)
+    };
+    Value::integer(value as i128, size, signed)
+}
+
+fn operator_name(op: BinaryOp) -> &'static str {
+    match op {
+        BinaryOp::Add => "+",
+        BinaryOp::Sub => "-",
+        BinaryOp::Mul => "*",
+        BinaryOp::Div => "/",
+        BinaryOp::Rem => "%",
+        BinaryOp::Shl => "<<",
+        BinaryOp::Shr => ">>",
+        BinaryOp::BitAnd => "&",
+        BinaryOp::BitOr => "|",
+        BinaryOp::BitXor => "^",
+        BinaryOp::And => "&&",
+        BinaryOp::Or => "||",
+        BinaryOp::Eq => "==",
+        BinaryOp::Ne => "!=",
+        BinaryOp::Lt => "<",
+        BinaryOp::Le => "<=",
+        BinaryOp::Gt => ">",
+        BinaryOp::Ge => ">=",
+    }
+}
+
+/// The result of a comparison, `None` for other operators
+fn compare(op: BinaryOp, ordering: Option<Ordering>) -> Option<bool> {
+    let ordering = ordering.unwrap_or(Ordering::Less);
+    match op {
+        BinaryOp::Eq => Some(ordering == Ordering::Equal),
+        BinaryOp::Ne => Some(ordering != Ordering::Equal),
+        BinaryOp::Lt => Some(ordering == Ordering::Less),
+        BinaryOp::Le => Some(ordering != Ordering::Greater),
+        BinaryOp::Gt => Some(ordering == Ordering::Greater),
+        BinaryOp::Ge => Some(ordering != Ordering::Less),
+        _ => None,
+    }
+}
+
+/// Applies an operator to numbers, or compares pointers
+fn arithmetic<'a>(op: BinaryOp, left: Scalar<'a>, right: Scalar<'a>) -> Result<Value<'a>> {
+    let name = operator_name(op);
+    let is_comparison = compare(op, None).is_some();
+    match (left, right) {
+        (
+            Scalar::Int(left, left_size, left_signed),
+            Scalar::Int(right, right_size, right_signed),
+        ) => integer_arithmetic(
+            op,
+            (left, left_size, left_signed),
+            (right, right_size, right_signed),
+        ),
+        // Pointers are compared as addresses
+        (left @ Scalar::Pointer(..), right) | (left, right @ Scalar::Pointer(..)) => {
+            if !is_comparison
+                || matches!(
+                    (&left, &right),
+                    (Scalar::Float(_), _) | (_, Scalar::Float(_))
+                )
+            {
+                return Err(ExpressionError::InvalidOperand(name));
+            }
+            let address = |scalar: &Scalar| match scalar {
+                Scalar::Pointer(address, _) => *address,
+                Scalar::Int(value, ..) => *value as u64,
+                Scalar::Float(_) => unreachable!(),
+            };
+            let ordering = address(&left).cmp(&address(&right));
+            Ok(Value::boolean(compare(op, Some(ordering)).unwrap_or(false)))
+        }
+        (left, right) => {
+            let (left, right) = (left.to_f64(), right.to_f64());
+            if let Some(result) = compare(op, left.partial_cmp(&right)) {
+                // Comparisons with NaN are false, apart from `!=`
+                let result = match left.partial_cmp(&right) {
+                    Some(_) => result,
+                    None => op == BinaryOp::Ne,
+                };
+                return Ok(Value::boolean(result));
+            }
+            let value = match op {
+                BinaryOp::Add => left + right,
+                BinaryOp::Sub => left - right,
+                BinaryOp::Mul => left * right,
+                BinaryOp::Div => left / right,
+                BinaryOp::Rem => left % right,
+                _ => return Err(ExpressionError::InvalidOperand(name)),
+            };
+            Ok(Value::float(value))
+        }
+    }
+}
+
+/// Applies an operator to integers after converting them to a common type like C does
+fn integer_arithmetic<'a>(
+    op: BinaryOp,
+    (left, left_size, left_signed): (i128, usize, bool),
+    (right, right_size, right_signed): (i128, usize, bool),
+) -> Result<Value<'a>> {
+    // The result of shifts has the type of the left operand
+    let (size, signed) = match op {
+        BinaryOp::Shl | BinaryOp::Shr => promote(left_size, left_signed),
+        _ => common_type((left_size, left_signed), (right_size, right_signed)),
+    };
+    let left = normalize(left, size, signed);
+    let right = match op {
+        BinaryOp::Shl | BinaryOp::Shr => right,
+        _ => normalize(right, size, signed),
+    };
+    let ordering = if signed {
+        left.cmp(&right)
+    } else {
+        (left as u128).cmp(&(right as u128))
+    };
+    if let Some(result) = compare(op, Some(ordering)) {
+        return Ok(Value::boolean(result));
+    }
+
+    let value = match op {
+        BinaryOp::Add => left.wrapping_add(right),
+        BinaryOp::Sub => left.wrapping_sub(right),
+        BinaryOp::Mul => left.wrapping_mul(right),
+        BinaryOp::Div | BinaryOp::Rem if right == 0 => return Err(ExpressionError::DivisionByZero),
+        BinaryOp::Div if signed => left.wrapping_div(right),
+        BinaryOp::Div => ((left as u128) / (right as u128)) as i128,
+        BinaryOp::Rem if signed => left.wrapping_rem(right),
+        BinaryOp::Rem => ((left as u128) % (right as u128)) as i128,
+        BinaryOp::Shl => left.wrapping_shl(right.clamp(0, 127) as u32),
+        BinaryOp::Shr if signed => left >> right.clamp(0, 127),
+        BinaryOp::Shr => ((left as u128) >> right.clamp(0, 127)) as i128,
+        BinaryOp::BitAnd => left & right,
+        BinaryOp::BitOr => left | right,
+        BinaryOp::BitXor => left ^ right,
+        _ => return Err(ExpressionError::InvalidOperand(operator_name(op))),
+    };
+    Ok(Value::integer(value, size, signed))
+}
+
+/// Integers smaller than `int` are operated on as `int`
+fn promote(size: usize, signed: bool) -> (usize, bool) {
+    if size < 4 {
+        (4, true)
+    } else {
+        (size, signed)
+    }
+}
+
+/// The type both operands of an arithmetic operator are converted to: the larger one, or
+/// the unsigned one of types of the same size
+fn common_type(left: (usize, bool), right: (usize, bool)) -> (usize, bool) {
+    let (left, right) = (promote(left.0, left.1), promote(right.0, right.1));
+    match left.0.cmp(&right.0) {
+        Ordering::Equal => (left.0, left.1 && right.1),
+        Ordering::Greater => left,
+        Ordering::Less => right,
+    }
+}
+
+/// Converts a value to an integer of `size` bytes
+fn normalize(value: i128, size: usize, signed: bool) -> i128 {
+    if size == 0 || size >= 16 {
+        return value;
+    }
+    let bits = size as u32 * 8;
+    let truncated = value as u128 & ((1 << bits) - 1);
+    if signed {
+        ((truncated << (128 - bits)) as i128) >> (128 - bits)
+    } else {
+        truncated as i128
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evaluate(op: BinaryOp, left: Value<'static>, right: Value<'static>) -> Result<String> {
+        let left = left.scalar("test")?;
+        let right = right.scalar("test")?;
+        let value = arithmetic(op, left, right)?;
+        Ok(match value.value_type {
+            Type::Int { signed: true, .. } => signed(&value.bytes).to_string(),
+            Type::Int { signed: false, .. } => unsigned(&value.bytes).to_string(),
+            Type::Float { .. } => format_float(&value.bytes),
+            _ => unreachable!(),
+        })
+    }
+
+    #[test]
+    fn test_integer_arithmetic() {
+        let int = |value| Value::integer(value, 4, true);
+        let unsigned = |value| Value::integer(value, 4, false);
+        assert_eq!(evaluate(BinaryOp::Add, int(2), int(3)).unwrap(), "5");
+        assert_eq!(evaluate(BinaryOp::Div, int(-7), int(2)).unwrap(), "-3");
+        assert_eq!(evaluate(BinaryOp::Rem, int(-7), int(2)).unwrap(), "-1");
+        // Mixed with unsigned, -1 converts to the largest value like in C
+        assert_eq!(
+            evaluate(BinaryOp::Add, int(-1), unsigned(0)).unwrap(),
+            "4294967295"
+        );
+        assert_eq!(evaluate(BinaryOp::Lt, int(-1), unsigned(0)).unwrap(), "0");
+        assert_eq!(
+            evaluate(BinaryOp::Lt, int(-1), Value::integer(0, 8, true)).unwrap(),
+            "1"
+        );
+        // `char` is promoted to `int`
+        let char_value = Value::new(Type::Char { signed: true }, vec![100], None);
+        assert_eq!(evaluate(BinaryOp::Mul, char_value, int(3)).unwrap(), "300");
+        assert_eq!(evaluate(BinaryOp::Shl, int(1), int(4)).unwrap(), "16");
+        assert_eq!(
+            evaluate(BinaryOp::Div, int(1), int(0)),
+            Err(ExpressionError::DivisionByZero)
+        );
+    }
+
+    #[test]
+    fn test_float_arithmetic_and_literals() {
+        assert_eq!(
+            evaluate(BinaryOp::Div, Value::integer(1, 4, true), Value::float(4.0)).unwrap(),
+            "0.25"
+        );
+        assert_eq!(
+            evaluate(BinaryOp::Ge, Value::float(2.5), Value::integer(2, 4, true)).unwrap(),
+            "1"
+        );
+        assert!(matches!(
+            integer_literal(0x7fffffff).value_type,
+            Type::Int {
+                size: 4,
+                signed: true
+            }
+        ));
+        assert!(matches!(
+            integer_literal(0x80000000).value_type,
+            Type::Int {
+                size: 8,
+                signed: true
+            }
+        ));
+        assert!(matches!(
+            integer_literal(u64::MAX as u128).value_type,
+            Type::Int {
+                size: 8,
+                signed: false
+            }
+        ));
+        assert_eq!(normalize(0x1ff, 1, true), -1);
+        assert_eq!(normalize(-1, 2, false), 0xffff);
+    }
+
+    #[test]
+    fn test_builtin_type_names() {
+        let name = |name| builtin_type(name).unwrap().name().unwrap();
+        assert_eq!(name("unsigned long long"), "unsigned long");
+        assert_eq!(name("i8"), "signed char");
+        assert_eq!(name("u16"), "unsigned short");
+        assert_eq!(name("f32"), "float");
+        let pointer = Type::Pointer(Box::new(Type::Pointer(Box::new(Type::Char {
+            signed: true,
+        }))));
+        assert_eq!(pointer.name().unwrap(), "char **");
+    }
+}
@@ -0,0 +1,687 @@
+use std::error::Error;
+use std::fmt::Display;
+
+use crate::dwarf::error::DwarfError;
+
+/// An expression of the source language, C or Rust, like `p->x + arr[i]`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Integer(u128),
+    Float(f64),
+    Char(u8),
+    Variable(String),
+    /// `$name`, a register like `$rip` or a convenience variable
+    Dollar(String),
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    /// `a.b`, which follows pointers too like the `.` of Rust
+    Member(Box<Expr>, String),
+    /// `a->b`
+    Arrow(Box<Expr>, String),
+    Index(Box<Expr>, Box<Expr>),
+    /// `(type) a` or `a as type`
+    Cast(TypeName, Box<Expr>),
+    /// `a ? b : c`
+    Conditional(Box<Expr>, Box<Expr>, Box<Expr>),
+    /// `a = b`, only convenience variables can be assigned
+    Assign(Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    Negate,
+    /// `!`, logical negation in C and for `bool`
+    Not,
+    /// `~`
+    Complement,
+    Deref,
+    AddressOf,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Shl,
+    Shr,
+    BitAnd,
+    BitOr,
+    BitXor,
+    And,
+    Or,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// The type of a cast, e.g. `struct point` with one pointer for `struct point *`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeName {
+    pub name: String,
+    pub pointers: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExpressionError {
+    InvalidCharacter(char),
+    InvalidNumber(String),
+    UnexpectedToken(String),
+    UnexpectedEnd,
+    NoSymbol(String),
+    NoMember(String),
+    UnknownType(String),
+    /// The value of a variable is not known at the pc
+    OptimizedOut,
+    /// An operation is not defined for the type of its operands, e.g. `*` of a struct
+    InvalidOperand(&'static str),
+    NotAnLvalue,
+    /// Only convenience variables can be assigned to
+    NotAssignable,
+    DivisionByZero,
+    IndexOutOfBounds {
+        index: i128,
+        len: u64,
+    },
+    Dwarf(DwarfError),
+}
+
+impl Display for ExpressionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExpressionError::InvalidCharacter(character) => {
+                write!(f, "invalid character '{}' in expression", character)
+            }
+            ExpressionError::InvalidNumber(number) => write!(f, "invalid number \"{}\"", number),
+            ExpressionError::UnexpectedToken(token) => {
+                write!(f, "syntax error in expression, near `{}'", token)
+            }
+            ExpressionError::UnexpectedEnd => f.write_str("unexpected end of expression"),
+            ExpressionError::NoSymbol(name) => {
+                write!(f, "no symbol \"{}\" in current context", name)
+            }
+            ExpressionError::NoMember(name) => write!(f, "there is no member named {}", name),
+            ExpressionError::UnknownType(name) => write!(f, "no type named {}", name),
+            ExpressionError::OptimizedOut => f.write_str("value has been optimized out"),
+            ExpressionError::InvalidOperand(operation) => {
+                write!(f, "invalid operand for {}", operation)
+            }
+            ExpressionError::NotAnLvalue => {
+                f.write_str("attempt to take address of value not located in memory")
+            }
+            ExpressionError::NotAssignable => {
+                f.write_str("only convenience variables like $x can be assigned")
+            }
+            ExpressionError::DivisionByZero => f.write_str("division by zero"),
+            ExpressionError::IndexOutOfBounds { index, len } => write!(
+                f,
+                "index out of bounds: the len is {} but the index is {}",
+                len, index
+            ),
+            ExpressionError::Dwarf(err) => err.fmt(f),
+        }
+    }
+}
+
+impl Error for ExpressionError {}
+
+impl From<DwarfError> for ExpressionError {
+    fn from(err: DwarfError) -> Self {
+        ExpressionError::Dwarf(err)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, ExpressionError>;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Integer(u128),
+    Float(f64),
+    Char(u8),
+    Identifier(String),
+    Dollar(String),
+    Punct(&'static str),
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Integer(value) => write!(f, "{}", value),
+            Token::Float(value) => write!(f, "{}", value),
+            Token::Char(value) => write!(f, "'{}'", char::from(*value)),
+            Token::Identifier(name) => f.write_str(name),
+            Token::Dollar(name) => write!(f, "${}", name),
+            Token::Punct(punct) => f.write_str(punct),
+        }
+    }
+}
+
+/// Operators, longest first so that `->` is not read as `-`
+const PUNCTUATION: [&str; 29] = [
+    "->", "<<", ">>", "<=", ">=", "==", "!=", "&&", "||", "+", "-", "*", "/", "%", "&", "|", "^",
+    "~", "!", "<", ">", "=", "(", ")", "[", "]", ".", "?", ":",
+];
+
+/// Names of types built into C and Rust, which casts are recognized by without debug info
+const BUILTIN_TYPE_WORDS: [&str; 12] = [
+    "char", "short", "int", "long", "signed", "unsigned", "float", "double", "void", "_Bool",
+    "struct", "union",
+];
+
+fn tokenize(text: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut position = 0;
+    while position < chars.len() {
+        let character = chars[position];
+        if character.is_whitespace() {
+            position += 1;
+        } else if character.is_ascii_digit() {
+            let start = position;
+            while position < chars.len()
+                && (chars[position].is_ascii_alphanumeric()
+                    || chars[position] == '.'
+                    || (matches!(chars[position], '+' | '-')
+                        && matches!(chars[position - 1], 'e' | 'E')
+                        && !chars[start..position].contains(&'x')))
+            {
+                position += 1;
+            }
+            let number: String = chars[start..position].iter().collect();
+            tokens.push(parse_number(&number)?);
+        } else if character.is_alphabetic() || character == '_' {
+            let start = position;
+            // Paths of Rust and C++ like `std::f` are one name
+            while position < chars.len()
+                && (chars[position].is_alphanumeric()
+                    || chars[position] == '_'
+                    || (chars[position] == ':' && chars.get(position + 1) == Some(&':')))
+            {
+                position += if chars[position] == ':' { 2 } else { 1 };
+            }
+            tokens.push(Token::Identifier(chars[start..position].iter().collect()));
+        } else if character == '$' {
+            let start = position + 1;
+            position = start;
+            while position < chars.len()
+                && (chars[position].is_alphanumeric() || chars[position] == '_')
+            {
+                position += 1;
+            }
+            tokens.push(Token::Dollar(chars[start..position].iter().collect()));
+        } else if character == '\'' {
+            let (value, len) = parse_char(&chars[position + 1..])?;
+            position += len + 1;
+            if chars.get(position) != Some(&'\'') {
+                return Err(ExpressionError::InvalidCharacter('\''));
+            }
+            position += 1;
+            tokens.push(Token::Char(value));
+        } else {
+            let rest: String = chars[position..chars.len().min(position + 2)]
+                .iter()
+                .collect();
+            match PUNCTUATION.iter().find(|punct| rest.starts_with(**punct)) {
+                Some(punct) => {
+                    tokens.push(Token::Punct(punct));
+                    position += punct.len();
+                }
+                None => return Err(ExpressionError::InvalidCharacter(character)),
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// Parses a literal of C: decimal, hexadecimal with `0x`, octal with a leading `0`, or a
+/// float. Suffixes like `u`, `UL` and `f` are ignored.
+fn parse_number(number: &str) -> Result<Token> {
+    let invalid = || ExpressionError::InvalidNumber(number.to_string());
+    let lower = number.to_ascii_lowercase();
+    if let Some(hex) = lower.strip_prefix("0x") {
+        let digits = hex.trim_end_matches(['u', 'l']);
+        return u128::from_str_radix(digits, 16)
+            .map(Token::Integer)
+            .map_err(|_| invalid());
+    }
+    if lower.contains(['.', 'e']) {
+        return lower
+            .trim_end_matches('f')
+            .parse()
+            .map(Token::Float)
+            .map_err(|_| invalid());
+    }
+    let digits = lower.trim_end_matches(['u', 'l']);
+    let radix = if digits.len() > 1 && digits.starts_with('0') {
+        8
+    } else {
+        10
+    };
+    u128::from_str_radix(digits, radix)
+        .map(Token::Integer)
+        .map_err(|_| invalid())
+}
+
+/// Parses the character of a character literal after its opening quote, returning it
+/// with the number of characters it was written with
+fn parse_char(chars: &[char]) -> Result<(u8, usize)> {
+    let invalid = ExpressionError::InvalidCharacter('\'');
+    match chars {
+        ['\\', escape, ..] => {
+            let value = match escape {
+                'n' => b'\n',
+                't' => b'\t',
+                'r' => b'\r',
+                'a' => 0x07,
+                'b' => 0x08,
+                'f' => 0x0c,
+                'v' => 0x0b,
+                '0'..='7' => {
+                    let digits: String = chars[1..]
+                        .iter()
+                        .take(3)
+                        .take_while(|c| c.is_digit(8))
+                        .collect();
+                    let value = u8::from_str_radix(&digits, 8).map_err(|_| invalid)?;
+                    return Ok((value, digits.len() + 1));
+                }
+                other if other.is_ascii() => *other as u8,
+                _ => return Err(invalid),
+            };
+            Ok((value, 2))
+        }
+        [character, ..] if character.is_ascii() => Ok((*character as u8, 1)),
+        _ => Err(invalid),
+    }
+}
+
+/// Parses expressions by recursive descent, with the precedences of C
+struct Parser<'t> {
+    tokens: Vec<Token>,
+    position: usize,
+    /// Whether a name is that of a type, to tell a cast like `(size_t) n` from a
+    /// parenthesized expression like `(n) - 1`
+    is_type: &'t dyn Fn(&str) -> bool,
+}
+
+/// Parses `text`. Names of types in casts other than those of C are recognized by `is_type`.
+pub fn parse_expression(text: &str, is_type: &dyn Fn(&str) -> bool) -> Result<Expr> {
+    let mut parser = Parser {
+        tokens: tokenize(text)?,
+        position: 0,
+        is_type,
+    };
+    let expr = parser.assignment()?;
+    match parser.peek() {
+        Some(token) => Err(ExpressionError::UnexpectedToken(token.to_string())),
+        None => Ok(expr),
+    }
+}
+
+impl<'t> Parser<'t> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Result<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token.ok_or(ExpressionError::UnexpectedEnd)
+    }
+
+    /// Consumes the operator `punct` if it is next
+    fn eat(&mut self, punct: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Punct(next)) if *next == punct) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, punct: &str) -> Result<()> {
+        match self.next()? {
+            Token::Punct(next) if next == punct => Ok(()),
+            token => Err(ExpressionError::UnexpectedToken(token.to_string())),
+        }
+    }
+
+    fn assignment(&mut self) -> Result<Expr> {
+        let target = self.conditional()?;
+        if self.eat("=") {
+            let value = self.assignment()?;
+            return Ok(Expr::Assign(Box::new(target), Box::new(value)));
+        }
+        Ok(target)
+    }
+
+    fn conditional(&mut self) -> Result<Expr> {
+        let condition = self.binary(0)?;
+        if !self.eat("?") {
+            return Ok(condition);
+        }
+        let then = self.assignment()?;
+        self.expect(":")?;
+        let otherwise = self.conditional()?;
+        Ok(Expr::Conditional(
+            Box::new(condition),
+            Box::new(then),
+            Box::new(otherwise),
+        ))
+    }
+
+    /// Parses binary operators of at least the precedence `min_level`, see `binary_operator`
+    fn binary(&mut self, min_level: u8) -> Result<Expr> {
+        let mut left = self.cast()?;
+        while let Some((op, level)) = self.peek().and_then(binary_operator) {
+            if level < min_level {
+                break;
+            }
+            self.position += 1;
+            // All binary operators are left associative
+            let right = self.binary(level + 1)?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    /// Parses a unary expression followed by Rust's `as` casts, which bind tighter than
+    /// binary operators
+    fn cast(&mut self) -> Result<Expr> {
+        let mut expr = self.unary()?;
+        while matches!(self.peek(), Some(Token::Identifier(word)) if word == "as") {
+            self.position += 1;
+            let type_name = self.rust_type_name()?;
+            expr = Expr::Cast(type_name, Box::new(expr));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        let op = match self.peek() {
+            Some(Token::Punct("-")) => UnaryOp::Negate,
+            Some(Token::Punct("!")) => UnaryOp::Not,
+            Some(Token::Punct("~")) => UnaryOp::Complement,
+            Some(Token::Punct("*")) => UnaryOp::Deref,
+            Some(Token::Punct("&")) => UnaryOp::AddressOf,
+            Some(Token::Punct("+")) => {
+                self.position += 1;
+                return self.unary();
+            }
+            Some(Token::Punct("(")) => match self.c_type_name() {
+                Some(type_name) => {
+                    let operand = self.unary()?;
+                    return Ok(Expr::Cast(type_name, Box::new(operand)));
+                }
+                None => return self.postfix(),
+            },
+            _ => return self.postfix(),
+        };
+        self.position += 1;
+        Ok(Expr::Unary(op, Box::new(self.unary()?)))
+    }
+
+    /// Parses the `(type)` of a C cast at the current `(`, leaving the position unchanged
+    /// if it is not one
+    fn c_type_name(&mut self) -> Option<TypeName> {
+        let start = self.position;
+        self.position += 1;
+        let mut words = Vec::new();
+        while let Some(Token::Identifier(word)) = self.peek() {
+            // Qualifiers do not change how values are read
+            if !matches!(word.as_str(), "const" | "volatile") {
+                words.push(word.clone());
+            }
+            self.position += 1;
+        }
+        let mut pointers = 0;
+        while self.eat("*") {
+            pointers += 1;
+        }
+        let name = words.join(" ");
+        let is_type = !words.is_empty()
+            && (words
+                .iter()
+                .any(|word| BUILTIN_TYPE_WORDS.contains(&word.as_str()))
+                || (self.is_type)(&name));
+        if is_type && self.eat(")") {
+            return Some(TypeName { name, pointers });
+        }
+        self.position = start;
+        None
+    }
+
+    /// Parses the type of an `as` cast, like `u64` or `*const u8`
+    fn rust_type_name(&mut self) -> Result<TypeName> {
+        let mut pointers = 0;
+        loop {
+            if self.eat("*") {
+                match self.next()? {
+                    Token::Identifier(word) if word == "const" || word == "mut" => {}
+                    token => return Err(ExpressionError::UnexpectedToken(token.to_string())),
+                }
+            } else if self.eat("&") {
+                if matches!(self.peek(), Some(Token::Identifier(word)) if word == "mut") {
+                    self.position += 1;
+                }
+            } else {
+                break;
+            }
+            pointers += 1;
+        }
+        match self.next()? {
+            Token::Identifier(name) => Ok(TypeName { name, pointers }),
+            token => Err(ExpressionError::UnexpectedToken(token.to_string())),
+        }
+    }
+
+    fn postfix(&mut self) -> Result<Expr> {
+        let mut expr = self.primary()?;
+        loop {
+            if self.eat("[") {
+                let index = self.assignment()?;
+                self.expect("]")?;
+                expr = Expr::Index(Box::new(expr), Box::new(index));
+            } else if self.eat(".") {
+                expr = Expr::Member(Box::new(expr), self.member_name()?);
+            } else if self.eat("->") {
+                expr = Expr::Arrow(Box::new(expr), self.member_name()?);
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    /// The name after `.` or `->`. Fields of Rust tuples, `t.0`, are named `__0`.
+    fn member_name(&mut self) -> Result<String> {
+        match self.next()? {
+            Token::Identifier(name) => Ok(name),
+            Token::Integer(index) => Ok(format!("__{}", index)),
+            token => Err(ExpressionError::UnexpectedToken(token.to_string())),
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr> {
+        match self.next()? {
+            Token::Integer(value) => Ok(Expr::Integer(value)),
+            Token::Float(value) => Ok(Expr::Float(value)),
+            Token::Char(value) => Ok(Expr::Char(value)),
+            Token::Identifier(name) => match name.as_str() {
+                "true" => Ok(Expr::Integer(1)),
+                "false" => Ok(Expr::Integer(0)),
+                _ => Ok(Expr::Variable(name)),
+            },
+            Token::Dollar(name) => Ok(Expr::Dollar(name)),
+            Token::Punct("(") => {
+                let expr = self.assignment()?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            token => Err(ExpressionError::UnexpectedToken(token.to_string())),
+        }
+    }
+}
+
+/// The binary operator of a token with its precedence, higher binds tighter
+fn binary_operator(token: &Token) -> Option<(BinaryOp, u8)> {
+    let punct = match token {
+        Token::Punct(punct) => *punct,
+        _ => return None,
+    };
+    Some(match punct {
+        "||" => (BinaryOp::Or, 0),
+        "&&" => (BinaryOp::And, 1),
+        "|" => (BinaryOp::BitOr, 2),
+        "^" => (BinaryOp::BitXor, 3),
+        "&" => (BinaryOp::BitAnd, 4),
+        "==" => (BinaryOp::Eq, 5),
+        "!=" => (BinaryOp::Ne, 5),
+        "<" => (BinaryOp::Lt, 6),
+        "<=" => (BinaryOp::Le, 6),
+        ">" => (BinaryOp::Gt, 6),
+        ">=" => (BinaryOp::Ge, 6),
+        "<<" => (BinaryOp::Shl, 7),
+        ">>" => (BinaryOp::Shr, 7),
+        "+" => (BinaryOp::Add, 8),
+        "-" => (BinaryOp::Sub, 8),
+        "*" => (BinaryOp::Mul, 9),
+        "/" => (BinaryOp::Div, 9),
+        "%" => (BinaryOp::Rem, 9),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Expr {
+        parse_expression(text, &|name| name == "size_t").unwrap()
+    }
+
+    fn variable(name: &str) -> Box<Expr> {
+        Box::new(Expr::Variable(name.to_string()))
+    }
+
+    #[test]
+    fn test_parse_precedence() {
+        assert_eq!(
+            parse("a + b * 2 == 7"),
+            Expr::Binary(
+                BinaryOp::Eq,
+                Box::new(Expr::Binary(
+                    BinaryOp::Add,
+                    variable("a"),
+                    Box::new(Expr::Binary(
+                        BinaryOp::Mul,
+                        variable("b"),
+                        Box::new(Expr::Integer(2))
+                    ))
+                )),
+                Box::new(Expr::Integer(7))
+            )
+        );
+        // Left associative
+        assert_eq!(
+            parse("a - b - c"),
+            Expr::Binary(
+                BinaryOp::Sub,
+                Box::new(Expr::Binary(BinaryOp::Sub, variable("a"), variable("b"))),
+                variable("c")
+            )
+        );
+        assert_eq!(
+            parse("*p->next"),
+            Expr::Unary(
+                UnaryOp::Deref,
+                Box::new(Expr::Arrow(variable("p"), "next".to_string()))
+            )
+        );
+        assert_eq!(
+            parse("&arr[1].x"),
+            Expr::Unary(
+                UnaryOp::AddressOf,
+                Box::new(Expr::Member(
+                    Box::new(Expr::Index(variable("arr"), Box::new(Expr::Integer(1)))),
+                    "x".to_string()
+                ))
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_casts() {
+        let cast = |name: &str, pointers, expr| {
+            Expr::Cast(
+                TypeName {
+                    name: name.to_string(),
+                    pointers,
+                },
+                expr,
+            )
+        };
+        assert_eq!(
+            parse("(unsigned char) c"),
+            cast("unsigned char", 0, variable("c"))
+        );
+        assert_eq!(
+            parse("(const struct point **) p"),
+            cast("struct point", 2, variable("p"))
+        );
+        assert_eq!(parse("(size_t)n"), cast("size_t", 0, variable("n")));
+        // Not a type, so a parenthesized expression
+        assert_eq!(
+            parse("(n) - 1"),
+            Expr::Binary(BinaryOp::Sub, variable("n"), Box::new(Expr::Integer(1)))
+        );
+        assert_eq!(
+            parse("x as u8 + 1"),
+            Expr::Binary(
+                BinaryOp::Add,
+                Box::new(cast("u8", 0, variable("x"))),
+                Box::new(Expr::Integer(1))
+            )
+        );
+        assert_eq!(parse("p as *const u8"), cast("u8", 1, variable("p")));
+    }
+
+    #[test]
+    fn test_parse_literals_and_names() {
+        assert_eq!(parse("0x1f"), Expr::Integer(0x1f));
+        assert_eq!(parse("010"), Expr::Integer(8));
+        assert_eq!(parse("10UL"), Expr::Integer(10));
+        assert_eq!(parse("1.5e-3"), Expr::Float(1.5e-3));
+        assert_eq!(parse("'\\n'"), Expr::Char(b'\n'));
+        assert_eq!(parse("'\\0'"), Expr::Char(0));
+        assert_eq!(parse("$rip"), Expr::Dollar("rip".to_string()));
+        assert_eq!(parse("t.0"), Expr::Member(variable("t"), "__0".to_string()));
+        assert_eq!(parse("std::x"), Expr::Variable("std::x".to_string()));
+        assert_eq!(
+            parse("$n = 1"),
+            Expr::Assign(
+                Box::new(Expr::Dollar("n".to_string())),
+                Box::new(Expr::Integer(1))
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let parse = |text| parse_expression(text, &|_| false);
+        assert_eq!(parse("a +"), Err(ExpressionError::UnexpectedEnd));
+        assert_eq!(
+            parse("a b"),
+            Err(ExpressionError::UnexpectedToken("b".to_string()))
+        );
+        assert_eq!(parse("a @ b"), Err(ExpressionError::InvalidCharacter('@')));
+        assert_eq!(
+            parse("0x"),
+            Err(ExpressionError::InvalidNumber("0x".to_string()))
+        );
+    }
+}
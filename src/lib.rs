@@ -1,5 +1,13 @@
+pub mod backtrace;
 pub mod breakpoint;
 pub mod command;
 pub mod debugger;
+pub mod disasm;
+pub mod dwarf;
 pub mod elf;
+pub mod eval;
+pub mod expr;
+pub mod maps;
 pub mod register;
+pub mod source;
+pub mod watchpoint;
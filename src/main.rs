@@ -1,7 +1,7 @@
 // std
 use std::env;
 use std::ffi::CString;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 // 3rd party
 use nix::sys::personality::Persona;
@@ -9,29 +9,55 @@ use nix::sys::{personality, ptrace};
 use nix::unistd::{execv, fork, ForkResult};
 
 // own
-mod breakpoint;
+use tamandua::debugger::Debugger;
+use tamandua::dwarf::sections::DwarfSections;
+use tamandua::dwarf::split::{self, SplitDwarf};
+use tamandua::elf::debuglink::{DebugFileLocator, DEFAULT_DEBUG_FILE_DIRECTORY};
+use tamandua::elf::file::ElfFile;
+use tamandua::elf::parser::ElfParser;
 
-mod command;
+const DEBUG_FILE_DIRECTORY_FLAG: &str = "--debug-file-directory";
 
-mod debugger;
-use crate::debugger::Debugger;
+fn execute_debugee(path: CString) {
+    ptrace::traceme().unwrap();
 
-mod elf;
+    let args = [CString::new("").unwrap()];
+    let Err(err) = execv(&path, &args[..]);
+    panic!("[execute_debugee] execv() failed: {}", err);
+}
 
-mod register;
+/// Usage: `tamandua [--debug-file-directory dir]... executable`
+/// Every `--debug-file-directory` adds a directory to search for separate debug files,
+/// replacing the default of `/usr/lib/debug`.
+fn parse_args(args: &[String]) -> (Vec<PathBuf>, &str) {
+    let mut debug_file_dirs = Vec::new();
+    let mut program_path = None;
 
-fn execute_debugee(path: CString) {
-    ptrace::traceme().unwrap();
+    let mut args = args.iter().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == DEBUG_FILE_DIRECTORY_FLAG {
+            let dir = args.next().expect("No debug file directory given");
+            debug_file_dirs.push(PathBuf::from(dir));
+        } else {
+            program_path = Some(arg.as_str());
+        }
+    }
+
+    if debug_file_dirs.is_empty() {
+        debug_file_dirs.push(PathBuf::from(DEFAULT_DEBUG_FILE_DIRECTORY));
+    }
 
-    let args = vec![CString::new("").unwrap()];
-    execv(&path, &args[..]).unwrap();
+    (
+        debug_file_dirs,
+        program_path.expect("No program path given"),
+    )
 }
 
 fn main() {
     let args: Vec<String> = env::args().collect();
-    let program_path_string = &args[1];
+    let (debug_file_dirs, program_path_string) = parse_args(&args);
 
-    let path = Path::new(&program_path_string);
+    let path = Path::new(program_path_string);
     let c_str_path = CString::new(path.to_str().unwrap()).expect("CString::new failed");
 
     match unsafe { fork() } {
@@ -40,7 +66,40 @@ fn main() {
             execute_debugee(c_str_path)
         }
         Ok(ForkResult::Parent { child }) => {
-            let mut debugger = Debugger::new(path, child);
+            let mut parser = ElfParser::new(path);
+            let mut elf_file = parser.parse_elf_file();
+
+            // Stripped executables keep their debug info in a separate file
+            let locator = DebugFileLocator::new(debug_file_dirs);
+            let debug_file_path = locator.locate(path, &elf_file);
+            let mut debug_parser = debug_file_path.as_deref().map(ElfParser::new);
+            if let Some(debug_parser) = debug_parser.as_mut() {
+                println!(
+                    "Reading debug info from {}",
+                    debug_file_path.as_ref().unwrap().display()
+                );
+                elf_file.merge_debug_file(debug_parser.parse_elf_file());
+            }
+
+            // Split DWARF leaves most of the debug info in .dwo files or a .dwp package
+            let split_paths = split::locate_split_files(path, &DwarfSections::new(&elf_file));
+            let mut split_parsers: Vec<ElfParser> = split_paths
+                .iter()
+                .map(|path| ElfParser::new(path))
+                .collect();
+            let split_files: Vec<ElfFile> = split_parsers
+                .iter_mut()
+                .map(|parser| parser.parse_elf_file())
+                .collect();
+            let mut split_dwarf = SplitDwarf::default();
+            for (split_path, split_file) in split_paths.iter().zip(&split_files) {
+                println!("Reading split debug info from {}", split_path.display());
+                if let Err(err) = split_dwarf.add_file(split_file) {
+                    eprintln!("Failed to read {}: {}", split_path.display(), err);
+                }
+            }
+
+            let mut debugger = Debugger::new(path, child, elf_file, &split_dwarf);
             debugger.run();
         }
         Err(err) => {
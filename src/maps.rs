@@ -0,0 +1,118 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use nix::unistd::Pid;
+
+/// A single line of `/proc/<pid>/maps`, a range of the address space of a process
+/// and what is mapped into it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryMap {
+    pub start: u64,
+    pub end: u64,
+    pub permissions: String,
+    /// Offset into the mapped file
+    pub offset: u64,
+    /// The mapped file, `None` for anonymous mappings and pseudo paths like `[stack]`
+    pub path: Option<PathBuf>,
+}
+
+impl MemoryMap {
+    pub fn contains(&self, address: u64) -> bool {
+        self.start <= address && address < self.end
+    }
+
+    pub fn is_executable(&self) -> bool {
+        self.permissions.contains('x')
+    }
+}
+
+pub fn read_memory_maps(pid: Pid) -> io::Result<Vec<MemoryMap>> {
+    let contents = fs::read_to_string(format!("/proc/{}/maps", pid))?;
+    Ok(parse_memory_maps(&contents))
+}
+
+/// Parses the contents of a maps file, lines that can't be parsed are skipped.
+/// The format of each line is `start-end perms offset dev inode path`
+pub fn parse_memory_maps(contents: &str) -> Vec<MemoryMap> {
+    contents.lines().filter_map(parse_memory_map).collect()
+}
+
+fn parse_memory_map(line: &str) -> Option<MemoryMap> {
+    let mut fields = line.split_whitespace();
+    let (start, end) = fields.next()?.split_once('-')?;
+    let permissions = fields.next()?.to_string();
+    let offset = u64::from_str_radix(fields.next()?, 16).ok()?;
+    let _device = fields.next()?;
+    let _inode = fields.next()?;
+    // Paths may contain spaces, so the rest of the line is the path
+    let path = fields.collect::<Vec<_>>().join(" ");
+
+    Some(MemoryMap {
+        start: u64::from_str_radix(start, 16).ok()?,
+        end: u64::from_str_radix(end, 16).ok()?,
+        permissions,
+        offset,
+        path: if path.starts_with('/') {
+            Some(PathBuf::from(path))
+        } else {
+            None
+        },
+    })
+}
+
+pub fn find_mapping(maps: &[MemoryMap], address: u64) -> Option<&MemoryMap> {
+    maps.iter().find(|map| map.contains(address))
+}
+
+/// The address the start of the file at `path` is mapped at, which is the load address
+/// of position independent executables and shared objects.
+pub fn load_address(maps: &[MemoryMap], path: &Path) -> Option<u64> {
+    let canonical_path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    maps.iter()
+        .filter(|map| {
+            map.path.as_deref() == Some(path) || map.path.as_deref() == Some(&canonical_path)
+        })
+        .map(|map| map.start - map.offset)
+        .min()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAPS: &str = "\
+555555554000-555555555000 r--p 00000000 00:2a 1234       /tmp/entry point
+555555555000-555555556000 r-xp 00001000 00:2a 1234       /tmp/entry point
+7ffff7dd3000-7ffff7dfb000 r--p 00000000 00:2a 5678       /usr/lib/x86_64-linux-gnu/libc.so.6
+7ffff7dfb000-7ffff7f90000 r-xp 00028000 00:2a 5678       /usr/lib/x86_64-linux-gnu/libc.so.6
+7ffffffde000-7ffffffff000 rw-p 00000000 00:00 0          [stack]
+";
+
+    #[test]
+    fn test_parse_memory_maps() {
+        let maps = parse_memory_maps(MAPS);
+        assert_eq!(maps.len(), 5);
+        assert_eq!(maps[1].start, 0x555555555000);
+        assert_eq!(maps[1].end, 0x555555556000);
+        assert_eq!(maps[1].offset, 0x1000);
+        assert!(maps[1].is_executable());
+        assert_eq!(maps[1].path, Some(PathBuf::from("/tmp/entry point")));
+        assert_eq!(maps[4].path, None);
+    }
+
+    #[test]
+    fn test_find_mapping_and_load_address() {
+        let maps = parse_memory_maps(MAPS);
+        let map = find_mapping(&maps, 0x7ffff7e00000).unwrap();
+        assert_eq!(
+            map.path.as_deref(),
+            Some(Path::new("/usr/lib/x86_64-linux-gnu/libc.so.6"))
+        );
+        assert_eq!(find_mapping(&maps, 0x1000), None);
+        assert_eq!(
+            load_address(&maps, Path::new("/usr/lib/x86_64-linux-gnu/libc.so.6")),
+            Some(0x7ffff7dd3000)
+        );
+    }
+}
@@ -1,7 +1,14 @@
 #![allow(dead_code)]
 
+use std::mem::MaybeUninit;
+use std::ptr;
+
+use nix::errno::Errno;
+use nix::libc::{self, user_fpregs_struct, user_regs_struct};
 use nix::{sys::ptrace, unistd::Pid};
 
+use crate::dwarf::unwind::{RegisterSet, RETURN_ADDRESS_REGISTER};
+
 #[rustfmt::skip]
 pub const REGISTERS: [Register; 27] = [
     Register{reg_kind: RegisterKind::R15,     dwarf_reg: 15, name: "r15"},      // 0
@@ -85,7 +92,10 @@ pub enum RegisterKind {
 
 pub fn get_register_value(pid: Pid, reg: RegisterKind) -> u64 {
     let regs = ptrace::getregs(pid).expect("Failed to getregs");
+    register_value(&regs, reg)
+}
 
+fn register_value(regs: &user_regs_struct, reg: RegisterKind) -> u64 {
     match reg {
         RegisterKind::Rax => regs.rax,
         RegisterKind::Rbx => regs.rbx,
@@ -154,21 +164,73 @@ pub fn set_register_value(pid: Pid, reg: RegisterKind, value: u64) {
     ptrace::setregs(pid, regs).expect("Failed to setregs");
 }
 
-pub fn get_register_value_from_dwarf_register(pid: Pid, reg_num: i32) -> u64 {
-    let mut reg_value = 0;
-    let mut found_reg = false;
-    for reg in REGISTERS {
-        if reg.dwarf_reg == reg_num {
-            reg_value = get_register_value(pid, reg.reg_kind);
-            found_reg = true;
-        }
+/// The value of the register with the DWARF register number `reg_num`, or `None`
+/// if it is not one of the general purpose registers
+pub fn get_register_value_from_dwarf_register(pid: Pid, reg_num: i32) -> Option<u64> {
+    REGISTERS
+        .iter()
+        .find(|reg| reg.dwarf_reg == reg_num)
+        .map(|reg| get_register_value(pid, reg.reg_kind.clone()))
+}
+
+/// The registers that have a DWARF register number, with the pc as the return address
+/// column, as the registers of the innermost frame
+pub fn get_register_set(pid: Pid) -> RegisterSet {
+    let regs = ptrace::getregs(pid).expect("Failed to getregs");
+    let mut registers = RegisterSet::default();
+    for reg in REGISTERS.iter().filter(|reg| reg.dwarf_reg >= 0) {
+        registers.set(
+            reg.dwarf_reg as u16,
+            register_value(&regs, reg.reg_kind.clone()),
+        );
+    }
+    registers.set(RETURN_ADDRESS_REGISTER, regs.rip);
+    registers
+}
+
+/// The bytes of the SSE register `xmm<index>`
+pub fn get_xmm_register(pid: Pid, index: usize) -> [u8; 16] {
+    let regs = get_fp_registers(pid);
+    words_to_bytes(&regs.xmm_space[index * 4..index * 4 + 4])
+}
+
+/// The bytes of the x87 register st0, an 80-bit extended precision float padded to 16 bytes
+pub fn get_st0_register(pid: Pid) -> [u8; 16] {
+    let regs = get_fp_registers(pid);
+    words_to_bytes(&regs.st_space[..4])
+}
+
+/// The x87 and SSE registers, which nix has no request for
+fn get_fp_registers(pid: Pid) -> user_fpregs_struct {
+    let mut regs = MaybeUninit::<user_fpregs_struct>::uninit();
+    // SAFETY: PTRACE_GETFPREGS fills the struct it is passed
+    let result = unsafe {
+        libc::ptrace(
+            libc::PTRACE_GETFPREGS,
+            libc::pid_t::from(pid),
+            ptr::null_mut::<libc::c_void>(),
+            regs.as_mut_ptr(),
+        )
+    };
+    Errno::result(result).expect("Failed to getfpregs");
+    // SAFETY: The request succeeded, so the struct is filled
+    unsafe { regs.assume_init() }
+}
+
+fn words_to_bytes(words: &[u32]) -> [u8; 16] {
+    let mut bytes = [0; 16];
+    for (chunk, word) in bytes.chunks_mut(4).zip(words) {
+        chunk.copy_from_slice(&word.to_le_bytes());
     }
+    bytes
+}
 
-    // The register has to be found during the for loop.
-    // If not an illegal reg num has been given, and we need to error
-    // TODO: Proper error handling instead of just panicing like this will
-    assert!(found_reg);
-    reg_value
+/// The DWARF register number of `reg`, `None` for registers DWARF has no number for
+pub fn get_dwarf_register_number(reg: &RegisterKind) -> Option<u16> {
+    REGISTERS
+        .iter()
+        .find(|register| register.reg_kind == *reg)
+        .and_then(|register| u16::try_from(register.dwarf_reg).ok())
 }
 
 pub fn get_register_name<'a>(reg: &'a Register) -> &'a str {
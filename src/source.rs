@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// Finds and caches the source files named by the debug information. The paths it
+/// records are those of the machine the executable was built on, which are mapped to
+/// local ones by substitution rules, or found relative to the compilation directory.
+#[derive(Debug, Clone, Default)]
+pub struct SourceFiles {
+    /// Prefixes of recorded paths and what they are replaced by, see [`Self::add_substitution`]
+    substitutions: Vec<(PathBuf, PathBuf)>,
+    /// Compilation directories of the units, longest first
+    comp_dirs: Vec<PathBuf>,
+    /// Directories paths relative to a compilation directory are looked up in
+    search_dirs: Vec<PathBuf>,
+    /// Lines of the files by recorded path, `None` for files that were not found
+    files: HashMap<PathBuf, Option<Rc<[String]>>>,
+}
+
+impl SourceFiles {
+    pub fn new(mut comp_dirs: Vec<PathBuf>, search_dirs: Vec<PathBuf>) -> Self {
+        comp_dirs.sort_by_key(|dir| std::cmp::Reverse(dir.components().count()));
+        comp_dirs.dedup();
+        Self {
+            substitutions: Vec::new(),
+            comp_dirs,
+            search_dirs,
+            files: HashMap::new(),
+        }
+    }
+
+    /// Makes recorded paths starting with the directory `from` start with `to` instead,
+    /// like `set substitute-path` of gdb. A rule for the same `from` is replaced.
+    pub fn add_substitution(&mut self, from: PathBuf, to: PathBuf) {
+        match self.substitutions.iter_mut().find(|(old, _)| *old == from) {
+            Some(rule) => rule.1 = to,
+            None => self.substitutions.push((from, to)),
+        }
+        // Files may now be found elsewhere
+        self.files.clear();
+    }
+
+    pub fn substitutions(&self) -> &[(PathBuf, PathBuf)] {
+        &self.substitutions
+    }
+
+    /// The local path of the source file recorded as `path`, `None` if it is not found
+    pub fn resolve(&self, path: &Path) -> Option<PathBuf> {
+        let path = self.substitute(path);
+        if path.is_file() {
+            return Some(path);
+        }
+
+        // Built elsewhere, the file may be in the same place relative to the compilation
+        // directory of its unit
+        let relative = self
+            .comp_dirs
+            .iter()
+            .find_map(|comp_dir| path.strip_prefix(self.substitute(comp_dir)).ok())
+            .unwrap_or(&path);
+        if relative.is_absolute() {
+            return None;
+        }
+        self.search_dirs
+            .iter()
+            .map(|dir| dir.join(relative))
+            .find(|path| path.is_file())
+    }
+
+    /// The lines of the source file recorded as `path`, read on first use
+    pub fn lines(&mut self, path: &Path) -> Option<Rc<[String]>> {
+        if let Some(lines) = self.files.get(path) {
+            return lines.clone();
+        }
+        let lines = self
+            .resolve(path)
+            .and_then(|local_path| fs::read_to_string(local_path).ok())
+            .map(|source| source.lines().map(String::from).collect::<Rc<[String]>>());
+        self.files.insert(path.to_path_buf(), lines.clone());
+        lines
+    }
+
+    /// Applies the first substitution rule matching `path`
+    fn substitute(&self, path: &Path) -> PathBuf {
+        self.substitutions
+            .iter()
+            .find_map(|(from, to)| Some(to.join(path.strip_prefix(from).ok()?)))
+            .unwrap_or_else(|| path.to_path_buf())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_source_files() {
+        let dir = std::env::temp_dir().join(format!("tamandua-source-{}", std::process::id()));
+        fs::create_dir_all(dir.join("checkout/src")).unwrap();
+        let file = dir.join("checkout/src/main.c");
+        fs::write(&file, "int main(void)\n{\n}\n").unwrap();
+
+        // Built in /build, which is the checkout here
+        let mut sources =
+            SourceFiles::new(vec![PathBuf::from("/build")], vec![dir.join("checkout")]);
+        let recorded = Path::new("/build/src/main.c");
+        assert_eq!(sources.resolve(recorded), Some(file.clone()));
+        assert_eq!(sources.resolve(Path::new("/elsewhere/src/main.c")), None);
+        assert_eq!(sources.lines(recorded).unwrap()[0], "int main(void)");
+
+        // Substitutions take precedence, and apply to whole directory names only
+        sources.add_substitution(PathBuf::from("/elsewhere"), dir.join("checkout"));
+        assert_eq!(
+            sources.resolve(Path::new("/elsewhere/src/main.c")),
+            Some(file.clone())
+        );
+        assert_eq!(sources.resolve(Path::new("/elsewhere2/src/main.c")), None);
+        sources.add_substitution(PathBuf::from("/elsewhere"), dir.clone());
+        assert_eq!(sources.substitutions().len(), 1);
+        assert_eq!(sources.resolve(Path::new("/elsewhere/src/main.c")), None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
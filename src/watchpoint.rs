@@ -0,0 +1,305 @@
+use std::error::Error;
+use std::fmt::Display;
+use std::mem::offset_of;
+use std::ptr;
+
+use nix::errno::Errno;
+use nix::libc;
+use nix::unistd::Pid;
+
+use crate::eval::Type;
+
+/// Number of the debug registers DR0 to DR3, which hold the addresses that are watched
+pub const ADDRESS_REGISTERS: usize = 4;
+/// The debug status register, which tells which address register made the process trap
+const DR6: usize = 6;
+/// The debug control register, which enables the address registers and sets what they
+/// trap on
+const DR7: usize = 7;
+
+/// What a hardware breakpoint or watchpoint traps on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchKind {
+    /// Executing the instruction at the address, set by `hbreak`
+    Execute,
+    /// Writing the memory, set by `watch`
+    Write,
+    /// Reading the memory, set by `rwatch`. The processor can only trap on reads and
+    /// writes together, so the writes that change the value are not reported.
+    Read,
+    /// Reading or writing the memory, set by `awatch`
+    Access,
+}
+
+impl WatchKind {
+    /// The R/W bits of the debug control register for the kind
+    fn condition(self) -> u64 {
+        match self {
+            WatchKind::Execute => 0b00,
+            WatchKind::Write => 0b01,
+            WatchKind::Read | WatchKind::Access => 0b11,
+        }
+    }
+
+    /// What the kind is called in the list of breakpoints
+    pub fn name(self) -> &'static str {
+        match self {
+            WatchKind::Execute => "hw breakpoint",
+            WatchKind::Write => "hw watchpoint",
+            WatchKind::Read => "read watchpoint",
+            WatchKind::Access => "acc watchpoint",
+        }
+    }
+
+    /// What the kind is called when it is set and reported
+    pub fn description(self) -> &'static str {
+        match self {
+            WatchKind::Execute => "Hardware assisted breakpoint",
+            WatchKind::Write => "Hardware watchpoint",
+            WatchKind::Read => "Hardware read watchpoint",
+            WatchKind::Access => "Hardware access (read/write) watchpoint",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WatchpointError {
+    /// The debug registers a watchpoint needs are used by others
+    NoDebugRegisters,
+    Ptrace(Errno),
+}
+
+impl Display for WatchpointError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WatchpointError::NoDebugRegisters => write!(
+                f,
+                "all {} debug registers are used by hardware breakpoints and watchpoints",
+                ADDRESS_REGISTERS
+            ),
+            WatchpointError::Ptrace(err) => write!(f, "cannot access the debug registers: {}", err),
+        }
+    }
+}
+
+impl Error for WatchpointError {}
+
+impl From<Errno> for WatchpointError {
+    fn from(err: Errno) -> Self {
+        WatchpointError::Ptrace(err)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, WatchpointError>;
+
+/// A hardware breakpoint or watchpoint, which uses one or more debug registers
+#[derive(Debug, Clone)]
+pub struct Watchpoint<'a> {
+    /// The number of the watchpoint, shared with the breakpoints
+    pub number: usize,
+    pub kind: WatchKind,
+    pub address: u64,
+    pub length: u64,
+    /// What is watched as it was given, an expression or `*0x...` for an address
+    pub expression: String,
+    /// The type of the watched expression, `None` for memory printed as bytes
+    pub value_type: Option<Type<'a>>,
+    /// The watched bytes when the watchpoint was set or last reported
+    pub value: Vec<u8>,
+    /// The debug registers the watchpoint uses, none while it is disabled
+    pub slots: Vec<usize>,
+    pub enabled: bool,
+    /// How often the watchpoint was reported
+    pub hit_count: usize,
+}
+
+/// The debug registers DR0 to DR3 of a process and which of them are in use
+#[derive(Debug, Default)]
+pub struct DebugRegisters {
+    used: [bool; ADDRESS_REGISTERS],
+}
+
+impl DebugRegisters {
+    /// Programs free debug registers to trap on `kind` of access to the `length` bytes at
+    /// `address`, one register for each aligned region of them. Returns the registers used.
+    pub fn insert(
+        &mut self,
+        pid: Pid,
+        kind: WatchKind,
+        address: u64,
+        length: u64,
+    ) -> Result<Vec<usize>> {
+        let regions = match kind {
+            WatchKind::Execute => vec![(address, 1)],
+            _ => aligned_regions(address, length),
+        };
+        let free: Vec<usize> = (0..ADDRESS_REGISTERS)
+            .filter(|&slot| !self.used[slot])
+            .take(regions.len())
+            .collect();
+        if free.len() < regions.len() {
+            return Err(WatchpointError::NoDebugRegisters);
+        }
+        // The addresses are set before the control register enables them
+        let mut control = read_debug_register(pid, DR7)?;
+        for (&slot, &(address, length)) in free.iter().zip(&regions) {
+            write_debug_register(pid, slot, address)?;
+            control = enable(control, slot, kind, length);
+        }
+        write_debug_register(pid, DR7, control)?;
+        for &slot in &free {
+            self.used[slot] = true;
+        }
+        Ok(free)
+    }
+
+    /// Disables the debug registers `slots` and frees them
+    pub fn remove(&mut self, pid: Pid, slots: &[usize]) -> Result<()> {
+        let control = slots
+            .iter()
+            .fold(read_debug_register(pid, DR7)?, |control, &slot| {
+                disable(control, slot)
+            });
+        write_debug_register(pid, DR7, control)?;
+        for &slot in slots {
+            self.used[slot] = false;
+        }
+        Ok(())
+    }
+
+    /// The debug registers in use whose condition made the process trap. The status
+    /// register is cleared, the processor leaves its bits set for the next trap.
+    pub fn take_triggered(&self, pid: Pid) -> Result<Vec<usize>> {
+        let status = read_debug_register(pid, DR6)?;
+        let slots = triggered(status);
+        if !slots.is_empty() {
+            write_debug_register(pid, DR6, 0)?;
+        }
+        Ok(slots.into_iter().filter(|&slot| self.used[slot]).collect())
+    }
+}
+
+/// Splits the `length` bytes at `address` into regions debug registers can watch, which
+/// are 1, 2, 4 or 8 bytes long and aligned to their length
+pub fn aligned_regions(address: u64, length: u64) -> Vec<(u64, u64)> {
+    let mut regions = Vec::new();
+    let end = address + length;
+    let mut address = address;
+    while address < end {
+        let length = [8, 4, 2, 1]
+            .into_iter()
+            .find(|&length| address.is_multiple_of(length) && address + length <= end)
+            .unwrap_or(1);
+        regions.push((address, length));
+        address += length;
+    }
+    regions
+}
+
+/// The debug control register `control` with `slot` locally enabled, trapping on `kind` of
+/// access to `length` bytes
+fn enable(control: u64, slot: usize, kind: WatchKind, length: u64) -> u64 {
+    let length_bits = match length {
+        1 => 0b00,
+        2 => 0b01,
+        8 => 0b10,
+        _ => 0b11,
+    };
+    let shift = 16 + 4 * slot;
+    let control = control & !(0b1111 << shift);
+    control | 1 << (2 * slot) | (kind.condition() | length_bits << 2) << shift
+}
+
+/// The debug control register `control` with `slot` disabled
+fn disable(control: u64, slot: usize) -> u64 {
+    control & !(0b11 << (2 * slot)) & !(0b1111 << (16 + 4 * slot))
+}
+
+/// The address registers whose bits are set in the debug status register `status`
+fn triggered(status: u64) -> Vec<usize> {
+    (0..ADDRESS_REGISTERS)
+        .filter(|slot| status & 1 << slot != 0)
+        .collect()
+}
+
+/// Offset of debug register `index` in the `user` area of the process
+fn debug_register_offset(index: usize) -> usize {
+    offset_of!(libc::user, u_debugreg) + index * 8
+}
+
+/// Reads debug register `index`, which nix has no request for
+fn read_debug_register(pid: Pid, index: usize) -> Result<u64> {
+    Errno::clear();
+    // SAFETY: PTRACE_PEEKUSER reads a word of the user area at the offset
+    let word = unsafe {
+        libc::ptrace(
+            libc::PTRACE_PEEKUSER,
+            libc::pid_t::from(pid),
+            debug_register_offset(index) as *mut libc::c_void,
+            ptr::null_mut::<libc::c_void>(),
+        )
+    };
+    // The word read may be -1, errno tells whether it failed
+    match Errno::last() {
+        Errno::UnknownErrno => Ok(word as u64),
+        err => Err(err.into()),
+    }
+}
+
+/// Writes debug register `index`, the kernel refuses values the processor would fault on
+fn write_debug_register(pid: Pid, index: usize, value: u64) -> Result<()> {
+    // SAFETY: PTRACE_POKEUSER writes a word of the user area at the offset
+    let result = unsafe {
+        libc::ptrace(
+            libc::PTRACE_POKEUSER,
+            libc::pid_t::from(pid),
+            debug_register_offset(index) as *mut libc::c_void,
+            value as *mut libc::c_void,
+        )
+    };
+    Errno::result(result)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aligned_regions() {
+        assert_eq!(aligned_regions(0x1000, 4), vec![(0x1000, 4)]);
+        assert_eq!(aligned_regions(0x1000, 8), vec![(0x1000, 8)]);
+        assert_eq!(aligned_regions(0x1000, 16), vec![(0x1000, 8), (0x1008, 8)]);
+        assert_eq!(
+            aligned_regions(0x1003, 6),
+            vec![(0x1003, 1), (0x1004, 4), (0x1008, 1)]
+        );
+        assert_eq!(aligned_regions(0x1002, 2), vec![(0x1002, 2)]);
+        assert_eq!(aligned_regions(0x1000, 0), vec![]);
+    }
+
+    #[test]
+    fn test_enable_disable() {
+        // Slot 0 writes 4 bytes: L0, R/W0 = 01, LEN0 = 11
+        assert_eq!(enable(0, 0, WatchKind::Write, 4), 0xd0001);
+        // Slot 1 executes: L1, R/W1 = 00, LEN1 = 00
+        assert_eq!(enable(0, 1, WatchKind::Execute, 1), 0x4);
+        // Slot 3 reads and writes 8 bytes: L3, R/W3 = 11, LEN3 = 10
+        assert_eq!(enable(0, 3, WatchKind::Access, 8), 0xb000_0040);
+        assert_eq!(enable(0, 2, WatchKind::Read, 2), 0x700_0010);
+
+        let control = enable(enable(0, 0, WatchKind::Write, 4), 2, WatchKind::Read, 2);
+        assert_eq!(disable(control, 0), 0x700_0010);
+        assert_eq!(disable(disable(control, 0), 2), 0);
+        // Enabling a slot again replaces its condition
+        assert_eq!(enable(control, 0, WatchKind::Access, 1), 0x703_0011);
+    }
+
+    #[test]
+    fn test_triggered() {
+        assert_eq!(triggered(0xffff0ff0), vec![]);
+        assert_eq!(triggered(0xffff0ff1), vec![0]);
+        // Single stepping sets BS, bit 14, along with the slots that were hit
+        assert_eq!(triggered(0x4000 | 0b1010), vec![1, 3]);
+    }
+}